@@ -10,7 +10,7 @@ TODO : continue to :
 		"12. Example wire transcripts"
 */
 
-// TODO : anti brute-force for auth & /public/
+// TODO : anti brute-force for /public/
 
 // TODO : gracefull panic (like `human_panic` crate but compatible with async) ?
 
@@ -65,6 +65,10 @@ async fn main() -> std::io::Result<()> {
 
 	temp_logger.push(vec![], Some("*CONSOLE_WHITESPACE*"));
 
+	if std::env::args().nth(2).as_deref() == Some("logs") {
+		return show_logs(&settings, std::env::args().skip(3).collect());
+	}
+
 	let logger =
 		pontus_onyx::http_server::load_or_create_logger(&settings, temp_logger, temp_logs_list);
 
@@ -75,12 +79,25 @@ async fn main() -> std::io::Result<()> {
 
 	let users = pontus_onyx::http_server::load_or_create_users(&settings, logger.clone());
 
+	if std::env::args().nth(2).as_deref() == Some("reset-2fa") {
+		return reset_2fa(users, &settings, std::env::args().nth(3), logger);
+	}
+	if std::env::args().nth(2).as_deref() == Some("convert-users") {
+		return convert_users(users, &settings, std::env::args().nth(3), logger);
+	}
+
 	logger
 		.lock()
 		.unwrap()
 		.push(vec![], Some("*CONSOLE_WHITESPACE*"));
 
-	let database = pontus_onyx::http_server::load_or_create_database(&settings, logger.clone());
+	let metrics = Arc::new(Mutex::new(pontus_onyx::http_server::Metrics::new()));
+
+	let database = pontus_onyx::http_server::load_or_create_database(
+		&settings,
+		metrics.clone(),
+		logger.clone(),
+	);
 
 	logger
 		.lock()
@@ -94,15 +111,22 @@ async fn main() -> std::io::Result<()> {
 	let oauth_form_tokens: Arc<Mutex<Vec<pontus_onyx::http_server::middlewares::OauthFormToken>>> =
 		Arc::new(Mutex::new(vec![]));
 
+	let sessions: Arc<Mutex<Vec<pontus_onyx::http_server::Session>>> = Arc::new(Mutex::new(vec![]));
+
+	let login_attempts = Arc::new(Mutex::new(pontus_onyx::http_server::LoginAttempts::new()));
+
 	let access_tokens: Arc<Mutex<Vec<pontus_onyx::http_server::AccessBearer>>> =
 		Arc::new(Mutex::new(vec![]));
 
-	logger.lock().unwrap().push(
-		vec![
-			(String::from("event"), String::from("setup")),
-			(String::from("level"), String::from("INFO")),
-		],
-		Some("starting servers"),
+	let authorization_codes: Arc<Mutex<Vec<pontus_onyx::http_server::AuthorizationCode>>> =
+		Arc::new(Mutex::new(vec![]));
+
+	pontus_onyx::http_server::logging::log(
+		&logger,
+		pontus_onyx::http_server::logging::Level::Info,
+		"setup",
+		&[],
+		"starting servers",
 	);
 
 	logger
@@ -111,6 +135,7 @@ async fn main() -> std::io::Result<()> {
 		.push(vec![], Some("*CONSOLE_WHITESPACE*"));
 
 	let workspace_path_for_event_loop = workspace_path.clone();
+	let metrics_for_event_loop = metrics.clone();
 	let (history_sender, history_receiver) =
 		std::sync::mpsc::channel::<pontus_onyx::http_server::DbEvent>();
 
@@ -127,6 +152,10 @@ async fn main() -> std::io::Result<()> {
 				row += ",\n";
 				event_file.write_all(row.as_bytes()).unwrap();
 				event_file.flush().unwrap();
+				metrics_for_event_loop
+					.lock()
+					.unwrap()
+					.record_event_written();
 			}
 		}
 	});
@@ -135,14 +164,71 @@ async fn main() -> std::io::Result<()> {
 		settings.clone(),
 		database.clone(),
 		access_tokens.clone(),
+		authorization_codes.clone(),
 		oauth_form_tokens.clone(),
+		sessions.clone(),
+		login_attempts.clone(),
 		users.clone(),
 		program_state.clone(),
+		metrics.clone(),
 		logger.clone(),
 		&workspace_path,
 		Some(history_sender.clone()),
 	);
 
+	pontus_onyx::http_server::spawn_reloader(
+		settings.clone(),
+		users.clone(),
+		program_state.clone(),
+		logger.clone(),
+	);
+
+	let metrics_admin_port = settings
+		.lock()
+		.unwrap()
+		.metrics
+		.as_ref()
+		.and_then(|metrics| metrics.admin_port);
+	if let Some(admin_port) = metrics_admin_port {
+		let settings_for_metrics = settings.clone();
+		let access_tokens_for_metrics = access_tokens.clone();
+		let metrics_for_server = metrics.clone();
+		let metrics_binding = actix_web::HttpServer::new(move || {
+			actix_web::App::new().configure(pontus_onyx::http_server::configure_metrics_server(
+				settings_for_metrics.clone(),
+				access_tokens_for_metrics.clone(),
+				metrics_for_server.clone(),
+			))
+		})
+		.workers(1)
+		.bind(format!("127.0.0.1:{admin_port}"));
+
+		match metrics_binding {
+			Ok(binding) => {
+				actix_web::rt::spawn(binding.run());
+
+				pontus_onyx::http_server::logging::log(
+					&logger,
+					pontus_onyx::http_server::logging::Level::Info,
+					"setup",
+					&[("module", "metrics")],
+					&format!(
+						"metrics should now be served on http://127.0.0.1:{admin_port}/metrics"
+					),
+				);
+			}
+			Err(e) => {
+				pontus_onyx::http_server::logging::log(
+					&logger,
+					pontus_onyx::http_server::logging::Level::Error,
+					"setup",
+					&[("module", "metrics")],
+					&format!("can not set up metrics server : {}", e),
+				);
+			}
+		}
+	}
+
 	if !program_state.lock().unwrap().https_mode {
 		println!();
 		println!("\t⚠ Falling back onto HTTP mode");
@@ -157,13 +243,12 @@ async fn main() -> std::io::Result<()> {
 
 	let http_port = settings.lock().unwrap().port;
 
-	logger.lock().unwrap().push(
-		vec![
-			(String::from("event"), String::from("setup")),
-			(String::from("module"), String::from("http")),
-			(String::from("level"), String::from("INFO")),
-		],
-		Some(&format!(
+	pontus_onyx::http_server::logging::log(
+		&logger,
+		pontus_onyx::http_server::logging::Level::Info,
+		"setup",
+		&[("module", "http")],
+		&format!(
 			"API should now listen to http://{}:{http_port}/",
 			settings
 				.lock()
@@ -171,7 +256,7 @@ async fn main() -> std::io::Result<()> {
 				.domain
 				.as_ref()
 				.unwrap_or_else(|| &localhost)
-		)),
+		),
 	);
 
 	logger
@@ -205,12 +290,12 @@ async fn main() -> std::io::Result<()> {
 		handles
 	};
 
-	logger.lock().unwrap().push(
-		vec![
-			(String::from("event"), String::from("startup")),
-			(String::from("level"), String::from("INFO")),
-		],
-		Some(&format!("Available handles are : {handles}",)),
+	pontus_onyx::http_server::logging::log(
+		&logger,
+		pontus_onyx::http_server::logging::Level::Info,
+		"startup",
+		&[],
+		&format!("Available handles are : {handles}",),
 	);
 
 	logger
@@ -241,6 +326,7 @@ async fn main() -> std::io::Result<()> {
 			.wrap(pontus_onyx::http_server::middlewares::Auth {
 				logger: logger_for_server.clone(),
 			})
+			.wrap(pontus_onyx::http_server::middlewares::Cors)
 			.wrap(pontus_onyx::http_server::middlewares::Logger {
 				logger: logger_for_server.clone(),
 			})
@@ -248,9 +334,13 @@ async fn main() -> std::io::Result<()> {
 				settings.clone(),
 				database.clone(),
 				access_tokens.clone(),
+				authorization_codes.clone(),
 				oauth_form_tokens.clone(),
+				sessions.clone(),
+				login_attempts.clone(),
 				users.clone(),
 				program_state.clone(),
+				metrics.clone(),
 				logger_for_server.clone(),
 				&workspace_path,
 				Some(history_sender.clone()),
@@ -261,13 +351,12 @@ async fn main() -> std::io::Result<()> {
 	match http_binding {
 		Ok(binding) => binding.run().await,
 		Err(e) => {
-			logger.lock().unwrap().push(
-				vec![
-					(String::from("event"), String::from("setup")),
-					(String::from("module"), String::from("http")),
-					(String::from("level"), String::from("ERROR")),
-				],
-				Some(&format!("can not set up HTTP server : {}", e)),
+			pontus_onyx::http_server::logging::log(
+				&logger,
+				pontus_onyx::http_server::logging::Level::Error,
+				"setup",
+				&[("module", "http")],
+				&format!("can not set up HTTP server : {}", e),
 			);
 
 			Err(e)
@@ -275,6 +364,303 @@ async fn main() -> std::io::Result<()> {
 	}
 }
 
+/// Disables the two-factor authentication of an user who lost its authenticator application and
+/// its recovery codes.
+fn reset_2fa(
+	mut users: pontus_onyx::http_server::Users,
+	settings: &pontus_onyx::http_server::Settings,
+	username: Option<String>,
+	logger: Arc<Mutex<charlie_buffalo::Logger>>,
+) -> std::io::Result<()> {
+	let username = match username {
+		Some(username) => username,
+		None => {
+			return Err(std::io::Error::new(
+				std::io::ErrorKind::InvalidInput,
+				"usage : pontus_onyx_cli <workspace> reset-2fa <username>",
+			));
+		}
+	};
+
+	if let Err(e) = users
+		.reset_totp(&username)
+		.map_err(|e| e.to_string())
+		.and_then(|_| users.save(&settings.userfile_path()))
+	{
+		return Err(std::io::Error::other(format!(
+			"can not reset two-factor authentication of `{username}` : {e}"
+		)));
+	}
+
+	pontus_onyx::http_server::logging::log(
+		&logger,
+		pontus_onyx::http_server::logging::Level::Info,
+		"reset_2fa",
+		&[("username", &username)],
+		&format!("two-factor authentication of `{username}` is disabled"),
+	);
+
+	return Ok(());
+}
+
+/// Writes the users file in another format (like TOML, to edit it by hand), and keeps the
+/// previous one as a `.bak` file.
+fn convert_users(
+	users: pontus_onyx::http_server::Users,
+	settings: &pontus_onyx::http_server::Settings,
+	format: Option<String>,
+	logger: Arc<Mutex<charlie_buffalo::Logger>>,
+) -> std::io::Result<()> {
+	let format: pontus_onyx::http_server::UsersFileFormat = match format {
+		Some(format) => format
+			.parse()
+			.map_err(|e| std::io::Error::new(std::io::ErrorKind::InvalidInput, e))?,
+		None => {
+			return Err(std::io::Error::new(
+				std::io::ErrorKind::InvalidInput,
+				"usage : pontus_onyx_cli <workspace> convert-users <toml|json|bin>",
+			));
+		}
+	};
+
+	let previous_path = settings.userfile_path();
+	let new_path = settings
+		.workspace_path
+		.join(format!("users.{}", format.extension()));
+
+	if new_path == previous_path {
+		return Err(std::io::Error::new(
+			std::io::ErrorKind::InvalidInput,
+			format!(
+				"users file `{}` is already in this format",
+				previous_path.to_string_lossy()
+			),
+		));
+	}
+
+	users.save(&new_path).map_err(|e| {
+		std::io::Error::other(format!(
+			"can not write `{}` : {e}",
+			new_path.to_string_lossy()
+		))
+	})?;
+
+	if previous_path.exists() {
+		let mut backup_path = previous_path.clone().into_os_string();
+		backup_path.push(".bak");
+		std::fs::rename(&previous_path, &backup_path)?;
+	}
+
+	pontus_onyx::http_server::logging::log(
+		&logger,
+		pontus_onyx::http_server::logging::Level::Info,
+		"convert_users",
+		&[],
+		&format!(
+			"users file converted from `{}` to `{}`",
+			previous_path.to_string_lossy(),
+			new_path.to_string_lossy()
+		),
+	);
+
+	return Ok(());
+}
+
+const LOGS_USAGE: &str = "usage : pontus_onyx_cli <workspace> logs [--level <DEBUG|INFO|WARNING|ERROR>] [--event <event>] [--module <module>] [--since <RFC 3339 date>] [--until <RFC 3339 date>] [--client-ip <ip>] [--follow]";
+
+/// Prints logs of the `logs.msgpack` file and of its rotated files which match filters of
+/// arguments, then the new ones while they are written if `--follow` is set.
+fn show_logs(
+	settings: &pontus_onyx::http_server::Settings,
+	args: Vec<String>,
+) -> std::io::Result<()> {
+	let usage_error = |message: String| {
+		std::io::Error::new(
+			std::io::ErrorKind::InvalidInput,
+			format!("{message}\n{LOGS_USAGE}"),
+		)
+	};
+
+	let mut filter = pontus_onyx::http_server::logging::LogFilter::default();
+	let mut follow = false;
+
+	let mut args = args.into_iter();
+	while let Some(arg) = args.next() {
+		if arg == "--follow" {
+			follow = true;
+			continue;
+		}
+
+		let value = args
+			.next()
+			.ok_or_else(|| usage_error(format!("missing value of `{arg}`")))?;
+		let parse_time = |value: &str| {
+			pontus_onyx::http_server::logging::parse_log_time(value)
+				.ok_or_else(|| usage_error(format!("`{value}` is not a RFC 3339 date")))
+		};
+
+		match arg.as_str() {
+			"--level" => filter.level = Some(value),
+			"--event" => filter.event = Some(value),
+			"--module" => filter.module = Some(value),
+			"--since" => filter.since = Some(parse_time(&value)?),
+			"--until" => filter.until = Some(parse_time(&value)?),
+			"--client-ip" => filter.client_ip = Some(value),
+			_ => return Err(usage_error(format!("unknown argument `{arg}`"))),
+		}
+	}
+
+	let logfile_path = settings.logfile_path();
+	// the current log file is kept open, in order to read its next logs even after a rotation
+	let mut logfile = std::fs::File::open(&logfile_path).ok();
+	let mut identity = logfile.as_ref().and_then(file_identity);
+	// read bytes of the current log file, where its next logs will be
+	let mut position = 0;
+
+	for path in pontus_onyx::http_server::logging::log_files(&logfile_path) {
+		if path == logfile_path {
+			if let Some(file) = &mut logfile {
+				read_new_logs(file, &mut position, &filter).map_err(|e| {
+					std::io::Error::other(format!(
+						"can not read `{}` : {e}",
+						path.to_string_lossy()
+					))
+				})?;
+			}
+			continue;
+		}
+
+		let bytes = match std::fs::read(&path) {
+			Ok(bytes) => bytes,
+			Err(e) if e.kind() == std::io::ErrorKind::NotFound => vec![],
+			Err(e) => {
+				return Err(std::io::Error::other(format!(
+					"can not read `{}` : {e}",
+					path.to_string_lossy()
+				)))
+			}
+		};
+
+		let (logs, _) = pontus_onyx::http_server::logging::decode_logs(&bytes);
+		for log in logs {
+			if filter.matches(&log) {
+				println!("{}", format_log(&log));
+			}
+		}
+	}
+
+	if follow {
+		loop {
+			std::thread::sleep(std::time::Duration::from_millis(500));
+
+			if let Some(file) = &mut logfile {
+				// truncated in place
+				if file
+					.metadata()
+					.map(|metadata| metadata.len() < position as u64)
+					.unwrap_or(false)
+				{
+					position = 0;
+				}
+
+				// also reads the last logs of a rotated file, before switching to the new one
+				if read_new_logs(file, &mut position, &filter).is_err() {
+					continue;
+				}
+			}
+
+			let current = std::fs::File::open(&logfile_path).ok();
+			let current_identity = current.as_ref().and_then(file_identity);
+			if current_identity != identity {
+				logfile = current;
+				identity = current_identity;
+				position = 0;
+
+				if let Some(file) = &mut logfile {
+					read_new_logs(file, &mut position, &filter).ok();
+				}
+			}
+		}
+	}
+
+	return Ok(());
+}
+
+/// Prints logs of `file` written after `position`, which is moved after them.
+///
+/// The last log can be incomplete, so it is decoded on next reads.
+fn read_new_logs(
+	file: &mut std::fs::File,
+	position: &mut usize,
+	filter: &pontus_onyx::http_server::logging::LogFilter,
+) -> std::io::Result<()> {
+	std::io::Seek::seek(file, std::io::SeekFrom::Start(*position as u64))?;
+
+	let mut bytes = vec![];
+	std::io::Read::read_to_end(file, &mut bytes)?;
+
+	let (logs, read_bytes) = pontus_onyx::http_server::logging::decode_logs(&bytes);
+	for log in logs {
+		if filter.matches(&log) {
+			println!("{}", format_log(&log));
+		}
+	}
+	*position += read_bytes;
+
+	return Ok(());
+}
+
+#[cfg(unix)]
+fn file_identity(file: &std::fs::File) -> Option<(u64, u64)> {
+	use std::os::unix::fs::MetadataExt;
+
+	return file
+		.metadata()
+		.ok()
+		.map(|metadata| (metadata.dev(), metadata.ino()));
+}
+
+#[cfg(not(unix))]
+fn file_identity(file: &std::fs::File) -> Option<std::time::SystemTime> {
+	return file
+		.metadata()
+		.ok()
+		.and_then(|metadata| metadata.created().ok());
+}
+
+fn format_log(log: &charlie_buffalo::Log) -> String {
+	let empty = String::new();
+	let attributes = log
+		.attributes
+		.iter()
+		.filter(|(key, _)| !["time", "level", "module", "event"].contains(&key.as_str()))
+		.map(|(key, value)| {
+			if value.is_empty() {
+				key.clone()
+			} else {
+				format!("{key}={value:?}")
+			}
+		})
+		.collect::<Vec<String>>()
+		.join(" ");
+
+	return format!(
+		"[{}] [{}] [{}] [{}]\t{}\t{}",
+		log.attributes
+			.get("time")
+			.unwrap_or(&empty)
+			.trim_matches('"'),
+		log.attributes.get("level").unwrap_or(&empty),
+		log.attributes
+			.get("module")
+			.unwrap_or(&empty)
+			.to_uppercase(),
+		log.attributes.get("event").unwrap_or(&empty),
+		log.content.as_deref().unwrap_or_default(),
+		attributes
+	);
+}
+
 /*
 TODO ?
 	Servers MAY support Content-Range headers [RANGE] on GET requests,
@@ -96,7 +96,6 @@ struct JsonResponse {
 
 #[cfg(feature = "actix_server")]
 pub fn build_http_json_response(
-	origin: &str,
 	request_method: &actix_web::http::Method,
 	code: actix_web::http::StatusCode,
 	etag: Option<crate::item::Etag>,
@@ -109,16 +108,6 @@ pub fn build_http_json_response(
 	if request_method == actix_web::http::Method::GET && code.is_success() {
 		response.insert_header((actix_web::http::header::CACHE_CONTROL, "no-cache"));
 	}
-	response.insert_header((actix_web::http::header::ACCESS_CONTROL_ALLOW_ORIGIN, origin));
-
-	if origin != "*" {
-		response.insert_header((actix_web::http::header::VARY, "Origin"));
-	}
-
-	response.insert_header((
-		actix_web::http::header::ACCESS_CONTROL_EXPOSE_HEADERS,
-		"Content-Length, Content-Type, ETag, Last-Modified",
-	));
 
 	if let Some(etag) = &etag {
 		let etag: String = (*etag).clone().into();
@@ -150,5 +139,5 @@ pub fn build_http_json_response(
 
 #[cfg(feature = "server")]
 pub trait Error: std::fmt::Debug + std::error::Error {
-	fn to_response(&self, origin: &str, should_have_body: bool) -> actix_web::HttpResponse;
+	fn to_response(&self, should_have_body: bool) -> actix_web::HttpResponse;
 }
@@ -54,14 +54,13 @@ impl std::fmt::Display for DeleteError {
 impl std::error::Error for DeleteError {}
 #[cfg(feature = "server")]
 impl crate::database::Error for DeleteError {
-	fn to_response(&self, origin: &str, should_have_body: bool) -> actix_web::HttpResponse {
+	fn to_response(&self, should_have_body: bool) -> actix_web::HttpResponse {
 		match self {
 			// TODO : we have to find a way to change method
 			Self::GetError(get_error) => {
-				crate::database::Error::to_response(get_error, origin, should_have_body)
+				crate::database::Error::to_response(get_error, should_have_body)
 			}
 			Self::DoesNotWorksForFolders => crate::database::build_http_json_response(
-				origin,
 				&actix_web::http::Method::PUT,
 				actix_web::http::StatusCode::BAD_REQUEST,
 				None,
@@ -73,7 +72,6 @@ impl crate::database::Error for DeleteError {
 				os_path: _,
 				error: _,
 			} => crate::database::build_http_json_response(
-				origin,
 				&actix_web::http::Method::PUT,
 				actix_web::http::StatusCode::INTERNAL_SERVER_ERROR,
 				None,
@@ -85,7 +83,6 @@ impl crate::database::Error for DeleteError {
 				os_path: _,
 				error: _,
 			} => crate::database::build_http_json_response(
-				origin,
 				&actix_web::http::Method::PUT,
 				actix_web::http::StatusCode::INTERNAL_SERVER_ERROR,
 				None,
@@ -97,7 +94,6 @@ impl crate::database::Error for DeleteError {
 				os_path: _,
 				error: _,
 			} => crate::database::build_http_json_response(
-				origin,
 				&actix_web::http::Method::PUT,
 				actix_web::http::StatusCode::INTERNAL_SERVER_ERROR,
 				None,
@@ -109,7 +105,6 @@ impl crate::database::Error for DeleteError {
 				os_path: _,
 				error: _,
 			} => crate::database::build_http_json_response(
-				origin,
 				&actix_web::http::Method::PUT,
 				actix_web::http::StatusCode::INTERNAL_SERVER_ERROR,
 				None,
@@ -121,7 +116,6 @@ impl crate::database::Error for DeleteError {
 				os_path: _,
 				error: _,
 			} => crate::database::build_http_json_response(
-				origin,
 				&actix_web::http::Method::PUT,
 				actix_web::http::StatusCode::INTERNAL_SERVER_ERROR,
 				None,
@@ -55,12 +55,11 @@ impl std::fmt::Display for GetError {
 impl std::error::Error for GetError {}
 #[cfg(feature = "server")]
 impl crate::database::Error for GetError {
-	fn to_response(&self, origin: &str, should_have_body: bool) -> actix_web::HttpResponse {
+	fn to_response(&self, should_have_body: bool) -> actix_web::HttpResponse {
 		match self {
 			Self::Conflict { item_path } => {
 				if item_path.starts_with("public/") {
 					crate::database::build_http_json_response(
-						origin,
 						&actix_web::http::Method::GET,
 						actix_web::http::StatusCode::NOT_FOUND,
 						None,
@@ -70,7 +69,6 @@ impl crate::database::Error for GetError {
 					)
 				} else {
 					crate::database::build_http_json_response(
-						origin,
 						&actix_web::http::Method::GET,
 						actix_web::http::StatusCode::CONFLICT,
 						None,
@@ -81,7 +79,6 @@ impl crate::database::Error for GetError {
 				}
 			}
 			Self::NotFound { item_path: _ } => crate::database::build_http_json_response(
-				origin,
 				&actix_web::http::Method::GET,
 				actix_web::http::StatusCode::NOT_FOUND,
 				None,
@@ -93,7 +90,6 @@ impl crate::database::Error for GetError {
 				item_path: _,
 				error: _,
 			} => crate::database::build_http_json_response(
-				origin,
 				&actix_web::http::Method::GET,
 				actix_web::http::StatusCode::BAD_REQUEST,
 				None,
@@ -102,7 +98,6 @@ impl crate::database::Error for GetError {
 				should_have_body,
 			),
 			Self::CanNotBeListed { item_path } => crate::database::build_http_json_response(
-				origin,
 				&actix_web::http::Method::GET,
 				actix_web::http::StatusCode::NOT_FOUND,
 				None,
@@ -115,7 +110,6 @@ impl crate::database::Error for GetError {
 				search: _,
 				found: _,
 			} => crate::database::build_http_json_response(
-				origin,
 				&actix_web::http::Method::GET,
 				actix_web::http::StatusCode::PRECONDITION_FAILED,
 				None,
@@ -128,7 +122,6 @@ impl crate::database::Error for GetError {
 				search: _,
 				found: _,
 			} => crate::database::build_http_json_response(
-				origin,
 				&actix_web::http::Method::GET,
 				actix_web::http::StatusCode::PRECONDITION_FAILED,
 				None,
@@ -140,7 +133,6 @@ impl crate::database::Error for GetError {
 				os_path: _,
 				error: _,
 			} => crate::database::build_http_json_response(
-				origin,
 				&actix_web::http::Method::GET,
 				actix_web::http::StatusCode::INTERNAL_SERVER_ERROR,
 				None,
@@ -152,7 +144,6 @@ impl crate::database::Error for GetError {
 				os_path: _,
 				error: _,
 			} => crate::database::build_http_json_response(
-				origin,
 				&actix_web::http::Method::GET,
 				actix_web::http::StatusCode::INTERNAL_SERVER_ERROR,
 				None,
@@ -161,7 +152,6 @@ impl crate::database::Error for GetError {
 				should_have_body,
 			),
 			Self::IOError { error: _ } => crate::database::build_http_json_response(
-				origin,
 				&actix_web::http::Method::GET,
 				actix_web::http::StatusCode::INTERNAL_SERVER_ERROR,
 				None,
@@ -170,7 +160,6 @@ impl crate::database::Error for GetError {
 				should_have_body,
 			),
 			Self::IsSystemFile => crate::database::build_http_json_response(
-				origin,
 				&actix_web::http::Method::GET,
 				actix_web::http::StatusCode::BAD_REQUEST,
 				None,
@@ -50,14 +50,13 @@ impl std::fmt::Display for PutError {
 impl std::error::Error for PutError {}
 #[cfg(feature = "server")]
 impl crate::database::Error for PutError {
-	fn to_response(&self, origin: &str, should_have_body: bool) -> actix_web::HttpResponse {
+	fn to_response(&self, should_have_body: bool) -> actix_web::HttpResponse {
 		match self {
 			// TODO : we have to find a way to change method
 			Self::GetError(get_error) => {
-				crate::database::Error::to_response(get_error, origin, should_have_body)
+				crate::database::Error::to_response(get_error, should_have_body)
 			}
 			Self::DoesNotWorksForFolders => crate::database::build_http_json_response(
-				origin,
 				&actix_web::http::Method::PUT,
 				actix_web::http::StatusCode::BAD_REQUEST,
 				None,
@@ -66,7 +65,6 @@ impl crate::database::Error for PutError {
 				should_have_body,
 			),
 			Self::ContentNotChanged => crate::database::build_http_json_response(
-				origin,
 				&actix_web::http::Method::PUT,
 				actix_web::http::StatusCode::NOT_MODIFIED,
 				None,
@@ -78,7 +76,6 @@ impl crate::database::Error for PutError {
 				os_path: _,
 				error: _,
 			} => crate::database::build_http_json_response(
-				origin,
 				&actix_web::http::Method::PUT,
 				actix_web::http::StatusCode::INTERNAL_SERVER_ERROR,
 				None,
@@ -90,7 +87,6 @@ impl crate::database::Error for PutError {
 				os_path: _,
 				error: _,
 			} => crate::database::build_http_json_response(
-				origin,
 				&actix_web::http::Method::PUT,
 				actix_web::http::StatusCode::INTERNAL_SERVER_ERROR,
 				None,
@@ -102,7 +98,6 @@ impl crate::database::Error for PutError {
 				os_path: _,
 				error: _,
 			} => crate::database::build_http_json_response(
-				origin,
 				&actix_web::http::Method::PUT,
 				actix_web::http::StatusCode::INTERNAL_SERVER_ERROR,
 				None,
@@ -114,7 +109,6 @@ impl crate::database::Error for PutError {
 				os_path: _,
 				error: _,
 			} => crate::database::build_http_json_response(
-				origin,
 				&actix_web::http::Method::PUT,
 				actix_web::http::StatusCode::INTERNAL_SERVER_ERROR,
 				None,
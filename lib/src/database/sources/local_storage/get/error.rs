@@ -48,12 +48,11 @@ impl std::fmt::Display for GetError {
 impl std::error::Error for GetError {}
 #[cfg(feature = "server")]
 impl crate::database::Error for GetError {
-	fn to_response(&self, origin: &str, should_have_body: bool) -> actix_web::HttpResponse {
+	fn to_response(&self, should_have_body: bool) -> actix_web::HttpResponse {
 		match self {
 			Self::Conflict { item_path } => {
 				if item_path.starts_with("public/") {
 					crate::database::build_http_json_response(
-						origin,
 						&actix_web::http::Method::GET,
 						actix_web::http::StatusCode::NOT_FOUND,
 						None,
@@ -63,7 +62,6 @@ impl crate::database::Error for GetError {
 					)
 				} else {
 					crate::database::build_http_json_response(
-						origin,
 						&actix_web::http::Method::GET,
 						actix_web::http::StatusCode::CONFLICT,
 						None,
@@ -74,7 +72,6 @@ impl crate::database::Error for GetError {
 				}
 			}
 			Self::NotFound { .. } => crate::database::build_http_json_response(
-				origin,
 				&actix_web::http::Method::GET,
 				actix_web::http::StatusCode::NOT_FOUND,
 				None,
@@ -83,7 +80,6 @@ impl crate::database::Error for GetError {
 				should_have_body,
 			),
 			Self::IncorrectItemName { .. } => crate::database::build_http_json_response(
-				origin,
 				&actix_web::http::Method::GET,
 				actix_web::http::StatusCode::BAD_REQUEST,
 				None,
@@ -92,7 +88,6 @@ impl crate::database::Error for GetError {
 				should_have_body,
 			),
 			Self::CanNotBeListed { item_path } => crate::database::build_http_json_response(
-				origin,
 				&actix_web::http::Method::GET,
 				actix_web::http::StatusCode::NOT_FOUND,
 				None,
@@ -101,7 +96,6 @@ impl crate::database::Error for GetError {
 				should_have_body,
 			),
 			Self::NoIfMatch { .. } => crate::database::build_http_json_response(
-				origin,
 				&actix_web::http::Method::GET,
 				actix_web::http::StatusCode::PRECONDITION_FAILED,
 				None,
@@ -110,7 +104,6 @@ impl crate::database::Error for GetError {
 				should_have_body,
 			),
 			Self::IfNoneMatch { .. } => crate::database::build_http_json_response(
-				origin,
 				&actix_web::http::Method::GET,
 				actix_web::http::StatusCode::PRECONDITION_FAILED,
 				None,
@@ -119,7 +112,6 @@ impl crate::database::Error for GetError {
 				should_have_body,
 			),
 			Self::CanNotGetStorage => crate::database::build_http_json_response(
-				origin,
 				&actix_web::http::Method::GET,
 				actix_web::http::StatusCode::INTERNAL_SERVER_ERROR,
 				None,
@@ -128,7 +120,6 @@ impl crate::database::Error for GetError {
 				should_have_body,
 			),
 			Self::CanNotSerializeFile { .. } => crate::database::build_http_json_response(
-				origin,
 				&actix_web::http::Method::GET,
 				actix_web::http::StatusCode::INTERNAL_SERVER_ERROR,
 				None,
@@ -137,7 +128,6 @@ impl crate::database::Error for GetError {
 				should_have_body,
 			),
 			Self::IsSystemFile => crate::database::build_http_json_response(
-				origin,
 				&actix_web::http::Method::GET,
 				actix_web::http::StatusCode::BAD_REQUEST,
 				None,
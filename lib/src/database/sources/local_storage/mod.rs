@@ -144,10 +144,9 @@ impl std::error::Error for LocalStorageError {}
 #[cfg(feature = "server")]
 impl crate::database::Error for LocalStorageError {
 	// TODO : we have to find a way to change method
-	fn to_response(&self, origin: &str, should_have_body: bool) -> actix_web::HttpResponse {
+	fn to_response(&self, should_have_body: bool) -> actix_web::HttpResponse {
 		match self {
 			Self::CanNotGetWindow => crate::database::build_http_json_response(
-				origin,
 				&actix_web::http::Method::GET,
 				actix_web::http::StatusCode::INTERNAL_SERVER_ERROR,
 				None,
@@ -156,7 +155,6 @@ impl crate::database::Error for LocalStorageError {
 				should_have_body,
 			),
 			Self::CanNotGetLocalStorage => crate::database::build_http_json_response(
-				origin,
 				&actix_web::http::Method::GET,
 				actix_web::http::StatusCode::INTERNAL_SERVER_ERROR,
 				None,
@@ -165,7 +163,6 @@ impl crate::database::Error for LocalStorageError {
 				should_have_body,
 			),
 			Self::ThereIsNoLocalStorage => crate::database::build_http_json_response(
-				origin,
 				&actix_web::http::Method::GET,
 				actix_web::http::StatusCode::INTERNAL_SERVER_ERROR,
 				None,
@@ -40,14 +40,13 @@ impl std::fmt::Display for PutError {
 impl std::error::Error for PutError {}
 #[cfg(feature = "server")]
 impl crate::database::Error for PutError {
-	fn to_response(&self, origin: &str, should_have_body: bool) -> actix_web::HttpResponse {
+	fn to_response(&self, should_have_body: bool) -> actix_web::HttpResponse {
 		match self {
 			// TODO : we have to find a way to change method
 			Self::GetError(get_error) => {
-				crate::database::Error::to_response(get_error, origin, should_have_body)
+				crate::database::Error::to_response(get_error, should_have_body)
 			}
 			Self::DoesNotWorksForFolders => crate::database::build_http_json_response(
-				origin,
 				&actix_web::http::Method::PUT,
 				actix_web::http::StatusCode::BAD_REQUEST,
 				None,
@@ -56,7 +55,6 @@ impl crate::database::Error for PutError {
 				should_have_body,
 			),
 			Self::ContentNotChanged => crate::database::build_http_json_response(
-				origin,
 				&actix_web::http::Method::PUT,
 				actix_web::http::StatusCode::NOT_MODIFIED,
 				None,
@@ -65,7 +63,6 @@ impl crate::database::Error for PutError {
 				should_have_body,
 			),
 			Self::CanNotSerializeFile { .. } => crate::database::build_http_json_response(
-				origin,
 				&actix_web::http::Method::PUT,
 				actix_web::http::StatusCode::INTERNAL_SERVER_ERROR,
 				None,
@@ -74,7 +71,6 @@ impl crate::database::Error for PutError {
 				should_have_body,
 			),
 			Self::CanNotDeserializeFile { .. } => crate::database::build_http_json_response(
-				origin,
 				&actix_web::http::Method::PUT,
 				actix_web::http::StatusCode::INTERNAL_SERVER_ERROR,
 				None,
@@ -83,7 +79,6 @@ impl crate::database::Error for PutError {
 				should_have_body,
 			),
 			Self::NoContentInside { .. } => crate::database::build_http_json_response(
-				origin,
 				&actix_web::http::Method::PUT,
 				actix_web::http::StatusCode::INTERNAL_SERVER_ERROR,
 				None,
@@ -92,7 +87,6 @@ impl crate::database::Error for PutError {
 				should_have_body,
 			),
 			Self::InternalError => crate::database::build_http_json_response(
-				origin,
 				&actix_web::http::Method::PUT,
 				actix_web::http::StatusCode::INTERNAL_SERVER_ERROR,
 				None,
@@ -35,10 +35,9 @@ impl std::fmt::Display for DeleteError {
 impl std::error::Error for DeleteError {}
 #[cfg(feature = "server")]
 impl crate::database::Error for DeleteError {
-	fn to_response(&self, origin: &str, should_have_body: bool) -> actix_web::HttpResponse {
+	fn to_response(&self, should_have_body: bool) -> actix_web::HttpResponse {
 		match self {
 			Self::Conflict { item_path: _ } => crate::database::build_http_json_response(
-				origin,
 				&actix_web::http::Method::DELETE,
 				actix_web::http::StatusCode::CONFLICT,
 				None,
@@ -47,7 +46,6 @@ impl crate::database::Error for DeleteError {
 				should_have_body,
 			),
 			Self::DoesNotWorksForFolders => crate::database::build_http_json_response(
-				origin,
 				&actix_web::http::Method::DELETE,
 				actix_web::http::StatusCode::BAD_REQUEST,
 				None,
@@ -56,7 +54,6 @@ impl crate::database::Error for DeleteError {
 				should_have_body,
 			),
 			Self::NotFound { item_path: _ } => crate::database::build_http_json_response(
-				origin,
 				&actix_web::http::Method::DELETE,
 				actix_web::http::StatusCode::NOT_FOUND,
 				None,
@@ -65,7 +62,6 @@ impl crate::database::Error for DeleteError {
 				should_have_body,
 			),
 			Self::NoContentInside { item_path: _ } => crate::database::build_http_json_response(
-				origin,
 				&actix_web::http::Method::DELETE,
 				actix_web::http::StatusCode::INTERNAL_SERVER_ERROR,
 				None,
@@ -77,7 +73,6 @@ impl crate::database::Error for DeleteError {
 				item_path: _,
 				error: _,
 			} => crate::database::build_http_json_response(
-				origin,
 				&actix_web::http::Method::DELETE,
 				actix_web::http::StatusCode::BAD_REQUEST,
 				None,
@@ -90,7 +85,6 @@ impl crate::database::Error for DeleteError {
 				search: _,
 				found: _,
 			} => crate::database::build_http_json_response(
-				origin,
 				&actix_web::http::Method::DELETE,
 				actix_web::http::StatusCode::PRECONDITION_FAILED,
 				None,
@@ -43,12 +43,11 @@ impl std::fmt::Display for GetError {
 impl std::error::Error for GetError {}
 #[cfg(feature = "server")]
 impl crate::database::Error for GetError {
-	fn to_response(&self, origin: &str, should_have_body: bool) -> actix_web::HttpResponse {
+	fn to_response(&self, should_have_body: bool) -> actix_web::HttpResponse {
 		match self {
 			Self::Conflict { item_path } => {
 				if item_path.starts_with("public/") {
 					crate::database::build_http_json_response(
-						origin,
 						&actix_web::http::Method::GET,
 						actix_web::http::StatusCode::NOT_FOUND,
 						None,
@@ -58,7 +57,6 @@ impl crate::database::Error for GetError {
 					)
 				} else {
 					crate::database::build_http_json_response(
-						origin,
 						&actix_web::http::Method::GET,
 						actix_web::http::StatusCode::CONFLICT,
 						None,
@@ -69,7 +67,6 @@ impl crate::database::Error for GetError {
 				}
 			}
 			Self::NotFound { item_path: _ } => crate::database::build_http_json_response(
-				origin,
 				&actix_web::http::Method::GET,
 				actix_web::http::StatusCode::NOT_FOUND,
 				None,
@@ -80,7 +77,6 @@ impl crate::database::Error for GetError {
 			Self::NoContentInside { item_path } => {
 				if item_path.starts_with("public/") {
 					crate::database::build_http_json_response(
-						origin,
 						&actix_web::http::Method::GET,
 						actix_web::http::StatusCode::NOT_FOUND,
 						None,
@@ -90,7 +86,6 @@ impl crate::database::Error for GetError {
 					)
 				} else {
 					crate::database::build_http_json_response(
-						origin,
 						&actix_web::http::Method::GET,
 						actix_web::http::StatusCode::INTERNAL_SERVER_ERROR,
 						None,
@@ -104,7 +99,6 @@ impl crate::database::Error for GetError {
 				item_path: _,
 				error: _,
 			} => crate::database::build_http_json_response(
-				origin,
 				&actix_web::http::Method::GET,
 				actix_web::http::StatusCode::BAD_REQUEST,
 				None,
@@ -113,7 +107,6 @@ impl crate::database::Error for GetError {
 				should_have_body,
 			),
 			Self::CanNotBeListed { item_path } => crate::database::build_http_json_response(
-				origin,
 				&actix_web::http::Method::GET,
 				actix_web::http::StatusCode::NOT_FOUND,
 				None,
@@ -126,7 +119,6 @@ impl crate::database::Error for GetError {
 				search: _,
 				found: _,
 			} => crate::database::build_http_json_response(
-				origin,
 				&actix_web::http::Method::GET,
 				actix_web::http::StatusCode::PRECONDITION_FAILED,
 				None,
@@ -139,7 +131,6 @@ impl crate::database::Error for GetError {
 				search: _,
 				found: _,
 			} => crate::database::build_http_json_response(
-				origin,
 				&actix_web::http::Method::GET,
 				actix_web::http::StatusCode::PRECONDITION_FAILED,
 				None,
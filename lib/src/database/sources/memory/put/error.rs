@@ -32,11 +32,10 @@ impl std::fmt::Display for PutError {
 impl std::error::Error for PutError {}
 #[cfg(feature = "server")]
 impl crate::database::Error for PutError {
-	fn to_response(&self, origin: &str, should_have_body: bool) -> actix_web::HttpResponse {
+	fn to_response(&self, should_have_body: bool) -> actix_web::HttpResponse {
 		match self {
-			Self::GetError(error) => error.to_response(origin, should_have_body),
+			Self::GetError(error) => error.to_response(should_have_body),
 			Self::NoContentInside { item_path: _ } => crate::database::build_http_json_response(
-				origin,
 				&actix_web::http::Method::PUT,
 				actix_web::http::StatusCode::INTERNAL_SERVER_ERROR,
 				None,
@@ -45,7 +44,6 @@ impl crate::database::Error for PutError {
 				should_have_body,
 			),
 			Self::DoesNotWorksForFolders => crate::database::build_http_json_response(
-				origin,
 				&actix_web::http::Method::PUT,
 				actix_web::http::StatusCode::BAD_REQUEST,
 				None,
@@ -54,7 +52,6 @@ impl crate::database::Error for PutError {
 				should_have_body,
 			),
 			Self::InternalError => crate::database::build_http_json_response(
-				origin,
 				&actix_web::http::Method::PUT,
 				actix_web::http::StatusCode::INTERNAL_SERVER_ERROR,
 				None,
@@ -63,7 +60,6 @@ impl crate::database::Error for PutError {
 				should_have_body,
 			),
 			Self::ContentNotChanged => crate::database::build_http_json_response(
-				origin,
 				&actix_web::http::Method::PUT,
 				actix_web::http::StatusCode::NOT_MODIFIED,
 				None,
@@ -75,7 +71,6 @@ impl crate::database::Error for PutError {
 				item_path: _,
 				error: _,
 			} => crate::database::build_http_json_response(
-				origin,
 				&actix_web::http::Method::PUT,
 				actix_web::http::StatusCode::INTERNAL_SERVER_ERROR,
 				None,
@@ -0,0 +1,870 @@
+use std::sync::{Arc, Mutex};
+
+#[cfg(test)]
+mod tests;
+
+#[derive(serde::Deserialize)]
+pub struct AccountGetQuery {
+	auth_result: Option<String>,
+}
+
+#[derive(serde::Deserialize)]
+pub struct AccountPostForm {
+	token: String,
+	action: String,
+	password: Option<String>,
+	new_password: Option<String>,
+	totp_code: Option<String>,
+	client_id: Option<String>,
+}
+
+#[allow(clippy::too_many_arguments)]
+#[actix_web::get("/account/{username}")]
+pub async fn get_account(
+	path: actix_web::web::Path<String>,
+	query: actix_web::web::Query<AccountGetQuery>,
+	request: actix_web::HttpRequest,
+	form_tokens: actix_web::web::Data<
+		Arc<Mutex<Vec<crate::http_server::middlewares::OauthFormToken>>>,
+	>,
+	access_tokens: actix_web::web::Data<Arc<Mutex<Vec<crate::http_server::AccessBearer>>>>,
+	sessions: actix_web::web::Data<Arc<Mutex<Vec<crate::http_server::Session>>>>,
+	users: actix_web::web::Data<Arc<Mutex<crate::http_server::Users>>>,
+	settings: actix_web::web::Data<Arc<Mutex<crate::http_server::Settings>>>,
+) -> actix_web::HttpResponse {
+	let username = path.into_inner();
+
+	let ip = match request.peer_addr() {
+		Some(ip) => ip,
+		None => return build_unknown_ip_page(),
+	};
+
+	let new_token = new_form_token(&form_tokens, ip);
+
+	if super::has_session(&request, &sessions, &username) {
+		return build_account_page(
+			&username,
+			&new_token,
+			&users.lock().unwrap(),
+			&access_tokens.lock().unwrap(),
+			&settings.lock().unwrap(),
+			"",
+		);
+	}
+
+	let message = match query.auth_result.as_deref() {
+		Some("wrong_credentials") => r#"<p class="error">Wrong credentials.</p>"#,
+		Some("security_issue") => {
+			r#"<p class="error">There is an security issue, please try again.</p>"#
+		}
+		Some("session_expired") => {
+			r#"<p class="error">Your session has expired, please log in again.</p>"#
+		}
+		Some("too_many_attempts") => {
+			r#"<p class="error">Too many failed attempts, please wait a moment before trying again.</p>"#
+		}
+		Some("account_locked") => {
+			r#"<p class="error">This account is temporarily locked after too many failed attempts.</p>"#
+		}
+		Some("logged_out") => r#"<p>You have been logged out.</p>"#,
+		_ => "",
+	};
+
+	return build_page(&format!(
+		r#"<h1>My account</h1>
+		<form method="post" action="/account/{}">
+			<input type="hidden" name="token" value="{}">
+			<input type="hidden" name="action" value="login">
+			<p>
+				Account : {}<br>
+				Password : <input type="password" name="password" value=""><br>
+				Two-factor code (if enabled) : <input type="text" name="totp_code" value="" autocomplete="one-time-code">
+			</p>{}
+			<input type="submit" value="Log in">
+		</form>"#,
+		pct_str::PctString::encode(username.chars(), pct_str::URIReserved),
+		crate::http_server::utils::escape_html(new_token.get_value()),
+		crate::http_server::utils::escape_html(&username),
+		message,
+	));
+}
+
+/// Every submission of the account page consumes its form token : the first one (`login`) needs
+/// the password of the user and opens its session, then other actions need the cookie of this
+/// session.
+#[allow(clippy::too_many_arguments)]
+#[actix_web::post("/account/{username}")]
+pub async fn post_account(
+	path: actix_web::web::Path<String>,
+	request: actix_web::HttpRequest,
+	form: actix_web::web::Form<AccountPostForm>,
+	form_tokens: actix_web::web::Data<
+		Arc<Mutex<Vec<crate::http_server::middlewares::OauthFormToken>>>,
+	>,
+	sessions: actix_web::web::Data<Arc<Mutex<Vec<crate::http_server::Session>>>>,
+	access_tokens: actix_web::web::Data<Arc<Mutex<Vec<crate::http_server::AccessBearer>>>>,
+	authorization_codes: actix_web::web::Data<
+		Arc<Mutex<Vec<crate::http_server::AuthorizationCode>>>,
+	>,
+	login_attempts: actix_web::web::Data<Arc<Mutex<crate::http_server::LoginAttempts>>>,
+	users: actix_web::web::Data<Arc<Mutex<crate::http_server::Users>>>,
+	settings: actix_web::web::Data<Arc<Mutex<crate::http_server::Settings>>>,
+	program_state: actix_web::web::Data<Arc<Mutex<crate::http_server::ProgramState>>>,
+	logger: actix_web::web::Data<Arc<Mutex<charlie_buffalo::Logger>>>,
+) -> actix_web::HttpResponse {
+	let username = path.into_inner();
+	let settings = settings.lock().unwrap().clone();
+
+	let ip = match request.peer_addr() {
+		Some(ip) => ip,
+		None => {
+			log(
+				&logger,
+				crate::http_server::logging::Level::Error,
+				&username,
+				"unknown IP address",
+			);
+
+			return build_unknown_ip_page();
+		}
+	};
+
+	let request_origin = request
+		.headers()
+		.get(actix_web::http::header::ORIGIN)
+		.and_then(|origin| origin.to_str().ok())
+		.and_then(crate::http_server::utils::origin_of);
+	let allowed_origins = crate::http_server::utils::server_origins(
+		&settings,
+		program_state.lock().unwrap().https_mode,
+	);
+	if !request_origin
+		.as_ref()
+		.map(|request_origin| allowed_origins.contains(request_origin))
+		.unwrap_or(false)
+	{
+		log(
+			&logger,
+			crate::http_server::logging::Level::Error,
+			&username,
+			&format!("wrong origin : {:?}", request_origin),
+		);
+
+		return redirect_to_login(&username, "security_issue");
+	}
+
+	let form_token = {
+		let mut form_tokens = form_tokens.lock().unwrap();
+		match form_tokens
+			.iter()
+			.position(|token| token.get_value() == form.token)
+		{
+			Some(position) => form_tokens.remove(position),
+			None => {
+				log(
+					&logger,
+					crate::http_server::logging::Level::Error,
+					&username,
+					"form token not found",
+				);
+
+				return redirect_to_login(&username, "security_issue");
+			}
+		}
+	};
+
+	if form_token.has_expirated() || !form_token.is_from(&ip) {
+		log(
+			&logger,
+			crate::http_server::logging::Level::Error,
+			&username,
+			"form token expired or issued for another IP address",
+		);
+
+		return redirect_to_login(&username, "security_issue");
+	}
+
+	let mut message = String::new();
+	let mut session_cookie = None;
+
+	if form.action == "login" {
+		if let Err(auth_result) = check_password(
+			&request,
+			&username,
+			form.password.as_deref().unwrap_or_default(),
+			Some(form.totp_code.as_deref().unwrap_or_default()),
+			&users,
+			&login_attempts,
+			&settings,
+			&logger,
+		)
+		.await
+		{
+			return redirect_to_login(&username, auth_result);
+		}
+
+		session_cookie = super::open_session(
+			&request,
+			&sessions,
+			&username,
+			program_state.lock().unwrap().https_mode || settings.force_https == Some(true),
+		);
+	} else {
+		if !super::has_session(&request, &sessions, &username) {
+			log(
+				&logger,
+				crate::http_server::logging::Level::Error,
+				&username,
+				"not logged in",
+			);
+
+			return redirect_to_login(
+				&username,
+				if request.cookie(super::SESSION_COOKIE_NAME).is_some() {
+					"session_expired"
+				} else {
+					"security_issue"
+				},
+			);
+		}
+
+		match form.action.as_str() {
+			"change_password" => {
+				let new_password = form.new_password.clone().unwrap_or_default();
+
+				if let Err(auth_result) = check_password(
+					&request,
+					&username,
+					form.password.as_deref().unwrap_or_default(),
+					None,
+					&users,
+					&login_attempts,
+					&settings,
+					&logger,
+				)
+				.await
+				{
+					if auth_result != "wrong_credentials" {
+						return redirect_to_login(&username, auth_result);
+					}
+
+					message = String::from(
+						r#"<p class="error">Your current password is not correct.</p>"#,
+					);
+				} else if new_password.is_empty() {
+					message = String::from(r#"<p class="error">The new password is empty.</p>"#);
+				} else {
+					let mut users = users.lock().unwrap();
+					let mut new_users = users.clone();
+					let result = new_users
+						.set_password(&username, &mut new_password.clone())
+						.map_err(|e| e.to_string())
+						.and_then(|_| new_users.save(&settings.userfile_path()));
+
+					match result {
+						Ok(()) => {
+							*users = new_users;
+
+							log(
+								&logger,
+								crate::http_server::logging::Level::Info,
+								&username,
+								"password changed",
+							);
+							message = String::from("<p>Your password has been changed.</p>");
+						}
+						Err(e) => {
+							log(
+								&logger,
+								crate::http_server::logging::Level::Error,
+								&username,
+								&format!("can not change password : {e}"),
+							);
+							message = String::from(
+								r#"<p class="error">Your password can not be changed, please try again later.</p>"#,
+							);
+						}
+					}
+				}
+			}
+			"revoke" => {
+				let client_id = form.client_id.clone().unwrap_or_default();
+
+				access_tokens.lock().unwrap().retain(|token| {
+					token.get_username() != username || token.get_client_id() != client_id
+				});
+				authorization_codes.lock().unwrap().retain(|code| {
+					code.get_username() != username || code.get_client_id() != client_id
+				});
+
+				let mut users = users.lock().unwrap();
+				if users
+					.get_grants(&username)
+					.map(|grants| grants.iter().any(|grant| grant.client_id == client_id))
+					.unwrap_or(false)
+				{
+					let mut new_users = users.clone();
+					let result = new_users
+						.remove_grant(&username, &client_id)
+						.map_err(|e| e.to_string())
+						.and_then(|_| new_users.save(&settings.userfile_path()));
+
+					match result {
+						Ok(()) => {
+							*users = new_users;
+						}
+						Err(e) => {
+							log(
+								&logger,
+								crate::http_server::logging::Level::Error,
+								&username,
+								&format!("can not forget access of `{client_id}` : {e}"),
+							);
+						}
+					}
+				}
+
+				log(
+					&logger,
+					crate::http_server::logging::Level::Info,
+					&username,
+					&format!("access of `{client_id}` revoked"),
+				);
+				message = format!(
+					"<p>The access of <code>{}</code> has been revoked.</p>",
+					crate::http_server::utils::escape_html(&client_id)
+				);
+			}
+			"totp_setup" => {
+				message = match update_users(&users, &settings, |users| {
+						users.start_totp(&username)
+					}) {
+						Ok(()) => String::from(
+							"<p>Please add this account in your authenticator application, then type its first code.</p>",
+						),
+						Err(e) => {
+							log(
+								&logger,
+								crate::http_server::logging::Level::Error,
+								&username,
+								&format!("can not set up two-factor authentication : {e}"),
+							);
+							String::from(
+								r#"<p class="error">The two-factor authentication can not be set up, please try again later.</p>"#,
+							)
+						}
+					};
+			}
+			"totp_confirm" => {
+				let code = form.totp_code.clone().unwrap_or_default();
+
+				message = match update_users(&users, &settings, |users| {
+					users.confirm_totp(&username, &code)
+				}) {
+					Ok(recovery_codes) => {
+						log(
+							&logger,
+							crate::http_server::logging::Level::Info,
+							&username,
+							"two-factor authentication enabled",
+						);
+
+						format!(
+								"<p>The two-factor authentication is enabled. If you lose your authenticator application, you can log in with one of these recovery codes, which can be used only once. Please write them down, they will not be shown again :</p><ul>{}</ul>",
+								recovery_codes
+									.iter()
+									.fold(String::new(), |acc, recovery_code| format!(
+										"{acc}<li><code>{recovery_code}</code></li>"
+									))
+							)
+					}
+					Err(UpdateError::Users(crate::http_server::UsersError::WrongTotpCode)) => {
+						String::from(
+							r#"<p class="error">This code is not correct, please try again.</p>"#,
+						)
+					}
+					Err(e) => {
+						log(
+							&logger,
+							crate::http_server::logging::Level::Error,
+							&username,
+							&format!("can not enable two-factor authentication : {e}"),
+						);
+						String::from(
+							r#"<p class="error">The two-factor authentication can not be enabled, please try again later.</p>"#,
+						)
+					}
+				};
+			}
+			"totp_disable" => {
+				if let Err(auth_result) = check_password(
+					&request,
+					&username,
+					form.password.as_deref().unwrap_or_default(),
+					None,
+					&users,
+					&login_attempts,
+					&settings,
+					&logger,
+				)
+				.await
+				{
+					if auth_result != "wrong_credentials" {
+						return redirect_to_login(&username, auth_result);
+					}
+
+					message = String::from(
+						r#"<p class="error">Your current password is not correct.</p>"#,
+					);
+				} else {
+					message = match update_users(&users, &settings, |users| {
+						users.reset_totp(&username)
+					}) {
+						Ok(()) => {
+							log(
+								&logger,
+								crate::http_server::logging::Level::Info,
+								&username,
+								"two-factor authentication disabled",
+							);
+							String::from("<p>The two-factor authentication is disabled.</p>")
+						}
+						Err(e) => {
+							log(
+								&logger,
+								crate::http_server::logging::Level::Error,
+								&username,
+								&format!("can not disable two-factor authentication : {e}"),
+							);
+							String::from(
+								r#"<p class="error">The two-factor authentication can not be disabled, please try again later.</p>"#,
+							)
+						}
+					};
+				}
+			}
+			"logout" => {
+				let mut response = redirect_to_login(&username, "logged_out");
+				response
+					.add_cookie(&super::close_session(&request, &sessions))
+					.ok();
+
+				return response;
+			}
+			_ => {
+				log(
+					&logger,
+					crate::http_server::logging::Level::Error,
+					&username,
+					&format!("unknown action `{}`", form.action),
+				);
+
+				return redirect_to_login(&username, "security_issue");
+			}
+		}
+	}
+
+	let new_token = new_form_token(&form_tokens, ip);
+
+	let mut response = build_account_page(
+		&username,
+		&new_token,
+		&users.lock().unwrap(),
+		&access_tokens.lock().unwrap(),
+		&settings,
+		&message,
+	);
+	if let Some(session_cookie) = session_cookie {
+		response.add_cookie(&session_cookie).ok();
+	}
+
+	return response;
+}
+
+/// Checks the password of `username` (and its two-factor code, if given) with the same
+/// brute-force protection than the OAuth form, and returns the `auth_result` to show on the login
+/// form if it fails.
+#[allow(clippy::too_many_arguments)]
+async fn check_password(
+	request: &actix_web::HttpRequest,
+	username: &str,
+	password: &str,
+	totp_code: Option<&str>,
+	users: &Arc<Mutex<crate::http_server::Users>>,
+	login_attempts: &Arc<Mutex<crate::http_server::LoginAttempts>>,
+	settings: &crate::http_server::Settings,
+	logger: &Arc<Mutex<charlie_buffalo::Logger>>,
+) -> Result<(), &'static str> {
+	let ip = request.peer_addr().map(|address| address.ip());
+
+	if let Err(refusal) = login_attempts.lock().unwrap().reserve(
+		ip,
+		username,
+		&settings.oauth_throttling.clone().unwrap_or_default(),
+	) {
+		log(
+			logger,
+			crate::http_server::logging::Level::Error,
+			username,
+			&format!("login refused : {:?}", refusal),
+		);
+
+		return Err(match refusal {
+			crate::http_server::LoginRefusal::TooManyAttempts(_) => "too_many_attempts",
+			crate::http_server::LoginRefusal::AccountLocked(_) => "account_locked",
+		});
+	}
+
+	let password_check = crate::http_server::authenticate(
+		settings,
+		users,
+		username,
+		&mut String::from(password),
+		logger,
+	)
+	.await;
+
+	let second_factor_check = match totp_code {
+		Some(totp_code) if password_check.is_correct() => {
+			users.lock().unwrap().check_totp(username, totp_code)
+		}
+		Some(_) => crate::http_server::SecondFactorCheck::Wrong,
+		None => crate::http_server::SecondFactorCheck::Disabled,
+	};
+
+	if !password_check.is_correct() || !second_factor_check.is_correct() {
+		let (ip_failures, username_failures) = login_attempts
+			.lock()
+			.unwrap()
+			.register_failure(ip, username);
+
+		log(
+			logger,
+			crate::http_server::logging::Level::Error,
+			username,
+			&format!(
+				"{} ({username_failures} failure(s) for this username, {ip_failures} for this IP address)",
+				if password_check.is_correct() {
+					"wrong two-factor code"
+				} else {
+					"wrong credentials"
+				}
+			),
+		);
+
+		return Err("wrong_credentials");
+	}
+
+	login_attempts
+		.lock()
+		.unwrap()
+		.register_success(ip, username);
+
+	if password_check == crate::http_server::PasswordCheck::Rehashed
+		|| second_factor_check == crate::http_server::SecondFactorCheck::Correct
+	{
+		if let Err(e) = users.lock().unwrap().save(&settings.userfile_path()) {
+			log(
+				logger,
+				crate::http_server::logging::Level::Error,
+				username,
+				&format!("can not save credentials : {e}"),
+			);
+		}
+	}
+
+	return Ok(());
+}
+
+/// Gives a new token to the forms of a page, like the OAuth form does.
+fn new_form_token(
+	form_tokens: &Arc<Mutex<Vec<crate::http_server::middlewares::OauthFormToken>>>,
+	ip: std::net::SocketAddr,
+) -> crate::http_server::middlewares::OauthFormToken {
+	let new_token = crate::http_server::middlewares::OauthFormToken::new(ip);
+
+	let mut form_tokens = form_tokens.lock().unwrap();
+	form_tokens.retain(|token| !token.should_be_cleaned(&ip));
+	form_tokens.push(new_token.clone());
+
+	return new_token;
+}
+
+/// Applies `change` on a copy of the users list, which only replaces the current one once saved.
+enum UpdateError {
+	Users(crate::http_server::UsersError),
+	Save(String),
+}
+impl std::fmt::Display for UpdateError {
+	fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+		match self {
+			Self::Users(e) => write!(f, "{e}"),
+			Self::Save(e) => write!(f, "{e}"),
+		}
+	}
+}
+
+fn update_users<T>(
+	users: &Arc<Mutex<crate::http_server::Users>>,
+	settings: &crate::http_server::Settings,
+	change: impl FnOnce(&mut crate::http_server::Users) -> Result<T, crate::http_server::UsersError>,
+) -> Result<T, UpdateError> {
+	let mut users = users.lock().unwrap();
+	let mut new_users = users.clone();
+
+	let result = change(&mut new_users).map_err(UpdateError::Users)?;
+	new_users
+		.save(&settings.userfile_path())
+		.map_err(UpdateError::Save)?;
+	*users = new_users;
+
+	return Ok(result);
+}
+
+fn build_account_page(
+	username: &str,
+	token: &crate::http_server::middlewares::OauthFormToken,
+	users: &crate::http_server::Users,
+	access_tokens: &[crate::http_server::AccessBearer],
+	settings: &crate::http_server::Settings,
+	message: &str,
+) -> actix_web::HttpResponse {
+	let token_lifetime =
+		std::time::Duration::from_secs(settings.token_lifetime_seconds.unwrap_or_else(|| {
+			crate::http_server::Settings::new(std::path::PathBuf::from("."))
+				.token_lifetime_seconds
+				.unwrap()
+		}));
+
+	// allowed applications, and also the ones which got a token before grants were remembered
+	let mut clients: Vec<(&str, Vec<String>, usize)> = users
+		.get_grants(username)
+		.map(|grants| {
+			grants
+				.iter()
+				.map(|grant| (grant.client_id.as_str(), grant.scopes.clone(), 0))
+				.collect()
+		})
+		.unwrap_or_default();
+	for access_token in access_tokens.iter().filter(|access_token| {
+		access_token.get_username() == username
+			&& access_token.get_emit_time().elapsed() < token_lifetime
+	}) {
+		let position = match clients
+			.iter()
+			.position(|(client_id, _, _)| *client_id == access_token.get_client_id())
+		{
+			Some(position) => position,
+			None => {
+				clients.push((access_token.get_client_id(), vec![], 0));
+				clients.len() - 1
+			}
+		};
+
+		let (_, scopes, count) = &mut clients[position];
+		for scope in access_token.get_scopes() {
+			if !scopes.iter().any(|listed_scope| {
+				crate::scope::Scope::try_from(listed_scope.as_str())
+					.map(|listed_scope| listed_scope.includes(scope))
+					.unwrap_or(false)
+			}) {
+				scopes.push(scope.to_string());
+			}
+		}
+		*count += 1;
+	}
+
+	let token_value = crate::http_server::utils::escape_html(token.get_value());
+	let action = format!(
+		"/account/{}",
+		pct_str::PctString::encode(username.chars(), pct_str::URIReserved)
+	);
+
+	let clients = if clients.is_empty() {
+		String::from("<p>No application has access to your data.</p>")
+	} else {
+		format!(
+			"<table>
+			<tr><th>Application</th><th>Allowed scopes</th><th>Active tokens</th><th></th></tr>{}
+		</table>",
+			clients
+				.iter()
+				.fold(String::new(), |acc, (client_id, scopes, count)| {
+					format!(
+						r#"{}
+			<tr>
+				<td><code>{}</code></td>
+				<td>{}</td>
+				<td>{}</td>
+				<td>
+					<form method="post" action="{}">
+						<input type="hidden" name="token" value="{}">
+						<input type="hidden" name="action" value="revoke">
+						<input type="hidden" name="client_id" value="{}">
+						<input type="submit" value="Revoke">
+					</form>
+				</td>
+			</tr>"#,
+						acc,
+						crate::http_server::utils::escape_html(client_id),
+						crate::http_server::utils::escape_html(&scopes.join(", ")),
+						count,
+						action,
+						token_value,
+						crate::http_server::utils::escape_html(client_id),
+					)
+				})
+		)
+	};
+
+	let two_factor = if users.has_totp(username) {
+		format!(
+			r#"<p>The two-factor authentication is enabled, with {} unused recovery code(s).</p>
+		<form method="post" action="{}">
+			<input type="hidden" name="token" value="{}">
+			<input type="hidden" name="action" value="totp_disable">
+			<p>Current password : <input type="password" name="password" value=""></p>
+			<input type="submit" value="Disable two-factor authentication">
+		</form>"#,
+			users.get_recovery_codes_count(username),
+			action,
+			token_value,
+		)
+	} else if let Some(uri) = users.get_totp_setup_uri(username, env!("CARGO_PKG_NAME")) {
+		format!(
+			r#"<p>Secret for your authenticator application : <a href="{}"><code>{}</code></a></p>
+		<form method="post" action="{}">
+			<input type="hidden" name="token" value="{}">
+			<input type="hidden" name="action" value="totp_confirm">
+			<p>Code : <input type="text" name="totp_code" value="" autocomplete="one-time-code"></p>
+			<input type="submit" value="Enable two-factor authentication">
+		</form>"#,
+			crate::http_server::utils::escape_html(&uri),
+			crate::http_server::utils::escape_html(&uri),
+			action,
+			token_value,
+		)
+	} else {
+		format!(
+			r#"<p>The two-factor authentication is disabled : your password is enough to access your data.</p>
+		<form method="post" action="{}">
+			<input type="hidden" name="token" value="{}">
+			<input type="hidden" name="action" value="totp_setup">
+			<input type="submit" value="Set up two-factor authentication">
+		</form>"#,
+			action, token_value,
+		)
+	};
+
+	let data_path = settings.data_path();
+	let storage_size = crate::http_server::utils::folder_size(&data_path.join(username))
+		+ crate::http_server::utils::folder_size(&data_path.join("public").join(username));
+
+	return build_page(&format!(
+		r#"<h1>My account : {}</h1>{}
+		<h2>Applications</h2>
+		{}
+		<h2>Storage</h2>
+		<p>Your data uses {}.</p>
+		<h2>Change my password</h2>
+		<form method="post" action="{}">
+			<input type="hidden" name="token" value="{}">
+			<input type="hidden" name="action" value="change_password">
+			<p>
+				Current password : <input type="password" name="password" value=""><br>
+				New password : <input type="password" name="new_password" value="">
+			</p>
+			<input type="submit" value="Change my password">
+		</form>
+		<h2>Two-factor authentication</h2>
+		{}
+		<hr>
+		<form method="post" action="{}">
+			<input type="hidden" name="token" value="{}">
+			<input type="hidden" name="action" value="logout">
+			<input type="submit" value="Log out">
+		</form>"#,
+		crate::http_server::utils::escape_html(username),
+		message,
+		clients,
+		human_size(storage_size),
+		action,
+		token_value,
+		two_factor,
+		action,
+		token_value,
+	));
+}
+
+fn build_page(content: &str) -> actix_web::HttpResponse {
+	actix_web::HttpResponse::Ok()
+		.content_type("text/html; charset=utf-8")
+		.insert_header((actix_web::http::header::CACHE_CONTROL, "no-store"))
+		.body(format!(
+			r#"<!DOCTYPE html>
+<html>
+	<head>
+		<meta charset="UTF-8">
+		<meta http-equiv="X-UA-Compatible" content="IE=edge">
+		<meta name="viewport" content="width=device-width, initial-scale=1.0">
+		<title>{} : my account</title>
+	</head>
+	<body>
+		{}
+	</body>
+</html>"#,
+			env!("CARGO_PKG_NAME"),
+			content
+		))
+}
+
+/// Form tokens and sessions are bound to the IP address of the user, so this page can not be
+/// used without it (like behind a Unix socket).
+fn build_unknown_ip_page() -> actix_web::HttpResponse {
+	let mut response = build_page(
+		r#"<h1>My account</h1>
+		<p class="error">Your IP address is unknown, so your account can not be managed here.</p>"#,
+	);
+	*response.status_mut() = actix_web::http::StatusCode::BAD_REQUEST;
+
+	response
+}
+
+fn redirect_to_login(username: &str, auth_result: &str) -> actix_web::HttpResponse {
+	actix_web::HttpResponse::Found()
+		.insert_header((
+			actix_web::http::header::LOCATION,
+			format!(
+				"/account/{}?auth_result={}",
+				pct_str::PctString::encode(username.chars(), pct_str::URIReserved),
+				auth_result
+			),
+		))
+		.finish()
+}
+
+fn human_size(bytes: u64) -> String {
+	let units = ["bytes", "KiB", "MiB", "GiB", "TiB"];
+
+	let mut size = bytes as f64;
+	let mut unit = 0;
+	while size >= 1024.0 && unit < units.len() - 1 {
+		size /= 1024.0;
+		unit += 1;
+	}
+
+	if unit == 0 {
+		return format!("{} {}", bytes, units[unit]);
+	} else {
+		return format!("{:.1} {}", size, units[unit]);
+	}
+}
+
+fn log(
+	logger: &Mutex<charlie_buffalo::Logger>,
+	level: crate::http_server::logging::Level,
+	username: &str,
+	message: &str,
+) {
+	crate::http_server::logging::log(logger, level, "account", &[("username", username)], message);
+}
@@ -0,0 +1,712 @@
+use std::sync::{Arc, Mutex};
+
+fn find_form_token(html: &str) -> String {
+	let start = html.find(r#"name="token" value=""#).unwrap() + r#"name="token" value=""#.len();
+	let end = start + html[start..].find('"').unwrap();
+
+	html[start..end]
+		.replace("&lt;", "<")
+		.replace("&gt;", ">")
+		.replace("&quot;", "\"")
+		.replace("&#39;", "'")
+		.replace("&amp;", "&")
+}
+
+#[actix_rt::test]
+async fn p4kz0wq7nfe2mhy() {
+	let settings = crate::http_server::Settings::new(tempfile::tempdir().unwrap().into_path());
+	let server_origin = format!(
+		"http://{}:{}",
+		settings.domain.clone().unwrap(),
+		settings.port
+	);
+	let userfile_path = settings.userfile_path();
+
+	std::fs::create_dir_all(settings.data_path().join("bob").join("documents")).unwrap();
+	std::fs::write(
+		settings
+			.data_path()
+			.join("bob")
+			.join("documents")
+			.join("a.txt"),
+		b"0123456789",
+	)
+	.unwrap();
+
+	let settings = Arc::new(Mutex::new(settings));
+
+	let mut users = crate::http_server::Users::new();
+	users
+		.insert("bob", &mut String::from("bob_password"))
+		.unwrap();
+	users
+		.insert("alice", &mut String::from("alice_password"))
+		.unwrap();
+	for client_id in ["https://app.example.com", "https://granted.example.com"] {
+		users
+			.add_grant(
+				"bob",
+				client_id,
+				&[crate::scope::Scope::try_from("documents:rw").unwrap()],
+			)
+			.unwrap();
+	}
+	let users = Arc::new(Mutex::new(users));
+
+	let scope = crate::scope::Scope {
+		module: String::from("documents"),
+		right_type: crate::scope::ScopeRightType::ReadWrite,
+	};
+	let access_tokens = Arc::new(Mutex::new(vec![
+		crate::http_server::AccessBearer::new(
+			vec![scope.clone()],
+			"https://app.example.com",
+			"https://app.example.com/callback",
+			"bob",
+		),
+		crate::http_server::AccessBearer::new(
+			vec![scope.clone()],
+			"https://<script>.example.com",
+			"https://other.example.com/callback",
+			"bob",
+		),
+		crate::http_server::AccessBearer::new(
+			vec![scope],
+			"https://alice-app.example.com",
+			"https://alice-app.example.com/callback",
+			"alice",
+		),
+	]));
+
+	let form_tokens: Arc<Mutex<Vec<crate::http_server::middlewares::OauthFormToken>>> =
+		Arc::new(Mutex::new(vec![]));
+	let sessions: Arc<Mutex<Vec<crate::http_server::Session>>> = Arc::new(Mutex::new(vec![]));
+	let authorization_codes: Arc<Mutex<Vec<crate::http_server::AuthorizationCode>>> =
+		Arc::new(Mutex::new(vec![]));
+	let login_attempts = Arc::new(Mutex::new(crate::http_server::LoginAttempts::new()));
+	let program_state = Arc::new(Mutex::new(crate::http_server::ProgramState::default()));
+
+	let logger = Arc::new(Mutex::new(charlie_buffalo::Logger::new(
+		charlie_buffalo::new_dispatcher(Box::new(|_| {})),
+		None,
+	)));
+
+	let app = actix_web::test::init_service(
+		actix_web::App::new()
+			.app_data(actix_web::web::Data::new(settings))
+			.app_data(actix_web::web::Data::new(users.clone()))
+			.app_data(actix_web::web::Data::new(access_tokens.clone()))
+			.app_data(actix_web::web::Data::new(form_tokens))
+			.app_data(actix_web::web::Data::new(sessions))
+			.app_data(actix_web::web::Data::new(authorization_codes))
+			.app_data(actix_web::web::Data::new(login_attempts))
+			.app_data(actix_web::web::Data::new(program_state))
+			.app_data(actix_web::web::Data::new(logger.clone()))
+			.wrap(crate::http_server::middlewares::Auth { logger })
+			.service(super::get_account)
+			.service(super::post_account),
+	)
+	.await;
+
+	let get_form_token = || async {
+		let request = actix_web::test::TestRequest::get()
+			.uri("/account/bob")
+			.peer_addr("127.0.0.1:12345".parse().unwrap())
+			.to_request();
+		let response = actix_web::test::call_service(&app, request).await;
+		assert_eq!(response.status(), actix_web::http::StatusCode::OK);
+
+		find_form_token(&String::from_utf8_lossy(
+			&actix_web::test::read_body(response).await,
+		))
+	};
+
+	let post = |form: Vec<(&'static str, String)>,
+	            origin: &str,
+	            session: Option<actix_web::cookie::Cookie<'static>>| {
+		let mut request = actix_web::test::TestRequest::post()
+			.uri("/account/bob")
+			.peer_addr("127.0.0.1:23456".parse().unwrap())
+			.insert_header((actix_web::http::header::ORIGIN, String::from(origin)))
+			.set_form(form);
+		if let Some(session) = session {
+			request = request.cookie(session);
+		}
+
+		request.to_request()
+	};
+
+	print!("#010 : login from another origin ... ");
+	let token = get_form_token().await;
+	let response = actix_web::test::call_service(
+		&app,
+		post(
+			vec![
+				("token", token),
+				("action", String::from("login")),
+				("password", String::from("bob_password")),
+			],
+			"https://evil.example.com",
+			None,
+		),
+	)
+	.await;
+	assert_eq!(response.status(), actix_web::http::StatusCode::FOUND);
+	assert_eq!(
+		response
+			.headers()
+			.get(actix_web::http::header::LOCATION)
+			.unwrap(),
+		"/account/bob?auth_result=security_issue"
+	);
+	println!("OK");
+
+	print!("#020 : login with wrong password ... ");
+	let token = get_form_token().await;
+	let response = actix_web::test::call_service(
+		&app,
+		post(
+			vec![
+				("token", token.clone()),
+				("action", String::from("login")),
+				("password", String::from("WRONG_PASSWORD")),
+			],
+			&server_origin,
+			None,
+		),
+	)
+	.await;
+	assert_eq!(
+		response
+			.headers()
+			.get(actix_web::http::header::LOCATION)
+			.unwrap(),
+		"/account/bob?auth_result=wrong_credentials"
+	);
+	println!("OK");
+
+	print!("#030 : reuse of a consumed form token ... ");
+	let response = actix_web::test::call_service(
+		&app,
+		post(
+			vec![
+				("token", token),
+				("action", String::from("login")),
+				("password", String::from("bob_password")),
+			],
+			&server_origin,
+			None,
+		),
+	)
+	.await;
+	assert_eq!(
+		response
+			.headers()
+			.get(actix_web::http::header::LOCATION)
+			.unwrap(),
+		"/account/bob?auth_result=security_issue"
+	);
+	println!("OK");
+
+	print!("#040 : action without login ... ");
+	let token = get_form_token().await;
+	let response = actix_web::test::call_service(
+		&app,
+		post(
+			vec![
+				("token", token),
+				("action", String::from("revoke")),
+				("client_id", String::from("https://app.example.com")),
+			],
+			&server_origin,
+			None,
+		),
+	)
+	.await;
+	assert_eq!(
+		response
+			.headers()
+			.get(actix_web::http::header::LOCATION)
+			.unwrap(),
+		"/account/bob?auth_result=security_issue"
+	);
+	assert_eq!(access_tokens.lock().unwrap().len(), 3);
+	println!("OK");
+
+	print!("#050 : login ... ");
+	let token = get_form_token().await;
+	let response = actix_web::test::call_service(
+		&app,
+		post(
+			vec![
+				("token", token),
+				("action", String::from("login")),
+				("password", String::from("bob_password")),
+			],
+			&server_origin,
+			None,
+		),
+	)
+	.await;
+	assert_eq!(response.status(), actix_web::http::StatusCode::OK);
+	let session = response
+		.response()
+		.cookies()
+		.find(|cookie| cookie.name() == super::super::SESSION_COOKIE_NAME)
+		.unwrap()
+		.into_owned();
+	let body = String::from_utf8_lossy(&actix_web::test::read_body(response).await).to_string();
+	assert!(body.contains("<td><code>https://app.example.com</code></td>"));
+	assert!(body.contains("<td><code>https://granted.example.com</code></td>"));
+	assert!(body.contains("<code>https://&lt;script&gt;.example.com</code>"));
+	assert!(!body.contains("alice-app.example.com"));
+	assert!(body.contains("documents:rw"));
+	assert!(body.contains("10 bytes"));
+	println!("OK");
+
+	print!("#060 : revoke ... ");
+	let response = actix_web::test::call_service(
+		&app,
+		post(
+			vec![
+				("token", find_form_token(&body)),
+				("action", String::from("revoke")),
+				("client_id", String::from("https://app.example.com")),
+			],
+			&server_origin,
+			Some(session.clone()),
+		),
+	)
+	.await;
+	assert_eq!(response.status(), actix_web::http::StatusCode::OK);
+	let body = String::from_utf8_lossy(&actix_web::test::read_body(response).await).to_string();
+	assert!(body.contains("The access of <code>https://app.example.com</code> has been revoked."));
+	assert!(!body.contains("<td><code>https://app.example.com</code></td>"));
+	assert_eq!(
+		access_tokens
+			.lock()
+			.unwrap()
+			.iter()
+			.map(|token| token.get_client_id())
+			.collect::<Vec<&str>>(),
+		vec![
+			"https://<script>.example.com",
+			"https://alice-app.example.com"
+		]
+	);
+	assert_eq!(
+		users
+			.lock()
+			.unwrap()
+			.get_grants("bob")
+			.unwrap()
+			.iter()
+			.map(|grant| grant.client_id.as_str())
+			.collect::<Vec<&str>>(),
+		vec!["https://granted.example.com"]
+	);
+	assert!(
+		!crate::http_server::Users::from_bytes(&std::fs::read(&userfile_path).unwrap())
+			.unwrap()
+			.is_granted(
+				"bob",
+				"https://app.example.com",
+				&[crate::scope::Scope::try_from("documents:r").unwrap()]
+			)
+	);
+	println!("OK");
+
+	print!("#070 : change password with wrong current password ... ");
+	let response = actix_web::test::call_service(
+		&app,
+		post(
+			vec![
+				("token", find_form_token(&body)),
+				("action", String::from("change_password")),
+				("password", String::from("WRONG_PASSWORD")),
+				("new_password", String::from("bob_new_password")),
+			],
+			&server_origin,
+			Some(session.clone()),
+		),
+	)
+	.await;
+	assert_eq!(response.status(), actix_web::http::StatusCode::OK);
+	let body = String::from_utf8_lossy(&actix_web::test::read_body(response).await).to_string();
+	assert!(body.contains("Your current password is not correct."));
+	println!("OK");
+
+	print!("#080 : change password ... ");
+	let response = actix_web::test::call_service(
+		&app,
+		post(
+			vec![
+				("token", find_form_token(&body)),
+				("action", String::from("change_password")),
+				("password", String::from("bob_password")),
+				("new_password", String::from("bob_new_password")),
+			],
+			&server_origin,
+			Some(session.clone()),
+		),
+	)
+	.await;
+	assert_eq!(response.status(), actix_web::http::StatusCode::OK);
+	let body = String::from_utf8_lossy(&actix_web::test::read_body(response).await).to_string();
+	assert!(body.contains("Your password has been changed."));
+	assert!(users
+		.lock()
+		.unwrap()
+		.check("bob", &mut String::from("bob_new_password"))
+		.is_correct());
+	assert!(
+		crate::http_server::Users::from_bytes(&std::fs::read(&userfile_path).unwrap())
+			.unwrap()
+			.check("bob", &mut String::from("bob_new_password"))
+			.is_correct()
+	);
+	println!("OK");
+
+	print!("#090 : account page with session cookie ... ");
+	let request = actix_web::test::TestRequest::get()
+		.uri("/account/bob")
+		.peer_addr("127.0.0.1:34567".parse().unwrap())
+		.cookie(session.clone())
+		.to_request();
+	let response = actix_web::test::call_service(&app, request).await;
+	assert_eq!(response.status(), actix_web::http::StatusCode::OK);
+	let body = String::from_utf8_lossy(&actix_web::test::read_body(response).await).to_string();
+	assert!(body.contains("My account : bob"));
+
+	let mut form_token_as_session = session.clone();
+	form_token_as_session.set_value(find_form_token(&body));
+	let request = actix_web::test::TestRequest::get()
+		.uri("/account/bob")
+		.peer_addr("127.0.0.1:45678".parse().unwrap())
+		.cookie(form_token_as_session)
+		.to_request();
+	let response = actix_web::test::call_service(&app, request).await;
+	let other_body =
+		String::from_utf8_lossy(&actix_web::test::read_body(response).await).to_string();
+	assert!(other_body.contains(r#"<input type="submit" value="Log in">"#));
+	println!("OK");
+
+	print!("#100 : logout ... ");
+	let token = find_form_token(&body);
+	let request = actix_web::test::TestRequest::post()
+		.uri("/account/bob")
+		.peer_addr("127.0.0.1:23456".parse().unwrap())
+		.insert_header((actix_web::http::header::ORIGIN, server_origin.clone()))
+		.cookie(session.clone())
+		.set_form([("token", token.clone()), ("action", String::from("logout"))])
+		.to_request();
+	let response = actix_web::test::call_service(&app, request).await;
+	assert_eq!(
+		response
+			.headers()
+			.get(actix_web::http::header::LOCATION)
+			.unwrap(),
+		"/account/bob?auth_result=logged_out"
+	);
+	let response = actix_web::test::call_service(
+		&app,
+		post(
+			vec![
+				("token", token),
+				("action", String::from("revoke")),
+				("client_id", String::from("https://<script>.example.com")),
+			],
+			&server_origin,
+			Some(session.clone()),
+		),
+	)
+	.await;
+	assert_eq!(
+		response
+			.headers()
+			.get(actix_web::http::header::LOCATION)
+			.unwrap(),
+		"/account/bob?auth_result=security_issue"
+	);
+
+	let request = actix_web::test::TestRequest::get()
+		.uri("/account/bob")
+		.peer_addr("127.0.0.1:34567".parse().unwrap())
+		.cookie(session)
+		.to_request();
+	let response = actix_web::test::call_service(&app, request).await;
+	let body = String::from_utf8_lossy(&actix_web::test::read_body(response).await).to_string();
+	assert!(body.contains(r#"<input type="submit" value="Log in">"#));
+	println!("OK");
+
+	print!("#110 : requests without IP address ... ");
+	let request = actix_web::test::TestRequest::get()
+		.uri("/account/bob")
+		.to_request();
+	let response = actix_web::test::call_service(&app, request).await;
+	assert_eq!(response.status(), actix_web::http::StatusCode::BAD_REQUEST);
+	let request = actix_web::test::TestRequest::post()
+		.uri("/account/bob")
+		.insert_header((actix_web::http::header::ORIGIN, server_origin.clone()))
+		.set_form([
+			("token", String::from("hzvbr8qpd3cmgkw")),
+			("action", String::from("logout")),
+		])
+		.to_request();
+	let response = actix_web::test::call_service(&app, request).await;
+	assert_eq!(response.status(), actix_web::http::StatusCode::BAD_REQUEST);
+	println!("OK");
+}
+
+#[actix_rt::test]
+async fn f9wn2kc6ruq0tzx() {
+	let settings = crate::http_server::Settings::new(tempfile::tempdir().unwrap().into_path());
+	let server_origin = format!(
+		"http://{}:{}",
+		settings.domain.clone().unwrap(),
+		settings.port
+	);
+	let userfile_path = settings.userfile_path();
+	let settings = Arc::new(Mutex::new(settings));
+
+	let mut users = crate::http_server::Users::new();
+	users
+		.insert("bob", &mut String::from("bob_password"))
+		.unwrap();
+	let users = Arc::new(Mutex::new(users));
+
+	let form_tokens: Arc<Mutex<Vec<crate::http_server::middlewares::OauthFormToken>>> =
+		Arc::new(Mutex::new(vec![]));
+	let sessions: Arc<Mutex<Vec<crate::http_server::Session>>> = Arc::new(Mutex::new(vec![]));
+	let access_tokens: Arc<Mutex<Vec<crate::http_server::AccessBearer>>> =
+		Arc::new(Mutex::new(vec![]));
+	let authorization_codes: Arc<Mutex<Vec<crate::http_server::AuthorizationCode>>> =
+		Arc::new(Mutex::new(vec![]));
+	let login_attempts = Arc::new(Mutex::new(crate::http_server::LoginAttempts::new()));
+	let program_state = Arc::new(Mutex::new(crate::http_server::ProgramState::default()));
+
+	let logger = Arc::new(Mutex::new(charlie_buffalo::Logger::new(
+		charlie_buffalo::new_dispatcher(Box::new(|_| {})),
+		None,
+	)));
+
+	let app = actix_web::test::init_service(
+		actix_web::App::new()
+			.app_data(actix_web::web::Data::new(settings))
+			.app_data(actix_web::web::Data::new(users.clone()))
+			.app_data(actix_web::web::Data::new(access_tokens))
+			.app_data(actix_web::web::Data::new(form_tokens))
+			.app_data(actix_web::web::Data::new(sessions))
+			.app_data(actix_web::web::Data::new(authorization_codes))
+			.app_data(actix_web::web::Data::new(login_attempts))
+			.app_data(actix_web::web::Data::new(program_state))
+			.app_data(actix_web::web::Data::new(logger))
+			.service(super::get_account)
+			.service(super::post_account),
+	)
+	.await;
+
+	let get_form_token = || async {
+		let request = actix_web::test::TestRequest::get()
+			.uri("/account/bob")
+			.peer_addr("127.0.0.1:12345".parse().unwrap())
+			.to_request();
+		let response = actix_web::test::call_service(&app, request).await;
+		assert_eq!(response.status(), actix_web::http::StatusCode::OK);
+
+		find_form_token(&String::from_utf8_lossy(
+			&actix_web::test::read_body(response).await,
+		))
+	};
+
+	let post = |form: Vec<(&'static str, String)>,
+	            session: Option<&actix_web::cookie::Cookie<'static>>| {
+		let mut request = actix_web::test::TestRequest::post()
+			.uri("/account/bob")
+			.peer_addr("127.0.0.1:23456".parse().unwrap())
+			.insert_header((actix_web::http::header::ORIGIN, server_origin.clone()))
+			.set_form(form);
+		if let Some(session) = session {
+			request = request.cookie(session.clone());
+		}
+
+		request.to_request()
+	};
+
+	let login = |totp_code: String| async {
+		let token = get_form_token().await;
+		let response = actix_web::test::call_service(
+			&app,
+			post(
+				vec![
+					("token", token),
+					("action", String::from("login")),
+					("password", String::from("bob_password")),
+					("totp_code", totp_code),
+				],
+				None,
+			),
+		)
+		.await;
+
+		if response.status() == actix_web::http::StatusCode::OK {
+			let session = response
+				.response()
+				.cookies()
+				.find(|cookie| cookie.name() == super::super::SESSION_COOKIE_NAME)
+				.unwrap()
+				.into_owned();
+
+			Some((
+				String::from_utf8_lossy(&actix_web::test::read_body(response).await).to_string(),
+				session,
+			))
+		} else {
+			assert_eq!(response.status(), actix_web::http::StatusCode::FOUND);
+			assert!(response
+				.headers()
+				.get(actix_web::http::header::LOCATION)
+				.unwrap()
+				.to_str()
+				.unwrap()
+				.ends_with("auth_result=wrong_credentials"));
+
+			None
+		}
+	};
+
+	print!("#010 : set up of two-factor authentication ... ");
+	let (html, session) = login(String::new()).await.unwrap();
+	assert!(html.contains(r#"<input type="submit" value="Set up two-factor authentication">"#));
+
+	let response = actix_web::test::call_service(
+		&app,
+		post(
+			vec![
+				("token", find_form_token(&html)),
+				("action", String::from("totp_setup")),
+			],
+			Some(&session),
+		),
+	)
+	.await;
+	assert_eq!(response.status(), actix_web::http::StatusCode::OK);
+	let html = String::from_utf8_lossy(&actix_web::test::read_body(response).await).to_string();
+	let start = html.find("otpauth://totp/pontus_onyx:bob?secret=").unwrap()
+		+ "otpauth://totp/pontus_onyx:bob?secret=".len();
+	let end = start + html[start..].find('&').unwrap();
+	let secret = base32::decode(
+		base32::Alphabet::RFC4648 { padding: false },
+		&html[start..end],
+	)
+	.unwrap();
+	println!("OK");
+
+	print!("#020 : confirmation with a wrong code ... ");
+	let step = crate::http_server::users::totp::current_step();
+	let wrong_code =
+		if crate::http_server::users::totp::find_step(&secret, "000000", step).is_some() {
+			"111111"
+		} else {
+			"000000"
+		};
+	let response = actix_web::test::call_service(
+		&app,
+		post(
+			vec![
+				("token", find_form_token(&html)),
+				("action", String::from("totp_confirm")),
+				("totp_code", String::from(wrong_code)),
+			],
+			Some(&session),
+		),
+	)
+	.await;
+	let html = String::from_utf8_lossy(&actix_web::test::read_body(response).await).to_string();
+	assert!(html.contains("This code is not correct"));
+	assert!(!users.lock().unwrap().has_totp("bob"));
+	println!("OK");
+
+	print!("#030 : confirmation with the current code ... ");
+	let response = actix_web::test::call_service(
+		&app,
+		post(
+			vec![
+				("token", find_form_token(&html)),
+				("action", String::from("totp_confirm")),
+				(
+					"totp_code",
+					crate::http_server::users::totp::code(&secret, step),
+				),
+			],
+			Some(&session),
+		),
+	)
+	.await;
+	let html = String::from_utf8_lossy(&actix_web::test::read_body(response).await).to_string();
+	assert!(html.contains("The two-factor authentication is enabled."));
+	let start = html.find("<li><code>").unwrap() + "<li><code>".len();
+	let recovery_code = String::from(&html[start..start + 11]);
+	assert!(users.lock().unwrap().has_totp("bob"));
+	assert!(
+		crate::http_server::Users::from_bytes(&std::fs::read(&userfile_path).unwrap())
+			.unwrap()
+			.has_totp("bob")
+	);
+	println!("OK");
+
+	print!("#040 : login without code, or with an already used code ... ");
+	assert!(login(String::new()).await.is_none());
+	assert!(login(crate::http_server::users::totp::code(&secret, step))
+		.await
+		.is_none());
+	println!("OK");
+
+	print!("#050 : login with the next code ... ");
+	let (html, _) = login(crate::http_server::users::totp::code(&secret, step + 1))
+		.await
+		.unwrap();
+	assert!(html.contains("with 10 unused recovery code(s)"));
+	println!("OK");
+
+	print!("#060 : login with a recovery code, only once ... ");
+	let (html, session) = login(recovery_code.to_uppercase()).await.unwrap();
+	assert!(html.contains("with 9 unused recovery code(s)"));
+	assert!(login(recovery_code).await.is_none());
+	println!("OK");
+
+	print!("#070 : disable two-factor authentication ... ");
+	let response = actix_web::test::call_service(
+		&app,
+		post(
+			vec![
+				("token", find_form_token(&html)),
+				("action", String::from("totp_disable")),
+				("password", String::from("WRONG_PASSWORD")),
+			],
+			Some(&session),
+		),
+	)
+	.await;
+	let html = String::from_utf8_lossy(&actix_web::test::read_body(response).await).to_string();
+	assert!(html.contains("Your current password is not correct."));
+	assert!(users.lock().unwrap().has_totp("bob"));
+
+	let response = actix_web::test::call_service(
+		&app,
+		post(
+			vec![
+				("token", find_form_token(&html)),
+				("action", String::from("totp_disable")),
+				("password", String::from("bob_password")),
+			],
+			Some(&session),
+		),
+	)
+	.await;
+	let html = String::from_utf8_lossy(&actix_web::test::read_body(response).await).to_string();
+	assert!(html.contains("The two-factor authentication is disabled."));
+	assert!(!users.lock().unwrap().has_totp("bob"));
+	assert!(login(String::new()).await.is_some());
+	println!("OK");
+}
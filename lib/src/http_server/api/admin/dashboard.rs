@@ -0,0 +1,112 @@
+use std::sync::{Arc, Mutex};
+
+use super::{authenticate, build_response};
+
+/// Web page of the admin dashboard, which uses the other `/admin/` JSON endpoints.
+///
+/// The browser asks the credentials, which are checked once here : a session is opened for the
+/// other endpoints, otherwise each of their concurrent requests would check the password again (and
+/// count as a login attempt).
+#[actix_web::get("/admin/")]
+pub async fn get_admin_dashboard(
+	request: actix_web::HttpRequest,
+	users: actix_web::web::Data<Arc<Mutex<crate::http_server::Users>>>,
+	login_attempts: actix_web::web::Data<Arc<Mutex<crate::http_server::LoginAttempts>>>,
+	sessions: actix_web::web::Data<Arc<Mutex<Vec<crate::http_server::Session>>>>,
+	program_state: actix_web::web::Data<Arc<Mutex<crate::http_server::ProgramState>>>,
+	settings: actix_web::web::Data<Arc<Mutex<crate::http_server::Settings>>>,
+	logger: actix_web::web::Data<Arc<Mutex<charlie_buffalo::Logger>>>,
+) -> actix_web::HttpResponse {
+	let settings = settings.lock().unwrap().clone();
+	let has_session = super::super::session_username(&request, &sessions).is_some();
+	let admin = match authenticate(
+		&request,
+		None,
+		&users,
+		&login_attempts,
+		&sessions,
+		&settings,
+		&logger,
+	)
+	.await
+	{
+		Ok(admin) => admin,
+		Err(response) => return response,
+	};
+
+	let rights = users
+		.lock()
+		.unwrap()
+		.get_rights(&admin)
+		.cloned()
+		.unwrap_or_default();
+	if !rights.contains(&crate::http_server::UserRight::ManageUsers)
+		&& !rights.contains(&crate::http_server::UserRight::ManageServerSettings)
+	{
+		return build_response(
+			&request,
+			actix_web::http::StatusCode::FORBIDDEN,
+			Some(format!(
+				"missing right `{}` or `{}`",
+				crate::http_server::UserRight::ManageUsers,
+				crate::http_server::UserRight::ManageServerSettings
+			)),
+		);
+	}
+
+	let template: &str = include_str!("../../static/admin.html");
+	let template = template.replace("{{app_name}}", env!("CARGO_PKG_NAME"));
+	let template = template.replace("{{app_version}}", env!("CARGO_PKG_VERSION"));
+
+	let mut response = actix_web::HttpResponse::Ok();
+	response.content_type("text/html; charset=utf-8");
+	response.insert_header((actix_web::http::header::CACHE_CONTROL, "no-store"));
+	if !has_session {
+		if let Some(session_cookie) = super::super::open_session(
+			&request,
+			&sessions,
+			&admin,
+			program_state.lock().unwrap().https_mode || settings.force_https == Some(true),
+		) {
+			response.cookie(session_cookie);
+		}
+	}
+
+	return response.body(template);
+}
+
+#[actix_web::get("/admin/me")]
+pub async fn get_admin_me(
+	request: actix_web::HttpRequest,
+	users: actix_web::web::Data<Arc<Mutex<crate::http_server::Users>>>,
+	login_attempts: actix_web::web::Data<Arc<Mutex<crate::http_server::LoginAttempts>>>,
+	sessions: actix_web::web::Data<Arc<Mutex<Vec<crate::http_server::Session>>>>,
+	settings: actix_web::web::Data<Arc<Mutex<crate::http_server::Settings>>>,
+	logger: actix_web::web::Data<Arc<Mutex<charlie_buffalo::Logger>>>,
+) -> actix_web::HttpResponse {
+	let settings = settings.lock().unwrap().clone();
+	let username = match authenticate(
+		&request,
+		None,
+		&users,
+		&login_attempts,
+		&sessions,
+		&settings,
+		&logger,
+	)
+	.await
+	{
+		Ok(username) => username,
+		Err(response) => return response,
+	};
+
+	let body = serde_json::json!({
+		"name": username,
+		"rights": users.lock().unwrap().get_rights(&username),
+	});
+
+	return actix_web::HttpResponse::Ok()
+		.content_type("application/json")
+		.insert_header((actix_web::http::header::CACHE_CONTROL, "no-store"))
+		.body(body.to_string());
+}
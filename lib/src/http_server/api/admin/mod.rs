@@ -0,0 +1,205 @@
+mod dashboard;
+mod server;
+mod users;
+
+pub use dashboard::*;
+pub use server::*;
+pub use users::*;
+
+#[cfg(test)]
+mod tests;
+
+use std::sync::{Arc, Mutex};
+
+/// Returns the name of the user of the session cookie of the request (opened on the account page,
+/// after its second factor if any), or else of its `Authorization: Basic` credentials, if it has
+/// the `required_right`.
+async fn authenticate(
+	request: &actix_web::HttpRequest,
+	required_right: Option<&crate::http_server::UserRight>,
+	users: &Arc<Mutex<crate::http_server::Users>>,
+	login_attempts: &Arc<Mutex<crate::http_server::LoginAttempts>>,
+	sessions: &Arc<Mutex<Vec<crate::http_server::Session>>>,
+	settings: &crate::http_server::Settings,
+	logger: &Arc<Mutex<charlie_buffalo::Logger>>,
+) -> Result<String, actix_web::HttpResponse> {
+	let username = match super::session_username(request, sessions) {
+		Some(username) => username,
+		None => check_credentials(request, users, login_attempts, settings, logger).await?,
+	};
+
+	if let Some(required_right) = required_right {
+		let has_right = users
+			.lock()
+			.unwrap()
+			.get_rights(&username)
+			.map(|rights| rights.contains(required_right))
+			.unwrap_or(false);
+
+		if !has_right {
+			crate::http_server::logging::log(
+				logger,
+				crate::http_server::logging::Level::Error,
+				"admin",
+				&[("username", &username)],
+				&format!("missing right `{}`", required_right),
+			);
+
+			return Err(build_response(
+				request,
+				actix_web::http::StatusCode::FORBIDDEN,
+				Some(format!("missing right `{}`", required_right)),
+			));
+		}
+	}
+
+	return Ok(username);
+}
+
+/// Checks the `Authorization: Basic` credentials of the request, with the same brute-force
+/// protection than the OAuth form.
+///
+/// They are refused for users with two-factor authentication, which have to log in on the account
+/// page instead, because they do not have any second factor.
+async fn check_credentials(
+	request: &actix_web::HttpRequest,
+	users: &Arc<Mutex<crate::http_server::Users>>,
+	login_attempts: &Arc<Mutex<crate::http_server::LoginAttempts>>,
+	settings: &crate::http_server::Settings,
+	logger: &Arc<Mutex<charlie_buffalo::Logger>>,
+) -> Result<String, actix_web::HttpResponse> {
+	let credentials = request
+		.headers()
+		.get(actix_web::http::header::AUTHORIZATION)
+		.and_then(|value| value.to_str().ok())
+		.and_then(|value| value.strip_prefix("Basic "))
+		.and_then(|value| base64::decode(value.trim()).ok())
+		.and_then(|value| String::from_utf8(value).ok());
+
+	let (username, mut password) = match credentials
+		.as_deref()
+		.and_then(|credentials| credentials.split_once(':'))
+	{
+		Some((username, password)) => (String::from(username), String::from(password)),
+		None => {
+			return Err(build_response(
+				request,
+				actix_web::http::StatusCode::UNAUTHORIZED,
+				None,
+			));
+		}
+	};
+
+	let ip = request.peer_addr().map(|address| address.ip());
+	let ip_attribute = match ip {
+		Some(ip) => ip.to_string(),
+		None => String::from("unknown"),
+	};
+
+	if let Err(refusal) = login_attempts.lock().unwrap().reserve(
+		ip,
+		&username,
+		&settings.oauth_throttling.clone().unwrap_or_default(),
+	) {
+		crate::http_server::logging::log(
+			logger,
+			crate::http_server::logging::Level::Error,
+			"admin",
+			&[("ip", &ip_attribute), ("username", &username)],
+			&format!("login refused : {:?}", refusal),
+		);
+
+		return Err(build_response(
+			request,
+			actix_web::http::StatusCode::TOO_MANY_REQUESTS,
+			None,
+		));
+	}
+
+	let password_check =
+		crate::http_server::authenticate(settings, users, &username, &mut password, logger).await;
+	if !password_check.is_correct() {
+		let (ip_failures, username_failures) = login_attempts
+			.lock()
+			.unwrap()
+			.register_failure(ip, &username);
+
+		crate::http_server::logging::log(
+			logger,
+			crate::http_server::logging::Level::Error,
+			"admin",
+			&[("ip", &ip_attribute), ("username", &username)],
+			&format!(
+				"wrong credentials ({username_failures} failure(s) for this username, {ip_failures} for this IP address)"
+			),
+		);
+
+		return Err(build_response(
+			request,
+			actix_web::http::StatusCode::UNAUTHORIZED,
+			None,
+		));
+	}
+
+	login_attempts
+		.lock()
+		.unwrap()
+		.register_success(ip, &username);
+
+	if password_check == crate::http_server::PasswordCheck::Rehashed {
+		if let Err(e) = users.lock().unwrap().save(&settings.userfile_path()) {
+			crate::http_server::logging::log(
+				logger,
+				crate::http_server::logging::Level::Error,
+				"admin",
+				&[],
+				&format!(
+					"can not save upgraded password hash of `{}` : {}",
+					username, e
+				),
+			);
+		}
+	}
+
+	if users.lock().unwrap().has_totp(&username) {
+		crate::http_server::logging::log(
+			logger,
+			crate::http_server::logging::Level::Error,
+			"admin",
+			&[("ip", &ip_attribute), ("username", &username)],
+			"password only login refused because of two-factor authentication",
+		);
+
+		return Err(build_response(
+			request,
+			actix_web::http::StatusCode::FORBIDDEN,
+			Some(String::from(
+				"two-factor authentication is enabled, log in on the account page first",
+			)),
+		));
+	}
+
+	return Ok(username);
+}
+
+fn build_response(
+	request: &actix_web::HttpRequest,
+	code: actix_web::http::StatusCode,
+	hint: Option<String>,
+) -> actix_web::HttpResponse {
+	let mut response =
+		crate::database::build_http_json_response(request.method(), code, None, None, hint, true);
+
+	if code == actix_web::http::StatusCode::UNAUTHORIZED {
+		response.headers_mut().insert(
+			actix_web::http::header::WWW_AUTHENTICATE,
+			actix_web::http::header::HeaderValue::from_str(&format!(
+				r#"Basic realm="{} admin", charset="UTF-8""#,
+				env!("CARGO_PKG_NAME")
+			))
+			.unwrap(),
+		);
+	}
+
+	return response;
+}
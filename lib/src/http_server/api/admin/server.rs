@@ -0,0 +1,329 @@
+use std::sync::{Arc, Mutex};
+
+use super::{authenticate, build_response};
+
+#[derive(serde::Deserialize)]
+pub struct EventsQuery {
+	limit: Option<usize>,
+}
+
+#[actix_web::get("/admin/tokens")]
+pub async fn get_admin_tokens(
+	request: actix_web::HttpRequest,
+	access_tokens: actix_web::web::Data<Arc<Mutex<Vec<crate::http_server::AccessBearer>>>>,
+	users: actix_web::web::Data<Arc<Mutex<crate::http_server::Users>>>,
+	login_attempts: actix_web::web::Data<Arc<Mutex<crate::http_server::LoginAttempts>>>,
+	sessions: actix_web::web::Data<Arc<Mutex<Vec<crate::http_server::Session>>>>,
+	settings: actix_web::web::Data<Arc<Mutex<crate::http_server::Settings>>>,
+	logger: actix_web::web::Data<Arc<Mutex<charlie_buffalo::Logger>>>,
+) -> actix_web::HttpResponse {
+	let settings = settings.lock().unwrap().clone();
+	if let Err(response) = authenticate(
+		&request,
+		Some(&crate::http_server::UserRight::ManageUsers),
+		&users,
+		&login_attempts,
+		&sessions,
+		&settings,
+		&logger,
+	)
+	.await
+	{
+		return response;
+	}
+
+	let token_lifetime =
+		std::time::Duration::from_secs(settings.token_lifetime_seconds.unwrap_or_else(|| {
+			crate::http_server::Settings::new(std::path::PathBuf::from("."))
+				.token_lifetime_seconds
+				.unwrap()
+		}));
+
+	let list: Vec<serde_json::Value> = access_tokens
+		.lock()
+		.unwrap()
+		.iter()
+		.filter(|token| token.get_emit_time().elapsed() < token_lifetime)
+		.map(|token| {
+			serde_json::json!({
+				"id": token_id(token),
+				"username": token.get_username(),
+				"client_id": token.get_client_id(),
+				"scopes": token
+					.get_scopes()
+					.iter()
+					.map(|scope| scope.to_string())
+					.collect::<Vec<String>>(),
+				"age_seconds": token.get_emit_time().elapsed().as_secs(),
+				"remaining_seconds": (token_lifetime - token.get_emit_time().elapsed()).as_secs(),
+			})
+		})
+		.collect();
+
+	return build_json_response(serde_json::Value::from(list));
+}
+
+#[allow(clippy::too_many_arguments)]
+#[actix_web::delete("/admin/tokens/{id}")]
+pub async fn delete_admin_token(
+	request: actix_web::HttpRequest,
+	path: actix_web::web::Path<String>,
+	access_tokens: actix_web::web::Data<Arc<Mutex<Vec<crate::http_server::AccessBearer>>>>,
+	users: actix_web::web::Data<Arc<Mutex<crate::http_server::Users>>>,
+	login_attempts: actix_web::web::Data<Arc<Mutex<crate::http_server::LoginAttempts>>>,
+	sessions: actix_web::web::Data<Arc<Mutex<Vec<crate::http_server::Session>>>>,
+	settings: actix_web::web::Data<Arc<Mutex<crate::http_server::Settings>>>,
+	logger: actix_web::web::Data<Arc<Mutex<charlie_buffalo::Logger>>>,
+) -> actix_web::HttpResponse {
+	let settings = settings.lock().unwrap().clone();
+	let admin = match authenticate(
+		&request,
+		Some(&crate::http_server::UserRight::ManageUsers),
+		&users,
+		&login_attempts,
+		&sessions,
+		&settings,
+		&logger,
+	)
+	.await
+	{
+		Ok(admin) => admin,
+		Err(response) => return response,
+	};
+
+	let id = path.into_inner();
+
+	let mut access_tokens = access_tokens.lock().unwrap();
+	let count_before = access_tokens.len();
+	access_tokens.retain(|token| token_id(token) != id);
+
+	if access_tokens.len() == count_before {
+		return build_response(&request, actix_web::http::StatusCode::NOT_FOUND, None);
+	}
+
+	crate::http_server::logging::log(
+		&logger,
+		crate::http_server::logging::Level::Info,
+		"admin",
+		&[("username", &admin)],
+		&format!("token `{id}` revoked"),
+	);
+
+	return build_response(&request, actix_web::http::StatusCode::NO_CONTENT, None);
+}
+
+#[allow(clippy::too_many_arguments)]
+#[actix_web::get("/admin/events")]
+pub async fn get_admin_events(
+	request: actix_web::HttpRequest,
+	query: actix_web::web::Query<EventsQuery>,
+	workspace_path: actix_web::web::Data<std::path::PathBuf>,
+	users: actix_web::web::Data<Arc<Mutex<crate::http_server::Users>>>,
+	login_attempts: actix_web::web::Data<Arc<Mutex<crate::http_server::LoginAttempts>>>,
+	sessions: actix_web::web::Data<Arc<Mutex<Vec<crate::http_server::Session>>>>,
+	settings: actix_web::web::Data<Arc<Mutex<crate::http_server::Settings>>>,
+	logger: actix_web::web::Data<Arc<Mutex<charlie_buffalo::Logger>>>,
+) -> actix_web::HttpResponse {
+	let settings = settings.lock().unwrap().clone();
+	if let Err(response) = authenticate(
+		&request,
+		Some(&crate::http_server::UserRight::ManageServerSettings),
+		&users,
+		&login_attempts,
+		&sessions,
+		&settings,
+		&logger,
+	)
+	.await
+	{
+		return response;
+	}
+
+	match crate::http_server::read_events(&workspace_path) {
+		Ok(events) => {
+			let events: Vec<crate::http_server::DbEvent> = events
+				.into_iter()
+				.rev()
+				.take(query.limit.unwrap_or(100))
+				.collect();
+
+			return build_json_response(serde_json::to_value(events).unwrap());
+		}
+		Err(e) => {
+			crate::http_server::logging::log(
+				&logger,
+				crate::http_server::logging::Level::Error,
+				"admin",
+				&[],
+				&format!("can not read events : {e}"),
+			);
+
+			return build_response(
+				&request,
+				actix_web::http::StatusCode::INTERNAL_SERVER_ERROR,
+				None,
+			);
+		}
+	}
+}
+
+/// Disk usage of the data of each user, including its public data.
+#[actix_web::get("/admin/storage")]
+pub async fn get_admin_storage(
+	request: actix_web::HttpRequest,
+	users: actix_web::web::Data<Arc<Mutex<crate::http_server::Users>>>,
+	login_attempts: actix_web::web::Data<Arc<Mutex<crate::http_server::LoginAttempts>>>,
+	sessions: actix_web::web::Data<Arc<Mutex<Vec<crate::http_server::Session>>>>,
+	settings: actix_web::web::Data<Arc<Mutex<crate::http_server::Settings>>>,
+	logger: actix_web::web::Data<Arc<Mutex<charlie_buffalo::Logger>>>,
+) -> actix_web::HttpResponse {
+	let settings = settings.lock().unwrap().clone();
+	if let Err(response) = authenticate(
+		&request,
+		Some(&crate::http_server::UserRight::ManageUsers),
+		&users,
+		&login_attempts,
+		&sessions,
+		&settings,
+		&logger,
+	)
+	.await
+	{
+		return response;
+	}
+
+	let data_path = settings.data_path();
+	let list: Vec<serde_json::Value> = users
+		.lock()
+		.unwrap()
+		.get_usernames()
+		.into_iter()
+		.map(|username| {
+			serde_json::json!({
+				"name": username,
+				"bytes": crate::http_server::utils::folder_size(&data_path.join(username))
+					+ crate::http_server::utils::folder_size(&data_path.join("public").join(username)),
+			})
+		})
+		.collect();
+
+	return build_json_response(serde_json::Value::from(list));
+}
+
+#[actix_web::get("/admin/settings")]
+pub async fn get_admin_settings(
+	request: actix_web::HttpRequest,
+	users: actix_web::web::Data<Arc<Mutex<crate::http_server::Users>>>,
+	login_attempts: actix_web::web::Data<Arc<Mutex<crate::http_server::LoginAttempts>>>,
+	sessions: actix_web::web::Data<Arc<Mutex<Vec<crate::http_server::Session>>>>,
+	settings: actix_web::web::Data<Arc<Mutex<crate::http_server::Settings>>>,
+	logger: actix_web::web::Data<Arc<Mutex<charlie_buffalo::Logger>>>,
+) -> actix_web::HttpResponse {
+	let settings = settings.lock().unwrap().clone();
+	if let Err(response) = authenticate(
+		&request,
+		Some(&crate::http_server::UserRight::ManageServerSettings),
+		&users,
+		&login_attempts,
+		&sessions,
+		&settings,
+		&logger,
+	)
+	.await
+	{
+		return response;
+	}
+
+	return build_json_response(serde_json::to_value(&settings).unwrap());
+}
+
+/// Replaces all settings, which are validated and saved before being used.
+///
+/// Some of them (like ports or HTTPS) are only used after a restart of the server.
+#[actix_web::put("/admin/settings")]
+pub async fn put_admin_settings(
+	request: actix_web::HttpRequest,
+	new_settings: actix_web::web::Json<crate::http_server::Settings>,
+	users: actix_web::web::Data<Arc<Mutex<crate::http_server::Users>>>,
+	login_attempts: actix_web::web::Data<Arc<Mutex<crate::http_server::LoginAttempts>>>,
+	sessions: actix_web::web::Data<Arc<Mutex<Vec<crate::http_server::Session>>>>,
+	settings: actix_web::web::Data<Arc<Mutex<crate::http_server::Settings>>>,
+	logger: actix_web::web::Data<Arc<Mutex<charlie_buffalo::Logger>>>,
+) -> actix_web::HttpResponse {
+	let current_settings = settings.lock().unwrap().clone();
+	let admin = match authenticate(
+		&request,
+		Some(&crate::http_server::UserRight::ManageServerSettings),
+		&users,
+		&login_attempts,
+		&sessions,
+		&current_settings,
+		&logger,
+	)
+	.await
+	{
+		Ok(admin) => admin,
+		Err(response) => return response,
+	};
+
+	let mut new_settings = new_settings.into_inner();
+	new_settings.workspace_path = current_settings.workspace_path;
+
+	if let Err(errors) = new_settings.validate() {
+		return build_response(
+			&request,
+			actix_web::http::StatusCode::BAD_REQUEST,
+			Some(errors.join(", ")),
+		);
+	}
+
+	if let Err(e) = new_settings.save() {
+		crate::http_server::logging::log(
+			&logger,
+			crate::http_server::logging::Level::Error,
+			"admin",
+			&[],
+			&format!("can not save settings : {e}"),
+		);
+
+		return build_response(
+			&request,
+			actix_web::http::StatusCode::INTERNAL_SERVER_ERROR,
+			None,
+		);
+	}
+
+	users
+		.lock()
+		.unwrap()
+		.set_password_hashing(new_settings.password_hashing.clone().unwrap_or_default());
+	*settings.lock().unwrap() = new_settings;
+
+	crate::http_server::logging::log(
+		&logger,
+		crate::http_server::logging::Level::Info,
+		"admin",
+		&[("username", &admin)],
+		"settings changed",
+	);
+
+	return build_response(&request, actix_web::http::StatusCode::NO_CONTENT, None);
+}
+
+/// Identifies a token without revealing it.
+fn token_id(token: &crate::http_server::AccessBearer) -> String {
+	use sha2::Digest;
+
+	sha2::Sha256::digest(token.get_name().as_bytes())
+		.iter()
+		.take(8)
+		.map(|byte| format!("{byte:02x}"))
+		.collect()
+}
+
+fn build_json_response(body: serde_json::Value) -> actix_web::HttpResponse {
+	actix_web::HttpResponse::Ok()
+		.content_type("application/json")
+		.insert_header((actix_web::http::header::CACHE_CONTROL, "no-store"))
+		.body(body.to_string())
+}
@@ -0,0 +1,828 @@
+use std::sync::{Arc, Mutex};
+
+fn basic(username: &str, password: &str) -> (actix_web::http::header::HeaderName, String) {
+	(
+		actix_web::http::header::AUTHORIZATION,
+		format!(
+			"Basic {}",
+			base64::encode(format!("{}:{}", username, password))
+		),
+	)
+}
+
+#[actix_rt::test]
+async fn tz1ob7wgk3hu8ycr() {
+	let settings = crate::http_server::Settings::new(tempfile::tempdir().unwrap().into_path());
+	let userfile_path = settings.userfile_path();
+	let settings = Arc::new(Mutex::new(settings));
+
+	let mut users = crate::http_server::Users::new();
+	users
+		.insert("admin", &mut String::from("admin_password"))
+		.unwrap();
+	users
+		.add_right("admin", crate::http_server::UserRight::ManageUsers)
+		.unwrap();
+	users
+		.insert("bob", &mut String::from("bob_password"))
+		.unwrap();
+	let users = Arc::new(Mutex::new(users));
+
+	let login_attempts = Arc::new(Mutex::new(crate::http_server::LoginAttempts::new()));
+
+	let access_tokens = Arc::new(Mutex::new(
+		["admin", "bob"]
+			.iter()
+			.map(|username| {
+				crate::http_server::AccessBearer::new(
+					vec![],
+					"https://app.example.com",
+					"https://app.example.com/",
+					username,
+				)
+			})
+			.collect::<Vec<crate::http_server::AccessBearer>>(),
+	));
+	let authorization_codes = Arc::new(Mutex::new(
+		["admin", "bob"]
+			.iter()
+			.map(|username| {
+				crate::http_server::AuthorizationCode::new(
+					vec![],
+					"https://app.example.com",
+					"https://app.example.com/",
+					username,
+					"E9Melhoa2OwvFrEMTJguCHaoeK1t8URWbuGJSstw-cM",
+				)
+			})
+			.collect::<Vec<crate::http_server::AuthorizationCode>>(),
+	));
+	let sessions = Arc::new(Mutex::new(
+		["admin", "bob"]
+			.iter()
+			.map(|username| {
+				crate::http_server::Session::new("127.0.0.1:12345".parse().unwrap(), username)
+			})
+			.collect::<Vec<crate::http_server::Session>>(),
+	));
+
+	let logger = Arc::new(Mutex::new(charlie_buffalo::Logger::new(
+		charlie_buffalo::new_dispatcher(Box::new(|_| {})),
+		None,
+	)));
+
+	let app = actix_web::test::init_service(
+		actix_web::App::new()
+			.app_data(actix_web::web::Data::new(settings))
+			.app_data(actix_web::web::Data::new(users.clone()))
+			.app_data(actix_web::web::Data::new(login_attempts))
+			.app_data(actix_web::web::Data::new(access_tokens.clone()))
+			.app_data(actix_web::web::Data::new(authorization_codes.clone()))
+			.app_data(actix_web::web::Data::new(sessions.clone()))
+			.app_data(actix_web::web::Data::new(logger.clone()))
+			.wrap(crate::http_server::middlewares::Auth { logger })
+			.service(super::get_admin_users)
+			.service(super::post_admin_users)
+			.service(super::delete_admin_user)
+			.service(super::put_admin_user_password)
+			.service(super::put_admin_user_right)
+			.service(super::delete_admin_user_right),
+	)
+	.await;
+
+	let tests: Vec<(
+		usize,
+		actix_web::test::TestRequest,
+		actix_web::http::StatusCode,
+	)> = vec![
+		(
+			010,
+			actix_web::test::TestRequest::get().uri("/admin/users"),
+			actix_web::http::StatusCode::UNAUTHORIZED,
+		),
+		(
+			020,
+			actix_web::test::TestRequest::get()
+				.uri("/admin/users")
+				.insert_header(basic("bob", "bob_password")),
+			actix_web::http::StatusCode::FORBIDDEN,
+		),
+		(
+			030,
+			actix_web::test::TestRequest::get()
+				.uri("/admin/users")
+				.insert_header(basic("admin", "WRONG_PASSWORD")),
+			actix_web::http::StatusCode::UNAUTHORIZED,
+		),
+		(
+			040,
+			actix_web::test::TestRequest::get()
+				.uri("/admin/users")
+				.insert_header((
+					actix_web::http::header::AUTHORIZATION,
+					"Bearer RANDOM_BEARER",
+				)),
+			actix_web::http::StatusCode::UNAUTHORIZED,
+		),
+		(
+			050,
+			actix_web::test::TestRequest::get()
+				.uri("/admin/users")
+				.insert_header(basic("admin", "admin_password")),
+			actix_web::http::StatusCode::OK,
+		),
+		(
+			060,
+			actix_web::test::TestRequest::post()
+				.uri("/admin/users")
+				.insert_header(basic("bob", "bob_password"))
+				.set_json(serde_json::json!({"name": "carol", "password": "carol_password"})),
+			actix_web::http::StatusCode::FORBIDDEN,
+		),
+		(
+			070,
+			actix_web::test::TestRequest::post()
+				.uri("/admin/users")
+				.insert_header(basic("admin", "admin_password"))
+				.set_json(serde_json::json!({
+					"name": "carol",
+					"password": "carol_password",
+					"rights": ["ManageApplications"],
+				})),
+			actix_web::http::StatusCode::CREATED,
+		),
+		(
+			080,
+			actix_web::test::TestRequest::post()
+				.uri("/admin/users")
+				.insert_header(basic("admin", "admin_password"))
+				.set_json(serde_json::json!({"name": "carol", "password": "other_password"})),
+			actix_web::http::StatusCode::CONFLICT,
+		),
+		(
+			090,
+			actix_web::test::TestRequest::post()
+				.uri("/admin/users")
+				.insert_header(basic("admin", "admin_password"))
+				.set_json(serde_json::json!({"name": "public", "password": "password"})),
+			actix_web::http::StatusCode::BAD_REQUEST,
+		),
+		(
+			093,
+			actix_web::test::TestRequest::post()
+				.uri("/admin/users")
+				.insert_header(basic("admin", "admin_password"))
+				.set_json(serde_json::json!({"name": "..", "password": "password"})),
+			actix_web::http::StatusCode::BAD_REQUEST,
+		),
+		(
+			096,
+			actix_web::test::TestRequest::post()
+				.uri("/admin/users")
+				.insert_header(basic("admin", "admin_password"))
+				.set_json(serde_json::json!({"name": ".readyz", "password": "password"})),
+			actix_web::http::StatusCode::BAD_REQUEST,
+		),
+		(
+			100,
+			actix_web::test::TestRequest::post()
+				.uri("/admin/users")
+				.insert_header(basic("admin", "admin_password"))
+				.set_json(serde_json::json!({"name": "dave", "password": ""})),
+			actix_web::http::StatusCode::BAD_REQUEST,
+		),
+		(
+			110,
+			actix_web::test::TestRequest::put()
+				.uri("/admin/users/carol/password")
+				.insert_header(basic("bob", "bob_password"))
+				.set_json(serde_json::json!({"password": "hacked"})),
+			actix_web::http::StatusCode::FORBIDDEN,
+		),
+		(
+			120,
+			actix_web::test::TestRequest::put()
+				.uri("/admin/users/bob/password")
+				.insert_header(basic("bob", "bob_password"))
+				.set_json(serde_json::json!({"password": "bob_new_password"})),
+			actix_web::http::StatusCode::NO_CONTENT,
+		),
+		(
+			130,
+			actix_web::test::TestRequest::put()
+				.uri("/admin/users/bob/password")
+				.insert_header(basic("bob", "bob_password"))
+				.set_json(serde_json::json!({"password": "bob_password"})),
+			actix_web::http::StatusCode::UNAUTHORIZED,
+		),
+		(
+			140,
+			actix_web::test::TestRequest::put()
+				.uri("/admin/users/nobody/password")
+				.insert_header(basic("admin", "admin_password"))
+				.set_json(serde_json::json!({"password": "password"})),
+			actix_web::http::StatusCode::NOT_FOUND,
+		),
+		(
+			150,
+			actix_web::test::TestRequest::put()
+				.uri("/admin/users/carol/rights/ManageUsers")
+				.insert_header(basic("admin", "admin_password")),
+			actix_web::http::StatusCode::NO_CONTENT,
+		),
+		(
+			160,
+			actix_web::test::TestRequest::put()
+				.uri("/admin/users/carol/rights/ManageUsers")
+				.insert_header(basic("admin", "admin_password")),
+			actix_web::http::StatusCode::CONFLICT,
+		),
+		(
+			170,
+			actix_web::test::TestRequest::put()
+				.uri("/admin/users/carol/rights/RANDOM_RIGHT")
+				.insert_header(basic("admin", "admin_password")),
+			actix_web::http::StatusCode::BAD_REQUEST,
+		),
+		(
+			180,
+			actix_web::test::TestRequest::delete()
+				.uri("/admin/users/carol/rights/ManageApplications")
+				.insert_header(basic("admin", "admin_password")),
+			actix_web::http::StatusCode::NO_CONTENT,
+		),
+		(
+			190,
+			actix_web::test::TestRequest::delete()
+				.uri("/admin/users/admin/rights/ManageUsers")
+				.insert_header(basic("admin", "admin_password")),
+			actix_web::http::StatusCode::CONFLICT,
+		),
+		(
+			200,
+			actix_web::test::TestRequest::delete()
+				.uri("/admin/users/admin")
+				.insert_header(basic("admin", "admin_password")),
+			actix_web::http::StatusCode::CONFLICT,
+		),
+		(
+			210,
+			actix_web::test::TestRequest::delete()
+				.uri("/admin/users/nobody")
+				.insert_header(basic("admin", "admin_password")),
+			actix_web::http::StatusCode::NOT_FOUND,
+		),
+		(
+			220,
+			actix_web::test::TestRequest::delete()
+				.uri("/admin/users/bob")
+				.insert_header(basic("bob", "bob_new_password")),
+			actix_web::http::StatusCode::FORBIDDEN,
+		),
+		(
+			230,
+			actix_web::test::TestRequest::delete()
+				.uri("/admin/users/bob")
+				.insert_header(basic("carol", "carol_password")),
+			actix_web::http::StatusCode::NO_CONTENT,
+		),
+	];
+
+	for test in tests {
+		let request = test.1.to_request();
+		print!(
+			"#{:03} : {} request to {} ... ",
+			test.0,
+			request.method(),
+			request.path()
+		);
+
+		let response = actix_web::test::call_service(&app, request).await;
+		assert_eq!(response.status(), test.2);
+
+		if test.2 == actix_web::http::StatusCode::UNAUTHORIZED {
+			assert!(response
+				.headers()
+				.contains_key(actix_web::http::header::WWW_AUTHENTICATE));
+		}
+
+		println!("OK");
+	}
+
+	let request = actix_web::test::TestRequest::get()
+		.uri("/admin/users")
+		.insert_header(basic("admin", "admin_password"))
+		.to_request();
+	let response = actix_web::test::call_service(&app, request).await;
+	let body: serde_json::Value =
+		serde_json::from_slice(&actix_web::test::read_body(response).await).unwrap();
+	assert_eq!(
+		body,
+		serde_json::json!([
+			{"name": "admin", "rights": ["ManageUsers"]},
+			{"name": "carol", "rights": ["ManageUsers"]},
+		])
+	);
+
+	let mut saved_users =
+		crate::http_server::Users::from_bytes(&std::fs::read(&userfile_path).unwrap()).unwrap();
+	assert_eq!(saved_users.get_usernames(), vec!["admin", "carol"]);
+	assert!(saved_users
+		.check("carol", &mut String::from("carol_password"))
+		.is_correct());
+	assert_eq!(
+		std::fs::read_dir(userfile_path.parent().unwrap())
+			.unwrap()
+			.count(),
+		1
+	);
+
+	assert_eq!(
+		access_tokens
+			.lock()
+			.unwrap()
+			.iter()
+			.map(|token| token.get_username())
+			.collect::<Vec<&str>>(),
+		vec!["admin"]
+	);
+	assert_eq!(
+		authorization_codes
+			.lock()
+			.unwrap()
+			.iter()
+			.map(|code| code.get_username())
+			.collect::<Vec<&str>>(),
+		vec!["admin"]
+	);
+	assert_eq!(
+		sessions
+			.lock()
+			.unwrap()
+			.iter()
+			.map(|session| session.get_username())
+			.collect::<Vec<&str>>(),
+		vec!["admin"]
+	);
+}
+
+#[actix_rt::test]
+async fn hx7pq2ml0vdy3rsk() {
+	let workspace_path = tempfile::tempdir().unwrap().into_path();
+	let settings = crate::http_server::Settings::new(workspace_path.clone());
+	settings.save().unwrap();
+	let settings = Arc::new(Mutex::new(settings));
+
+	let mut users = crate::http_server::Users::new();
+	users
+		.insert("admin", &mut String::from("admin_password"))
+		.unwrap();
+	users
+		.add_right("admin", crate::http_server::UserRight::ManageUsers)
+		.unwrap();
+	users
+		.add_right("admin", crate::http_server::UserRight::ManageServerSettings)
+		.unwrap();
+	users
+		.insert("bob", &mut String::from("bob_password"))
+		.unwrap();
+	let users = Arc::new(Mutex::new(users));
+
+	let token = crate::http_server::AccessBearer::new(
+		vec![crate::scope::Scope {
+			module: String::from("documents"),
+			right_type: crate::scope::ScopeRightType::ReadWrite,
+		}],
+		"https://app.example.com",
+		"https://app.example.com/callback",
+		"bob",
+	);
+	let access_tokens = Arc::new(Mutex::new(vec![token]));
+
+	std::fs::create_dir_all(workspace_path.join("data").join("bob").join("documents")).unwrap();
+	std::fs::write(
+		workspace_path
+			.join("data")
+			.join("bob")
+			.join("documents")
+			.join("a.txt"),
+		b"0123456789",
+	)
+	.unwrap();
+	std::fs::create_dir_all(workspace_path.join("data").join("public").join("bob")).unwrap();
+	std::fs::write(
+		workspace_path
+			.join("data")
+			.join("public")
+			.join("bob")
+			.join("b.txt"),
+		b"01234",
+	)
+	.unwrap();
+
+	let mut events = String::new();
+	for path in ["bob/documents/a.txt", "public/bob/b.txt"] {
+		events += &serde_json::to_string(&crate::http_server::DbEvent {
+			id: String::from(path),
+			date: time::OffsetDateTime::now_utc(),
+			method: crate::http_server::DbEventMethod::Create,
+			path: String::from(path),
+			etag: crate::item::Etag::from("ETAG"),
+			user: String::from("bob"),
+			dbversion: String::from(env!("CARGO_PKG_VERSION")),
+		})
+		.unwrap();
+		events += ",\n";
+	}
+	std::fs::write(workspace_path.join("events.bin"), events).unwrap();
+
+	let login_attempts = Arc::new(Mutex::new(crate::http_server::LoginAttempts::new()));
+
+	let logger = Arc::new(Mutex::new(charlie_buffalo::Logger::new(
+		charlie_buffalo::new_dispatcher(Box::new(|_| {})),
+		None,
+	)));
+
+	let app = actix_web::test::init_service(
+		actix_web::App::new()
+			.app_data(actix_web::web::Data::new(settings.clone()))
+			.app_data(actix_web::web::Data::new(users))
+			.app_data(actix_web::web::Data::new(access_tokens.clone()))
+			.app_data(actix_web::web::Data::new(login_attempts))
+			.app_data(actix_web::web::Data::new(Arc::new(Mutex::new(Vec::<
+				crate::http_server::Session,
+			>::new()))))
+			.app_data(actix_web::web::Data::new(Arc::new(Mutex::new(
+				crate::http_server::ProgramState::default(),
+			))))
+			.app_data(actix_web::web::Data::new(workspace_path.clone()))
+			.app_data(actix_web::web::Data::new(logger.clone()))
+			.wrap(crate::http_server::middlewares::Auth { logger })
+			.service(super::get_admin_dashboard)
+			.service(super::get_admin_me)
+			.service(super::get_admin_tokens)
+			.service(super::delete_admin_token)
+			.service(super::get_admin_events)
+			.service(super::get_admin_storage)
+			.service(super::get_admin_settings)
+			.service(super::put_admin_settings),
+	)
+	.await;
+
+	let mut invalid_settings = serde_json::to_value(settings.lock().unwrap().clone()).unwrap();
+	invalid_settings["port"] = serde_json::json!(0);
+	let mut valid_settings = serde_json::to_value(settings.lock().unwrap().clone()).unwrap();
+	valid_settings["admin_email"] = serde_json::json!("admin@example.com");
+
+	let tests: Vec<(
+		usize,
+		actix_web::test::TestRequest,
+		actix_web::http::StatusCode,
+	)> = vec![
+		(
+			010,
+			actix_web::test::TestRequest::get().uri("/admin/"),
+			actix_web::http::StatusCode::UNAUTHORIZED,
+		),
+		(
+			020,
+			actix_web::test::TestRequest::get()
+				.uri("/admin/")
+				.insert_header(basic("bob", "bob_password")),
+			actix_web::http::StatusCode::FORBIDDEN,
+		),
+		(
+			030,
+			actix_web::test::TestRequest::get()
+				.uri("/admin/")
+				.insert_header(basic("admin", "admin_password")),
+			actix_web::http::StatusCode::OK,
+		),
+		(
+			040,
+			actix_web::test::TestRequest::get()
+				.uri("/admin/me")
+				.insert_header(basic("bob", "bob_password")),
+			actix_web::http::StatusCode::OK,
+		),
+		(
+			050,
+			actix_web::test::TestRequest::get()
+				.uri("/admin/tokens")
+				.insert_header(basic("bob", "bob_password")),
+			actix_web::http::StatusCode::FORBIDDEN,
+		),
+		(
+			060,
+			actix_web::test::TestRequest::get()
+				.uri("/admin/events")
+				.insert_header(basic("bob", "bob_password")),
+			actix_web::http::StatusCode::FORBIDDEN,
+		),
+		(
+			070,
+			actix_web::test::TestRequest::get()
+				.uri("/admin/settings")
+				.insert_header(basic("bob", "bob_password")),
+			actix_web::http::StatusCode::FORBIDDEN,
+		),
+		(
+			080,
+			actix_web::test::TestRequest::put()
+				.uri("/admin/settings")
+				.insert_header(basic("admin", "admin_password"))
+				.set_json(invalid_settings),
+			actix_web::http::StatusCode::BAD_REQUEST,
+		),
+		(
+			090,
+			actix_web::test::TestRequest::put()
+				.uri("/admin/settings")
+				.insert_header(basic("admin", "admin_password"))
+				.set_json(valid_settings),
+			actix_web::http::StatusCode::NO_CONTENT,
+		),
+		(
+			100,
+			actix_web::test::TestRequest::delete()
+				.uri("/admin/tokens/RANDOM_ID")
+				.insert_header(basic("admin", "admin_password")),
+			actix_web::http::StatusCode::NOT_FOUND,
+		),
+	];
+
+	for test in tests {
+		let request = test.1.to_request();
+		print!(
+			"#{:03} : {} request to {} ... ",
+			test.0,
+			request.method(),
+			request.path()
+		);
+
+		let response = actix_web::test::call_service(&app, request).await;
+		assert_eq!(response.status(), test.2);
+
+		println!("OK");
+	}
+
+	assert_eq!(
+		settings.lock().unwrap().admin_email,
+		String::from("admin@example.com")
+	);
+	let saved_settings: crate::http_server::Settings =
+		toml::from_slice(&std::fs::read(workspace_path.join("settings.toml")).unwrap()).unwrap();
+	assert_eq!(
+		saved_settings.admin_email,
+		String::from("admin@example.com")
+	);
+
+	let request = actix_web::test::TestRequest::get()
+		.uri("/admin/storage")
+		.insert_header(basic("admin", "admin_password"))
+		.to_request();
+	let response = actix_web::test::call_service(&app, request).await;
+	let body: serde_json::Value =
+		serde_json::from_slice(&actix_web::test::read_body(response).await).unwrap();
+	assert_eq!(
+		body,
+		serde_json::json!([
+			{"name": "admin", "bytes": 0},
+			{"name": "bob", "bytes": 15},
+		])
+	);
+
+	let request = actix_web::test::TestRequest::get()
+		.uri("/admin/events?limit=1")
+		.insert_header(basic("admin", "admin_password"))
+		.to_request();
+	let response = actix_web::test::call_service(&app, request).await;
+	let body: serde_json::Value =
+		serde_json::from_slice(&actix_web::test::read_body(response).await).unwrap();
+	assert_eq!(body.as_array().unwrap().len(), 1);
+	assert_eq!(body[0]["path"], serde_json::json!("public/bob/b.txt"));
+
+	let request = actix_web::test::TestRequest::get()
+		.uri("/admin/tokens")
+		.insert_header(basic("admin", "admin_password"))
+		.to_request();
+	let response = actix_web::test::call_service(&app, request).await;
+	let body: serde_json::Value =
+		serde_json::from_slice(&actix_web::test::read_body(response).await).unwrap();
+	assert_eq!(body.as_array().unwrap().len(), 1);
+	assert_eq!(body[0]["username"], serde_json::json!("bob"));
+	assert_eq!(body[0]["scopes"], serde_json::json!(["documents:rw"]));
+
+	let request = actix_web::test::TestRequest::delete()
+		.uri(&format!(
+			"/admin/tokens/{}",
+			body[0]["id"].as_str().unwrap()
+		))
+		.insert_header(basic("admin", "admin_password"))
+		.to_request();
+	let response = actix_web::test::call_service(&app, request).await;
+	assert_eq!(response.status(), actix_web::http::StatusCode::NO_CONTENT);
+	assert!(access_tokens.lock().unwrap().is_empty());
+}
+
+#[actix_rt::test]
+async fn r6dk2wq9zf0lnxe() {
+	let settings = Arc::new(Mutex::new(crate::http_server::Settings::new(
+		tempfile::tempdir().unwrap().into_path(),
+	)));
+
+	let mut users = crate::http_server::Users::new();
+	users
+		.insert("admin", &mut String::from("admin_password"))
+		.unwrap();
+	users
+		.add_right("admin", crate::http_server::UserRight::ManageUsers)
+		.unwrap();
+	users.start_totp("admin").unwrap();
+	let uri = users.get_totp_setup_uri("admin", "pontus_onyx").unwrap();
+	let secret = base32::decode(
+		base32::Alphabet::RFC4648 { padding: false },
+		uri.split_once("secret=")
+			.unwrap()
+			.1
+			.split('&')
+			.next()
+			.unwrap(),
+	)
+	.unwrap();
+	users
+		.confirm_totp(
+			"admin",
+			&crate::http_server::users::totp::code(
+				&secret,
+				crate::http_server::users::totp::current_step(),
+			),
+		)
+		.unwrap();
+	let users = Arc::new(Mutex::new(users));
+
+	let session = crate::http_server::Session::new("127.0.0.1:12345".parse().unwrap(), "admin");
+	let sessions = Arc::new(Mutex::new(vec![session.clone()]));
+
+	let login_attempts = Arc::new(Mutex::new(crate::http_server::LoginAttempts::new()));
+
+	let logger = Arc::new(Mutex::new(charlie_buffalo::Logger::new(
+		charlie_buffalo::new_dispatcher(Box::new(|_| {})),
+		None,
+	)));
+
+	let app = actix_web::test::init_service(
+		actix_web::App::new()
+			.app_data(actix_web::web::Data::new(settings))
+			.app_data(actix_web::web::Data::new(users))
+			.app_data(actix_web::web::Data::new(login_attempts))
+			.app_data(actix_web::web::Data::new(sessions))
+			.app_data(actix_web::web::Data::new(logger.clone()))
+			.wrap(crate::http_server::middlewares::Auth { logger })
+			.service(super::get_admin_users),
+	)
+	.await;
+
+	let tests = vec![
+		(
+			010,
+			actix_web::test::TestRequest::get()
+				.uri("/admin/users")
+				.peer_addr("127.0.0.1:12345".parse().unwrap())
+				.insert_header(basic("admin", "admin_password")),
+			actix_web::http::StatusCode::FORBIDDEN,
+		),
+		(
+			020,
+			actix_web::test::TestRequest::get()
+				.uri("/admin/users")
+				.peer_addr("127.0.0.1:12345".parse().unwrap())
+				.cookie(actix_web::cookie::Cookie::new(
+					super::super::SESSION_COOKIE_NAME,
+					String::from(session.get_value()),
+				)),
+			actix_web::http::StatusCode::OK,
+		),
+		(
+			030,
+			actix_web::test::TestRequest::get()
+				.uri("/admin/users")
+				.peer_addr("192.168.0.1:12345".parse().unwrap())
+				.cookie(actix_web::cookie::Cookie::new(
+					super::super::SESSION_COOKIE_NAME,
+					String::from(session.get_value()),
+				)),
+			actix_web::http::StatusCode::UNAUTHORIZED,
+		),
+		(
+			040,
+			actix_web::test::TestRequest::get()
+				.uri("/admin/users")
+				.peer_addr("127.0.0.1:12345".parse().unwrap())
+				.cookie(actix_web::cookie::Cookie::new(
+					super::super::SESSION_COOKIE_NAME,
+					"RANDOM_SESSION",
+				)),
+			actix_web::http::StatusCode::UNAUTHORIZED,
+		),
+	];
+
+	for test in tests {
+		print!(
+			"#{:03} : admin with two-factor authentication gets {} ... ",
+			test.0, test.2
+		);
+
+		let response = actix_web::test::call_service(&app, test.1.to_request()).await;
+		assert_eq!(response.status(), test.2);
+
+		println!("OK");
+	}
+}
+
+#[actix_rt::test]
+async fn c3mv8yt1kp5wqhd() {
+	let settings = Arc::new(Mutex::new(crate::http_server::Settings::new(
+		tempfile::tempdir().unwrap().into_path(),
+	)));
+
+	let mut users = crate::http_server::Users::new();
+	users
+		.insert("admin", &mut String::from("admin_password"))
+		.unwrap();
+	users
+		.add_right("admin", crate::http_server::UserRight::ManageUsers)
+		.unwrap();
+	let users = Arc::new(Mutex::new(users));
+
+	let sessions: Arc<Mutex<Vec<crate::http_server::Session>>> = Arc::new(Mutex::new(vec![]));
+	let login_attempts = Arc::new(Mutex::new(crate::http_server::LoginAttempts::new()));
+	let program_state = Arc::new(Mutex::new(crate::http_server::ProgramState::default()));
+
+	let logger = Arc::new(Mutex::new(charlie_buffalo::Logger::new(
+		charlie_buffalo::new_dispatcher(Box::new(|_| {})),
+		None,
+	)));
+
+	let app = actix_web::test::init_service(
+		actix_web::App::new()
+			.app_data(actix_web::web::Data::new(settings))
+			.app_data(actix_web::web::Data::new(users))
+			.app_data(actix_web::web::Data::new(login_attempts))
+			.app_data(actix_web::web::Data::new(sessions.clone()))
+			.app_data(actix_web::web::Data::new(program_state))
+			.app_data(actix_web::web::Data::new(logger.clone()))
+			.wrap(crate::http_server::middlewares::Auth { logger })
+			.service(super::get_admin_dashboard)
+			.service(super::get_admin_me)
+			.service(super::get_admin_users),
+	)
+	.await;
+
+	print!("#010 : dashboard opens a session ... ");
+	let request = actix_web::test::TestRequest::get()
+		.uri("/admin/")
+		.peer_addr("127.0.0.1:12345".parse().unwrap())
+		.insert_header(basic("admin", "admin_password"))
+		.to_request();
+	let response = actix_web::test::call_service(&app, request).await;
+	assert_eq!(response.status(), actix_web::http::StatusCode::OK);
+	let session_cookie = response
+		.response()
+		.cookies()
+		.find(|cookie| cookie.name() == super::super::SESSION_COOKIE_NAME)
+		.unwrap()
+		.into_owned();
+	assert_eq!(sessions.lock().unwrap().len(), 1);
+	println!("OK");
+
+	let tests = vec![
+		(020, "/admin/me", None),
+		(030, "/admin/users", None),
+		// the password is not checked again
+		(040, "/admin/users", Some("WRONG_PASSWORD")),
+		(050, "/admin/", Some("admin_password")),
+	];
+
+	for test in tests {
+		print!(
+			"#{:03} : GET request to {} with session cookie and password {:?} ... ",
+			test.0, test.1, test.2
+		);
+
+		let mut request = actix_web::test::TestRequest::get()
+			.uri(test.1)
+			.peer_addr("127.0.0.1:12345".parse().unwrap())
+			.cookie(session_cookie.clone());
+		if let Some(password) = test.2 {
+			request = request.insert_header(basic("admin", password));
+		}
+		let response = actix_web::test::call_service(&app, request.to_request()).await;
+		assert_eq!(response.status(), actix_web::http::StatusCode::OK);
+
+		println!("OK");
+	}
+
+	// no other session is opened while the current one is still valid
+	assert_eq!(sessions.lock().unwrap().len(), 1);
+}
@@ -0,0 +1,454 @@
+use std::sync::{Arc, Mutex};
+
+use super::{authenticate, build_response};
+
+#[derive(serde::Deserialize)]
+pub struct NewUserRequest {
+	name: String,
+	password: String,
+	#[serde(default)]
+	rights: Vec<crate::http_server::UserRight>,
+}
+
+#[derive(serde::Deserialize)]
+pub struct PasswordRequest {
+	password: String,
+}
+
+#[actix_web::get("/admin/users")]
+pub async fn get_admin_users(
+	request: actix_web::HttpRequest,
+	users: actix_web::web::Data<Arc<Mutex<crate::http_server::Users>>>,
+	login_attempts: actix_web::web::Data<Arc<Mutex<crate::http_server::LoginAttempts>>>,
+	sessions: actix_web::web::Data<Arc<Mutex<Vec<crate::http_server::Session>>>>,
+	settings: actix_web::web::Data<Arc<Mutex<crate::http_server::Settings>>>,
+	logger: actix_web::web::Data<Arc<Mutex<charlie_buffalo::Logger>>>,
+) -> actix_web::HttpResponse {
+	let settings = settings.lock().unwrap().clone();
+	if let Err(response) = authenticate(
+		&request,
+		Some(&crate::http_server::UserRight::ManageUsers),
+		&users,
+		&login_attempts,
+		&sessions,
+		&settings,
+		&logger,
+	)
+	.await
+	{
+		return response;
+	}
+
+	let users = users.lock().unwrap();
+	let list: Vec<serde_json::Value> = users
+		.get_usernames()
+		.into_iter()
+		.map(|username| {
+			serde_json::json!({
+				"name": username,
+				"rights": users.get_rights(username),
+			})
+		})
+		.collect();
+
+	return actix_web::HttpResponse::Ok()
+		.content_type("application/json")
+		.insert_header((actix_web::http::header::CACHE_CONTROL, "no-store"))
+		.body(serde_json::Value::from(list).to_string());
+}
+
+#[actix_web::post("/admin/users")]
+pub async fn post_admin_users(
+	request: actix_web::HttpRequest,
+	new_user: actix_web::web::Json<NewUserRequest>,
+	users: actix_web::web::Data<Arc<Mutex<crate::http_server::Users>>>,
+	login_attempts: actix_web::web::Data<Arc<Mutex<crate::http_server::LoginAttempts>>>,
+	sessions: actix_web::web::Data<Arc<Mutex<Vec<crate::http_server::Session>>>>,
+	settings: actix_web::web::Data<Arc<Mutex<crate::http_server::Settings>>>,
+	logger: actix_web::web::Data<Arc<Mutex<charlie_buffalo::Logger>>>,
+) -> actix_web::HttpResponse {
+	let settings = settings.lock().unwrap().clone();
+	let admin = match authenticate(
+		&request,
+		Some(&crate::http_server::UserRight::ManageUsers),
+		&users,
+		&login_attempts,
+		&sessions,
+		&settings,
+		&logger,
+	)
+	.await
+	{
+		Ok(admin) => admin,
+		Err(response) => return response,
+	};
+
+	let new_user = new_user.into_inner();
+	let name = new_user.name.trim().to_lowercase();
+
+	// same rule than users of other authentication backends, because names are used in paths
+	if !crate::http_server::auth_backends::is_valid_username(&name) {
+		return build_response(
+			&request,
+			actix_web::http::StatusCode::BAD_REQUEST,
+			Some(String::from("incorrect username")),
+		);
+	}
+
+	if new_user.password.is_empty() {
+		return build_response(
+			&request,
+			actix_web::http::StatusCode::BAD_REQUEST,
+			Some(String::from("empty password")),
+		);
+	}
+
+	let mut password = new_user.password;
+	if let Err(response) = apply_change(&request, &users, &settings, &logger, |users| {
+		users.insert(&name, &mut password)?;
+		for right in new_user.rights {
+			users.add_right(&name, right).ok();
+		}
+
+		Ok(())
+	}) {
+		return response;
+	}
+
+	log_change(&logger, &admin, &format!("user `{name}` created"));
+
+	return build_response(&request, actix_web::http::StatusCode::CREATED, None);
+}
+
+/// Also revokes the access tokens, authorization codes and sessions of this user, so they can
+/// not be used anymore if an user with the same name is created later.
+#[allow(clippy::too_many_arguments)]
+#[actix_web::delete("/admin/users/{username}")]
+pub async fn delete_admin_user(
+	request: actix_web::HttpRequest,
+	path: actix_web::web::Path<String>,
+	access_tokens: actix_web::web::Data<Arc<Mutex<Vec<crate::http_server::AccessBearer>>>>,
+	authorization_codes: actix_web::web::Data<
+		Arc<Mutex<Vec<crate::http_server::AuthorizationCode>>>,
+	>,
+	sessions: actix_web::web::Data<Arc<Mutex<Vec<crate::http_server::Session>>>>,
+	users: actix_web::web::Data<Arc<Mutex<crate::http_server::Users>>>,
+	login_attempts: actix_web::web::Data<Arc<Mutex<crate::http_server::LoginAttempts>>>,
+	settings: actix_web::web::Data<Arc<Mutex<crate::http_server::Settings>>>,
+	logger: actix_web::web::Data<Arc<Mutex<charlie_buffalo::Logger>>>,
+) -> actix_web::HttpResponse {
+	let settings = settings.lock().unwrap().clone();
+	let admin = match authenticate(
+		&request,
+		Some(&crate::http_server::UserRight::ManageUsers),
+		&users,
+		&login_attempts,
+		&sessions,
+		&settings,
+		&logger,
+	)
+	.await
+	{
+		Ok(admin) => admin,
+		Err(response) => return response,
+	};
+
+	let username = path.into_inner();
+
+	if username == admin {
+		return build_response(
+			&request,
+			actix_web::http::StatusCode::CONFLICT,
+			Some(String::from("can not delete yourself")),
+		);
+	}
+
+	if let Err(response) = apply_change(&request, &users, &settings, &logger, |users| {
+		users.remove(&username)
+	}) {
+		return response;
+	}
+
+	access_tokens
+		.lock()
+		.unwrap()
+		.retain(|token| token.get_username() != username);
+	authorization_codes
+		.lock()
+		.unwrap()
+		.retain(|code| code.get_username() != username);
+	sessions
+		.lock()
+		.unwrap()
+		.retain(|session| session.get_username() != username);
+
+	log_change(&logger, &admin, &format!("user `{username}` deleted"));
+
+	return build_response(&request, actix_web::http::StatusCode::NO_CONTENT, None);
+}
+
+/// Any user can change its own password, but only users with `ManageUsers` right can reset the
+/// password of other users.
+#[allow(clippy::too_many_arguments)]
+#[actix_web::put("/admin/users/{username}/password")]
+pub async fn put_admin_user_password(
+	request: actix_web::HttpRequest,
+	path: actix_web::web::Path<String>,
+	body: actix_web::web::Json<PasswordRequest>,
+	users: actix_web::web::Data<Arc<Mutex<crate::http_server::Users>>>,
+	login_attempts: actix_web::web::Data<Arc<Mutex<crate::http_server::LoginAttempts>>>,
+	sessions: actix_web::web::Data<Arc<Mutex<Vec<crate::http_server::Session>>>>,
+	settings: actix_web::web::Data<Arc<Mutex<crate::http_server::Settings>>>,
+	logger: actix_web::web::Data<Arc<Mutex<charlie_buffalo::Logger>>>,
+) -> actix_web::HttpResponse {
+	let username = path.into_inner();
+
+	let settings = settings.lock().unwrap().clone();
+	let admin = match authenticate(
+		&request,
+		None,
+		&users,
+		&login_attempts,
+		&sessions,
+		&settings,
+		&logger,
+	)
+	.await
+	{
+		Ok(admin) => admin,
+		Err(response) => return response,
+	};
+
+	if admin != username
+		&& !users
+			.lock()
+			.unwrap()
+			.get_rights(&admin)
+			.map(|rights| rights.contains(&crate::http_server::UserRight::ManageUsers))
+			.unwrap_or(false)
+	{
+		return build_response(
+			&request,
+			actix_web::http::StatusCode::FORBIDDEN,
+			Some(format!(
+				"missing right `{}`",
+				crate::http_server::UserRight::ManageUsers
+			)),
+		);
+	}
+
+	let mut password = body.into_inner().password;
+	if password.is_empty() {
+		return build_response(
+			&request,
+			actix_web::http::StatusCode::BAD_REQUEST,
+			Some(String::from("empty password")),
+		);
+	}
+
+	if let Err(response) = apply_change(&request, &users, &settings, &logger, |users| {
+		users.set_password(&username, &mut password)
+	}) {
+		return response;
+	}
+
+	log_change(
+		&logger,
+		&admin,
+		&format!("password of user `{username}` changed"),
+	);
+
+	return build_response(&request, actix_web::http::StatusCode::NO_CONTENT, None);
+}
+
+#[actix_web::put("/admin/users/{username}/rights/{right}")]
+pub async fn put_admin_user_right(
+	request: actix_web::HttpRequest,
+	path: actix_web::web::Path<(String, String)>,
+	users: actix_web::web::Data<Arc<Mutex<crate::http_server::Users>>>,
+	login_attempts: actix_web::web::Data<Arc<Mutex<crate::http_server::LoginAttempts>>>,
+	sessions: actix_web::web::Data<Arc<Mutex<Vec<crate::http_server::Session>>>>,
+	settings: actix_web::web::Data<Arc<Mutex<crate::http_server::Settings>>>,
+	logger: actix_web::web::Data<Arc<Mutex<charlie_buffalo::Logger>>>,
+) -> actix_web::HttpResponse {
+	let settings = settings.lock().unwrap().clone();
+	let admin = match authenticate(
+		&request,
+		Some(&crate::http_server::UserRight::ManageUsers),
+		&users,
+		&login_attempts,
+		&sessions,
+		&settings,
+		&logger,
+	)
+	.await
+	{
+		Ok(admin) => admin,
+		Err(response) => return response,
+	};
+
+	let (username, right) = path.into_inner();
+	let right = match parse_right(&right) {
+		Some(right) => right,
+		None => {
+			return build_response(
+				&request,
+				actix_web::http::StatusCode::BAD_REQUEST,
+				Some(format!("unknown right `{right}`")),
+			);
+		}
+	};
+
+	if let Err(response) = apply_change(&request, &users, &settings, &logger, |users| {
+		users.add_right(&username, right.clone())
+	}) {
+		return response;
+	}
+
+	log_change(
+		&logger,
+		&admin,
+		&format!("right `{right}` granted to user `{username}`"),
+	);
+
+	return build_response(&request, actix_web::http::StatusCode::NO_CONTENT, None);
+}
+
+#[actix_web::delete("/admin/users/{username}/rights/{right}")]
+pub async fn delete_admin_user_right(
+	request: actix_web::HttpRequest,
+	path: actix_web::web::Path<(String, String)>,
+	users: actix_web::web::Data<Arc<Mutex<crate::http_server::Users>>>,
+	login_attempts: actix_web::web::Data<Arc<Mutex<crate::http_server::LoginAttempts>>>,
+	sessions: actix_web::web::Data<Arc<Mutex<Vec<crate::http_server::Session>>>>,
+	settings: actix_web::web::Data<Arc<Mutex<crate::http_server::Settings>>>,
+	logger: actix_web::web::Data<Arc<Mutex<charlie_buffalo::Logger>>>,
+) -> actix_web::HttpResponse {
+	let settings = settings.lock().unwrap().clone();
+	let admin = match authenticate(
+		&request,
+		Some(&crate::http_server::UserRight::ManageUsers),
+		&users,
+		&login_attempts,
+		&sessions,
+		&settings,
+		&logger,
+	)
+	.await
+	{
+		Ok(admin) => admin,
+		Err(response) => return response,
+	};
+
+	let (username, right) = path.into_inner();
+	let right = match parse_right(&right) {
+		Some(right) => right,
+		None => {
+			return build_response(
+				&request,
+				actix_web::http::StatusCode::BAD_REQUEST,
+				Some(format!("unknown right `{right}`")),
+			);
+		}
+	};
+
+	if username == admin && right == crate::http_server::UserRight::ManageUsers {
+		return build_response(
+			&request,
+			actix_web::http::StatusCode::CONFLICT,
+			Some(format!("can not revoke your own `{right}` right")),
+		);
+	}
+
+	if let Err(response) = apply_change(&request, &users, &settings, &logger, |users| {
+		users.remove_right(&username, right.clone())
+	}) {
+		return response;
+	}
+
+	log_change(
+		&logger,
+		&admin,
+		&format!("right `{right}` revoked from user `{username}`"),
+	);
+
+	return build_response(&request, actix_web::http::StatusCode::NO_CONTENT, None);
+}
+
+/// Rights are named like in `UserRight`, for example `ManageUsers`.
+fn parse_right(right: &str) -> Option<crate::http_server::UserRight> {
+	serde_json::from_value(serde_json::Value::String(String::from(right))).ok()
+}
+
+/// Applies `change` on a copy of the users list, which replaces the current one only if it has
+/// been successfully saved.
+fn apply_change(
+	request: &actix_web::HttpRequest,
+	users: &Arc<Mutex<crate::http_server::Users>>,
+	settings: &crate::http_server::Settings,
+	logger: &Arc<Mutex<charlie_buffalo::Logger>>,
+	change: impl FnOnce(&mut crate::http_server::Users) -> Result<(), crate::http_server::UsersError>,
+) -> Result<(), actix_web::HttpResponse> {
+	let mut users = users.lock().unwrap();
+
+	let mut new_users = users.clone();
+	if let Err(e) = change(&mut new_users) {
+		let status = match e {
+			crate::http_server::UsersError::UserNotFound
+			| crate::http_server::UsersError::GrantNotFound => actix_web::http::StatusCode::NOT_FOUND,
+			crate::http_server::UsersError::PasswordHashing(_) => {
+				crate::http_server::logging::log(
+					logger,
+					crate::http_server::logging::Level::Error,
+					"admin",
+					&[],
+					&e.to_string(),
+				);
+
+				return Err(build_response(
+					request,
+					actix_web::http::StatusCode::INTERNAL_SERVER_ERROR,
+					None,
+				));
+			}
+			crate::http_server::UsersError::UserAlreadyExists
+			| crate::http_server::UsersError::RightAlreadyGranted
+			| crate::http_server::UsersError::RightNotGranted
+			| crate::http_server::UsersError::TotpAlreadyEnabled
+			| crate::http_server::UsersError::TotpNotStarted
+			| crate::http_server::UsersError::WrongTotpCode => actix_web::http::StatusCode::CONFLICT,
+		};
+
+		return Err(build_response(request, status, Some(e.to_string())));
+	}
+
+	if let Err(e) = new_users.save(&settings.userfile_path()) {
+		crate::http_server::logging::log(
+			logger,
+			crate::http_server::logging::Level::Error,
+			"admin",
+			&[],
+			&format!("can not save users list : {e}"),
+		);
+
+		return Err(build_response(
+			request,
+			actix_web::http::StatusCode::INTERNAL_SERVER_ERROR,
+			None,
+		));
+	}
+
+	*users = new_users;
+
+	return Ok(());
+}
+
+fn log_change(logger: &Arc<Mutex<charlie_buffalo::Logger>>, admin: &str, message: &str) {
+	crate::http_server::logging::log(
+		logger,
+		crate::http_server::logging::Level::Info,
+		"admin",
+		&[("username", admin)],
+		message,
+	);
+}
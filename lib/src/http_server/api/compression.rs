@@ -0,0 +1,155 @@
+use std::io::Write;
+
+/// Content codings of responses, by preference of the server when clients accept several of
+/// them with the same weight.
+fn supported_encodings() -> [actix_web::http::header::Encoding; 4] {
+	return [
+		actix_web::http::header::Encoding::brotli(),
+		actix_web::http::header::Encoding::zstd(),
+		actix_web::http::header::Encoding::gzip(),
+		actix_web::http::header::Encoding::identity(),
+	];
+}
+
+/// Content coding of the response according to the `Accept-Encoding` header of this request, or
+/// `None` if its content should be sent as it is.
+///
+/// When clients reject all supported codings (like `identity;q=0`), the content is also sent as
+/// it is, instead of a `406 Not Acceptable` response.
+pub fn negotiate_encoding(
+	request: &actix_web::HttpRequest,
+) -> Option<actix_web::http::header::ContentEncoding> {
+	let accept_encoding: actix_web::http::header::AcceptEncoding =
+		actix_web::http::header::Header::parse(request).ok()?;
+
+	return match accept_encoding.negotiate(supported_encodings().iter()) {
+		Some(actix_web::http::header::Encoding::Known(encoding))
+			if encoding != actix_web::http::header::ContentEncoding::Identity =>
+		{
+			Some(encoding)
+		}
+		_ => None,
+	};
+}
+
+/// Returns `true` for textual content types, and `false` for other ones (like images, videos or
+/// archives) which are already compressed or unknown.
+pub fn is_compressible(content_type: &str) -> bool {
+	let essence = content_type
+		.split(';')
+		.next()
+		.unwrap_or_default()
+		.trim()
+		.to_lowercase();
+
+	return essence.starts_with("text/")
+		|| essence.ends_with("+json")
+		|| essence.ends_with("+xml")
+		|| [
+			"application/json",
+			"application/xml",
+			"application/javascript",
+			"application/ecmascript",
+			"application/x-javascript",
+			"application/x-www-form-urlencoded",
+			"application/yaml",
+			"application/x-yaml",
+			"application/toml",
+			"application/wasm",
+			"image/bmp",
+			"image/x-icon",
+			"image/vnd.microsoft.icon",
+			"font/ttf",
+			"font/otf",
+		]
+		.contains(&essence.as_str());
+}
+
+pub fn compress(
+	content: &[u8],
+	encoding: actix_web::http::header::ContentEncoding,
+) -> std::io::Result<Vec<u8>> {
+	return match encoding {
+		actix_web::http::header::ContentEncoding::Brotli => {
+			// quality of 5 (of 11) is fast enough to compress on each request
+			let mut encoder = brotli::CompressorWriter::new(vec![], 4096, 5, 22);
+			encoder.write_all(content)?;
+			Ok(encoder.into_inner())
+		}
+		actix_web::http::header::ContentEncoding::Zstd => zstd::encode_all(content, 0),
+		actix_web::http::header::ContentEncoding::Gzip => {
+			let mut encoder = flate2::write::GzEncoder::new(vec![], flate2::Compression::default());
+			encoder.write_all(content)?;
+			encoder.finish()
+		}
+		_ => Ok(content.to_vec()),
+	};
+}
+
+/// Sends `content` with the coding negotiated with this request, or as it is.
+///
+/// The `ETag` of the response stays the same strong value whatever its coding, although
+/// RFC 9110 (section 8.8.3) expects distinct ones for each representation : remoteStorage clients
+/// compare it to etags of folder listings and send it back in `If-Match` of `PUT` and `DELETE`
+/// requests, which use the strong comparison, so it has to identify the version of the document
+/// rather than its encoded bytes. `Range` requests, which would rely on it, are not supported.
+pub fn compressed_response(
+	mut response: actix_web::HttpResponseBuilder,
+	request: &actix_web::HttpRequest,
+	content: Vec<u8>,
+) -> actix_web::HttpResponse {
+	if let Some(encoding) = negotiate_encoding(request) {
+		if let Ok(compressed_content) = compress(&content, encoding) {
+			response.insert_header((actix_web::http::header::CONTENT_ENCODING, encoding.as_str()));
+
+			return response.body(compressed_content);
+		}
+	}
+
+	return response.body(content);
+}
+
+/// Same headers than [`compressed_response`] for `HEAD` requests, without compressing `content`.
+///
+/// The body is not sent, but gives the `Content-Length` header, which is omitted when the content
+/// would be encoded (its length is only known once compressed).
+pub fn compressed_head_response(
+	mut response: actix_web::HttpResponseBuilder,
+	request: &actix_web::HttpRequest,
+	content: Vec<u8>,
+) -> actix_web::HttpResponse {
+	if let Some(encoding) = negotiate_encoding(request) {
+		response.insert_header((actix_web::http::header::CONTENT_ENCODING, encoding.as_str()));
+
+		return response.body(actix_web::body::None::new());
+	}
+
+	return response.body(content);
+}
+
+#[test]
+fn h4xw9bq2dmz7ktp() {
+	let tests = vec![
+		(010, "text/plain", true),
+		(020, "text/html; charset=utf-8", true),
+		(030, "application/json", true),
+		(040, "application/ld+json", true),
+		(050, "image/svg+xml", true),
+		(060, "APPLICATION/JAVASCRIPT", true),
+		(070, "image/png", false),
+		(080, "video/mp4", false),
+		(090, "application/zip", false),
+		(100, "application/gzip", false),
+		(110, "font/woff2", false),
+		(120, "application/octet-stream", false),
+		(130, "", false),
+	];
+
+	for test in tests {
+		print!("#{:03} : is {:?} compressible ... ", test.0, test.1);
+
+		assert_eq!(is_compressible(test.1), test.2);
+
+		println!("OK");
+	}
+}
@@ -7,19 +7,11 @@ pub async fn delete_item(
 	database: actix_web::web::Data<std::sync::Arc<std::sync::Mutex<crate::database::Database>>>,
 	logger: actix_web::web::Data<Arc<Mutex<charlie_buffalo::Logger>>>,
 	dbevent_sender: actix_web::web::Data<std::sync::mpsc::Sender<crate::http_server::DbEvent>>,
+	metrics: Option<actix_web::web::Data<Arc<Mutex<crate::http_server::Metrics>>>>,
 	access_tokens: actix_web::web::Data<
 		std::sync::Arc<std::sync::Mutex<Vec<crate::http_server::AccessBearer>>>,
 	>,
 ) -> impl actix_web::Responder {
-	// TODO : check security issue about this ?
-	let all_origins = actix_web::http::header::HeaderValue::from_bytes(b"*").unwrap();
-	let origin = request
-		.headers()
-		.get(actix_web::http::header::ORIGIN)
-		.unwrap_or(&all_origins)
-		.to_str()
-		.unwrap();
-
 	let local_path = crate::item::ItemPath::from(path.into_inner().as_str());
 
 	match database.lock().unwrap().delete(
@@ -52,7 +44,7 @@ pub async fn delete_item(
 				None => String::from("Unknown"),
 			};
 
-			dbevent_sender
+			if dbevent_sender
 				.send(crate::http_server::DbEvent {
 					id: ulid::Ulid::new().to_string(),
 					method: crate::http_server::DbEventMethod::Delete,
@@ -62,10 +54,14 @@ pub async fn delete_item(
 					user,
 					dbversion: String::from(env!("CARGO_PKG_VERSION")),
 				})
-				.ok();
+				.is_ok()
+			{
+				if let Some(metrics) = &metrics {
+					metrics.lock().unwrap().record_event_queued();
+				}
+			}
 
 			return crate::database::build_http_json_response(
-				origin,
 				request.method(),
 				actix_web::http::StatusCode::OK,
 				Some(etag),
@@ -79,14 +75,12 @@ pub async fn delete_item(
 				return crate::database::Error::to_response(
 					&*e.downcast::<crate::database::sources::memory::DeleteError>()
 						.unwrap(),
-					origin,
 					true,
 				);
 			} else if e.is::<crate::database::sources::folder::DeleteError>() {
 				return crate::database::Error::to_response(
 					&*e.downcast::<crate::database::sources::folder::DeleteError>()
 						.unwrap(),
-					origin,
 					true,
 				);
 			} else {
@@ -101,7 +95,6 @@ pub async fn delete_item(
 				);
 
 				return crate::database::build_http_json_response(
-					origin,
 					request.method(),
 					actix_web::http::StatusCode::INTERNAL_SERVER_ERROR,
 					None,
@@ -7,19 +7,11 @@ pub async fn get_item(
 	database: actix_web::web::Data<std::sync::Arc<std::sync::Mutex<crate::database::Database>>>,
 	logger: actix_web::web::Data<Arc<Mutex<charlie_buffalo::Logger>>>,
 ) -> impl actix_web::Responder {
-	// TODO : check security issue about this ?
-	let all_origins = actix_web::http::header::HeaderValue::from_bytes(b"*").unwrap();
-	let origin = request
-		.headers()
-		.get(actix_web::http::header::ORIGIN)
-		.unwrap_or(&all_origins)
-		.to_str()
-		.unwrap();
-
 	let local_path = crate::item::ItemPath::from(path.into_inner().as_str());
 
 	// TODO : If-Match does not works with GET ?
-	match database.lock().unwrap().get(
+	// the lock of the database is released before building (and compressing) the response
+	let item = database.lock().unwrap().get(
 		&local_path,
 		super::convert_actix_if_match(&request)
 			.first()
@@ -27,7 +19,9 @@ pub async fn get_item(
 		&super::convert_actix_if_none_match(&request)
 			.iter()
 			.collect::<Vec<&crate::item::Etag>>(),
-	) {
+	);
+
+	match item {
 		Ok(crate::item::Item::Document {
 			etag,
 			content: Some(content),
@@ -49,19 +43,16 @@ pub async fn get_item(
 				));
 			}
 			response.insert_header((actix_web::http::header::CACHE_CONTROL, "no-cache"));
-			response.insert_header((actix_web::http::header::ACCESS_CONTROL_ALLOW_ORIGIN, origin));
-
-			if origin != "*" {
-				response.insert_header((actix_web::http::header::VARY, "Origin"));
-			}
-
-			response.insert_header((
-				actix_web::http::header::ACCESS_CONTROL_EXPOSE_HEADERS,
-				"Content-Length, Content-Type, Etag, Last-Modified",
-			));
+			response.insert_header((actix_web::http::header::VARY, "Accept-Encoding"));
+			// already compressed media (like images or videos) are not compressed again
+			let is_compressible = super::compression::is_compressible(&content_type);
 			response.content_type(content_type);
 
-			return response.body(content);
+			return if is_compressible {
+				super::compression::compressed_response(response, &request, content)
+			} else {
+				response.body(content)
+			};
 		}
 		Ok(crate::item::Item::Folder {
 			etag: folder_etag,
@@ -112,7 +103,6 @@ pub async fn get_item(
 						);
 
 						return crate::database::build_http_json_response(
-							origin,
 							request.method(),
 							actix_web::http::StatusCode::INTERNAL_SERVER_ERROR,
 							None,
@@ -130,23 +120,17 @@ pub async fn get_item(
 			response.content_type("application/ld+json");
 			response.insert_header((actix_web::http::header::ETAG, folder_etag));
 			response.insert_header((actix_web::http::header::CACHE_CONTROL, "no-cache"));
-			response.insert_header((actix_web::http::header::ACCESS_CONTROL_ALLOW_ORIGIN, origin));
+			response.insert_header((actix_web::http::header::VARY, "Accept-Encoding"));
 
-			if origin != "*" {
-				response.insert_header((actix_web::http::header::VARY, "Origin"));
-			}
-
-			response.insert_header((
-				actix_web::http::header::ACCESS_CONTROL_EXPOSE_HEADERS,
-				"Content-Length, Content-Type, Etag, Last-Modified",
-			));
-
-			return response.body(
+			return super::compression::compressed_response(
+				response,
+				&request,
 				serde_json::json!({
 					"@context": "http://remotestorage.io/spec/folder-description",
 					"items": items_result,
 				})
-				.to_string(),
+				.to_string()
+				.into_bytes(),
 			);
 		}
 		Ok(crate::item::Item::Document { content: None, .. }) => {
@@ -161,7 +145,6 @@ pub async fn get_item(
 			);
 
 			return crate::database::build_http_json_response(
-				origin,
 				request.method(),
 				actix_web::http::StatusCode::INTERNAL_SERVER_ERROR,
 				None,
@@ -182,7 +165,6 @@ pub async fn get_item(
 			);
 
 			return crate::database::build_http_json_response(
-				origin,
 				request.method(),
 				actix_web::http::StatusCode::INTERNAL_SERVER_ERROR,
 				None,
@@ -192,18 +174,16 @@ pub async fn get_item(
 			);
 		}
 		Err(e) => {
-			if e.is::<crate::database::sources::memory::GetError>() {
+			let mut response = if e.is::<crate::database::sources::memory::GetError>() {
 				crate::database::Error::to_response(
 					&*e.downcast::<crate::database::sources::memory::GetError>()
 						.unwrap(),
-					origin,
 					true,
 				)
 			} else if e.is::<crate::database::sources::folder::GetError>() {
 				crate::database::Error::to_response(
 					&*e.downcast::<crate::database::sources::folder::GetError>()
 						.unwrap(),
-					origin,
 					true,
 				)
 			} else {
@@ -218,7 +198,6 @@ pub async fn get_item(
 				);
 
 				crate::database::build_http_json_response(
-					origin,
 					request.method(),
 					actix_web::http::StatusCode::INTERNAL_SERVER_ERROR,
 					None,
@@ -226,7 +205,15 @@ pub async fn get_item(
 					None,
 					true,
 				)
-			}
+			};
+
+			// caches have to know that responses to conditional requests also depend on it
+			response.headers_mut().insert(
+				actix_web::http::header::VARY,
+				actix_web::http::header::HeaderValue::from_static("Accept-Encoding"),
+			);
+
+			response
 		}
 	}
 }
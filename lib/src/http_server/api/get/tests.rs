@@ -244,3 +244,200 @@ async fn if_none_match() {
 		println!("OK");
 	}
 }
+
+#[actix_rt::test]
+async fn compression() {
+	let text = "HELLO WORLD ".repeat(100);
+
+	let database =
+		crate::database::Database::new(Box::new(crate::database::sources::MemoryStorage {
+			root_item: crate::item::Item::new_folder(vec![(
+				"user",
+				crate::item::Item::new_folder(vec![(
+					"a",
+					crate::item::Item::new_folder(vec![
+						(
+							"text",
+							crate::item::Item::Document {
+								etag: crate::item::Etag::from("A"),
+								content: Some(text.as_bytes().to_vec()),
+								content_type: crate::item::ContentType::from("text/plain"),
+								last_modified: Some(time::OffsetDateTime::now_utc()),
+							},
+						),
+						(
+							"image",
+							crate::item::Item::Document {
+								etag: crate::item::Etag::from("B"),
+								content: Some(text.as_bytes().to_vec()),
+								content_type: crate::item::ContentType::from("image/png"),
+								last_modified: Some(time::OffsetDateTime::now_utc()),
+							},
+						),
+					]),
+				)]),
+			)]),
+		}));
+	let database = std::sync::Arc::new(std::sync::Mutex::new(database));
+
+	let logger = charlie_buffalo::Logger::new(
+		charlie_buffalo::new_dispatcher(Box::from(move |log: charlie_buffalo::Log| {
+			println!("{:?} : {:?}", log.attributes, log.content);
+		})),
+		charlie_buffalo::new_dropper(Box::from(|_: &charlie_buffalo::Logger| {})),
+	);
+	let logger = std::sync::Arc::new(std::sync::Mutex::new(logger));
+
+	let app = actix_web::test::init_service(
+		actix_web::App::new()
+			.app_data(actix_web::web::Data::new(database))
+			.app_data(actix_web::web::Data::new(logger))
+			.service(super::get_item)
+			.service(super::super::head_item),
+	)
+	.await;
+
+	let tests = vec![
+		(010, "/storage/user/a/text", None, None),
+		(020, "/storage/user/a/text", Some("gzip"), Some("gzip")),
+		(030, "/storage/user/a/text", Some("br"), Some("br")),
+		(040, "/storage/user/a/text", Some("zstd"), Some("zstd")),
+		(
+			050,
+			"/storage/user/a/text",
+			Some("gzip;q=0.5, br;q=0.8"),
+			Some("br"),
+		),
+		(060, "/storage/user/a/text", Some("identity"), None),
+		(070, "/storage/user/a/text", Some("deflate"), None),
+		(080, "/storage/user/a/image", Some("gzip"), None),
+		(090, "/storage/user/a/", Some("gzip"), Some("gzip")),
+	];
+
+	for test in tests {
+		print!(
+			"#{:03} : GET request to {} with Accept-Encoding = {:?} ... ",
+			test.0, test.1, test.2
+		);
+
+		let mut request = actix_web::test::TestRequest::get().uri(test.1);
+		if let Some(accept_encoding) = test.2 {
+			request =
+				request.insert_header((actix_web::http::header::ACCEPT_ENCODING, accept_encoding));
+		}
+		let response = actix_web::test::call_service(&app, request.to_request()).await;
+
+		assert_eq!(response.status(), StatusCode::OK);
+		assert_eq!(
+			response
+				.headers()
+				.get(actix_web::http::header::VARY)
+				.unwrap(),
+			"Accept-Encoding"
+		);
+		let content_encoding = response
+			.headers()
+			.get(actix_web::http::header::CONTENT_ENCODING)
+			.map(|value| String::from(value.to_str().unwrap()));
+		assert_eq!(content_encoding.as_deref(), test.3);
+		let etag = response
+			.headers()
+			.get(actix_web::http::header::ETAG)
+			.map(|value| String::from(value.to_str().unwrap()))
+			.unwrap();
+
+		let body = actix_web::test::read_body(response).await.to_vec();
+
+		let mut request = actix_web::test::TestRequest::default()
+			.method(Method::HEAD)
+			.uri(test.1);
+		if let Some(accept_encoding) = test.2 {
+			request =
+				request.insert_header((actix_web::http::header::ACCEPT_ENCODING, accept_encoding));
+		}
+		let response = actix_web::test::call_service(&app, request.to_request()).await;
+
+		assert_eq!(response.status(), StatusCode::OK);
+		assert_eq!(
+			response
+				.headers()
+				.get(actix_web::http::header::VARY)
+				.unwrap(),
+			"Accept-Encoding"
+		);
+		assert_eq!(
+			response
+				.headers()
+				.get(actix_web::http::header::CONTENT_ENCODING)
+				.map(|value| value.to_str().unwrap()),
+			test.3
+		);
+		assert_eq!(
+			response
+				.headers()
+				.get(actix_web::http::header::ETAG)
+				.map(|value| value.to_str().unwrap()),
+			Some(etag.as_str())
+		);
+		// dropped when sent, but sets the `Content-Length` header of the response
+		assert_eq!(
+			actix_web::body::MessageBody::size(response.response().body()),
+			match test.3 {
+				Some(_) => actix_web::body::BodySize::None,
+				None => actix_web::body::BodySize::Sized(body.len() as u64),
+			}
+		);
+
+		let body = match test.3 {
+			Some("gzip") => {
+				let mut result = vec![];
+				std::io::Read::read_to_end(
+					&mut flate2::read::GzDecoder::new(&body[..]),
+					&mut result,
+				)
+				.unwrap();
+				result
+			}
+			Some("br") => {
+				let mut result = vec![];
+				std::io::Read::read_to_end(
+					&mut brotli::Decompressor::new(&body[..], 4096),
+					&mut result,
+				)
+				.unwrap();
+				result
+			}
+			Some("zstd") => zstd::decode_all(&body[..]).unwrap(),
+			_ => body,
+		};
+		if test.1.ends_with('/') {
+			let listing: serde_json::Value = serde_json::from_slice(&body).unwrap();
+			assert_eq!(listing["items"]["text"]["ETag"], "A");
+		} else {
+			assert_eq!(body, text.as_bytes());
+		}
+
+		let mut request = actix_web::test::TestRequest::get()
+			.uri(test.1)
+			// like proxies which compress responses, which weaken their etags
+			.insert_header(actix_web::http::header::IfNoneMatch::Items(vec![
+				EntityTag::new(test.3.is_some(), etag),
+			]));
+		if let Some(accept_encoding) = test.2 {
+			request =
+				request.insert_header((actix_web::http::header::ACCEPT_ENCODING, accept_encoding));
+		}
+		let response = actix_web::test::call_service(&app, request.to_request()).await;
+
+		assert_ne!(response.status(), StatusCode::OK);
+		assert_eq!(
+			response
+				.headers()
+				.get(actix_web::http::header::VARY)
+				.unwrap(),
+			"Accept-Encoding"
+		);
+
+		println!("OK");
+	}
+}
@@ -7,18 +7,10 @@ pub async fn head_item(
 	database: actix_web::web::Data<std::sync::Arc<std::sync::Mutex<crate::database::Database>>>,
 	logger: actix_web::web::Data<Arc<Mutex<charlie_buffalo::Logger>>>,
 ) -> impl actix_web::Responder {
-	// TODO : check security issue about this ?
-	let all_origins = actix_web::http::header::HeaderValue::from_bytes(b"*").unwrap();
-	let origin = request
-		.headers()
-		.get(actix_web::http::header::ORIGIN)
-		.unwrap_or(&all_origins)
-		.to_str()
-		.unwrap();
-
 	let local_path = crate::item::ItemPath::from(path.into_inner().as_str());
 
-	match database.lock().unwrap().get(
+	// the lock of the database is released before building (and compressing) the response
+	let item = database.lock().unwrap().get(
 		&local_path,
 		super::convert_actix_if_match(&request)
 			.first()
@@ -26,12 +18,14 @@ pub async fn head_item(
 		&super::convert_actix_if_none_match(&request)
 			.iter()
 			.collect::<Vec<&crate::item::Etag>>(),
-	) {
+	);
+
+	match item {
 		Ok(crate::item::Item::Document {
 			etag,
+			content: Some(content),
 			content_type,
 			last_modified,
-			..
 		}) => {
 			let etag: String = etag.into();
 			let mut response = actix_web::HttpResponse::Ok();
@@ -45,20 +39,37 @@ pub async fn head_item(
 				));
 			}
 			response.insert_header((actix_web::http::header::CACHE_CONTROL, "no-cache"));
-			response.insert_header((actix_web::http::header::ACCESS_CONTROL_ALLOW_ORIGIN, origin));
-			response.insert_header((
-				actix_web::http::header::ACCESS_CONTROL_EXPOSE_HEADERS,
-				"Content-Length, Content-Type, Etag, Last-Modified",
-			));
-
-			if origin != "*" {
-				response.insert_header((actix_web::http::header::VARY, "Origin"));
-			}
+			response.insert_header((actix_web::http::header::VARY, "Accept-Encoding"));
 
 			let content_type: String = content_type.into();
+			let is_compressible = super::compression::is_compressible(&content_type);
 			response.content_type(content_type);
 
-			return response.finish();
+			return if is_compressible {
+				super::compression::compressed_head_response(response, &request, content)
+			} else {
+				response.body(content)
+			};
+		}
+		Ok(crate::item::Item::Document { content: None, .. }) => {
+			logger.lock().unwrap().push(
+				vec![
+					(String::from("level"), String::from("ERROR")),
+					(String::from("module"), String::from("https?")),
+					(String::from("method"), String::from("HEAD")),
+					(String::from("path"), local_path.to_string()),
+				],
+				Some("document has no content"),
+			);
+
+			return crate::database::build_http_json_response(
+				request.method(),
+				actix_web::http::StatusCode::INTERNAL_SERVER_ERROR,
+				None,
+				None,
+				None,
+				false,
+			);
 		}
 		Ok(crate::item::Item::Folder {
 			etag: folder_etag,
@@ -109,7 +120,6 @@ pub async fn head_item(
 						);
 
 						return crate::database::build_http_json_response(
-							origin,
 							request.method(),
 							actix_web::http::StatusCode::INTERNAL_SERVER_ERROR,
 							None,
@@ -126,17 +136,18 @@ pub async fn head_item(
 			response.content_type("application/ld+json");
 			response.insert_header((actix_web::http::header::ETAG, folder_etag));
 			response.insert_header((actix_web::http::header::CACHE_CONTROL, "no-cache"));
-			response.insert_header((actix_web::http::header::ACCESS_CONTROL_ALLOW_ORIGIN, origin));
-			response.insert_header((
-				actix_web::http::header::ACCESS_CONTROL_EXPOSE_HEADERS,
-				"Content-Length, Content-Type, Etag, Last-Modified",
-			));
+			response.insert_header((actix_web::http::header::VARY, "Accept-Encoding"));
 
-			if origin != "*" {
-				response.insert_header((actix_web::http::header::VARY, "Origin"));
-			}
-
-			return response.finish();
+			return super::compression::compressed_head_response(
+				response,
+				&request,
+				serde_json::json!({
+					"@context": "http://remotestorage.io/spec/folder-description",
+					"items": items_result,
+				})
+				.to_string()
+				.into_bytes(),
+			);
 		}
 		Ok(crate::item::Item::Folder { content: None, .. }) => {
 			logger.lock().unwrap().push(
@@ -150,7 +161,6 @@ pub async fn head_item(
 			);
 
 			crate::database::build_http_json_response(
-				origin,
 				request.method(),
 				actix_web::http::StatusCode::INTERNAL_SERVER_ERROR,
 				None,
@@ -160,18 +170,16 @@ pub async fn head_item(
 			)
 		}
 		Err(e) => {
-			if e.is::<crate::database::sources::memory::GetError>() {
+			let mut response = if e.is::<crate::database::sources::memory::GetError>() {
 				crate::database::Error::to_response(
 					&*e.downcast::<crate::database::sources::memory::GetError>()
 						.unwrap(),
-					origin,
 					true,
 				)
 			} else if e.is::<crate::database::sources::folder::GetError>() {
 				crate::database::Error::to_response(
 					&*e.downcast::<crate::database::sources::folder::GetError>()
 						.unwrap(),
-					origin,
 					true,
 				)
 			} else {
@@ -186,7 +194,6 @@ pub async fn head_item(
 				);
 
 				crate::database::build_http_json_response(
-					origin,
 					request.method(),
 					actix_web::http::StatusCode::INTERNAL_SERVER_ERROR,
 					None,
@@ -194,7 +201,15 @@ pub async fn head_item(
 					None,
 					true,
 				)
-			}
+			};
+
+			// caches have to know that responses to conditional requests also depend on it
+			response.headers_mut().insert(
+				actix_web::http::header::VARY,
+				actix_web::http::header::HeaderValue::from_static("Accept-Encoding"),
+			);
+
+			response
 		}
 	}
 }
@@ -1,3 +1,6 @@
+mod account;
+mod admin;
+mod compression;
 mod delete;
 mod get;
 mod head;
@@ -5,11 +8,13 @@ mod oauth;
 mod options;
 mod put;
 
+pub use account::{get_account, post_account};
+pub use admin::*;
 pub use delete::delete_item;
 pub use get::get_item;
 pub use head::head_item;
 pub use oauth::*;
-pub use options::options_item;
+pub use options::{join_methods, options_item, storage_methods};
 pub use put::put_item;
 
 fn convert_actix_if_match(request: &actix_web::HttpRequest) -> Vec<crate::item::Etag> {
@@ -45,3 +50,80 @@ fn convert_actix_if_none_match(request: &actix_web::HttpRequest) -> Vec<crate::i
 		Err(_) => vec![],
 	}
 }
+
+const SESSION_COOKIE_NAME: &str = "pontus_onyx_session";
+
+/// Returns `true` if this request has the cookie of a valid session of `username`, which was
+/// opened from the same IP address.
+fn has_session(
+	request: &actix_web::HttpRequest,
+	sessions: &std::sync::Arc<std::sync::Mutex<Vec<crate::http_server::Session>>>,
+	username: &str,
+) -> bool {
+	session_username(request, sessions).as_deref() == Some(username)
+}
+
+/// Name of the user of the session of the cookie of this request, if it is still opened.
+fn session_username(
+	request: &actix_web::HttpRequest,
+	sessions: &std::sync::Arc<std::sync::Mutex<Vec<crate::http_server::Session>>>,
+) -> Option<String> {
+	let value = String::from(request.cookie(SESSION_COOKIE_NAME)?.value());
+	let ip = request.peer_addr()?;
+
+	sessions
+		.lock()
+		.unwrap()
+		.iter()
+		.find(|session| {
+			session.get_value() == value && session.is_from(&ip) && !session.has_expirated()
+		})
+		.map(|session| String::from(session.get_username()))
+}
+
+/// Opens a new session of `username`, which avoids to ask its password again.
+///
+/// Sessions are bound to the IP address of the user, so none is opened if it is unknown.
+fn open_session(
+	request: &actix_web::HttpRequest,
+	sessions: &std::sync::Arc<std::sync::Mutex<Vec<crate::http_server::Session>>>,
+	username: &str,
+	secure: bool,
+) -> Option<actix_web::cookie::Cookie<'static>> {
+	let session = crate::http_server::Session::new(request.peer_addr()?, username);
+
+	let mut sessions = sessions.lock().unwrap();
+	sessions.retain(|session| !session.has_expirated());
+	sessions.push(session.clone());
+
+	let cookie =
+		actix_web::cookie::Cookie::build(SESSION_COOKIE_NAME, String::from(session.get_value()))
+			.path("/")
+			.http_only(true)
+			.secure(secure)
+			.same_site(actix_web::cookie::SameSite::Lax)
+			.max_age(actix_web::cookie::time::Duration::minutes(30))
+			.finish();
+
+	Some(cookie)
+}
+
+/// Closes the session of the cookie of this request, if any.
+fn close_session(
+	request: &actix_web::HttpRequest,
+	sessions: &std::sync::Arc<std::sync::Mutex<Vec<crate::http_server::Session>>>,
+) -> actix_web::cookie::Cookie<'static> {
+	if let Some(cookie) = request.cookie(SESSION_COOKIE_NAME) {
+		sessions
+			.lock()
+			.unwrap()
+			.retain(|session| session.get_value() != cookie.value());
+	}
+
+	let mut cookie = actix_web::cookie::Cookie::build(SESSION_COOKIE_NAME, "")
+		.path("/")
+		.finish();
+	cookie.make_removal();
+
+	cookie
+}
@@ -5,8 +5,12 @@ pub struct OauthGetQuery {
 	client_id: String,
 	response_type: String,
 	auth_result: Option<String>,
+	state: Option<String>,
+	code_challenge: Option<String>,
+	code_challenge_method: Option<String>,
 }
 
+#[allow(clippy::too_many_arguments)]
 #[actix_web::get("/oauth/{username}")]
 pub async fn get_oauth(
 	path: actix_web::web::Path<String>,
@@ -15,28 +19,132 @@ pub async fn get_oauth(
 	form_tokens: actix_web::web::Data<
 		std::sync::Arc<std::sync::Mutex<Vec<crate::http_server::middlewares::OauthFormToken>>>,
 	>,
+	sessions: actix_web::web::Data<
+		std::sync::Arc<std::sync::Mutex<Vec<crate::http_server::Session>>>,
+	>,
+	settings: actix_web::web::Data<std::sync::Arc<std::sync::Mutex<crate::http_server::Settings>>>,
+	users: actix_web::web::Data<std::sync::Arc<std::sync::Mutex<crate::http_server::Users>>>,
+	access_tokens: actix_web::web::Data<
+		std::sync::Arc<std::sync::Mutex<Vec<crate::http_server::AccessBearer>>>,
+	>,
+	authorization_codes: actix_web::web::Data<
+		std::sync::Arc<std::sync::Mutex<Vec<crate::http_server::AuthorizationCode>>>,
+	>,
+	logger: actix_web::web::Data<std::sync::Arc<std::sync::Mutex<charlie_buffalo::Logger>>>,
 ) -> impl actix_web::Responder {
 	let username = path.into_inner();
 
+	match query.response_type.as_str() {
+		"token" => {}
+		"code" => {
+			if query
+				.code_challenge
+				.as_deref()
+				.unwrap_or_default()
+				.is_empty()
+			{
+				return super::build_error_page(
+					"This client uses the authorization code flow without PKCE code challenge, which is not supported.",
+				);
+			}
+			if query.code_challenge_method.as_deref() != Some("S256") {
+				return super::build_error_page(
+					"This client does not use the `S256` PKCE code challenge method, which is the only one supported.",
+				);
+			}
+		}
+		_ => {
+			return super::build_error_page(
+				"This client requested an unsupported response type (only `token` and `code` are supported).",
+			);
+		}
+	}
+
+	if let Err(message) = super::check_client(
+		&query.client_id,
+		&query.redirect_uri,
+		&settings.lock().unwrap(),
+	) {
+		return super::build_error_page(&message);
+	}
+
+	let requested_scopes: Vec<crate::scope::Scope> = match super::decode(&query.scope)
+		.split(' ')
+		.map(|scope| crate::scope::Scope::try_from(scope.trim()))
+		.collect()
+	{
+		Ok(requested_scopes) => requested_scopes,
+		Err(_) => {
+			return super::build_error_page("This client requested an incorrect scope.");
+		}
+	};
+
+	let client_id = super::decode(&query.client_id);
+	let already_granted =
+		users
+			.lock()
+			.unwrap()
+			.is_granted(&username, &client_id, &requested_scopes);
+
+	if already_granted && super::super::has_session(&request, &sessions, &username) {
+		logger.lock().unwrap().push(
+			vec![
+				(String::from("event"), String::from("oauth")),
+				(String::from("level"), String::from("INFO")),
+				(String::from("username"), username.clone()),
+			],
+			Some(&format!(
+				"access already allowed to `{client_id}`, consent skipped"
+			)),
+		);
+
+		let redirect = super::authorize(
+			super::AuthorizationRequest {
+				response_type: query.response_type.clone(),
+				scopes: requested_scopes,
+				client_id,
+				redirect_uri: super::decode(&query.redirect_uri),
+				username,
+				state: query.state.as_deref().map(super::decode),
+				code_challenge: query.code_challenge.clone().unwrap_or_default(),
+			},
+			&access_tokens,
+			&authorization_codes,
+		);
+
+		return actix_web::HttpResponse::Found()
+			.insert_header((actix_web::http::header::LOCATION, redirect))
+			.finish();
+	}
+
 	let mut response = actix_web::HttpResponse::build(actix_web::http::StatusCode::OK);
 
 	// TODO : sanitize user data before printing it ?
-	// TODO : do not panic if input data is incorrect (especially scopes)
 
-	let scopes = pct_str::PctString::new(&query.scope)
-		.unwrap()
-		.decode()
-		.split(' ')
-		.map(|scope_string| {
-			(std::convert::TryFrom::try_from(scope_string.trim())
-				as Result<crate::scope::Scope, crate::scope::ScopeParsingError>)
-				.unwrap()
-		})
+	let scopes = requested_scopes
+		.iter()
 		.map(|scope| {
+			let select = format!(
+				r#"<select name="{}{}">{}<option value="">no access</option></select>"#,
+				super::GRANT_FIELD_PREFIX,
+				scope.module,
+				match scope.right_type {
+					crate::scope::ScopeRightType::ReadWrite => format!(
+						r#"<option value="rw" selected>{}</option><option value="r">{}</option>"#,
+						crate::scope::ScopeRightType::ReadWrite,
+						crate::scope::ScopeRightType::Read,
+					),
+					crate::scope::ScopeRightType::Read => format!(
+						r#"<option value="r" selected>{}</option>"#,
+						crate::scope::ScopeRightType::Read,
+					),
+				}
+			);
+
 			if scope.module == "*" {
 				format!(
 					r#"{} on {}<br><i style="color:red;">{}<br>{}</i>"#,
-					scope.right_type,
+					select,
 					r#"<strong style="color:red;">all modules</strong>"#,
 					r#"It is maybe an security issue."#,
 					r#"You should accept it only if you know why this client need it !"#,
@@ -44,7 +152,7 @@ pub async fn get_oauth(
 			} else {
 				format!(
 					r#"{} on <a href="../storage/{}/{}/">/storage/{}/{}/</a> and <a href="../storage/public/{}/{}/">/storage/public/{}/{}/</a>"#,
-					scope.right_type, username, scope.module, username, scope.module, username, scope.module, username, scope.module
+					select, username, scope.module, username, scope.module, username, scope.module, username, scope.module
 				)
 			}
 		})
@@ -79,19 +187,20 @@ pub async fn get_oauth(
 		<h1>Allow access ?</h1>
 		<p>You are on your account management for this database.</p>
 		<p>The client : {}</p>
-		<p>Request following access to this scope(s) : <ul>{}</ul></p>
 		<form method="post" action="/oauth">
+			<p>Request following access to this scope(s), which you can reduce : <ul>{}</ul></p>{}
 			<input type="hidden" name="client_id" value="{}">
 			<input type="hidden" name="redirect_uri" value="{}">
 			<input type="hidden" name="response_type" value="{}">
 			<input type="hidden" name="scope" value="{}">
 			<input type="hidden" name="username" value="{}">
 			<input type="hidden" name="allow" value="Allow">
-			<input type="hidden" name="token" value="{}">
+			<input type="hidden" name="token" value="{}">{}
 
 			<p>If you agree to this request, please write your password :<br>
 				Account : {}<br>
-				Password : <input type="password" name="password" value="">
+				Password : <input type="password" name="password" value=""><br>
+				Two-factor code (if enabled) : <input type="text" name="totp_code" value="" autocomplete="one-time-code">
 			</p>
 
 			<p><i>If success, you will be directly redirected on this client, with credentials.</i></p>{}
@@ -102,6 +211,11 @@ pub async fn get_oauth(
 		env!("CARGO_PKG_NAME"),
 		query.client_id,
 		scopes,
+		if already_granted {
+			"\n\t\t\t<p>You already allowed this access, please log in to continue.</p>"
+		} else {
+			""
+		},
 		query.client_id,
 		pct_str::PctString::encode(
 			pct_str::PctString::new(&query.redirect_uri)
@@ -123,6 +237,22 @@ pub async fn get_oauth(
 			pct_str::URIReserved
 		),
 		pct_str::PctString::encode(new_token.get_value().chars(), pct_str::URIReserved),
+		[
+			("state", &query.state),
+			("code_challenge", &query.code_challenge),
+			("code_challenge_method", &query.code_challenge_method),
+		]
+		.iter()
+		.filter_map(|(name, value)| value.as_ref().map(|value| (name, value)))
+		.fold(String::new(), |acc, (name, value)| {
+			format!(
+				r#"{}
+			<input type="hidden" name="{}" value="{}">"#,
+				acc,
+				name,
+				pct_str::PctString::encode(value.chars(), pct_str::URIReserved)
+			)
+		}),
 		pct_str::PctString::encode(
 			pct_str::PctString::new(&username).unwrap().decode().chars(),
 			pct_str::URIReserved
@@ -133,6 +263,15 @@ pub async fn get_oauth(
 			Some(code) if code == "security_issue" => String::from(
 				r#"<p class="error">There is an security issue, please try again.</p>"#
 			),
+			Some(code) if code == "too_many_attempts" => String::from(
+				r#"<p class="error">Too many failed attempts, please wait a moment before trying again.</p>"#
+			),
+			Some(code) if code == "no_scope" => String::from(
+				r#"<p class="error">Please allow at least one of the requested accesses.</p>"#
+			),
+			Some(code) if code == "account_locked" => String::from(
+				r#"<p class="error">This account is temporarily locked after too many failed attempts.</p>"#
+			),
 			Some(code) => format!(r#"<p class="error">Unknown error : {}.</p>"#, code),
 			None => String::new(),
 		}
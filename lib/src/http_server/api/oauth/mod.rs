@@ -1,5 +1,202 @@
 mod get;
 mod post;
+mod token;
 
 pub use get::get_oauth;
 pub use post::post_oauth;
+pub use token::post_oauth_token;
+
+#[cfg(test)]
+mod tests;
+
+fn build_error_page(message: &str) -> actix_web::HttpResponse {
+	actix_web::HttpResponse::BadRequest()
+		.content_type("text/html; charset=utf-8")
+		.body(format!(
+			r#"<!DOCTYPE html>
+<html>
+	<head>
+		<meta charset="UTF-8">
+		<meta http-equiv="X-UA-Compatible" content="IE=edge">
+		<meta name="viewport" content="width=device-width, initial-scale=1.0">
+		<title>{} : access request refused</title>
+	</head>
+	<body>
+		<h1>Access request refused</h1>
+		<p class="error">{}</p>
+		<p>Please contact the developers of this client.</p>
+	</body>
+</html>"#,
+			env!("CARGO_PKG_NAME"),
+			message
+		))
+}
+
+/// Checks that `redirect_uri` shares the origin of `client_id`, as required by remoteStorage,
+/// and that this client is trusted by the `trusted_client_origins` setting, if any.
+fn check_client(
+	client_id: &str,
+	redirect_uri: &str,
+	settings: &crate::http_server::Settings,
+) -> Result<(), String> {
+	let client_origin = match crate::http_server::utils::origin_of(&decode(client_id)) {
+		Some(origin) => origin,
+		None => {
+			return Err(String::from(
+				"The identifier of this client is not the URL of an web origin.",
+			));
+		}
+	};
+
+	let redirect_origin = match crate::http_server::utils::origin_of(&decode(redirect_uri)) {
+		Some(origin) => origin,
+		None => {
+			return Err(String::from(
+				"The redirection address of this client is not an absolute URL.",
+			));
+		}
+	};
+
+	if client_origin != redirect_origin {
+		return Err(format!(
+			"This client (<code>{client_origin}</code>) asks to redirect you to another origin (<code>{redirect_origin}</code>), your credentials could be stolen."
+		));
+	}
+
+	if let Some(trusted_client_origins) = &settings.trusted_client_origins {
+		if !trusted_client_origins.iter().any(|trusted| {
+			crate::http_server::utils::origin_of(trusted).as_ref() == Some(&client_origin)
+		}) {
+			return Err(format!(
+				"This client (<code>{client_origin}</code>) is not trusted by this server."
+			));
+		}
+	}
+
+	return Ok(());
+}
+
+/// Values of the OAuth requests are percent-encoded once more by the authorization form.
+fn decode(value: &str) -> String {
+	match pct_str::PctString::new(value) {
+		Ok(value) => value.decode(),
+		Err(_) => String::from(value),
+	}
+}
+
+/// Prefix of the fields of the authorization form where the user chooses the access given for
+/// each requested module.
+const GRANT_FIELD_PREFIX: &str = "grant_";
+
+/// Reduces the requested scopes according to the choices of the user in the authorization form.
+///
+/// A scope can only be downgraded (from `rw` to `r`) or removed (empty value), and is kept as
+/// requested when there is no choice for its module.
+fn reduce_scopes(
+	requested_scopes: Vec<crate::scope::Scope>,
+	choices: &std::collections::HashMap<String, String>,
+) -> Vec<crate::scope::Scope> {
+	requested_scopes
+		.into_iter()
+		.filter_map(
+			|scope| match choices.get(&format!("{}{}", GRANT_FIELD_PREFIX, scope.module)) {
+				None => Some(scope),
+				Some(choice) => crate::scope::ScopeRightType::try_from(choice.as_str())
+					.ok()
+					.map(|right_type| crate::scope::Scope {
+						right_type,
+						module: scope.module.clone(),
+					})
+					.filter(|chosen| scope.includes(chosen)),
+			},
+		)
+		.collect()
+}
+
+/// Access request of a client, with decoded values.
+struct AuthorizationRequest {
+	response_type: String,
+	scopes: Vec<crate::scope::Scope>,
+	client_id: String,
+	redirect_uri: String,
+	username: String,
+	state: Option<String>,
+	code_challenge: String,
+}
+
+/// Issues an access token (`token` response type) or an authorization code (`code` response type,
+/// with PKCE) for this request, and returns the address of the client where the user should be
+/// redirected.
+fn authorize(
+	request: AuthorizationRequest,
+	access_tokens: &std::sync::Arc<std::sync::Mutex<Vec<crate::http_server::AccessBearer>>>,
+	authorization_codes: &std::sync::Arc<
+		std::sync::Mutex<Vec<crate::http_server::AuthorizationCode>>,
+	>,
+) -> String {
+	// TODO : what if redirect_uri already contains fragment `#something` ?
+
+	let state = request
+		.state
+		.as_ref()
+		.filter(|state| !state.is_empty())
+		.map(|state| pct_str::PctString::encode(state.chars(), pct_str::URIReserved));
+
+	if request.response_type == "code" {
+		let new_code = crate::http_server::AuthorizationCode::new(
+			request.scopes,
+			&request.client_id,
+			&request.redirect_uri,
+			&request.username,
+			&request.code_challenge,
+		);
+
+		let mut codes = authorization_codes.lock().unwrap();
+		codes.retain(|code| !code.has_expirated());
+		codes.push(new_code.clone());
+
+		format!(
+			"{}{}code={}{}",
+			request.redirect_uri,
+			if request.redirect_uri.contains('?') {
+				'&'
+			} else {
+				'?'
+			},
+			pct_str::PctString::encode(new_code.get_value().chars(), pct_str::URIReserved),
+			match state {
+				Some(state) => format!("&state={state}"),
+				None => String::new(),
+			}
+		)
+	} else {
+		let new_token = crate::http_server::AccessBearer::new(
+			request.scopes,
+			&request.client_id,
+			&request.redirect_uri,
+			&request.username,
+		);
+		access_tokens.lock().unwrap().push(new_token.clone());
+
+		format!(
+			"{}#access_token={}&token_type={}&scope={}{}",
+			request.redirect_uri,
+			pct_str::PctString::encode(new_token.get_name().chars(), pct_str::URIReserved),
+			"bearer",
+			pct_str::PctString::encode(
+				new_token
+					.get_scopes()
+					.iter()
+					.map(|scope| scope.to_string())
+					.collect::<Vec<String>>()
+					.join(" ")
+					.chars(),
+				pct_str::URIReserved
+			),
+			match state {
+				Some(state) => format!("&state={state}"),
+				None => String::new(),
+			}
+		)
+	}
+}
@@ -9,10 +9,17 @@ pub struct OauthPostQuery {
 	response_type: String,
 	username: String,
 	password: String,
+	totp_code: Option<String>,
 	allow: String,
 	token: String,
+	state: Option<String>,
+	code_challenge: Option<String>,
+	code_challenge_method: Option<String>,
+	#[serde(flatten)]
+	scope_choices: std::collections::HashMap<String, String>,
 }
 
+#[allow(clippy::too_many_arguments)]
 #[actix_web::post("/oauth")]
 pub async fn post_oauth(
 	request: actix_web::HttpRequest,
@@ -20,308 +27,406 @@ pub async fn post_oauth(
 	form_tokens: actix_web::web::Data<
 		Arc<Mutex<Vec<crate::http_server::middlewares::OauthFormToken>>>,
 	>,
+	sessions: actix_web::web::Data<Arc<Mutex<Vec<crate::http_server::Session>>>>,
 	access_tokens: actix_web::web::Data<Arc<Mutex<Vec<crate::http_server::AccessBearer>>>>,
+	authorization_codes: actix_web::web::Data<
+		Arc<Mutex<Vec<crate::http_server::AuthorizationCode>>>,
+	>,
+	login_attempts: actix_web::web::Data<Arc<Mutex<crate::http_server::LoginAttempts>>>,
 	users: actix_web::web::Data<Arc<Mutex<crate::http_server::Users>>>,
 	settings: actix_web::web::Data<Arc<Mutex<crate::http_server::Settings>>>,
 	program_state: actix_web::web::Data<Arc<Mutex<crate::http_server::ProgramState>>>,
+	metrics: Option<actix_web::web::Data<Arc<Mutex<crate::http_server::Metrics>>>>,
 	logger: actix_web::web::Data<Arc<Mutex<charlie_buffalo::Logger>>>,
 ) -> actix_web::Result<actix_web::HttpResponse> {
 	let _host = request.headers().get("host");
 	let origin = request.headers().get("origin");
 	let _referer = request.headers().get("referer");
 
-	let localhost = String::from("localhost");
-	let current_domain = settings
-		.lock()
-		.unwrap()
-		.domain
-		.as_ref()
-		.unwrap_or_else(|| &localhost)
-		.clone();
+	let settings_copy = settings.lock().unwrap().clone();
+
+	let malformed_field = [
+		("redirect_uri", Some(&form.redirect_uri)),
+		("token", Some(&form.token)),
+		("state", form.state.as_ref()),
+		("code_challenge", form.code_challenge.as_ref()),
+		("code_challenge_method", form.code_challenge_method.as_ref()),
+	]
+	.into_iter()
+	.find(|(_, value)| {
+		value
+			.map(|value| pct_str::PctString::new(value).is_err())
+			.unwrap_or(false)
+	});
+	if let Some((name, _)) = malformed_field {
+		crate::http_server::logging::log(
+			&logger,
+			crate::http_server::logging::Level::Error,
+			"oauth_submit",
+			&[],
+			&format!(
+				"malformed percent-encoding of `{name}` from client `{}`",
+				form.client_id
+			),
+		);
+
+		return Ok(super::build_error_page(&format!(
+			"This client sent a malformed `{name}` parameter."
+		)));
+	}
+
+	if let Err(message) = super::check_client(&form.client_id, &form.redirect_uri, &settings_copy) {
+		crate::http_server::logging::log(
+			&logger,
+			crate::http_server::logging::Level::Error,
+			"oauth_submit",
+			&[],
+			&format!(
+				"refused client `{}` with redirect_uri `{}`",
+				form.client_id, form.redirect_uri
+			),
+		);
+
+		return Ok(super::build_error_page(&message));
+	}
+
+	// before any check of the password, so an unusable request does not count as an attempt
+	let unsupported_request = match form.response_type.as_str() {
+		"token" => None,
+		"code" if form.code_challenge.as_deref().unwrap_or_default().is_empty() => {
+			Some("This client uses the authorization code flow without PKCE code challenge, which is not supported.")
+		}
+		"code" if form.code_challenge_method.as_deref() != Some("S256") => {
+			Some("This client does not use the `S256` PKCE code challenge method, which is the only one supported.")
+		}
+		"code" => None,
+		_ => Some(
+			"This client requested an unsupported response type (only `token` and `code` are supported).",
+		),
+	};
+	if let Some(message) = unsupported_request {
+		crate::http_server::logging::log(
+			&logger,
+			crate::http_server::logging::Level::Error,
+			"oauth_submit",
+			&[],
+			&format!(
+				"unsupported request of client `{}` : response_type `{}`, code_challenge_method `{}`",
+				form.client_id,
+				form.response_type,
+				form.code_challenge_method.as_deref().unwrap_or_default()
+			),
+		);
+
+		return Ok(super::build_error_page(message));
+	}
 
 	match origin {
 		Some(path) => {
-			let settings = settings.lock().unwrap().clone();
-
-			let mut allowed_domains = vec![];
-			// TODO : probably a security issue :
-			allowed_domains.push(format!("http://{current_domain}:{}", settings.port));
-			// TODO : probably a security issue :
-			if settings.port == 80 {
-				allowed_domains.push(String::from("http://{current_domain}"));
-			}
-			if program_state.lock().unwrap().https_mode {
-				if let Some(https) = settings.https.clone() {
-					allowed_domains.push(format!("https://{current_domain}:{}", https.port));
-					if https.port != 443 {
-						allowed_domains.push(String::from("https://{current_domain}"));
-					}
-				}
-			}
-			if let Some(domain) = settings.domain {
-				// TODO : probably a security issue :
-				allowed_domains.push(format!("http://{}:{}", domain, settings.port));
-				// TODO : probably a security issue :
-				if settings.port == 80 {
-					allowed_domains.push(format!("http://{}", domain));
-				}
-				if program_state.lock().unwrap().https_mode {
-					if let Some(https) = settings.https.clone() {
-						allowed_domains.push(format!("https://{}:{}", domain, https.port));
-						if https.port != 443 {
-							allowed_domains.push(format!("https://{}", domain));
-						}
-					}
-				}
-			}
+			let allowed_origins = crate::http_server::utils::server_origins(
+				&settings_copy,
+				program_state.lock().unwrap().https_mode,
+			);
 
-			if !allowed_domains.contains(&String::from(path.to_str().unwrap_or_default())) {
-				logger.lock().unwrap().push(
-					vec![
-						(String::from("event"), String::from("oauth_submit")),
-						(String::from("level"), String::from("ERROR")),
-					],
-					Some(&format!("wrong origin : {:?}", path)),
+			let request_origin = path
+				.to_str()
+				.ok()
+				.and_then(crate::http_server::utils::origin_of);
+
+			if !request_origin
+				.map(|request_origin| allowed_origins.contains(&request_origin))
+				.unwrap_or(false)
+			{
+				crate::http_server::logging::log(
+					&logger,
+					crate::http_server::logging::Level::Error,
+					"oauth_submit",
+					&[],
+					&format!("wrong origin : {:?}", path),
 				);
 
-				return Ok(actix_web::HttpResponse::Found()
-					.insert_header((
-						actix_web::http::header::LOCATION,
-						format!(
-							"/oauth/{}?redirect_uri={}&scope={}&client_id={}&response_type={}&auth_result={}",
-							form.username,
-							pct_str::PctString::encode(
-								pct_str::PctString::new(
-									&form.redirect_uri
-								)
-									.unwrap()
-									.decode()
-									.chars(),
-								pct_str::URIReserved
-							),
-							form.scope,
-							form.client_id,
-							form.response_type,
-							"security_issue"
-						),
-					))
-					.finish());
+				return Ok(redirect_to_form(&form, "security_issue"));
 			}
 		}
 		None => {
-			logger.lock().unwrap().push(
-				vec![
-					(String::from("event"), String::from("oauth_submit")),
-					(String::from("level"), String::from("ERROR")),
-				],
-				Some("no origin"),
+			crate::http_server::logging::log(
+				&logger,
+				crate::http_server::logging::Level::Error,
+				"oauth_submit",
+				&[],
+				"no origin",
 			);
 
-			return Ok(actix_web::HttpResponse::Found()
-				.insert_header((
-					actix_web::http::header::LOCATION,
-					format!(
-						"/oauth/{}?redirect_uri={}&scope={}&client_id={}&response_type={}&auth_result={}",
-						form.username,
-						pct_str::PctString::encode(
-							pct_str::PctString::new(
-								&form.redirect_uri
-							)
-								.unwrap()
-								.decode()
-								.chars(),
-							pct_str::URIReserved
-						),
-						form.scope,
-						form.client_id,
-						form.response_type,
-						"security_issue"
-					),
-				))
-				.finish());
+			return Ok(redirect_to_form(&form, "security_issue"));
 		}
 	}
 
-	let token = pct_str::PctString::new(&form.token).unwrap().decode();
+	let token = super::decode(&form.token);
 
-	let form_tokens = form_tokens.lock().unwrap();
-	let token_search = form_tokens.iter().find(|e| e.get_value() == token);
-	match token_search {
+	match form_tokens
+		.lock()
+		.unwrap()
+		.iter()
+		.find(|e| e.get_value() == token)
+	{
 		Some(token_found) => {
 			if token_found.has_expirated() {
-				logger.lock().unwrap().push(
-					vec![
-						(String::from("event"), String::from("oauth_submit")),
-						(String::from("level"), String::from("ERROR")),
-					],
-					Some(&format!("expirated form token : {:?}", token_found)),
+				crate::http_server::logging::log(
+					&logger,
+					crate::http_server::logging::Level::Error,
+					"oauth_submit",
+					&[],
+					&format!("expirated form token : {:?}", token_found),
 				);
 
-				return Ok(actix_web::HttpResponse::Found()
-					.insert_header((
-						actix_web::http::header::LOCATION,
-						format!(
-							"/oauth/{}?redirect_uri={}&scope={}&client_id={}&response_type={}&auth_result={}",
-							form.username,
-							pct_str::PctString::encode(
-								pct_str::PctString::new(
-									&form.redirect_uri
-								)
-									.unwrap()
-									.decode()
-									.chars(),
-								pct_str::URIReserved
-							),
-							form.scope,
-							form.client_id,
-							form.response_type,
-							"security_issue"
-						),
-					))
-					.finish());
+				return Ok(redirect_to_form(&form, "security_issue"));
 			}
 		}
 		None => {
-			logger.lock().unwrap().push(
-				vec![
-					(String::from("event"), String::from("oauth_submit")),
-					(String::from("level"), String::from("ERROR")),
-				],
-				Some("token not found"),
+			crate::http_server::logging::log(
+				&logger,
+				crate::http_server::logging::Level::Error,
+				"oauth_submit",
+				&[],
+				"token not found",
 			);
 
-			return Ok(actix_web::HttpResponse::Found()
-				.insert_header((
-					actix_web::http::header::LOCATION,
-					format!(
-						"/oauth/{}?redirect_uri={}&scope={}&client_id={}&response_type={}&auth_result={}",
-						form.username,
-						pct_str::PctString::encode(
-							pct_str::PctString::new(
-								&form.redirect_uri
-							)
-								.unwrap()
-								.decode()
-								.chars(),
-							pct_str::URIReserved
-						),
-						form.scope,
-						form.client_id,
-						form.response_type,
-						"security_issue"
-					),
-				))
-				.finish());
+			return Ok(redirect_to_form(&form, "security_issue"));
 		}
 	}
 
 	if form.allow == "Allow" {
-		std::thread::sleep(std::time::Duration::from_secs(
-			settings
-				.lock()
-				.unwrap()
-				.oauth_wait_seconds
-				.unwrap_or_else(|| {
-					crate::http_server::Settings::new(std::path::PathBuf::from("."))
-						.oauth_wait_seconds
-						.unwrap()
-				}),
-		));
-		if users
+		// the same name is checked, throttled and authorized
+		let username = super::decode(&form.username);
+
+		let ip = request.peer_addr().map(|address| address.ip());
+		let ip_attribute = match ip {
+			Some(ip) => ip.to_string(),
+			None => String::from("unknown"),
+		};
+
+		let throttling = settings_copy.oauth_throttling.clone().unwrap_or_default();
+		if let Err(refusal) = login_attempts
 			.lock()
 			.unwrap()
-			.check(&form.username, &mut String::from(&form.password))
+			.reserve(ip, &username, &throttling)
 		{
-			// TODO : what if form.redirect_uri already contains fragment `#something` ?
+			let (auth_result, message) = match refusal {
+				crate::http_server::LoginRefusal::TooManyAttempts(wait) => (
+					"too_many_attempts",
+					format!(
+						"too many failed attempts, refused during {} more seconds",
+						wait.as_secs() + 1
+					),
+				),
+				crate::http_server::LoginRefusal::AccountLocked(wait) => (
+					"account_locked",
+					format!("account locked during {} more seconds", wait.as_secs() + 1),
+				),
+			};
+
+			crate::http_server::logging::log(
+				&logger,
+				crate::http_server::logging::Level::Error,
+				"oauth_submit",
+				&[("ip", &ip_attribute), ("username", &username)],
+				&message,
+			);
+
+			return Ok(redirect_to_form(&form, auth_result));
+		}
+
+		let password_check = crate::http_server::authenticate(
+			&settings_copy,
+			&users,
+			&username,
+			&mut String::from(&form.password),
+			&logger,
+		)
+		.await;
 
-			let scopes = pct_str::PctString::new(&form.scope)
+		let second_factor_check = if password_check.is_correct() {
+			users
+				.lock()
+				.unwrap()
+				.check_totp(&username, form.totp_code.as_deref().unwrap_or_default())
+		} else {
+			crate::http_server::SecondFactorCheck::Wrong
+		};
+
+		if password_check == crate::http_server::PasswordCheck::Rehashed
+			|| second_factor_check == crate::http_server::SecondFactorCheck::Correct
+		{
+			if let Err(e) = users.lock().unwrap().save(&settings_copy.userfile_path()) {
+				crate::http_server::logging::log(
+					&logger,
+					crate::http_server::logging::Level::Error,
+					"oauth_submit",
+					&[],
+					&format!("can not save credentials of `{}` : {}", username, e),
+				);
+			}
+		}
+
+		if let Some(metrics) = &metrics {
+			metrics
+				.lock()
 				.unwrap()
-				.decode()
+				.record_oauth_login(second_factor_check.is_correct());
+		}
+
+		if second_factor_check.is_correct() {
+			login_attempts
+				.lock()
+				.unwrap()
+				.register_success(ip, &username);
+
+			let requested_scopes: Vec<crate::scope::Scope> = match super::decode(&form.scope)
 				.split(' ')
-				.map(|e| crate::scope::Scope::try_from(e.trim()).unwrap())
-				.collect();
+				.map(|e| crate::scope::Scope::try_from(e.trim()))
+				.collect()
+			{
+				Ok(requested_scopes) => requested_scopes,
+				Err(e) => {
+					crate::http_server::logging::log(
+						&logger,
+						crate::http_server::logging::Level::Error,
+						"oauth_submit",
+						&[],
+						&format!("incorrect scope : {:?}", e),
+					);
 
-			let new_token = crate::http_server::AccessBearer::new(
-				scopes,
-				&pct_str::PctString::new(&form.client_id).unwrap().decode(),
-				&pct_str::PctString::new(&form.username).unwrap().decode(),
+					return Ok(redirect_to_form(&form, "security_issue"));
+				}
+			};
+
+			let scopes = super::reduce_scopes(requested_scopes, &form.scope_choices);
+			if scopes.is_empty() {
+				return Ok(redirect_to_form(&form, "no_scope"));
+			}
+
+			let code_challenge = form.code_challenge.clone().unwrap_or_default();
+
+			let client_id = super::decode(&form.client_id);
+
+			// users only known by another authentication backend can not be remembered
+			if users.lock().unwrap().get_grants(&username).is_some() {
+				let mut users = users.lock().unwrap();
+				let mut new_users = users.clone();
+				let result = new_users
+					.add_grant(&username, &client_id, &scopes)
+					.map_err(|e| e.to_string())
+					.and_then(|_| new_users.save(&settings_copy.userfile_path()));
+
+				match result {
+					Ok(()) => {
+						*users = new_users;
+					}
+					Err(e) => {
+						crate::http_server::logging::log(
+							&logger,
+							crate::http_server::logging::Level::Error,
+							"oauth_submit",
+							&[("username", &username)],
+							&format!("can not remember access of `{client_id}` : {e}"),
+						);
+					}
+				}
+			}
+
+			let redirect = super::authorize(
+				super::AuthorizationRequest {
+					response_type: form.response_type.clone(),
+					scopes,
+					client_id,
+					redirect_uri: super::decode(&form.redirect_uri),
+					username: username.clone(),
+					state: form.state.as_deref().map(super::decode),
+					code_challenge,
+				},
+				&access_tokens,
+				&authorization_codes,
 			);
-			access_tokens.lock().unwrap().push(new_token.clone());
-
-			let redirect = format!(
-				"{}#access_token={}&token_type={}",
-				pct_str::PctString::new(&form.redirect_uri)
-					.unwrap()
-					.decode(),
-				pct_str::PctString::encode(new_token.get_name().chars(), pct_str::URIReserved),
-				"bearer"
+
+			let session = super::super::open_session(
+				&request,
+				&sessions,
+				&username,
+				program_state.lock().unwrap().https_mode || settings_copy.force_https == Some(true),
 			);
 
-			Ok(actix_web::HttpResponse::Found()
-				.insert_header((actix_web::http::header::LOCATION, redirect))
-				.finish()) // todo : some text for users ?
+			let mut response = actix_web::HttpResponse::Found();
+			response.insert_header((actix_web::http::header::LOCATION, redirect));
+			if let Some(session) = session {
+				response.cookie(session);
+			}
+
+			Ok(response.finish()) // todo : some text for users ?
 		} else {
-			logger.lock().unwrap().push(
-				vec![
-					(String::from("event"), String::from("oauth_submit")),
-					(String::from("level"), String::from("ERROR")),
-				],
-				Some("wrong credentials"),
+			let (ip_failures, username_failures) = login_attempts
+				.lock()
+				.unwrap()
+				.register_failure(ip, &username);
+
+			crate::http_server::logging::log(
+				&logger,
+				crate::http_server::logging::Level::Error,
+				"oauth_submit",
+				&[("ip", &ip_attribute), ("username", &username)],
+				&format!(
+					"{} ({username_failures} failure(s) for this username, {ip_failures} for this IP address)",
+					if password_check.is_correct() {
+						"wrong two-factor code"
+					} else {
+						"wrong credentials"
+					}
+				),
 			);
 
-			Ok(actix_web::HttpResponse::Found()
-				.insert_header((
-					actix_web::http::header::LOCATION,
-					format!(
-						"/oauth/{}?redirect_uri={}&scope={}&client_id={}&response_type={}&auth_result={}",
-						form.username,
-						pct_str::PctString::encode(
-							pct_str::PctString::new(
-								&form.redirect_uri
-							)
-								.unwrap()
-								.decode()
-								.chars(),
-							pct_str::URIReserved
-						),
-						form.scope,
-						form.client_id,
-						form.response_type,
-						"wrong_credentials"
-					),
-				))
-				.finish()) // todo : some text for users ?
+			Ok(redirect_to_form(&form, "wrong_credentials")) // todo : some text for users ?
 		}
 	} else {
-		logger.lock().unwrap().push(
-			vec![
-				(String::from("event"), String::from("oauth_submit")),
-				(String::from("level"), String::from("ERROR")),
-			],
-			Some("not allowed"),
+		crate::http_server::logging::log(
+			&logger,
+			crate::http_server::logging::Level::Error,
+			"oauth_submit",
+			&[],
+			"not allowed",
 		);
 
-		Ok(actix_web::HttpResponse::Found()
-			.insert_header((
-				actix_web::http::header::LOCATION,
-				format!(
-					"/oauth/{}?redirect_uri={}&scope={}&client_id={}&response_type={}&auth_result={}",
-					form.username,
-					pct_str::PctString::encode(
-						pct_str::PctString::new(
-							&form.redirect_uri
-						)
-							.unwrap()
-							.decode()
-							.chars(),
-						pct_str::URIReserved
-					),
-					form.scope,
-					form.client_id,
-					form.response_type,
-					"security_issue"
-				),
-			))
-			.finish()) // todo : some text for users ?
+		Ok(redirect_to_form(&form, "security_issue")) // todo : some text for users ?
 	}
 }
+
+/// Sends back the user to the authorization form, with all parameters of the initial request.
+fn redirect_to_form(form: &OauthPostQuery, auth_result: &str) -> actix_web::HttpResponse {
+	let mut location = format!(
+		"/oauth/{}?redirect_uri={}&scope={}&client_id={}&response_type={}",
+		pct_str::PctString::encode(super::decode(&form.username).chars(), pct_str::URIReserved),
+		pct_str::PctString::encode(
+			super::decode(&form.redirect_uri).chars(),
+			pct_str::URIReserved
+		),
+		form.scope,
+		form.client_id,
+		form.response_type,
+	);
+	for (name, value) in [
+		("state", &form.state),
+		("code_challenge", &form.code_challenge),
+		("code_challenge_method", &form.code_challenge_method),
+	] {
+		if let Some(value) = value.as_ref().filter(|value| !value.is_empty()) {
+			location += &format!(
+				"&{}={}",
+				name,
+				pct_str::PctString::encode(super::decode(value).chars(), pct_str::URIReserved)
+			);
+		}
+	}
+	location += &format!("&auth_result={}", auth_result);
+
+	actix_web::HttpResponse::Found()
+		.insert_header((actix_web::http::header::LOCATION, location))
+		.finish()
+}
@@ -0,0 +1,1432 @@
+use std::sync::{Arc, Mutex};
+
+fn find_form_token(html: &str) -> String {
+	let start = html.find(r#"name="token" value=""#).unwrap() + r#"name="token" value=""#.len();
+	let end = start + html[start..].find('"').unwrap();
+
+	String::from(&html[start..end])
+}
+
+fn find_query_value(uri: &str, name: &str) -> Option<String> {
+	let query = uri.split_once('?')?.1;
+
+	query
+		.split('&')
+		.filter_map(|pair| pair.split_once('='))
+		.find(|(key, _)| *key == name)
+		.map(|(_, value)| String::from(pct_str::PctString::new(value).unwrap().decode().as_str()))
+}
+
+#[actix_rt::test]
+async fn r2mw8vq1ou3ekc6y() {
+	let settings = crate::http_server::Settings::new(tempfile::tempdir().unwrap().into_path());
+	let server_origin = format!(
+		"http://{}:{}",
+		settings.domain.clone().unwrap(),
+		settings.port
+	);
+	let settings = Arc::new(Mutex::new(settings));
+
+	let mut users = crate::http_server::Users::new();
+	users.insert("user", &mut String::from("password")).unwrap();
+	let users = Arc::new(Mutex::new(users));
+
+	let form_tokens: Arc<Mutex<Vec<crate::http_server::middlewares::OauthFormToken>>> =
+		Arc::new(Mutex::new(vec![]));
+	let sessions: Arc<Mutex<Vec<crate::http_server::Session>>> = Arc::new(Mutex::new(vec![]));
+	let access_tokens: Arc<Mutex<Vec<crate::http_server::AccessBearer>>> =
+		Arc::new(Mutex::new(vec![]));
+	let authorization_codes: Arc<Mutex<Vec<crate::http_server::AuthorizationCode>>> =
+		Arc::new(Mutex::new(vec![]));
+	let login_attempts = Arc::new(Mutex::new(crate::http_server::LoginAttempts::new()));
+	let program_state = Arc::new(Mutex::new(crate::http_server::ProgramState::default()));
+
+	let logger = Arc::new(Mutex::new(charlie_buffalo::Logger::new(
+		charlie_buffalo::new_dispatcher(Box::new(|_| {})),
+		None,
+	)));
+
+	let app = actix_web::test::init_service(
+		actix_web::App::new()
+			.app_data(actix_web::web::Data::new(settings))
+			.app_data(actix_web::web::Data::new(users))
+			.app_data(actix_web::web::Data::new(form_tokens))
+			.app_data(actix_web::web::Data::new(sessions))
+			.app_data(actix_web::web::Data::new(access_tokens.clone()))
+			.app_data(actix_web::web::Data::new(authorization_codes))
+			.app_data(actix_web::web::Data::new(login_attempts))
+			.app_data(actix_web::web::Data::new(program_state))
+			.app_data(actix_web::web::Data::new(logger))
+			.service(super::get_oauth)
+			.service(super::post_oauth)
+			.service(super::post_oauth_token),
+	)
+	.await;
+
+	// example from RFC 7636, appendix B
+	let code_verifier = "dBjftJeZ4CVP-mB92K27uhbUJU1p1r_wW1gFWFOEjXk";
+	let code_challenge = "E9Melhoa2OwvFrEMTJguCHaoeK1t8URWbuGJSstw-cM";
+
+	let mut codes = vec![];
+	for _ in 0..2 {
+		let request = actix_web::test::TestRequest::get()
+			.uri(&format!(
+				"/oauth/user?redirect_uri={}&scope=documents:rw&client_id={}&response_type=code&state=xyz&code_challenge={}&code_challenge_method=S256",
+				"https%3A%2F%2Fapp.example.com%2Fcallback",
+				"https%3A%2F%2Fapp.example.com",
+				code_challenge,
+			))
+			.peer_addr("127.0.0.1:12345".parse().unwrap())
+			.to_request();
+		let response = actix_web::test::call_service(&app, request).await;
+		assert_eq!(response.status(), actix_web::http::StatusCode::OK);
+
+		let html = actix_web::test::read_body(response).await;
+		let form_token = find_form_token(&String::from_utf8_lossy(&html));
+
+		let request = actix_web::test::TestRequest::post()
+			.uri("/oauth")
+			.insert_header((actix_web::http::header::ORIGIN, server_origin.clone()))
+			.set_form([
+				("redirect_uri", "https://app.example.com/callback"),
+				("scope", "documents:rw"),
+				("client_id", "https://app.example.com"),
+				("response_type", "code"),
+				("username", "user"),
+				("password", "password"),
+				("allow", "Allow"),
+				("token", &form_token),
+				("state", "xyz"),
+				("code_challenge", code_challenge),
+				("code_challenge_method", "S256"),
+			])
+			.to_request();
+		let response = actix_web::test::call_service(&app, request).await;
+		assert_eq!(response.status(), actix_web::http::StatusCode::FOUND);
+
+		let location = response
+			.headers()
+			.get(actix_web::http::header::LOCATION)
+			.unwrap()
+			.to_str()
+			.unwrap();
+		assert!(location.starts_with("https://app.example.com/callback?code="));
+		assert!(!location.contains("access_token"));
+		assert_eq!(
+			find_query_value(location, "state"),
+			Some(String::from("xyz"))
+		);
+
+		codes.push(find_query_value(location, "code").unwrap());
+	}
+
+	let tests = vec![
+		(
+			010,
+			"authorization_code",
+			codes[0].clone(),
+			"https://app.example.com/callback",
+			"WRONG_VERIFIER",
+			actix_web::http::StatusCode::BAD_REQUEST,
+		),
+		(
+			020,
+			"authorization_code",
+			codes[0].clone(),
+			"https://app.example.com/callback",
+			code_verifier,
+			actix_web::http::StatusCode::BAD_REQUEST,
+		),
+		(
+			030,
+			"password",
+			codes[1].clone(),
+			"https://app.example.com/callback",
+			code_verifier,
+			actix_web::http::StatusCode::BAD_REQUEST,
+		),
+		(
+			040,
+			"authorization_code",
+			codes[1].clone(),
+			"https://app.example.com/callback",
+			code_verifier,
+			actix_web::http::StatusCode::OK,
+		),
+		(
+			050,
+			"authorization_code",
+			codes[1].clone(),
+			"https://app.example.com/callback",
+			code_verifier,
+			actix_web::http::StatusCode::BAD_REQUEST,
+		),
+	];
+
+	for test in tests {
+		print!(
+			"#{:03} : POST request to /oauth/token with grant_type = {} and code_verifier = {} ... ",
+			test.0, test.1, test.4
+		);
+
+		let request = actix_web::test::TestRequest::post()
+			.uri("/oauth/token")
+			.set_form([
+				("grant_type", test.1),
+				("code", &test.2),
+				("redirect_uri", test.3),
+				("client_id", "https://app.example.com"),
+				("code_verifier", test.4),
+			])
+			.to_request();
+		let response = actix_web::test::call_service(&app, request).await;
+
+		assert_eq!(response.status(), test.5);
+		assert_eq!(
+			response
+				.headers()
+				.get(actix_web::http::header::CACHE_CONTROL)
+				.unwrap(),
+			"no-store"
+		);
+
+		let body: serde_json::Value =
+			serde_json::from_slice(&actix_web::test::read_body(response).await).unwrap();
+		if test.5 == actix_web::http::StatusCode::OK {
+			assert_eq!(body["token_type"], "bearer");
+			assert_eq!(body["scope"], "documents:rw");
+			assert!(access_tokens
+				.lock()
+				.unwrap()
+				.iter()
+				.any(|token| token.get_name() == body["access_token"]));
+		} else {
+			assert!(body["error"].is_string());
+		}
+
+		println!("OK");
+	}
+}
+
+#[actix_rt::test]
+async fn gz0ntk4pa81ywdh() {
+	let form_tokens: Arc<Mutex<Vec<crate::http_server::middlewares::OauthFormToken>>> =
+		Arc::new(Mutex::new(vec![]));
+	let sessions: Arc<Mutex<Vec<crate::http_server::Session>>> = Arc::new(Mutex::new(vec![]));
+
+	let settings = Arc::new(Mutex::new(crate::http_server::Settings::new(
+		tempfile::tempdir().unwrap().into_path(),
+	)));
+
+	let users = Arc::new(Mutex::new(crate::http_server::Users::new()));
+	let access_tokens: Arc<Mutex<Vec<crate::http_server::AccessBearer>>> =
+		Arc::new(Mutex::new(vec![]));
+	let authorization_codes: Arc<Mutex<Vec<crate::http_server::AuthorizationCode>>> =
+		Arc::new(Mutex::new(vec![]));
+
+	let logger = Arc::new(Mutex::new(charlie_buffalo::Logger::new(
+		charlie_buffalo::new_dispatcher(Box::new(|_| {})),
+		None,
+	)));
+
+	let app = actix_web::test::init_service(
+		actix_web::App::new()
+			.app_data(actix_web::web::Data::new(form_tokens))
+			.app_data(actix_web::web::Data::new(sessions))
+			.app_data(actix_web::web::Data::new(settings))
+			.app_data(actix_web::web::Data::new(users))
+			.app_data(actix_web::web::Data::new(access_tokens))
+			.app_data(actix_web::web::Data::new(authorization_codes))
+			.app_data(actix_web::web::Data::new(logger))
+			.service(super::get_oauth),
+	)
+	.await;
+
+	let tests = vec![
+		(
+			010,
+			"response_type=token",
+			actix_web::http::StatusCode::OK,
+		),
+		(
+			020,
+			"response_type=code&code_challenge=E9Melhoa2OwvFrEMTJguCHaoeK1t8URWbuGJSstw-cM&code_challenge_method=S256",
+			actix_web::http::StatusCode::OK,
+		),
+		(
+			030,
+			"response_type=code",
+			actix_web::http::StatusCode::BAD_REQUEST,
+		),
+		(
+			040,
+			"response_type=code&code_challenge=dBjftJeZ4CVP-mB92K27uhbUJU1p1r_wW1gFWFOEjXk&code_challenge_method=plain",
+			actix_web::http::StatusCode::BAD_REQUEST,
+		),
+		(
+			050,
+			"response_type=id_token",
+			actix_web::http::StatusCode::BAD_REQUEST,
+		),
+	];
+
+	for test in tests {
+		print!(
+			"#{:03} : GET request to /oauth/user with {} ... ",
+			test.0, test.1
+		);
+
+		let request = actix_web::test::TestRequest::get()
+			.uri(&format!(
+				"/oauth/user?redirect_uri=https%3A%2F%2Fapp.example.com%2F&scope=documents:rw&client_id=https%3A%2F%2Fapp.example.com&{}",
+				test.1
+			))
+			.peer_addr("127.0.0.1:12345".parse().unwrap())
+			.to_request();
+		let response = actix_web::test::call_service(&app, request).await;
+
+		assert_eq!(response.status(), test.2);
+
+		println!("OK");
+	}
+}
+
+#[actix_rt::test]
+async fn w5ceh0tq8dlk3ybm() {
+	let mut settings = crate::http_server::Settings::new(tempfile::tempdir().unwrap().into_path());
+	settings.trusted_client_origins = Some(vec![
+		String::from("https://app.example.com/"),
+		String::from("http://localhost:8000"),
+	]);
+	let server_origin = format!(
+		"http://{}:{}",
+		settings.domain.clone().unwrap(),
+		settings.port
+	);
+	let settings = Arc::new(Mutex::new(settings));
+
+	let form_tokens: Arc<Mutex<Vec<crate::http_server::middlewares::OauthFormToken>>> =
+		Arc::new(Mutex::new(vec![]));
+	let sessions: Arc<Mutex<Vec<crate::http_server::Session>>> = Arc::new(Mutex::new(vec![]));
+	let access_tokens: Arc<Mutex<Vec<crate::http_server::AccessBearer>>> =
+		Arc::new(Mutex::new(vec![]));
+	let authorization_codes: Arc<Mutex<Vec<crate::http_server::AuthorizationCode>>> =
+		Arc::new(Mutex::new(vec![]));
+	let users = Arc::new(Mutex::new(crate::http_server::Users::new()));
+	let login_attempts = Arc::new(Mutex::new(crate::http_server::LoginAttempts::new()));
+	let program_state = Arc::new(Mutex::new(crate::http_server::ProgramState::default()));
+
+	let logger = Arc::new(Mutex::new(charlie_buffalo::Logger::new(
+		charlie_buffalo::new_dispatcher(Box::new(|_| {})),
+		None,
+	)));
+
+	let app = actix_web::test::init_service(
+		actix_web::App::new()
+			.app_data(actix_web::web::Data::new(settings))
+			.app_data(actix_web::web::Data::new(users))
+			.app_data(actix_web::web::Data::new(form_tokens))
+			.app_data(actix_web::web::Data::new(sessions))
+			.app_data(actix_web::web::Data::new(access_tokens))
+			.app_data(actix_web::web::Data::new(authorization_codes))
+			.app_data(actix_web::web::Data::new(login_attempts))
+			.app_data(actix_web::web::Data::new(program_state))
+			.app_data(actix_web::web::Data::new(logger))
+			.service(super::get_oauth)
+			.service(super::post_oauth),
+	)
+	.await;
+
+	let tests = vec![
+		(
+			010,
+			"https://app.example.com",
+			"https://app.example.com/callback",
+			true,
+		),
+		(
+			020,
+			"https://app.example.com",
+			"https://app.example.com:443/",
+			true,
+		),
+		(030, "http://localhost:8000", "http://localhost:8000/", true),
+		(
+			040,
+			"https://app.example.com",
+			"https://evil.example.com/callback",
+			false,
+		),
+		(
+			050,
+			"https://app.example.com",
+			"http://app.example.com/callback",
+			false,
+		),
+		(
+			060,
+			"https://app.example.com",
+			"https://app.example.com:8443/",
+			false,
+		),
+		(070, "https://app.example.com", "/callback", false),
+		(080, "my_app", "https://app.example.com/", false),
+		(
+			090,
+			"https://other.example.com",
+			"https://other.example.com/",
+			false,
+		),
+	];
+
+	for test in tests {
+		print!(
+			"#{:03} : request with client_id = {} and redirect_uri = {} ... ",
+			test.0, test.1, test.2
+		);
+
+		let request = actix_web::test::TestRequest::get()
+			.uri(&format!(
+				"/oauth/user?redirect_uri={}&scope=documents:rw&client_id={}&response_type=token",
+				pct_str::PctString::encode(test.2.chars(), pct_str::URIReserved),
+				pct_str::PctString::encode(test.1.chars(), pct_str::URIReserved),
+			))
+			.peer_addr("127.0.0.1:12345".parse().unwrap())
+			.to_request();
+		let response = actix_web::test::call_service(&app, request).await;
+
+		if test.3 {
+			assert_eq!(response.status(), actix_web::http::StatusCode::OK);
+		} else {
+			assert_eq!(response.status(), actix_web::http::StatusCode::BAD_REQUEST);
+		}
+
+		let request = actix_web::test::TestRequest::post()
+			.uri("/oauth")
+			.insert_header((actix_web::http::header::ORIGIN, server_origin.clone()))
+			.set_form([
+				("redirect_uri", test.2),
+				("scope", "documents:rw"),
+				("client_id", test.1),
+				("response_type", "token"),
+				("username", "user"),
+				("password", "password"),
+				("allow", "Allow"),
+				("token", "UNKNOWN_TOKEN"),
+			])
+			.to_request();
+		let response = actix_web::test::call_service(&app, request).await;
+
+		if test.3 {
+			// then stopped by the form token, which is unknown
+			assert_eq!(response.status(), actix_web::http::StatusCode::FOUND);
+		} else {
+			assert_eq!(response.status(), actix_web::http::StatusCode::BAD_REQUEST);
+			assert!(
+				String::from_utf8_lossy(&actix_web::test::read_body(response).await)
+					.contains("Access request refused")
+			);
+		}
+
+		println!("OK");
+	}
+}
+
+#[actix_rt::test]
+async fn fq6s0znmb2vyc8x() {
+	let mut settings = crate::http_server::Settings::new(tempfile::tempdir().unwrap().into_path());
+	settings.oauth_throttling = Some(crate::http_server::SettingsOauthThrottling {
+		free_attempts: 2,
+		backoff_base_seconds: 60,
+		backoff_max_seconds: 60,
+		lockout_failures: 10,
+		lockout_seconds: 60,
+	});
+	let server_origin = format!(
+		"http://{}:{}",
+		settings.domain.clone().unwrap(),
+		settings.port
+	);
+	let settings = Arc::new(Mutex::new(settings));
+
+	let mut users = crate::http_server::Users::new();
+	users.insert("user", &mut String::from("password")).unwrap();
+	let users = Arc::new(Mutex::new(users));
+
+	let form_tokens: Arc<Mutex<Vec<crate::http_server::middlewares::OauthFormToken>>> =
+		Arc::new(Mutex::new(vec![]));
+	let sessions: Arc<Mutex<Vec<crate::http_server::Session>>> = Arc::new(Mutex::new(vec![]));
+	let access_tokens: Arc<Mutex<Vec<crate::http_server::AccessBearer>>> =
+		Arc::new(Mutex::new(vec![]));
+	let authorization_codes: Arc<Mutex<Vec<crate::http_server::AuthorizationCode>>> =
+		Arc::new(Mutex::new(vec![]));
+	let login_attempts = Arc::new(Mutex::new(crate::http_server::LoginAttempts::new()));
+	let program_state = Arc::new(Mutex::new(crate::http_server::ProgramState::default()));
+
+	let logs = Arc::new(Mutex::new(vec![]));
+	let logs_for_dispatcher = logs.clone();
+	let logger = Arc::new(Mutex::new(charlie_buffalo::Logger::new(
+		charlie_buffalo::new_dispatcher(Box::new(move |log: charlie_buffalo::Log| {
+			logs_for_dispatcher.lock().unwrap().push(log);
+		})),
+		None,
+	)));
+
+	let app = actix_web::test::init_service(
+		actix_web::App::new()
+			.app_data(actix_web::web::Data::new(settings))
+			.app_data(actix_web::web::Data::new(users))
+			.app_data(actix_web::web::Data::new(form_tokens))
+			.app_data(actix_web::web::Data::new(sessions))
+			.app_data(actix_web::web::Data::new(access_tokens))
+			.app_data(actix_web::web::Data::new(authorization_codes))
+			.app_data(actix_web::web::Data::new(login_attempts))
+			.app_data(actix_web::web::Data::new(program_state))
+			.app_data(actix_web::web::Data::new(logger))
+			.service(super::get_oauth)
+			.service(super::post_oauth),
+	)
+	.await;
+
+	let tests = vec![
+		(010, "WRONG_PASSWORD", "wrong_credentials"),
+		(020, "WRONG_PASSWORD", "wrong_credentials"),
+		(030, "password", "too_many_attempts"),
+		(040, "WRONG_PASSWORD", "too_many_attempts"),
+	];
+
+	for test in tests {
+		print!(
+			"#{:03} : POST request to /oauth with password = {} ... ",
+			test.0, test.1
+		);
+
+		let request = actix_web::test::TestRequest::get()
+			.uri("/oauth/user?redirect_uri=https%3A%2F%2Fapp.example.com%2F&scope=documents:rw&client_id=https%3A%2F%2Fapp.example.com&response_type=token")
+			.peer_addr("127.0.0.1:12345".parse().unwrap())
+			.to_request();
+		let response = actix_web::test::call_service(&app, request).await;
+		let html = actix_web::test::read_body(response).await;
+		let form_token = find_form_token(&String::from_utf8_lossy(&html));
+
+		let request = actix_web::test::TestRequest::post()
+			.uri("/oauth")
+			.peer_addr("127.0.0.1:12345".parse().unwrap())
+			.insert_header((actix_web::http::header::ORIGIN, server_origin.clone()))
+			.set_form([
+				("redirect_uri", "https://app.example.com/"),
+				("scope", "documents:rw"),
+				("client_id", "https://app.example.com"),
+				("response_type", "token"),
+				("username", "user"),
+				("password", test.1),
+				("allow", "Allow"),
+				("token", &form_token),
+			])
+			.to_request();
+		let response = actix_web::test::call_service(&app, request).await;
+
+		assert_eq!(response.status(), actix_web::http::StatusCode::FOUND);
+		let location = response
+			.headers()
+			.get(actix_web::http::header::LOCATION)
+			.unwrap()
+			.to_str()
+			.unwrap();
+		assert!(location.ends_with(&format!("&auth_result={}", test.2)));
+
+		println!("OK");
+	}
+
+	let logs = logs.lock().unwrap();
+	assert_eq!(logs.len(), 4);
+	for log in logs.iter() {
+		assert_eq!(log.attributes.get("ip"), Some(&String::from("127.0.0.1")));
+		assert_eq!(log.attributes.get("username"), Some(&String::from("user")));
+	}
+}
+
+#[actix_rt::test]
+async fn v8hk3nq0ra6wzle() {
+	let settings = crate::http_server::Settings::new(tempfile::tempdir().unwrap().into_path());
+	let server_origin = format!(
+		"http://{}:{}",
+		settings.domain.clone().unwrap(),
+		settings.port
+	);
+	let userfile_path = settings.userfile_path();
+	let settings = Arc::new(Mutex::new(settings));
+
+	let mut users = crate::http_server::Users::new();
+	users.insert("user", &mut String::from("password")).unwrap();
+	let users = Arc::new(Mutex::new(users));
+
+	let form_tokens: Arc<Mutex<Vec<crate::http_server::middlewares::OauthFormToken>>> =
+		Arc::new(Mutex::new(vec![]));
+	let sessions: Arc<Mutex<Vec<crate::http_server::Session>>> = Arc::new(Mutex::new(vec![]));
+	let access_tokens: Arc<Mutex<Vec<crate::http_server::AccessBearer>>> =
+		Arc::new(Mutex::new(vec![]));
+	let authorization_codes: Arc<Mutex<Vec<crate::http_server::AuthorizationCode>>> =
+		Arc::new(Mutex::new(vec![]));
+	let login_attempts = Arc::new(Mutex::new(crate::http_server::LoginAttempts::new()));
+	let program_state = Arc::new(Mutex::new(crate::http_server::ProgramState::default()));
+
+	let logger = Arc::new(Mutex::new(charlie_buffalo::Logger::new(
+		charlie_buffalo::new_dispatcher(Box::new(|_| {})),
+		None,
+	)));
+
+	let app = actix_web::test::init_service(
+		actix_web::App::new()
+			.app_data(actix_web::web::Data::new(settings))
+			.app_data(actix_web::web::Data::new(users.clone()))
+			.app_data(actix_web::web::Data::new(form_tokens))
+			.app_data(actix_web::web::Data::new(sessions))
+			.app_data(actix_web::web::Data::new(access_tokens.clone()))
+			.app_data(actix_web::web::Data::new(authorization_codes))
+			.app_data(actix_web::web::Data::new(login_attempts))
+			.app_data(actix_web::web::Data::new(program_state))
+			.app_data(actix_web::web::Data::new(logger))
+			.service(super::get_oauth)
+			.service(super::post_oauth),
+	)
+	.await;
+
+	let get_uri = |scope: &str| {
+		format!(
+			"/oauth/user?redirect_uri={}&scope={}&client_id={}&response_type=token",
+			"https%3A%2F%2Fapp.example.com%2Fcallback", scope, "https%3A%2F%2Fapp.example.com",
+		)
+	};
+
+	let request = actix_web::test::TestRequest::get()
+		.uri(&get_uri("documents:rw%20contacts:r"))
+		.peer_addr("127.0.0.1:12345".parse().unwrap())
+		.to_request();
+	let response = actix_web::test::call_service(&app, request).await;
+	assert_eq!(response.status(), actix_web::http::StatusCode::OK);
+	let html = actix_web::test::read_body(response).await;
+	assert!(!String::from_utf8_lossy(&html).contains("You already allowed this access"));
+	let form_token = find_form_token(&String::from_utf8_lossy(&html));
+
+	let request = actix_web::test::TestRequest::post()
+		.uri("/oauth")
+		.peer_addr("127.0.0.1:23456".parse().unwrap())
+		.insert_header((actix_web::http::header::ORIGIN, server_origin.clone()))
+		.set_form([
+			("redirect_uri", "https://app.example.com/callback"),
+			("scope", "documents:rw contacts:r"),
+			("client_id", "https://app.example.com"),
+			("response_type", "token"),
+			("username", "user"),
+			("password", "password"),
+			("allow", "Allow"),
+			("token", &form_token),
+		])
+		.to_request();
+	let response = actix_web::test::call_service(&app, request).await;
+	assert_eq!(response.status(), actix_web::http::StatusCode::FOUND);
+	let session = response
+		.response()
+		.cookies()
+		.find(|cookie| cookie.name() == super::super::SESSION_COOKIE_NAME)
+		.unwrap()
+		.into_owned();
+	assert!(session.http_only().unwrap_or(false));
+	assert_eq!(access_tokens.lock().unwrap().len(), 1);
+
+	assert!(
+		crate::http_server::Users::from_bytes(&std::fs::read(&userfile_path).unwrap())
+			.unwrap()
+			.is_granted(
+				"user",
+				"https://app.example.com",
+				&[crate::scope::Scope::try_from("contacts:r").unwrap()]
+			)
+	);
+
+	let tests = vec![
+		(
+			010,
+			"documents:r",
+			Some(session.clone()),
+			"127.0.0.1:34567",
+			actix_web::http::StatusCode::FOUND,
+		),
+		(
+			020,
+			"documents:rw%20contacts:r",
+			Some(session.clone()),
+			"127.0.0.1:34567",
+			actix_web::http::StatusCode::FOUND,
+		),
+		(
+			030,
+			"contacts:rw",
+			Some(session.clone()),
+			"127.0.0.1:34567",
+			actix_web::http::StatusCode::OK,
+		),
+		(
+			040,
+			"documents:r",
+			None,
+			"127.0.0.1:34567",
+			actix_web::http::StatusCode::OK,
+		),
+		(
+			050,
+			"documents:r",
+			Some(session.clone()),
+			"10.0.0.1:34567",
+			actix_web::http::StatusCode::OK,
+		),
+		(
+			060,
+			"documents:r",
+			Some(actix_web::cookie::Cookie::new(
+				super::super::SESSION_COOKIE_NAME,
+				"RANDOM_SESSION",
+			)),
+			"127.0.0.1:34567",
+			actix_web::http::StatusCode::OK,
+		),
+	];
+
+	for test in tests {
+		print!(
+			"#{:03} : GET request to /oauth/user with scope {} ... ",
+			test.0, test.1
+		);
+
+		let mut request = actix_web::test::TestRequest::get()
+			.uri(&get_uri(test.1))
+			.peer_addr(test.3.parse().unwrap());
+		if let Some(session) = test.2 {
+			request = request.cookie(session);
+		}
+
+		let response = actix_web::test::call_service(&app, request.to_request()).await;
+		assert_eq!(response.status(), test.4);
+
+		if test.4 == actix_web::http::StatusCode::FOUND {
+			assert!(response
+				.headers()
+				.get(actix_web::http::header::LOCATION)
+				.unwrap()
+				.to_str()
+				.unwrap()
+				.starts_with("https://app.example.com/callback#access_token="));
+		}
+
+		println!("OK");
+	}
+
+	assert_eq!(access_tokens.lock().unwrap().len(), 3);
+
+	users
+		.lock()
+		.unwrap()
+		.remove_grant("user", "https://app.example.com")
+		.unwrap();
+
+	let request = actix_web::test::TestRequest::get()
+		.uri(&get_uri("documents:r"))
+		.peer_addr("127.0.0.1:34567".parse().unwrap())
+		.cookie(session)
+		.to_request();
+	let response = actix_web::test::call_service(&app, request).await;
+	assert_eq!(response.status(), actix_web::http::StatusCode::OK);
+}
+
+#[actix_rt::test]
+async fn t3jy7ck0pwx5nfo() {
+	let settings = crate::http_server::Settings::new(tempfile::tempdir().unwrap().into_path());
+	let server_origin = format!(
+		"http://{}:{}",
+		settings.domain.clone().unwrap(),
+		settings.port
+	);
+	let settings = Arc::new(Mutex::new(settings));
+
+	let mut users = crate::http_server::Users::new();
+	users.insert("user", &mut String::from("password")).unwrap();
+	let users = Arc::new(Mutex::new(users));
+
+	let form_tokens: Arc<Mutex<Vec<crate::http_server::middlewares::OauthFormToken>>> =
+		Arc::new(Mutex::new(vec![]));
+	let sessions: Arc<Mutex<Vec<crate::http_server::Session>>> = Arc::new(Mutex::new(vec![]));
+	let access_tokens: Arc<Mutex<Vec<crate::http_server::AccessBearer>>> =
+		Arc::new(Mutex::new(vec![]));
+	let authorization_codes: Arc<Mutex<Vec<crate::http_server::AuthorizationCode>>> =
+		Arc::new(Mutex::new(vec![]));
+	let login_attempts = Arc::new(Mutex::new(crate::http_server::LoginAttempts::new()));
+	let program_state = Arc::new(Mutex::new(crate::http_server::ProgramState::default()));
+
+	let logger = Arc::new(Mutex::new(charlie_buffalo::Logger::new(
+		charlie_buffalo::new_dispatcher(Box::new(|_| {})),
+		None,
+	)));
+
+	let app = actix_web::test::init_service(
+		actix_web::App::new()
+			.app_data(actix_web::web::Data::new(settings))
+			.app_data(actix_web::web::Data::new(users.clone()))
+			.app_data(actix_web::web::Data::new(form_tokens))
+			.app_data(actix_web::web::Data::new(sessions))
+			.app_data(actix_web::web::Data::new(access_tokens.clone()))
+			.app_data(actix_web::web::Data::new(authorization_codes))
+			.app_data(actix_web::web::Data::new(login_attempts))
+			.app_data(actix_web::web::Data::new(program_state))
+			.app_data(actix_web::web::Data::new(logger))
+			.service(super::get_oauth)
+			.service(super::post_oauth),
+	)
+	.await;
+
+	let tests = vec![
+		(010, vec![], Some("contacts:rw documents:r")),
+		(
+			020,
+			vec![("grant_contacts", "r")],
+			Some("contacts:r documents:r"),
+		),
+		(030, vec![("grant_contacts", "")], Some("documents:r")),
+		(040, vec![("grant_documents", "rw")], Some("contacts:rw")),
+		(
+			050,
+			vec![("grant_contacts", ""), ("grant_documents", "")],
+			None,
+		),
+	];
+
+	for test in tests {
+		print!(
+			"#{:03} : allow access with choices {:?} ... ",
+			test.0, test.1
+		);
+
+		let request = actix_web::test::TestRequest::get()
+			.uri("/oauth/user?redirect_uri=https%3A%2F%2Fapp.example.com%2F&scope=contacts:rw%20documents:r&client_id=https%3A%2F%2Fapp.example.com&response_type=token")
+			.peer_addr("127.0.0.1:12345".parse().unwrap())
+			.to_request();
+		let response = actix_web::test::call_service(&app, request).await;
+		assert_eq!(response.status(), actix_web::http::StatusCode::OK);
+		let html = String::from_utf8_lossy(&actix_web::test::read_body(response).await).to_string();
+		assert!(html.contains(r#"<select name="grant_contacts"><option value="rw" selected>"#));
+		assert!(html.contains(r#"<select name="grant_documents"><option value="r" selected>"#));
+		assert!(!html.contains(r#"<select name="grant_documents"><option value="rw""#));
+
+		let form_token = find_form_token(&html);
+		let mut form = vec![
+			("redirect_uri", "https://app.example.com/"),
+			("scope", "contacts:rw documents:r"),
+			("client_id", "https://app.example.com"),
+			("response_type", "token"),
+			("username", "user"),
+			("password", "password"),
+			("allow", "Allow"),
+			("token", &form_token),
+		];
+		form.extend(test.1.iter().cloned());
+
+		let request = actix_web::test::TestRequest::post()
+			.uri("/oauth")
+			.insert_header((actix_web::http::header::ORIGIN, server_origin.clone()))
+			.set_form(form)
+			.to_request();
+		let response = actix_web::test::call_service(&app, request).await;
+		assert_eq!(response.status(), actix_web::http::StatusCode::FOUND);
+		let location = response
+			.headers()
+			.get(actix_web::http::header::LOCATION)
+			.unwrap()
+			.to_str()
+			.unwrap();
+
+		match test.2 {
+			Some(scope) => {
+				assert!(location.starts_with("https://app.example.com/#access_token="));
+				assert!(location.ends_with(&format!(
+					"&scope={}",
+					pct_str::PctString::encode(scope.chars(), pct_str::URIReserved)
+				)));
+				assert_eq!(
+					access_tokens
+						.lock()
+						.unwrap()
+						.last()
+						.unwrap()
+						.get_scopes()
+						.iter()
+						.map(|scope| scope.to_string())
+						.collect::<Vec<String>>()
+						.join(" "),
+					scope
+				);
+			}
+			None => {
+				assert!(location.ends_with("&auth_result=no_scope"));
+			}
+		}
+
+		users
+			.lock()
+			.unwrap()
+			.remove_grant("user", "https://app.example.com")
+			.ok();
+
+		println!("OK");
+	}
+
+	assert_eq!(access_tokens.lock().unwrap().len(), 4);
+}
+
+#[actix_rt::test]
+async fn k2xv9dm4qa7cneu() {
+	let settings = crate::http_server::Settings::new(tempfile::tempdir().unwrap().into_path());
+	let server_origin = format!(
+		"http://{}:{}",
+		settings.domain.clone().unwrap(),
+		settings.port
+	);
+	let settings = Arc::new(Mutex::new(settings));
+
+	let mut users = crate::http_server::Users::new();
+	users.insert("user", &mut String::from("password")).unwrap();
+	users.start_totp("user").unwrap();
+	let uri = users.get_totp_setup_uri("user", "pontus_onyx").unwrap();
+	let secret = base32::decode(
+		base32::Alphabet::RFC4648 { padding: false },
+		uri.split_once("secret=")
+			.unwrap()
+			.1
+			.split('&')
+			.next()
+			.unwrap(),
+	)
+	.unwrap();
+	let step = crate::http_server::users::totp::current_step();
+	users
+		.confirm_totp(
+			"user",
+			&crate::http_server::users::totp::code(&secret, step),
+		)
+		.unwrap();
+	let users = Arc::new(Mutex::new(users));
+
+	let form_tokens: Arc<Mutex<Vec<crate::http_server::middlewares::OauthFormToken>>> =
+		Arc::new(Mutex::new(vec![]));
+	let sessions: Arc<Mutex<Vec<crate::http_server::Session>>> = Arc::new(Mutex::new(vec![]));
+	let access_tokens: Arc<Mutex<Vec<crate::http_server::AccessBearer>>> =
+		Arc::new(Mutex::new(vec![]));
+	let authorization_codes: Arc<Mutex<Vec<crate::http_server::AuthorizationCode>>> =
+		Arc::new(Mutex::new(vec![]));
+	let login_attempts = Arc::new(Mutex::new(crate::http_server::LoginAttempts::new()));
+	let program_state = Arc::new(Mutex::new(crate::http_server::ProgramState::default()));
+
+	let logger = Arc::new(Mutex::new(charlie_buffalo::Logger::new(
+		charlie_buffalo::new_dispatcher(Box::new(|_| {})),
+		None,
+	)));
+
+	let app = actix_web::test::init_service(
+		actix_web::App::new()
+			.app_data(actix_web::web::Data::new(settings))
+			.app_data(actix_web::web::Data::new(users))
+			.app_data(actix_web::web::Data::new(form_tokens))
+			.app_data(actix_web::web::Data::new(sessions))
+			.app_data(actix_web::web::Data::new(access_tokens.clone()))
+			.app_data(actix_web::web::Data::new(authorization_codes))
+			.app_data(actix_web::web::Data::new(login_attempts))
+			.app_data(actix_web::web::Data::new(program_state))
+			.app_data(actix_web::web::Data::new(logger))
+			.service(super::get_oauth)
+			.service(super::post_oauth),
+	)
+	.await;
+
+	let tests = vec![
+		(010, String::new(), false),
+		(
+			020,
+			crate::http_server::users::totp::code(&secret, step),
+			false,
+		),
+		(
+			030,
+			crate::http_server::users::totp::code(&secret, step + 1),
+			true,
+		),
+	];
+
+	for test in tests {
+		print!(
+			"#{:03} : allow access with two-factor code `{}` ... ",
+			test.0, test.1
+		);
+
+		let request = actix_web::test::TestRequest::get()
+			.uri("/oauth/user?redirect_uri=https%3A%2F%2Fapp.example.com%2F&scope=documents:rw&client_id=https%3A%2F%2Fapp.example.com&response_type=token")
+			.peer_addr("127.0.0.1:12345".parse().unwrap())
+			.to_request();
+		let response = actix_web::test::call_service(&app, request).await;
+		let html = String::from_utf8_lossy(&actix_web::test::read_body(response).await).to_string();
+		assert!(html.contains(r#"<input type="text" name="totp_code""#));
+
+		let form_token = find_form_token(&html);
+		let request = actix_web::test::TestRequest::post()
+			.uri("/oauth")
+			.insert_header((actix_web::http::header::ORIGIN, server_origin.clone()))
+			.set_form([
+				("redirect_uri", "https://app.example.com/"),
+				("scope", "documents:rw"),
+				("client_id", "https://app.example.com"),
+				("response_type", "token"),
+				("username", "user"),
+				("password", "password"),
+				("totp_code", &test.1),
+				("allow", "Allow"),
+				("token", &form_token),
+			])
+			.to_request();
+		let response = actix_web::test::call_service(&app, request).await;
+		assert_eq!(response.status(), actix_web::http::StatusCode::FOUND);
+		let location = response
+			.headers()
+			.get(actix_web::http::header::LOCATION)
+			.unwrap()
+			.to_str()
+			.unwrap();
+
+		if test.2 {
+			assert!(location.starts_with("https://app.example.com/#access_token="));
+		} else {
+			assert!(location.ends_with("&auth_result=wrong_credentials"));
+		}
+
+		println!("OK");
+	}
+
+	assert_eq!(access_tokens.lock().unwrap().len(), 1);
+}
+
+#[actix_rt::test]
+async fn y7pc3fj0mxn2duk() {
+	let mut settings = crate::http_server::Settings::new(tempfile::tempdir().unwrap().into_path());
+	let server_origin = format!(
+		"http://{}:{}",
+		settings.domain.clone().unwrap(),
+		settings.port
+	);
+	let htpasswd_path = settings.workspace_path.join(".htpasswd");
+	std::fs::write(&htpasswd_path, "htuser:{SHA}VBPuJHI7uixaa6LQGWx4s+5GKNE=\n").unwrap();
+	settings.auth_backend = Some(crate::http_server::SettingsAuthBackend::Htpasswd {
+		path: htpasswd_path.to_string_lossy().to_string(),
+	});
+	let settings = Arc::new(Mutex::new(settings));
+
+	let mut users = crate::http_server::Users::new();
+	users.insert("user", &mut String::from("password")).unwrap();
+	let users = Arc::new(Mutex::new(users));
+
+	let form_tokens: Arc<Mutex<Vec<crate::http_server::middlewares::OauthFormToken>>> =
+		Arc::new(Mutex::new(vec![]));
+	let sessions: Arc<Mutex<Vec<crate::http_server::Session>>> = Arc::new(Mutex::new(vec![]));
+	let access_tokens: Arc<Mutex<Vec<crate::http_server::AccessBearer>>> =
+		Arc::new(Mutex::new(vec![]));
+	let authorization_codes: Arc<Mutex<Vec<crate::http_server::AuthorizationCode>>> =
+		Arc::new(Mutex::new(vec![]));
+	let login_attempts = Arc::new(Mutex::new(crate::http_server::LoginAttempts::new()));
+	let program_state = Arc::new(Mutex::new(crate::http_server::ProgramState::default()));
+
+	let logger = Arc::new(Mutex::new(charlie_buffalo::Logger::new(
+		charlie_buffalo::new_dispatcher(Box::new(|_| {})),
+		None,
+	)));
+
+	let app = actix_web::test::init_service(
+		actix_web::App::new()
+			.app_data(actix_web::web::Data::new(settings))
+			.app_data(actix_web::web::Data::new(users))
+			.app_data(actix_web::web::Data::new(form_tokens))
+			.app_data(actix_web::web::Data::new(sessions))
+			.app_data(actix_web::web::Data::new(access_tokens.clone()))
+			.app_data(actix_web::web::Data::new(authorization_codes))
+			.app_data(actix_web::web::Data::new(login_attempts))
+			.app_data(actix_web::web::Data::new(program_state))
+			.app_data(actix_web::web::Data::new(logger))
+			.service(super::get_oauth)
+			.service(super::post_oauth),
+	)
+	.await;
+
+	let tests = vec![
+		(010, "htuser", "myPassword", true),
+		(020, "htuser", "password", false),
+		(030, "user", "password", false),
+	];
+
+	for test in tests {
+		print!(
+			"#{:03} : allow access as `{}` with htpasswd backend ... ",
+			test.0, test.1
+		);
+
+		let request = actix_web::test::TestRequest::get()
+			.uri(&format!("/oauth/{}?redirect_uri=https%3A%2F%2Fapp.example.com%2F&scope=documents:rw&client_id=https%3A%2F%2Fapp.example.com&response_type=token", test.1))
+			.peer_addr("127.0.0.1:12345".parse().unwrap())
+			.to_request();
+		let response = actix_web::test::call_service(&app, request).await;
+		let form_token = find_form_token(&String::from_utf8_lossy(
+			&actix_web::test::read_body(response).await,
+		));
+
+		let request = actix_web::test::TestRequest::post()
+			.uri("/oauth")
+			.insert_header((actix_web::http::header::ORIGIN, server_origin.clone()))
+			.set_form([
+				("redirect_uri", "https://app.example.com/"),
+				("scope", "documents:rw"),
+				("client_id", "https://app.example.com"),
+				("response_type", "token"),
+				("username", test.1),
+				("password", test.2),
+				("allow", "Allow"),
+				("token", &form_token),
+			])
+			.to_request();
+		let response = actix_web::test::call_service(&app, request).await;
+		assert_eq!(response.status(), actix_web::http::StatusCode::FOUND);
+		let location = response
+			.headers()
+			.get(actix_web::http::header::LOCATION)
+			.unwrap()
+			.to_str()
+			.unwrap();
+
+		if test.3 {
+			assert!(location.starts_with("https://app.example.com/#access_token="));
+		} else {
+			assert!(location.ends_with("&auth_result=wrong_credentials"));
+		}
+
+		println!("OK");
+	}
+
+	let access_tokens = access_tokens.lock().unwrap();
+	assert_eq!(access_tokens.len(), 1);
+	assert_eq!(access_tokens[0].get_username(), "htuser");
+}
+
+#[actix_rt::test]
+async fn q4nw8zr1ce6jxat() {
+	let settings = crate::http_server::Settings::new(tempfile::tempdir().unwrap().into_path());
+	let server_origin = format!(
+		"http://{}:{}",
+		settings.domain.clone().unwrap(),
+		settings.port
+	);
+	let settings = Arc::new(Mutex::new(settings));
+
+	let mut users = crate::http_server::Users::new();
+	users.insert("user", &mut String::from("password")).unwrap();
+	let users = Arc::new(Mutex::new(users));
+
+	let form_tokens: Arc<Mutex<Vec<crate::http_server::middlewares::OauthFormToken>>> =
+		Arc::new(Mutex::new(vec![]));
+	let sessions: Arc<Mutex<Vec<crate::http_server::Session>>> = Arc::new(Mutex::new(vec![]));
+	let access_tokens: Arc<Mutex<Vec<crate::http_server::AccessBearer>>> =
+		Arc::new(Mutex::new(vec![]));
+	let authorization_codes: Arc<Mutex<Vec<crate::http_server::AuthorizationCode>>> =
+		Arc::new(Mutex::new(vec![]));
+	let login_attempts = Arc::new(Mutex::new(crate::http_server::LoginAttempts::new()));
+	let program_state = Arc::new(Mutex::new(crate::http_server::ProgramState::default()));
+
+	let logger = Arc::new(Mutex::new(charlie_buffalo::Logger::new(
+		charlie_buffalo::new_dispatcher(Box::new(|_| {})),
+		None,
+	)));
+
+	let app = actix_web::test::init_service(
+		actix_web::App::new()
+			.app_data(actix_web::web::Data::new(settings))
+			.app_data(actix_web::web::Data::new(users))
+			.app_data(actix_web::web::Data::new(form_tokens))
+			.app_data(actix_web::web::Data::new(sessions))
+			.app_data(actix_web::web::Data::new(access_tokens.clone()))
+			.app_data(actix_web::web::Data::new(authorization_codes.clone()))
+			.app_data(actix_web::web::Data::new(login_attempts))
+			.app_data(actix_web::web::Data::new(program_state))
+			.app_data(actix_web::web::Data::new(logger))
+			.service(super::get_oauth)
+			.service(super::post_oauth),
+	)
+	.await;
+
+	let tests = vec![
+		(010, "id_token", None, None, "password"),
+		(020, "id_token", None, None, "wrong"),
+		(030, "code", None, None, "password"),
+		(040, "code", None, None, "wrong"),
+		(
+			050,
+			"code",
+			Some("dBjftJeZ4CVP-mB92K27uhbUJU1p1r_wW1gFWFOEjXk"),
+			Some("plain"),
+			"password",
+		),
+		(
+			060,
+			"code",
+			Some("dBjftJeZ4CVP-mB92K27uhbUJU1p1r_wW1gFWFOEjXk"),
+			Some("plain"),
+			"wrong",
+		),
+	];
+
+	for test in tests {
+		print!(
+			"#{:03} : POST request to /oauth with response_type `{}`, code_challenge_method `{:?}` and password `{}` ... ",
+			test.0, test.1, test.3, test.4
+		);
+
+		let request = actix_web::test::TestRequest::get()
+			.uri("/oauth/user?redirect_uri=https%3A%2F%2Fapp.example.com%2F&scope=documents:rw&client_id=https%3A%2F%2Fapp.example.com&response_type=token")
+			.peer_addr("127.0.0.1:12345".parse().unwrap())
+			.to_request();
+		let response = actix_web::test::call_service(&app, request).await;
+		let html = String::from_utf8_lossy(&actix_web::test::read_body(response).await).to_string();
+
+		let form_token = find_form_token(&html);
+		let mut form = vec![
+			("redirect_uri", "https://app.example.com/"),
+			("scope", "documents:rw"),
+			("client_id", "https://app.example.com"),
+			("response_type", test.1),
+			("username", "user"),
+			("password", test.4),
+			("allow", "Allow"),
+			("token", &form_token),
+		];
+		if let Some(code_challenge) = test.2 {
+			form.push(("code_challenge", code_challenge));
+		}
+		if let Some(code_challenge_method) = test.3 {
+			form.push(("code_challenge_method", code_challenge_method));
+		}
+
+		let request = actix_web::test::TestRequest::post()
+			.uri("/oauth")
+			.insert_header((actix_web::http::header::ORIGIN, server_origin.clone()))
+			.peer_addr("127.0.0.1:12345".parse().unwrap())
+			.set_form(form)
+			.to_request();
+		let response = actix_web::test::call_service(&app, request).await;
+
+		assert_eq!(response.status(), actix_web::http::StatusCode::BAD_REQUEST);
+		assert!(response
+			.headers()
+			.get(actix_web::http::header::LOCATION)
+			.is_none());
+
+		println!("OK");
+	}
+
+	assert!(access_tokens.lock().unwrap().is_empty());
+	assert!(authorization_codes.lock().unwrap().is_empty());
+}
+
+#[actix_rt::test]
+async fn m8zt3kv6wr1xqpe() {
+	let settings = crate::http_server::Settings::new(tempfile::tempdir().unwrap().into_path());
+	let server_origin = format!(
+		"http://{}:{}",
+		settings.domain.clone().unwrap(),
+		settings.port
+	);
+	let settings = Arc::new(Mutex::new(settings));
+
+	let users = Arc::new(Mutex::new(crate::http_server::Users::new()));
+
+	let form_tokens: Arc<Mutex<Vec<crate::http_server::middlewares::OauthFormToken>>> =
+		Arc::new(Mutex::new(vec![]));
+	let sessions: Arc<Mutex<Vec<crate::http_server::Session>>> = Arc::new(Mutex::new(vec![]));
+	let access_tokens: Arc<Mutex<Vec<crate::http_server::AccessBearer>>> =
+		Arc::new(Mutex::new(vec![]));
+	let authorization_codes: Arc<Mutex<Vec<crate::http_server::AuthorizationCode>>> =
+		Arc::new(Mutex::new(vec![]));
+	let login_attempts = Arc::new(Mutex::new(crate::http_server::LoginAttempts::new()));
+	let program_state = Arc::new(Mutex::new(crate::http_server::ProgramState::default()));
+
+	let logger = Arc::new(Mutex::new(charlie_buffalo::Logger::new(
+		charlie_buffalo::new_dispatcher(Box::new(|_| {})),
+		None,
+	)));
+
+	let app = actix_web::test::init_service(
+		actix_web::App::new()
+			.app_data(actix_web::web::Data::new(settings))
+			.app_data(actix_web::web::Data::new(users))
+			.app_data(actix_web::web::Data::new(form_tokens))
+			.app_data(actix_web::web::Data::new(sessions))
+			.app_data(actix_web::web::Data::new(access_tokens))
+			.app_data(actix_web::web::Data::new(authorization_codes))
+			.app_data(actix_web::web::Data::new(login_attempts))
+			.app_data(actix_web::web::Data::new(program_state))
+			.app_data(actix_web::web::Data::new(logger))
+			.service(super::get_oauth)
+			.service(super::post_oauth),
+	)
+	.await;
+
+	let tests = vec![
+		(010, "user", "%zz", None),
+		(020, "user", "xyz%2", None),
+		(030, "user", "xyz%20abc", Some("/oauth/user?")),
+		(040, "a/b?c", "xyz", Some("/oauth/a%2Fb%3Fc?")),
+	];
+
+	for test in tests {
+		print!(
+			"#{:03} : POST request to /oauth with username `{}` and state `{}` ... ",
+			test.0, test.1, test.2
+		);
+
+		let request = actix_web::test::TestRequest::get()
+			.uri("/oauth/user?redirect_uri=https%3A%2F%2Fapp.example.com%2F&scope=documents:rw&client_id=https%3A%2F%2Fapp.example.com&response_type=token")
+			.peer_addr("127.0.0.1:12345".parse().unwrap())
+			.to_request();
+		let response = actix_web::test::call_service(&app, request).await;
+		let html = String::from_utf8_lossy(&actix_web::test::read_body(response).await).to_string();
+
+		let form_token = find_form_token(&html);
+		let request = actix_web::test::TestRequest::post()
+			.uri("/oauth")
+			.insert_header((actix_web::http::header::ORIGIN, server_origin.clone()))
+			.peer_addr("127.0.0.1:12345".parse().unwrap())
+			.set_form([
+				("redirect_uri", "https://app.example.com/"),
+				("scope", "documents:rw"),
+				("client_id", "https://app.example.com"),
+				("response_type", "token"),
+				("username", test.1),
+				("password", "wrong"),
+				("allow", "Allow"),
+				("token", &form_token),
+				("state", test.2),
+			])
+			.to_request();
+		let response = actix_web::test::call_service(&app, request).await;
+
+		match test.3 {
+			Some(location) => {
+				assert_eq!(response.status(), actix_web::http::StatusCode::FOUND);
+				assert!(response
+					.headers()
+					.get(actix_web::http::header::LOCATION)
+					.unwrap()
+					.to_str()
+					.unwrap()
+					.starts_with(location));
+			}
+			None => {
+				assert_eq!(response.status(), actix_web::http::StatusCode::BAD_REQUEST);
+			}
+		}
+
+		println!("OK");
+	}
+}
+
+#[actix_rt::test]
+async fn d5qh9wn2ty7mkcz() {
+	let settings = crate::http_server::Settings::new(tempfile::tempdir().unwrap().into_path());
+	let server_origin = format!(
+		"http://{}:{}",
+		settings.domain.clone().unwrap(),
+		settings.port
+	);
+	let settings = Arc::new(Mutex::new(settings));
+
+	let mut users = crate::http_server::Users::new();
+	users.insert("user", &mut String::from("password")).unwrap();
+	let users = Arc::new(Mutex::new(users));
+
+	let form_tokens: Arc<Mutex<Vec<crate::http_server::middlewares::OauthFormToken>>> =
+		Arc::new(Mutex::new(vec![]));
+	let sessions: Arc<Mutex<Vec<crate::http_server::Session>>> = Arc::new(Mutex::new(vec![]));
+	let access_tokens: Arc<Mutex<Vec<crate::http_server::AccessBearer>>> =
+		Arc::new(Mutex::new(vec![]));
+	let authorization_codes: Arc<Mutex<Vec<crate::http_server::AuthorizationCode>>> =
+		Arc::new(Mutex::new(vec![]));
+	let login_attempts = Arc::new(Mutex::new(crate::http_server::LoginAttempts::new()));
+	let program_state = Arc::new(Mutex::new(crate::http_server::ProgramState::default()));
+
+	let logger = Arc::new(Mutex::new(charlie_buffalo::Logger::new(
+		charlie_buffalo::new_dispatcher(Box::new(|_| {})),
+		None,
+	)));
+
+	let app = actix_web::test::init_service(
+		actix_web::App::new()
+			.app_data(actix_web::web::Data::new(settings))
+			.app_data(actix_web::web::Data::new(users))
+			.app_data(actix_web::web::Data::new(form_tokens))
+			.app_data(actix_web::web::Data::new(sessions.clone()))
+			.app_data(actix_web::web::Data::new(access_tokens.clone()))
+			.app_data(actix_web::web::Data::new(authorization_codes))
+			.app_data(actix_web::web::Data::new(login_attempts))
+			.app_data(actix_web::web::Data::new(program_state))
+			.app_data(actix_web::web::Data::new(logger))
+			.service(super::get_oauth)
+			.service(super::post_oauth),
+	)
+	.await;
+
+	let request = actix_web::test::TestRequest::get()
+		.uri("/oauth/user?redirect_uri=https%3A%2F%2Fapp.example.com%2F&scope=documents:rw&client_id=https%3A%2F%2Fapp.example.com&response_type=token")
+		.peer_addr("127.0.0.1:12345".parse().unwrap())
+		.to_request();
+	let response = actix_web::test::call_service(&app, request).await;
+	let html = String::from_utf8_lossy(&actix_web::test::read_body(response).await).to_string();
+
+	let form_token = find_form_token(&html);
+	let request = actix_web::test::TestRequest::post()
+		.uri("/oauth")
+		.insert_header((actix_web::http::header::ORIGIN, server_origin.clone()))
+		.peer_addr("127.0.0.1:12345".parse().unwrap())
+		.set_form([
+			("redirect_uri", "https://app.example.com/"),
+			("scope", "documents:rw"),
+			("client_id", "https://app.example.com"),
+			("response_type", "token"),
+			("username", "us%65r"),
+			("password", "password"),
+			("allow", "Allow"),
+			("token", &form_token),
+		])
+		.to_request();
+	let response = actix_web::test::call_service(&app, request).await;
+	assert_eq!(response.status(), actix_web::http::StatusCode::FOUND);
+
+	// the checked user is the one of the token and of the session
+	assert_eq!(
+		access_tokens
+			.lock()
+			.unwrap()
+			.iter()
+			.map(|token| String::from(token.get_username()))
+			.collect::<Vec<String>>(),
+		vec![String::from("user")]
+	);
+	assert_eq!(
+		sessions
+			.lock()
+			.unwrap()
+			.iter()
+			.map(|session| String::from(session.get_username()))
+			.collect::<Vec<String>>(),
+		vec![String::from("user")]
+	);
+}
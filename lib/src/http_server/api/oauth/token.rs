@@ -0,0 +1,118 @@
+use std::sync::{Arc, Mutex};
+
+#[derive(serde::Deserialize)]
+pub struct OauthTokenQuery {
+	grant_type: String,
+	code: String,
+	redirect_uri: String,
+	client_id: String,
+	code_verifier: String,
+}
+
+/// Token endpoint of the authorization code flow, see
+/// [RFC 6749 section 4.1.3](https://datatracker.ietf.org/doc/html/rfc6749#section-4.1.3).
+#[actix_web::post("/oauth/token")]
+pub async fn post_oauth_token(
+	form: actix_web::web::Form<OauthTokenQuery>,
+	authorization_codes: actix_web::web::Data<
+		Arc<Mutex<Vec<crate::http_server::AuthorizationCode>>>,
+	>,
+	access_tokens: actix_web::web::Data<Arc<Mutex<Vec<crate::http_server::AccessBearer>>>>,
+	settings: actix_web::web::Data<Arc<Mutex<crate::http_server::Settings>>>,
+	logger: actix_web::web::Data<Arc<Mutex<charlie_buffalo::Logger>>>,
+) -> impl actix_web::Responder {
+	if form.grant_type != "authorization_code" {
+		return build_token_response(
+			actix_web::http::StatusCode::BAD_REQUEST,
+			serde_json::json!({ "error": "unsupported_grant_type" }),
+		);
+	}
+
+	// the code is removed in all cases, because it can only be used once
+	let code = {
+		let mut codes = authorization_codes.lock().unwrap();
+		codes.retain(|code| !code.has_expirated());
+
+		let position = codes.iter().position(|code| code.get_value() == form.code);
+		position.map(|position| codes.remove(position))
+	};
+
+	let code = match code {
+		Some(code) => code,
+		None => {
+			logger.lock().unwrap().push(
+				vec![
+					(String::from("event"), String::from("oauth_token")),
+					(String::from("level"), String::from("ERROR")),
+				],
+				Some("authorization code not found or expirated"),
+			);
+
+			return build_token_response(
+				actix_web::http::StatusCode::BAD_REQUEST,
+				serde_json::json!({ "error": "invalid_grant" }),
+			);
+		}
+	};
+
+	if code.get_client_id() != form.client_id
+		|| code.get_redirect_uri() != form.redirect_uri
+		|| !code.check_code_verifier(&form.code_verifier)
+	{
+		logger.lock().unwrap().push(
+			vec![
+				(String::from("event"), String::from("oauth_token")),
+				(String::from("level"), String::from("ERROR")),
+			],
+			Some(&format!(
+				"authorization code of `{}` does not match request",
+				code.get_username()
+			)),
+		);
+
+		return build_token_response(
+			actix_web::http::StatusCode::BAD_REQUEST,
+			serde_json::json!({ "error": "invalid_grant" }),
+		);
+	}
+
+	let new_token = code.into_access_bearer();
+	access_tokens.lock().unwrap().push(new_token.clone());
+
+	let expires_in = settings
+		.lock()
+		.unwrap()
+		.token_lifetime_seconds
+		.unwrap_or_else(|| {
+			crate::http_server::Settings::new(std::path::PathBuf::from("."))
+				.token_lifetime_seconds
+				.unwrap()
+		});
+
+	return build_token_response(
+		actix_web::http::StatusCode::OK,
+		serde_json::json!({
+			"access_token": new_token.get_name(),
+			"token_type": "bearer",
+			"expires_in": expires_in,
+			"scope": new_token
+				.get_scopes()
+				.iter()
+				.map(|scope| scope.to_string())
+				.collect::<Vec<String>>()
+				.join(" "),
+		}),
+	);
+}
+
+fn build_token_response(
+	code: actix_web::http::StatusCode,
+	body: serde_json::Value,
+) -> actix_web::HttpResponse {
+	let mut response = actix_web::HttpResponse::build(code);
+	response.content_type("application/json");
+	response.insert_header((actix_web::http::header::CACHE_CONTROL, "no-store"));
+	response.insert_header((actix_web::http::header::PRAGMA, "no-cache"));
+
+	return response.body(body.to_string());
+}
@@ -0,0 +1,95 @@
+use std::sync::{Arc, Mutex};
+
+/// Answers with the methods allowed on this item with the access token of the request (or
+/// without token), in the `Allow` header.
+///
+/// Preflight requests of browsers are answered by the
+/// [`Cors`](crate::http_server::middlewares::Cors) middleware, because they never have tokens.
+#[actix_web::options("/storage/{requested_item:.*}")]
+pub async fn options_item(
+	request: actix_web::HttpRequest,
+	access_tokens: actix_web::web::Data<Arc<Mutex<Vec<crate::http_server::AccessBearer>>>>,
+) -> impl actix_web::Responder {
+	// the token has already been checked by the `Auth` middleware
+	let token = request
+		.headers()
+		.get(actix_web::http::header::AUTHORIZATION)
+		.and_then(|value| value.to_str().ok())
+		.and_then(|value| value.strip_prefix("Bearer "))
+		.and_then(|name| {
+			access_tokens
+				.lock()
+				.unwrap()
+				.iter()
+				.find(|token| token.get_name() == name.trim())
+				.cloned()
+		});
+
+	let mut response = actix_web::HttpResponse::Ok();
+	response.insert_header((actix_web::http::header::CACHE_CONTROL, "no-cache"));
+	response.insert_header((
+		actix_web::http::header::ALLOW,
+		join_methods(&allowed_storage_methods(request.path(), token.as_ref())),
+	));
+
+	return response.finish();
+}
+
+/// Methods which can be used on `path` of the storage (like `/storage/user/documents/a`) with
+/// enough rights : folders can only be read.
+pub fn storage_methods(path: &str) -> Vec<actix_web::http::Method> {
+	if path.ends_with('/') {
+		vec![
+			actix_web::http::Method::OPTIONS,
+			actix_web::http::Method::GET,
+			actix_web::http::Method::HEAD,
+		]
+	} else {
+		vec![
+			actix_web::http::Method::OPTIONS,
+			actix_web::http::Method::GET,
+			actix_web::http::Method::HEAD,
+			actix_web::http::Method::PUT,
+			actix_web::http::Method::DELETE,
+		]
+	}
+}
+
+/// Methods of [`storage_methods`] allowed by the scopes of `token`, or only the ones allowed
+/// without token : documents of public folders can be read by anyone.
+fn allowed_storage_methods(
+	path: &str,
+	token: Option<&crate::http_server::AccessBearer>,
+) -> Vec<actix_web::http::Method> {
+	let is_public_document = path.starts_with("/storage/public/") && !path.ends_with('/');
+
+	storage_methods(path)
+		.into_iter()
+		.filter(|method| {
+			*method == actix_web::http::Method::OPTIONS
+				|| (is_public_document
+					&& (*method == actix_web::http::Method::GET
+						|| *method == actix_web::http::Method::HEAD))
+				|| token
+					.map(|token| {
+						token
+							.get_scopes()
+							.iter()
+							.any(|scope| scope.is_allowed(method, path, token.get_username()))
+					})
+					.unwrap_or(false)
+		})
+		.collect()
+}
+
+/// Value of `Allow` and `Access-Control-Allow-Methods` headers.
+pub fn join_methods(methods: &[actix_web::http::Method]) -> String {
+	methods
+		.iter()
+		.map(|method| method.as_str())
+		.collect::<Vec<&str>>()
+		.join(", ")
+}
+
+#[cfg(test)]
+mod tests;
@@ -0,0 +1,224 @@
+use std::sync::{Arc, Mutex};
+
+// mirrors the examples of the remoteStorage draft, with the `myfavoritedrinks` application
+#[actix_rt::test]
+async fn z7kq2vd9mxh4rcn() {
+	let settings = Arc::new(Mutex::new(crate::http_server::Settings::new(
+		std::path::PathBuf::from("."),
+	)));
+
+	let read_write_token = crate::http_server::AccessBearer::new(
+		vec![crate::scope::Scope {
+			module: String::from("myfavoritedrinks"),
+			right_type: crate::scope::ScopeRightType::ReadWrite,
+		}],
+		"https://drinks-unhosted.5apps.com",
+		"",
+		"michiel",
+	);
+	let read_token = crate::http_server::AccessBearer::new(
+		vec![crate::scope::Scope {
+			module: String::from("myfavoritedrinks"),
+			right_type: crate::scope::ScopeRightType::Read,
+		}],
+		"https://drinks-unhosted.5apps.com",
+		"",
+		"michiel",
+	);
+	let access_tokens = Arc::new(Mutex::new(vec![
+		read_write_token.clone(),
+		read_token.clone(),
+	]));
+
+	let logger = Arc::new(Mutex::new(charlie_buffalo::Logger::new(
+		charlie_buffalo::new_dispatcher(Box::new(|_| {})),
+		None,
+	)));
+
+	let app = actix_web::test::init_service(
+		actix_web::App::new()
+			.app_data(actix_web::web::Data::new(settings.clone()))
+			.app_data(actix_web::web::Data::new(access_tokens.clone()))
+			.wrap(crate::http_server::middlewares::Auth { logger })
+			.wrap(crate::http_server::middlewares::Cors)
+			.service(super::options_item),
+	)
+	.await;
+
+	let origin = "https://drinks-unhosted.5apps.com";
+
+	// preflight requests, without token
+	let tests = vec![
+		(
+			010,
+			"/storage/michiel/myfavoritedrinks/",
+			"GET",
+			"OPTIONS, GET, HEAD",
+		),
+		(
+			020,
+			"/storage/michiel/myfavoritedrinks/test",
+			"PUT",
+			"OPTIONS, GET, HEAD, PUT, DELETE",
+		),
+		(
+			030,
+			"/storage/michiel/myfavoritedrinks/test",
+			"DELETE",
+			"OPTIONS, GET, HEAD, PUT, DELETE",
+		),
+		(
+			040,
+			"/storage/public/michiel/myfavoritedrinks/",
+			"PUT",
+			"OPTIONS, GET, HEAD",
+		),
+	];
+
+	for test in tests {
+		print!(
+			"#{:03} : preflight of {} request to {} ... ",
+			test.0, test.2, test.1
+		);
+
+		let request = actix_web::test::TestRequest::default()
+			.method(actix_web::http::Method::OPTIONS)
+			.uri(test.1)
+			.insert_header((actix_web::http::header::ORIGIN, origin))
+			.insert_header((
+				actix_web::http::header::ACCESS_CONTROL_REQUEST_METHOD,
+				test.2,
+			))
+			.insert_header((
+				actix_web::http::header::ACCESS_CONTROL_REQUEST_HEADERS,
+				"Authorization, Content-Type, If-None-Match",
+			))
+			.to_request();
+		let response = actix_web::test::call_service(&app, request).await;
+
+		assert_eq!(response.status(), actix_web::http::StatusCode::OK);
+		assert_eq!(
+			response
+				.headers()
+				.get(actix_web::http::header::ACCESS_CONTROL_ALLOW_ORIGIN)
+				.unwrap(),
+			origin
+		);
+		assert_eq!(
+			response
+				.headers()
+				.get(actix_web::http::header::ACCESS_CONTROL_ALLOW_METHODS)
+				.unwrap(),
+			test.3
+		);
+		let allowed_headers = response
+			.headers()
+			.get(actix_web::http::header::ACCESS_CONTROL_ALLOW_HEADERS)
+			.unwrap()
+			.to_str()
+			.unwrap();
+		for header in ["Authorization", "Content-Type", "If-None-Match"] {
+			assert!(allowed_headers.contains(header));
+		}
+
+		println!("OK");
+	}
+
+	// requests of applications, with or without token
+	let tests = vec![
+		(
+			110,
+			"/storage/michiel/myfavoritedrinks/test",
+			Some(&read_write_token),
+			200,
+			Some("OPTIONS, GET, HEAD, PUT, DELETE"),
+		),
+		(
+			120,
+			"/storage/michiel/myfavoritedrinks/test",
+			Some(&read_token),
+			200,
+			Some("OPTIONS, GET, HEAD"),
+		),
+		(
+			130,
+			"/storage/michiel/myfavoritedrinks/",
+			Some(&read_write_token),
+			200,
+			Some("OPTIONS, GET, HEAD"),
+		),
+		(
+			140,
+			"/storage/michiel/myfavoritedrinks/test",
+			None,
+			200,
+			Some("OPTIONS"),
+		),
+		(
+			150,
+			"/storage/public/michiel/myfavoritedrinks/test",
+			None,
+			200,
+			Some("OPTIONS, GET, HEAD"),
+		),
+		(
+			160,
+			"/storage/public/michiel/myfavoritedrinks/",
+			None,
+			200,
+			Some("OPTIONS"),
+		),
+		(
+			170,
+			"/storage/public/michiel/myfavoritedrinks/test",
+			Some(&read_token),
+			200,
+			Some("OPTIONS, GET, HEAD"),
+		),
+		(
+			180,
+			"/storage/public/michiel/myfavoritedrinks/test",
+			Some(&read_write_token),
+			200,
+			Some("OPTIONS, GET, HEAD, PUT, DELETE"),
+		),
+		(
+			190,
+			"/storage/michiel/other/test",
+			Some(&read_write_token),
+			200,
+			Some("OPTIONS"),
+		),
+	];
+
+	for test in tests {
+		print!(
+			"#{:03} : OPTIONS request to {} (with token : {}) ... ",
+			test.0,
+			test.1,
+			test.2.is_some()
+		);
+
+		let mut request = actix_web::test::TestRequest::default()
+			.method(actix_web::http::Method::OPTIONS)
+			.uri(test.1);
+		if let Some(token) = test.2 {
+			request = request.insert_header((
+				actix_web::http::header::AUTHORIZATION,
+				format!("Bearer {}", token.get_name()),
+			));
+		}
+		let response = actix_web::test::call_service(&app, request.to_request()).await;
+
+		assert_eq!(response.status().as_u16(), test.3);
+		assert_eq!(
+			response
+				.headers()
+				.get(actix_web::http::header::ALLOW)
+				.map(|value| value.to_str().unwrap()),
+			test.4
+		);
+
+		println!("OK");
+	}
+}
@@ -7,6 +7,7 @@ TODO :
 	using a header of the following form (no double quotes here):
 		Authorization: Bearer <access_token>
 */
+#[allow(clippy::too_many_arguments)]
 #[actix_web::put("/storage/{requested_item:.*}")]
 pub async fn put_item(
 	mut request_payload: actix_web::web::Payload,
@@ -15,6 +16,7 @@ pub async fn put_item(
 	database: actix_web::web::Data<std::sync::Arc<std::sync::Mutex<crate::database::Database>>>,
 	logger: actix_web::web::Data<Arc<Mutex<charlie_buffalo::Logger>>>,
 	dbevent_sender: actix_web::web::Data<std::sync::mpsc::Sender<crate::http_server::DbEvent>>,
+	metrics: Option<actix_web::web::Data<Arc<Mutex<crate::http_server::Metrics>>>>,
 	access_tokens: actix_web::web::Data<
 		std::sync::Arc<std::sync::Mutex<Vec<crate::http_server::AccessBearer>>>,
 	>,
@@ -28,18 +30,8 @@ pub async fn put_item(
 
 	let content_type = request.headers().get("content-type");
 
-	// TODO : check security issue about this ?
-	let all_origins = actix_web::http::header::HeaderValue::from_bytes(b"*").unwrap();
-	let origin = request
-		.headers()
-		.get(actix_web::http::header::ORIGIN)
-		.unwrap_or(&all_origins)
-		.to_str()
-		.unwrap();
-
 	if content_type.is_none() {
 		return crate::database::build_http_json_response(
-			origin,
 			request.method(),
 			actix_web::http::StatusCode::BAD_REQUEST,
 			None,
@@ -90,7 +82,7 @@ pub async fn put_item(
 			.collect::<Vec<&crate::item::Etag>>(),
 	) {
 		crate::database::PutResult::Created(new_etag, last_modified) => {
-			dbevent_sender
+			if dbevent_sender
 				.send(crate::http_server::DbEvent {
 					id: ulid::Ulid::new().to_string(),
 					method: crate::http_server::DbEventMethod::Create,
@@ -100,10 +92,14 @@ pub async fn put_item(
 					user,
 					dbversion: String::from(env!("CARGO_PKG_VERSION")),
 				})
-				.ok();
+				.is_ok()
+			{
+				if let Some(metrics) = &metrics {
+					metrics.lock().unwrap().record_event_queued();
+				}
+			}
 
 			return crate::database::build_http_json_response(
-				origin,
 				request.method(),
 				actix_web::http::StatusCode::CREATED,
 				Some(new_etag),
@@ -113,7 +109,7 @@ pub async fn put_item(
 			);
 		}
 		crate::database::PutResult::Updated(new_etag, last_modified) => {
-			dbevent_sender
+			if dbevent_sender
 				.send(crate::http_server::DbEvent {
 					id: ulid::Ulid::new().to_string(),
 					method: crate::http_server::DbEventMethod::Update,
@@ -123,10 +119,14 @@ pub async fn put_item(
 					user,
 					dbversion: String::from(env!("CARGO_PKG_VERSION")),
 				})
-				.ok();
+				.is_ok()
+			{
+				if let Some(metrics) = &metrics {
+					metrics.lock().unwrap().record_event_queued();
+				}
+			}
 
 			return crate::database::build_http_json_response(
-				origin,
 				request.method(),
 				actix_web::http::StatusCode::OK,
 				Some(new_etag),
@@ -140,14 +140,12 @@ pub async fn put_item(
 				crate::database::Error::to_response(
 					&*e.downcast::<crate::database::sources::memory::PutError>()
 						.unwrap(),
-					origin,
 					true,
 				)
 			} else if e.is::<crate::database::sources::folder::PutError>() {
 				crate::database::Error::to_response(
 					&*e.downcast::<crate::database::sources::folder::PutError>()
 						.unwrap(),
-					origin,
 					true,
 				)
 			} else {
@@ -162,7 +160,6 @@ pub async fn put_item(
 				);
 
 				crate::database::build_http_json_response(
-					origin,
 					request.method(),
 					actix_web::http::StatusCode::INTERNAL_SERVER_ERROR,
 					None,
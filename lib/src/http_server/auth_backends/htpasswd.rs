@@ -0,0 +1,196 @@
+/// Apache-style password file, with one `username:hash` per line.
+///
+/// Supported hashes are bcrypt (`htpasswd -B`), APR1-MD5 (`htpasswd -m`, the default one of
+/// Apache) and SHA-1 (`htpasswd -s`). The file is read on each check, so changes are used
+/// without restarting the server.
+pub struct HtpasswdBackend {
+	path: std::path::PathBuf,
+}
+impl HtpasswdBackend {
+	pub fn new(path: impl Into<std::path::PathBuf>) -> Self {
+		Self { path: path.into() }
+	}
+}
+impl super::AuthBackend for HtpasswdBackend {
+	fn authenticate(
+		&mut self,
+		username: &str,
+		password: &mut String,
+	) -> Result<crate::http_server::PasswordCheck, String> {
+		let content = std::fs::read_to_string(&self.path).map_err(|e| {
+			zeroize::Zeroize::zeroize(password);
+			format!("can not read `{}` : {}", self.path.to_string_lossy(), e)
+		})?;
+
+		let is_correct = content
+			.lines()
+			.map(|line| line.trim())
+			.filter(|line| !line.is_empty() && !line.starts_with('#'))
+			.filter_map(|line| line.split_once(':'))
+			.find(|(name, _)| *name == username)
+			.map(|(_, hash)| verify(password, hash))
+			.unwrap_or(false);
+
+		zeroize::Zeroize::zeroize(password);
+
+		if is_correct {
+			return Ok(crate::http_server::PasswordCheck::Correct);
+		} else {
+			return Ok(crate::http_server::PasswordCheck::Wrong);
+		}
+	}
+}
+
+fn verify(password: &str, hash: &str) -> bool {
+	if hash.starts_with("$2y$") || hash.starts_with("$2b$") || hash.starts_with("$2a$") {
+		bcrypt::verify(password, hash).unwrap_or(false)
+	} else if let Some(salt_and_hash) = hash.strip_prefix(APR1_MAGIC) {
+		match salt_and_hash.split_once('$') {
+			Some((salt, _)) => apr1(password, salt) == hash,
+			None => false,
+		}
+	} else if let Some(encoded) = hash.strip_prefix("{SHA}") {
+		base64::encode(hmac_sha1_compact::Hash::hash(password.as_bytes())) == encoded
+	} else {
+		// like DES crypt or plain text, which are not safe
+		false
+	}
+}
+
+const APR1_MAGIC: &str = "$apr1$";
+
+/// MD5-based hash of Apache, derived from the one of FreeBSD.
+fn apr1(password: &str, salt: &str) -> String {
+	use md5::Digest;
+
+	let password = password.as_bytes();
+	let salt = &salt.as_bytes()[..salt.len().min(8)];
+
+	let alternate = md5::Md5::new()
+		.chain_update(password)
+		.chain_update(salt)
+		.chain_update(password)
+		.finalize();
+
+	let mut hasher = md5::Md5::new()
+		.chain_update(password)
+		.chain_update(APR1_MAGIC)
+		.chain_update(salt);
+	for chunk in (0..password.len()).step_by(16) {
+		hasher.update(&alternate[..(password.len() - chunk).min(16)]);
+	}
+	let mut length = password.len();
+	while length > 0 {
+		if length & 1 == 1 {
+			hasher.update([0]);
+		} else {
+			hasher.update(&password[..1]);
+		}
+		length >>= 1;
+	}
+	let mut result = hasher.finalize();
+
+	for i in 0..1000 {
+		let mut hasher = md5::Md5::new();
+		if i & 1 == 1 {
+			hasher.update(password);
+		} else {
+			hasher.update(result);
+		}
+		if i % 3 != 0 {
+			hasher.update(salt);
+		}
+		if i % 7 != 0 {
+			hasher.update(password);
+		}
+		if i & 1 == 1 {
+			hasher.update(result);
+		} else {
+			hasher.update(password);
+		}
+		result = hasher.finalize();
+	}
+
+	const ALPHABET: &[u8] = b"./0123456789ABCDEFGHIJKLMNOPQRSTUVWXYZabcdefghijklmnopqrstuvwxyz";
+	let mut encoded = String::new();
+	for (bytes, length) in [
+		([result[0], result[6], result[12]], 4),
+		([result[1], result[7], result[13]], 4),
+		([result[2], result[8], result[14]], 4),
+		([result[3], result[9], result[15]], 4),
+		([result[4], result[10], result[5]], 4),
+		([0, 0, result[11]], 2),
+	] {
+		let mut value = u32::from_be_bytes([0, bytes[0], bytes[1], bytes[2]]);
+		for _ in 0..length {
+			encoded.push(ALPHABET[(value & 0x3f) as usize] as char);
+			value >>= 6;
+		}
+	}
+
+	return format!(
+		"{}{}${}",
+		APR1_MAGIC,
+		String::from_utf8_lossy(salt),
+		encoded
+	);
+}
+
+#[test]
+fn n3vb8yq1kdw5xge() {
+	use super::AuthBackend;
+
+	// from `openssl passwd -apr1 -salt r31..... myPassword`
+	assert_eq!(
+		apr1("myPassword", "r31....."),
+		"$apr1$r31.....$HqJZimcKQFAMYayBlzkrA/"
+	);
+
+	let tempdir = tempfile::tempdir().unwrap();
+	let path = tempdir.path().join(".htpasswd");
+	std::fs::write(
+		&path,
+		format!(
+			"# comment\n\napr1_user:$apr1$r31.....$HqJZimcKQFAMYayBlzkrA/\nbcrypt_user:{}\nsha_user:{{SHA}}{}\nplain_user:myPassword\n",
+			bcrypt::hash("myPassword", 4).unwrap().replacen("$2b$", "$2y$", 1),
+			// from `htpasswd -nbs sha_user myPassword`
+			"VBPuJHI7uixaa6LQGWx4s+5GKNE=",
+		),
+	)
+	.unwrap();
+
+	let mut backend = HtpasswdBackend::new(&path);
+
+	let tests = vec![
+		(010, "apr1_user", "myPassword", true),
+		(020, "apr1_user", "MyPassword", false),
+		(030, "bcrypt_user", "myPassword", true),
+		(040, "bcrypt_user", "", false),
+		(050, "sha_user", "myPassword", true),
+		(060, "sha_user", "password", false),
+		(070, "plain_user", "myPassword", false),
+		(080, "RANDOM", "myPassword", false),
+		(090, "# comment", "", false),
+	];
+
+	for test in tests {
+		print!(
+			"#{:03} : check password of `{}` in htpasswd file ... ",
+			test.0, test.1
+		);
+
+		assert_eq!(
+			backend
+				.authenticate(test.1, &mut String::from(test.2))
+				.unwrap()
+				.is_correct(),
+			test.3
+		);
+
+		println!("OK");
+	}
+
+	assert!(HtpasswdBackend::new(tempdir.path().join("RANDOM"))
+		.authenticate("apr1_user", &mut String::from("myPassword"))
+		.is_err());
+}
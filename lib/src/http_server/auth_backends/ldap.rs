@@ -0,0 +1,196 @@
+/// Checks passwords with a simple bind on a LDAP server, as the DN of the user.
+pub struct LdapBackend {
+	settings: crate::http_server::SettingsLdap,
+}
+impl LdapBackend {
+	pub fn new(settings: crate::http_server::SettingsLdap) -> Self {
+		Self { settings }
+	}
+}
+impl super::AuthBackend for LdapBackend {
+	fn authenticate(
+		&mut self,
+		username: &str,
+		password: &mut String,
+	) -> Result<crate::http_server::PasswordCheck, String> {
+		// it would be an anonymous bind, which is accepted by most servers
+		if password.is_empty() {
+			return Ok(crate::http_server::PasswordCheck::Wrong);
+		}
+
+		let settings = self.settings.clone();
+		let dn = settings
+			.bind_dn
+			.replace("{username}", &ldap3::dn_escape(username));
+		let mut bind_password = password.clone();
+		zeroize::Zeroize::zeroize(password);
+
+		// `ldap3` runs its own asynchronous runtime, which can not be started from the one of the
+		// HTTP server, even in its blocking tasks (see `authenticate`), so it has its own thread.
+		return std::thread::spawn(move || {
+			let result = bind(&settings, &dn, &bind_password);
+			zeroize::Zeroize::zeroize(&mut bind_password);
+
+			result
+		})
+		.join()
+		.unwrap_or_else(|_| Err(String::from("LDAP client panicked")));
+	}
+}
+
+fn bind(
+	settings: &crate::http_server::SettingsLdap,
+	dn: &str,
+	password: &str,
+) -> Result<crate::http_server::PasswordCheck, String> {
+	let timeout = std::time::Duration::from_secs(settings.timeout_seconds);
+
+	let mut connection = ldap3::LdapConn::with_settings(
+		ldap3::LdapConnSettings::new()
+			.set_conn_timeout(timeout)
+			.set_starttls(settings.starttls),
+		&settings.url,
+	)
+	.map_err(|e| format!("can not connect to `{}` : {}", settings.url, e))?;
+
+	let result = connection
+		.with_timeout(timeout)
+		.simple_bind(dn, password)
+		.map_err(|e| format!("can not bind to `{}` : {}", settings.url, e))?;
+
+	connection.unbind().ok();
+
+	match result.rc {
+		0 => Ok(crate::http_server::PasswordCheck::Correct),
+		// invalidCredentials, inappropriateAuthentication
+		49 | 48 => Ok(crate::http_server::PasswordCheck::Wrong),
+		rc => Err(format!("LDAP error {} : {}", rc, result.text)),
+	}
+}
+
+#[test]
+fn e1hq7tz5wcm9pkb() {
+	use super::AuthBackend;
+	use std::io::{Read, Write};
+
+	// answers to bind requests (`[APPLICATION 0]`) with success only for the DN and password of
+	// `user`, and ignores other requests (like unbind)
+	let listener = std::net::TcpListener::bind("127.0.0.1:0").unwrap();
+	let url = format!("ldap://{}", listener.local_addr().unwrap());
+	std::thread::spawn(move || {
+		for stream in listener.incoming() {
+			let mut stream = stream.unwrap();
+
+			let mut buffer = [0_u8; 1024];
+			let length = stream.read(&mut buffer).unwrap();
+			let message = &buffer[..length];
+
+			// LDAPMessage ::= SEQUENCE { messageID INTEGER (1 byte long here), protocolOp ... }
+			assert_eq!(message[0], 0x30);
+			let start = if message[1] & 0x80 == 0 {
+				2
+			} else {
+				2 + (message[1] & 0x7f) as usize
+			};
+			assert_eq!(&message[start..start + 2], &[0x02, 0x01]);
+			let message_id = message[start + 2];
+			assert_eq!(message[start + 3], 0x60);
+
+			let expected_dn = b"uid=user,ou=people,dc=example,dc=com";
+			let expected_password = b"password";
+			let result_code = if message
+				.windows(expected_dn.len())
+				.any(|window| window == expected_dn)
+				&& message.ends_with(expected_password)
+			{
+				0
+			} else {
+				49
+			};
+
+			stream
+				.write_all(&[
+					0x30,
+					0x0c,
+					0x02,
+					0x01,
+					message_id,
+					0x61,
+					0x07,
+					0x0a,
+					0x01,
+					result_code,
+					0x04,
+					0x00,
+					0x04,
+					0x00,
+				])
+				.unwrap();
+		}
+	});
+
+	let mut backend = LdapBackend::new(crate::http_server::SettingsLdap {
+		url,
+		bind_dn: String::from("uid={username},ou=people,dc=example,dc=com"),
+		starttls: false,
+		timeout_seconds: 5,
+	});
+
+	let tests = vec![
+		(010, "user", "password", true),
+		(020, "user", "PASSWORD", false),
+		(030, "user", "", false),
+		(040, "other", "password", false),
+		(050, "user,ou=people", "password", false),
+	];
+
+	for test in tests {
+		print!(
+			"#{:03} : bind as `{}` on LDAP stand-in ... ",
+			test.0, test.1
+		);
+
+		assert_eq!(
+			backend
+				.authenticate(test.1, &mut String::from(test.2))
+				.unwrap()
+				.is_correct(),
+			test.3
+		);
+
+		println!("OK");
+	}
+
+	print!("#060 : bind from the runtime of the HTTP server ... ");
+	let mut settings = crate::http_server::Settings::new(std::path::PathBuf::from("."));
+	settings.auth_backend = Some(crate::http_server::SettingsAuthBackend::Ldap(
+		backend.settings.clone(),
+	));
+	let users = std::sync::Arc::new(std::sync::Mutex::new(crate::http_server::Users::new()));
+	let logger = std::sync::Arc::new(std::sync::Mutex::new(charlie_buffalo::Logger::new(
+		charlie_buffalo::new_dispatcher(Box::new(|_| {})),
+		None,
+	)));
+	assert!(actix_rt::System::new()
+		.block_on(crate::http_server::authenticate(
+			&settings,
+			&users,
+			"user",
+			&mut String::from("password"),
+			&logger,
+		))
+		.is_correct());
+	println!("OK");
+
+	let listener = std::net::TcpListener::bind("127.0.0.1:0").unwrap();
+	let url = format!("ldap://{}", listener.local_addr().unwrap());
+	drop(listener);
+	assert!(LdapBackend::new(crate::http_server::SettingsLdap {
+		url,
+		bind_dn: String::from("uid={username},ou=people,dc=example,dc=com"),
+		starttls: false,
+		timeout_seconds: 5,
+	})
+	.authenticate("user", &mut String::from("password"))
+	.is_err());
+}
@@ -0,0 +1,153 @@
+mod htpasswd;
+mod ldap;
+
+pub use htpasswd::HtpasswdBackend;
+pub use ldap::LdapBackend;
+
+use std::sync::{Arc, Mutex};
+
+/// Checks passwords of users.
+///
+/// Whatever the backend, rights, remembered grants and two-factor authentication of users are
+/// stored in the users file (so users which are only known by another backend do not have them),
+/// and the data of each user is stored in `/storage/<username>/`.
+pub trait AuthBackend {
+	/// Checks the password of `username`, which is zeroized after use.
+	///
+	/// Errors are only about the backend itself (like an unreachable server), not about
+	/// credentials.
+	fn authenticate(
+		&mut self,
+		username: &str,
+		password: &mut String,
+	) -> Result<crate::http_server::PasswordCheck, String>;
+}
+
+impl AuthBackend for crate::http_server::Users {
+	fn authenticate(
+		&mut self,
+		username: &str,
+		password: &mut String,
+	) -> Result<crate::http_server::PasswordCheck, String> {
+		Ok(self.check(username, password))
+	}
+}
+
+/// Checks the password of `username` with the backend chosen in settings.
+///
+/// Backends are slow on purpose (password hashes) or wait for another server (LDAP), so they are
+/// used in the thread pool of blocking tasks, instead of the threads which handle requests.
+pub async fn authenticate(
+	settings: &crate::http_server::Settings,
+	users: &Arc<Mutex<crate::http_server::Users>>,
+	username: &str,
+	password: &mut String,
+	logger: &Arc<Mutex<charlie_buffalo::Logger>>,
+) -> crate::http_server::PasswordCheck {
+	let backend = settings.auth_backend.clone().unwrap_or_default();
+	let users = users.clone();
+	let username_for_backend = String::from(username);
+	// moved without copy, so it is still zeroized after use
+	let mut password = std::mem::take(password);
+
+	let result = actix_web::web::block(move || {
+		let username = username_for_backend.as_str();
+		let password = &mut password;
+
+		match backend {
+			crate::http_server::SettingsAuthBackend::UsersFile => Ok(
+				crate::http_server::Users::check_shared(&users, username, password),
+			),
+			_ if !is_valid_username(username) => {
+				zeroize::Zeroize::zeroize(password);
+				Ok(crate::http_server::PasswordCheck::Wrong)
+			}
+			crate::http_server::SettingsAuthBackend::Htpasswd { path } => {
+				HtpasswdBackend::new(path).authenticate(username, password)
+			}
+			crate::http_server::SettingsAuthBackend::Ldap(ldap) => {
+				LdapBackend::new(ldap).authenticate(username, password)
+			}
+		}
+	})
+	.await
+	.unwrap_or_else(|e| Err(format!("authentication task failed : {e}")));
+
+	match result {
+		Ok(password_check) => password_check,
+		Err(e) => {
+			logger.lock().unwrap().push(
+				vec![
+					(String::from("event"), String::from("authentication")),
+					(String::from("level"), String::from("ERROR")),
+					(String::from("username"), String::from(username)),
+				],
+				Some(&format!("authentication backend failed : {e}")),
+			);
+
+			crate::http_server::PasswordCheck::Wrong
+		}
+	}
+}
+
+/// Usernames of other backends are used as is in paths of the storage, so they are restricted to
+/// characters which are safe there.
+pub(crate) fn is_valid_username(username: &str) -> bool {
+	!username.is_empty()
+		&& username != "public"
+		&& !username.starts_with('.')
+		&& username
+			.chars()
+			.all(|c| c.is_ascii_alphanumeric() || ['.', '_', '-', '@'].contains(&c))
+}
+
+#[test]
+fn c6nw1rq8zkh0ftv() {
+	assert!(is_valid_username("user"));
+	assert!(is_valid_username("first.last@example.com"));
+	assert!(is_valid_username("user_1-2"));
+	assert!(!is_valid_username(""));
+	assert!(!is_valid_username("public"));
+	assert!(!is_valid_username(".."));
+	assert!(!is_valid_username(".hidden"));
+	assert!(!is_valid_username("a/b"));
+	assert!(!is_valid_username("a,ou=admins"));
+	assert!(!is_valid_username("*"));
+	assert!(!is_valid_username("é"));
+}
+
+#[test]
+fn q5lt2xh8vbj4nre() {
+	let mut settings = crate::http_server::Settings::new(std::path::PathBuf::from("."));
+	settings.auth_backend = Some(crate::http_server::SettingsAuthBackend::Ldap(
+		crate::http_server::SettingsLdap {
+			url: String::from("ldap://localhost:389"),
+			bind_dn: String::from("uid={username},ou=people,dc=example,dc=com"),
+			starttls: true,
+			timeout_seconds: 5,
+		},
+	));
+	assert_eq!(settings.validate(), Ok(()));
+
+	let content = String::from_utf8(toml::to_vec(&settings).unwrap()).unwrap();
+	assert!(content.contains("[auth_backend]\ntype = \"ldap\"\n"));
+	assert_eq!(
+		toml::from_str::<crate::http_server::Settings>(&content)
+			.unwrap()
+			.auth_backend,
+		settings.auth_backend
+	);
+
+	let settings: crate::http_server::Settings = toml::from_str(&format!(
+		"{}\n[auth_backend]\ntype = \"ldap\"\nurl = \"http://localhost\"\nbind_dn = \"uid=user\"\n",
+		content.split("[auth_backend]").next().unwrap()
+	))
+	.unwrap();
+	assert_eq!(
+		settings.validate(),
+		Err(vec![
+			String::from("incorrect LDAP URL `http://localhost`"),
+			String::from("LDAP bind DN should contain `{username}`"),
+		])
+	);
+}
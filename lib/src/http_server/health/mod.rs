@@ -0,0 +1,230 @@
+use std::sync::{Arc, Mutex};
+
+/// The database is probed again by `/readyz` only after this delay, so frequent requests (which
+/// do not need any access token) can not keep it busy.
+const DATABASE_PROBE_INTERVAL: std::time::Duration = std::time::Duration::from_secs(10);
+
+/// Answers while the server can still handle requests, in order to be restarted by orchestrators
+/// otherwise : a panic while the database is locked makes all next requests of the storage fail.
+#[actix_web::route("/healthz", method = "GET", method = "HEAD")]
+pub async fn get_healthz(
+	database: actix_web::web::Data<Arc<Mutex<crate::database::Database>>>,
+) -> impl actix_web::Responder {
+	return if database.is_poisoned() {
+		build_response(
+			false,
+			serde_json::json!({ "database": "database lock is poisoned" }),
+		)
+	} else {
+		build_response(true, serde_json::json!({ "database": "ok" }))
+	};
+}
+
+/// Answers when the server is ready to serve users, in order to route requests to it : the
+/// database can be read and written, users are loaded and the TLS certificate is not about to
+/// expire.
+///
+/// Only the status of each check is answered, reasons of failures are logged.
+#[actix_web::route("/readyz", method = "GET", method = "HEAD")]
+pub async fn get_readyz(
+	database: actix_web::web::Data<Arc<Mutex<crate::database::Database>>>,
+	users: actix_web::web::Data<Arc<Mutex<crate::http_server::Users>>>,
+	settings: actix_web::web::Data<Arc<Mutex<crate::http_server::Settings>>>,
+	program_state: actix_web::web::Data<Arc<Mutex<crate::http_server::ProgramState>>>,
+	logger: actix_web::web::Data<Arc<Mutex<charlie_buffalo::Logger>>>,
+) -> impl actix_web::Responder {
+	let settings = match settings.lock() {
+		Ok(settings) => settings.clone(),
+		Err(_) => return build_response(false, serde_json::json!({ "settings": "error" })),
+	};
+	let https_mode = program_state
+		.lock()
+		.map(|program_state| program_state.https_mode)
+		.unwrap_or(false);
+
+	let checks = [
+		(
+			"database",
+			check_database_regularly(&database, &program_state),
+		),
+		("users", check_users(&users, &settings)),
+		("certificate", check_certificate(&settings, https_mode)),
+	];
+
+	let is_ready = checks.iter().all(|(_, result)| result.is_ok());
+	let checks: serde_json::Map<String, serde_json::Value> = checks
+		.into_iter()
+		.map(|(name, result)| {
+			if let Err(e) = &result {
+				crate::http_server::logging::log(
+					&logger,
+					crate::http_server::logging::Level::Warning,
+					"readyz",
+					&[],
+					&format!("{name} check failed : {e}"),
+				);
+			}
+
+			(
+				String::from(name),
+				serde_json::Value::from(if result.is_ok() { "ok" } else { "error" }),
+			)
+		})
+		.collect();
+
+	return build_response(is_ready, serde_json::Value::from(checks));
+}
+
+fn build_response(is_ok: bool, checks: serde_json::Value) -> actix_web::HttpResponse {
+	let mut response = if is_ok {
+		actix_web::HttpResponse::Ok()
+	} else {
+		actix_web::HttpResponse::ServiceUnavailable()
+	};
+	response.insert_header((actix_web::http::header::CACHE_CONTROL, "no-store"));
+
+	return response.json(serde_json::json!({
+		"status": if is_ok { "ok" } else { "error" },
+		"checks": checks,
+	}));
+}
+
+/// Result of the last [`check_database`] if it has been done less than
+/// [`DATABASE_PROBE_INTERVAL`] ago, otherwise does it again.
+///
+/// A poisoned lock is always reported, since it does not need to lock the database.
+fn check_database_regularly(
+	database: &Mutex<crate::database::Database>,
+	program_state: &Mutex<crate::http_server::ProgramState>,
+) -> Result<(), String> {
+	if database.is_poisoned() {
+		return Err(String::from("database lock is poisoned"));
+	}
+
+	let last_probe = program_state
+		.lock()
+		.ok()
+		.and_then(|program_state| program_state.database_probe.clone());
+	if let Some((probe_time, result)) = last_probe {
+		if probe_time.elapsed() < DATABASE_PROBE_INTERVAL {
+			return result;
+		}
+	}
+
+	let result = check_database(database);
+	if let Ok(mut program_state) = program_state.lock() {
+		program_state.database_probe = Some((std::time::Instant::now(), result.clone()));
+	}
+
+	return result;
+}
+
+/// Writes, reads and removes a probe document, out of the folders of users (their names can not
+/// start with `.`).
+fn check_database(database: &Mutex<crate::database::Database>) -> Result<(), String> {
+	let mut database = database
+		.lock()
+		.map_err(|_| String::from("database lock is poisoned"))?;
+
+	let path = crate::item::ItemPath::from(".readyz/probe");
+	let content = ulid::Ulid::new().to_string();
+
+	if let crate::database::PutResult::Err(e) = database.put(
+		&path,
+		crate::item::Item::new_doc(content.as_bytes(), "text/plain"),
+		&crate::item::Etag::from(""),
+		&[],
+	) {
+		return Err(format!("can not write probe document : {e}"));
+	}
+
+	let read_result = match database.get(&path, &crate::item::Etag::from(""), &[]) {
+		Ok(crate::item::Item::Document {
+			content: Some(read_content),
+			..
+		}) if read_content == content.as_bytes() => Ok(()),
+		Ok(_) => Err(String::from("probe document is not the written one")),
+		Err(e) => Err(format!("can not read probe document : {e}")),
+	};
+
+	database
+		.delete(&path, &crate::item::Etag::from(""))
+		.map_err(|e| format!("can not remove probe document : {e}"))?;
+
+	return read_result;
+}
+
+fn check_users(
+	users: &Mutex<crate::http_server::Users>,
+	settings: &crate::http_server::Settings,
+) -> Result<(), String> {
+	let users = users
+		.lock()
+		.map_err(|_| String::from("users lock is poisoned"))?;
+
+	// users of other backends are not in the users file
+	let uses_users_file = matches!(
+		settings.auth_backend,
+		None | Some(crate::http_server::SettingsAuthBackend::UsersFile)
+	);
+	if uses_users_file && users.get_usernames().is_empty() {
+		return Err(String::from("no user loaded"));
+	}
+
+	return Ok(());
+}
+
+fn check_certificate(
+	settings: &crate::http_server::Settings,
+	https_mode: bool,
+) -> Result<(), String> {
+	let https = match settings.resolved_https() {
+		Some(https) if https_mode => https,
+		_ => return Ok(()),
+	};
+
+	let expiration = certificate_expiration(std::path::Path::new(&https.certfile_path))?;
+	let min_validity = time::Duration::days(https.min_certificate_validity_days as i64);
+
+	if expiration - time::OffsetDateTime::now_utc() < min_validity {
+		return Err(format!(
+			"certificate `{}` expires on {}",
+			https.certfile_path,
+			expiration
+				.format(&time::format_description::well_known::Rfc3339)
+				.unwrap_or_default()
+		));
+	}
+
+	return Ok(());
+}
+
+/// End of validity of the first certificate of this PEM file.
+pub fn certificate_expiration(
+	certfile_path: &std::path::Path,
+) -> Result<time::OffsetDateTime, String> {
+	let certfile = std::fs::File::open(certfile_path).map_err(|e| {
+		format!(
+			"can not open cert file `{}` : {}",
+			certfile_path.to_string_lossy(),
+			e
+		)
+	})?;
+
+	let certificates = rustls_pemfile::certs(&mut std::io::BufReader::new(certfile))
+		.map_err(|e| format!("can not read SSL certificate : {e}"))?;
+	let certificate = certificates.first().ok_or_else(|| {
+		format!(
+			"no certificate found in `{}`",
+			certfile_path.to_string_lossy()
+		)
+	})?;
+
+	let (_, certificate) = x509_parser::parse_x509_certificate(certificate)
+		.map_err(|e| format!("can not parse SSL certificate : {e}"))?;
+
+	return Ok(certificate.validity().not_after.to_datetime());
+}
+
+#[cfg(test)]
+mod tests;
@@ -0,0 +1,151 @@
+use std::sync::{Arc, Mutex};
+
+#[test]
+fn t6pw3ks9bnh2xqe() {
+	let tempdir = tempfile::tempdir().unwrap();
+
+	let tests = vec![
+		(010, Some(30), true, 7, true),
+		(020, Some(3), true, 7, false),
+		(030, Some(3), true, 2, true),
+		(040, Some(3), false, 7, true),
+		(050, Some(-1), true, 0, false),
+		(060, None, true, 7, false),
+		(070, None, false, 7, true),
+	];
+
+	for test in tests {
+		print!(
+			"#{:03} : certificate valid during {:?} days (HTTPS mode : {}, minimum : {} days) ... ",
+			test.0, test.1, test.2, test.3
+		);
+
+		let certfile_path = tempdir.path().join(format!("cert_{:03}.pem", test.0));
+		if let Some(days) = test.1 {
+			let mut params = rcgen::CertificateParams::new(vec![String::from("localhost")]);
+			params.not_after = time::OffsetDateTime::now_utc() + time::Duration::days(days);
+			let certificate = rcgen::Certificate::from_params(params).unwrap();
+			std::fs::write(&certfile_path, certificate.serialize_pem().unwrap()).unwrap();
+		}
+
+		let mut settings = crate::http_server::Settings::new(tempdir.path().to_path_buf());
+		let https = settings.https.as_mut().unwrap();
+		https.certfile_path = String::from(certfile_path.to_string_lossy());
+		https.min_certificate_validity_days = test.3;
+
+		assert_eq!(super::check_certificate(&settings, test.2).is_ok(), test.4);
+
+		println!("OK");
+	}
+}
+
+#[actix_rt::test]
+async fn g2vn7cx5mdq8rtz() {
+	let settings = Arc::new(Mutex::new(crate::http_server::Settings::new(
+		std::path::PathBuf::from("."),
+	)));
+	let program_state = Arc::new(Mutex::new(crate::http_server::ProgramState::default()));
+
+	let mut users = crate::http_server::Users::new();
+	users.insert("user", &mut String::from("password")).unwrap();
+	let users = Arc::new(Mutex::new(users));
+
+	let database = Arc::new(Mutex::new(crate::database::Database::new(Box::new(
+		crate::database::sources::MemoryStorage {
+			root_item: crate::item::Item::new_folder(vec![]),
+		},
+	))));
+
+	let logger = Arc::new(Mutex::new(charlie_buffalo::Logger::new(
+		charlie_buffalo::new_dispatcher(Box::new(|_| {})),
+		None,
+	)));
+
+	let app = actix_web::test::init_service(
+		actix_web::App::new()
+			.app_data(actix_web::web::Data::new(settings.clone()))
+			.app_data(actix_web::web::Data::new(program_state.clone()))
+			.app_data(actix_web::web::Data::new(users.clone()))
+			.app_data(actix_web::web::Data::new(database.clone()))
+			.app_data(actix_web::web::Data::new(logger.clone()))
+			.wrap(crate::http_server::middlewares::Auth { logger })
+			.service(super::get_healthz)
+			.service(super::get_readyz),
+	)
+	.await;
+
+	// the second field is the change made before the request, the last one is the failing check
+	let tests = vec![
+		(010, None, "/healthz", 200, None),
+		(020, None, "/readyz", 200, None),
+		(030, Some("remove user"), "/readyz", 503, Some("users")),
+		(040, None, "/healthz", 200, None),
+		(
+			050,
+			Some("poison database"),
+			"/healthz",
+			503,
+			Some("database"),
+		),
+		(060, None, "/readyz", 503, Some("database")),
+	];
+	let mut first_probe_time = None;
+
+	for test in tests {
+		print!(
+			"#{:03} : GET request to {} (after : {:?}) ... ",
+			test.0, test.2, test.1
+		);
+
+		match test.1 {
+			Some("remove user") => users.lock().unwrap().remove("user").unwrap(),
+			Some("poison database") => {
+				let database = database.clone();
+				std::thread::spawn(move || {
+					let _lock = database.lock().unwrap();
+					panic!("poisoning database lock");
+				})
+				.join()
+				.ok();
+			}
+			_ => {}
+		}
+
+		let request = actix_web::test::TestRequest::get()
+			.uri(test.2)
+			.insert_header((actix_web::http::header::AUTHORIZATION, "Bearer RANDOM"))
+			.to_request();
+		let response = actix_web::test::call_service(&app, request).await;
+		assert_eq!(response.status().as_u16(), test.3);
+
+		let body = actix_web::test::read_body(response).await;
+		let body: serde_json::Value = serde_json::from_slice(&body).unwrap();
+		match test.4 {
+			Some(check) => {
+				assert_eq!(body["status"], "error");
+				if test.2 == "/healthz" {
+					assert_eq!(body["checks"][check], "database lock is poisoned");
+				} else {
+					assert_eq!(body["checks"][check], "error");
+				}
+			}
+			None => {
+				assert_eq!(body["status"], "ok");
+			}
+		}
+
+		// the database is probed only once in this test
+		if test.2 == "/readyz" {
+			let probe_time = program_state
+				.lock()
+				.unwrap()
+				.database_probe
+				.as_ref()
+				.unwrap()
+				.0;
+			assert_eq!(*first_probe_time.get_or_insert(probe_time), probe_time);
+		}
+
+		println!("OK");
+	}
+}
@@ -2,11 +2,15 @@ use std::sync::{Arc, Mutex};
 
 pub fn load_or_create_database(
 	settings: &super::Settings,
+	metrics: Arc<Mutex<crate::http_server::Metrics>>,
 	logger: Arc<Mutex<charlie_buffalo::Logger>>,
 ) -> Arc<Mutex<crate::database::Database>> {
 	let database = Arc::new(Mutex::new(crate::database::Database::new(Box::new(
-		crate::database::sources::FolderStorage {
-			root_folder_path: settings.data_path(),
+		crate::http_server::MeasuredSource {
+			source: Box::new(crate::database::sources::FolderStorage {
+				root_folder_path: settings.data_path(),
+			}),
+			metrics,
 		},
 	))));
 
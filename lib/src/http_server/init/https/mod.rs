@@ -0,0 +1,289 @@
+use std::sync::{Arc, Mutex, RwLock};
+
+mod self_signed;
+
+pub use self_signed::*;
+
+pub fn setup_and_run_https_server(
+	settings: Arc<Mutex<super::Settings>>,
+	database: Arc<Mutex<crate::database::Database>>,
+	access_tokens: Arc<Mutex<Vec<crate::http_server::AccessBearer>>>,
+	authorization_codes: Arc<Mutex<Vec<crate::http_server::AuthorizationCode>>>,
+	oauth_form_tokens: Arc<Mutex<Vec<crate::http_server::middlewares::OauthFormToken>>>,
+	sessions: Arc<Mutex<Vec<crate::http_server::Session>>>,
+	login_attempts: Arc<Mutex<crate::http_server::LoginAttempts>>,
+	users: Arc<Mutex<crate::http_server::Users>>,
+	program_state: Arc<Mutex<crate::http_server::ProgramState>>,
+	metrics: Arc<Mutex<crate::http_server::Metrics>>,
+	logger: Arc<Mutex<charlie_buffalo::Logger>>,
+	workspace_path: &std::path::Path,
+	history_sender: Option<std::sync::mpsc::Sender<crate::http_server::DbEvent>>,
+) {
+	let settings_for_setup = settings.lock().unwrap().clone();
+
+	match renew_self_signed_certificate(&settings_for_setup) {
+		Ok(true) => {
+			logger.lock().unwrap().push(
+				vec![
+					(String::from("event"), String::from("setup")),
+					(String::from("module"), String::from("https")),
+					(String::from("level"), String::from("INFO")),
+				],
+				Some(&format!(
+					"self-signed certificate generated in `{}`",
+					settings_for_setup.self_signed_path().to_string_lossy()
+				)),
+			);
+		}
+		Ok(false) => {}
+		Err(e) => {
+			logger.lock().unwrap().push(
+				vec![
+					(String::from("event"), String::from("setup")),
+					(String::from("module"), String::from("https")),
+					(String::from("level"), String::from("ERROR")),
+				],
+				Some(&format!("can not generate self-signed certificate : {e}")),
+			);
+		}
+	}
+
+	let settings_https = match settings_for_setup.resolved_https() {
+		Some(settings_https) => settings_https,
+		None => {
+			logger.lock().unwrap().push(
+				vec![
+					(String::from("event"), String::from("setup")),
+					(String::from("module"), String::from("https")),
+					(String::from("level"), String::from("ERROR")),
+				],
+				Some("no HTTPS settings found"),
+			);
+
+			return;
+		}
+	};
+
+	let certificate_resolver = match CertificateResolver::new(&settings_https) {
+		Ok(certificate_resolver) => Arc::new(certificate_resolver),
+		Err(e) => {
+			logger.lock().unwrap().push(
+				vec![
+					(String::from("event"), String::from("setup")),
+					(String::from("module"), String::from("https")),
+					(String::from("level"), String::from("ERROR")),
+				],
+				Some(&e),
+			);
+
+			return;
+		}
+	};
+
+	let server_config = rustls::ServerConfig::builder()
+		.with_safe_defaults()
+		.with_no_client_auth()
+		.with_cert_resolver(certificate_resolver.clone());
+
+	let enable_hsts = settings_https.enable_hsts;
+	let https_port = settings_https.port;
+
+	let localhost = String::from("localhost");
+	let domain = settings_for_setup
+		.domain
+		.as_ref()
+		.unwrap_or(&localhost)
+		.clone();
+
+	let program_state_for_server = program_state.clone();
+	let workspace_path_for_server = workspace_path.to_path_buf();
+	let logger_for_server = logger.clone();
+	match actix_web::HttpServer::new(move || {
+		actix_web::App::new()
+			.wrap(crate::http_server::middlewares::Hsts {
+				enable: enable_hsts,
+			})
+			.wrap(crate::http_server::middlewares::Auth {
+				logger: logger_for_server.clone(),
+			})
+			.wrap(crate::http_server::middlewares::Cors)
+			.wrap(crate::http_server::middlewares::Logger {
+				logger: logger_for_server.clone(),
+			})
+			.configure(crate::http_server::configure_server(
+				settings.clone(),
+				database.clone(),
+				access_tokens.clone(),
+				authorization_codes.clone(),
+				oauth_form_tokens.clone(),
+				sessions.clone(),
+				login_attempts.clone(),
+				users.clone(),
+				program_state_for_server.clone(),
+				metrics.clone(),
+				logger_for_server.clone(),
+				&workspace_path_for_server,
+				history_sender.clone(),
+			))
+	})
+	.bind_rustls(format!("{domain}:{https_port}"), server_config)
+	{
+		Ok(serverd) => {
+			logger.lock().unwrap().push(
+				vec![
+					(String::from("event"), String::from("setup")),
+					(String::from("module"), String::from("https")),
+					(String::from("level"), String::from("INFO")),
+				],
+				Some(&format!(
+					"API should now listen to https://{domain}:{https_port}/"
+				)),
+			);
+
+			let mut program_state = program_state.lock().unwrap();
+			program_state.https_mode = true;
+			program_state.certificate_resolver = Some(certificate_resolver);
+
+			let https_server = serverd.run();
+
+			std::thread::spawn(move || {
+				let sys = actix_web::rt::System::new();
+				sys.block_on(https_server)
+			});
+		}
+		Err(e) => {
+			logger.lock().unwrap().push(
+				vec![
+					(String::from("event"), String::from("setup")),
+					(String::from("module"), String::from("https")),
+					(String::from("level"), String::from("ERROR")),
+				],
+				Some(&format!("can not set up HTTPS server : {}", e)),
+			);
+		}
+	}
+}
+
+/// Certificate of the HTTPS server, which can be replaced while it runs (when it is renewed, for
+/// example).
+pub struct CertificateResolver {
+	certified_key: RwLock<Arc<rustls::sign::CertifiedKey>>,
+}
+impl CertificateResolver {
+	pub fn new(settings_https: &super::SettingsHTTPS) -> Result<Self, String> {
+		return Ok(Self {
+			certified_key: RwLock::new(Arc::new(load_certified_key(settings_https)?)),
+		});
+	}
+
+	/// Reads again the key and cert files, and keeps the current certificate if they are not
+	/// correct.
+	pub fn reload(&self, settings_https: &super::SettingsHTTPS) -> Result<(), String> {
+		let certified_key = load_certified_key(settings_https)?;
+
+		*self
+			.certified_key
+			.write()
+			.map_err(|_| String::from("certificate lock is poisoned"))? = Arc::new(certified_key);
+
+		return Ok(());
+	}
+
+	pub fn certified_key(&self) -> Option<Arc<rustls::sign::CertifiedKey>> {
+		return self.certified_key.read().ok().map(|key| key.clone());
+	}
+}
+impl rustls::server::ResolvesServerCert for CertificateResolver {
+	fn resolve(&self, _: rustls::server::ClientHello) -> Option<Arc<rustls::sign::CertifiedKey>> {
+		return self.certified_key();
+	}
+}
+impl std::fmt::Debug for CertificateResolver {
+	fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+		f.debug_struct("CertificateResolver")
+			.finish_non_exhaustive()
+	}
+}
+
+/// Loads the full chain of certificates of the cert file (the certificate of the server first,
+/// then intermediate ones), and the private key of the key file : PKCS#8 (`PRIVATE KEY`), PKCS#1
+/// (`RSA PRIVATE KEY`) or SEC1 (`EC PRIVATE KEY`).
+fn load_certified_key(
+	settings_https: &super::SettingsHTTPS,
+) -> Result<rustls::sign::CertifiedKey, String> {
+	let cert_items = read_pem_file(&settings_https.certfile_path, "cert")?;
+	let key_items = read_pem_file(&settings_https.keyfile_path, "key")?;
+
+	let cert_chain: Vec<rustls::Certificate> = cert_items
+		.iter()
+		.filter_map(|item| match item {
+			rustls_pemfile::Item::X509Certificate(certificate) => {
+				Some(rustls::Certificate(certificate.clone()))
+			}
+			_ => None,
+		})
+		.collect();
+	if cert_chain.is_empty() {
+		return Err(if cert_items.is_empty() {
+			format!(
+				"no certificate found in cert file `{}`",
+				settings_https.certfile_path
+			)
+		} else {
+			format!(
+				"no certificate found in cert file `{}`, it only contains a private key",
+				settings_https.certfile_path
+			)
+		});
+	}
+
+	let signing_key = match key_items.into_iter().find(|item| {
+		matches!(
+			item,
+			rustls_pemfile::Item::PKCS8Key(_)
+				| rustls_pemfile::Item::RSAKey(_)
+				| rustls_pemfile::Item::ECKey(_)
+		)
+	}) {
+		Some(rustls_pemfile::Item::PKCS8Key(key)) => {
+			rustls::sign::any_supported_type(&rustls::PrivateKey(key)).map_err(|_| {
+				String::from(
+					"can not load PKCS#8 private key : only RSA, ECDSA (P-256 or P-384) and Ed25519 keys are supported",
+				)
+			})?
+		}
+		Some(rustls_pemfile::Item::RSAKey(key)) => Arc::new(
+			rustls::sign::RsaSigningKey::new(&rustls::PrivateKey(key)).map_err(|_| {
+				String::from("can not load PKCS#1 RSA private key : it is not a correct RSA key")
+			})?,
+		),
+		Some(rustls_pemfile::Item::ECKey(key)) => rustls::sign::any_ecdsa_type(
+			&rustls::PrivateKey(key),
+		)
+		.map_err(|_| {
+			String::from(
+				"can not load SEC1 EC private key : only P-256 and P-384 curves are supported",
+			)
+		})?,
+		_ => {
+			return Err(format!(
+				"no private key found in key file `{}`, it should contain a `PRIVATE KEY`, `RSA PRIVATE KEY` or `EC PRIVATE KEY` block",
+				settings_https.keyfile_path
+			))
+		}
+	};
+
+	return Ok(rustls::sign::CertifiedKey::new(cert_chain, signing_key));
+}
+
+/// All blocks of this PEM file which are known by `rustls_pemfile`.
+fn read_pem_file(path: &str, kind: &str) -> Result<Vec<rustls_pemfile::Item>, String> {
+	let file = std::fs::File::open(path)
+		.map_err(|e| format!("can not open {kind} file `{path}` : {e}"))?;
+
+	return rustls_pemfile::read_all(&mut std::io::BufReader::new(file))
+		.map_err(|e| format!("can not read {kind} file `{path}` : {e}"));
+}
+
+#[cfg(test)]
+mod tests;
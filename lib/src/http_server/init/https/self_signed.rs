@@ -0,0 +1,399 @@
+/// Common name of the local certificate authority, which has to stay the same in order to sign
+/// new certificates with the saved key.
+const LOCAL_CA_NAME: &str = "Pontus Onyx local CA";
+
+/// Validity of the certificate of the local certificate authority.
+const LOCAL_CA_VALIDITY_DAYS: i64 = 3650;
+
+/// Generates the self-signed certificate of the workspace (see
+/// [`SettingsSelfSigned`](crate::http_server::SettingsSelfSigned)) if it does not exist, can not
+/// be loaded, is not for `domain` anymore, or expires in less than
+/// `min_certificate_validity_days`.
+///
+/// Returns `true` if new key and cert files have been written, and `false` if current ones are
+/// kept or if self-signed certificates are not enabled.
+pub fn renew_self_signed_certificate(
+	settings: &crate::http_server::Settings,
+) -> Result<bool, String> {
+	let self_signed = match &settings.https {
+		Some(https)
+			if https.keyfile_path.trim().is_empty() && https.certfile_path.trim().is_empty() =>
+		{
+			match &https.self_signed {
+				Some(self_signed) => self_signed,
+				None => return Ok(false),
+			}
+		}
+		_ => return Ok(false),
+	};
+	let https = match settings.resolved_https() {
+		Some(https) => https,
+		None => return Ok(false),
+	};
+
+	let keyfile_path = std::path::PathBuf::from(&https.keyfile_path);
+	let certfile_path = std::path::PathBuf::from(&https.certfile_path);
+	let now = time::OffsetDateTime::now_utc();
+
+	let domain = settings
+		.domain
+		.clone()
+		.unwrap_or_else(|| String::from("localhost"));
+
+	if super::load_certified_key(&https).is_ok()
+		&& read_certificate(&certfile_path, subject_alt_names) == Some(vec![name_of(&domain)])
+	{
+		if let Ok(expiration) = crate::http_server::certificate_expiration(&certfile_path) {
+			if expiration - now >= time::Duration::days(https.min_certificate_validity_days as i64)
+			{
+				return Ok(false);
+			}
+		}
+	}
+
+	std::fs::create_dir_all(settings.self_signed_path()).map_err(|e| {
+		format!(
+			"can not create folder `{}` : {e}",
+			settings.self_signed_path().to_string_lossy()
+		)
+	})?;
+
+	let mut params = rcgen::CertificateParams::default();
+	params.distinguished_name = rcgen::DistinguishedName::new();
+	params
+		.distinguished_name
+		.push(rcgen::DnType::CommonName, domain.clone());
+	params.subject_alt_names = vec![match domain.parse::<std::net::IpAddr>() {
+		Ok(ip) => rcgen::SanType::IpAddress(ip),
+		Err(_) => rcgen::SanType::DnsName(domain.clone()),
+	}];
+	// one day earlier, in case of clocks of clients which are late
+	params.not_before = now - time::Duration::days(1);
+	params.not_after = now + time::Duration::days(self_signed.validity_days as i64);
+
+	let certificate = rcgen::Certificate::from_params(params)
+		.map_err(|e| format!("can not generate certificate : {e}"))?;
+	let cert_pem = if self_signed.local_ca {
+		certificate.serialize_pem_with_signer(&local_ca(
+			settings,
+			&domain,
+			self_signed.validity_days,
+		)?)
+	} else {
+		certificate.serialize_pem()
+	}
+	.map_err(|e| format!("can not sign certificate : {e}"))?;
+
+	write_key(&keyfile_path, &certificate.serialize_private_key_pem())?;
+	write_pem(&certfile_path, &cert_pem)?;
+
+	return Ok(true);
+}
+
+/// Certificate authority of `ca_key.pem` and `ca_cert.pem` in the folder of the self-signed
+/// certificate.
+///
+/// They are generated if they do not exist yet. The key is kept as long as it can be loaded, so
+/// devices which trust this authority also trust its new certificates, but its certificate is
+/// generated again if it expires before `validity_days` or if `domain` changed.
+///
+/// This authority can only sign certificates for `domain` (and its sub-domains), so trusting it
+/// does not allow its key to impersonate other web sites.
+fn local_ca(
+	settings: &crate::http_server::Settings,
+	domain: &str,
+	validity_days: u64,
+) -> Result<rcgen::Certificate, String> {
+	let ca_keyfile_path = settings.self_signed_path().join("ca_key.pem");
+	let ca_certfile_path = settings.self_signed_path().join("ca_cert.pem");
+	let now = time::OffsetDateTime::now_utc();
+
+	let key_pair = match std::fs::read_to_string(&ca_keyfile_path) {
+		Ok(pem) => Some(rcgen::KeyPair::from_pem(&pem).map_err(|e| {
+			format!(
+				"can not load local CA key file `{}` : {e}",
+				ca_keyfile_path.to_string_lossy()
+			)
+		})?),
+		Err(e) if e.kind() == std::io::ErrorKind::NotFound => None,
+		Err(e) => {
+			return Err(format!(
+				"can not read local CA key file `{}` : {e}",
+				ca_keyfile_path.to_string_lossy()
+			));
+		}
+	};
+	let is_new_key = key_pair.is_none();
+	let needs_new_cert = is_new_key
+		|| match crate::http_server::certificate_expiration(&ca_certfile_path) {
+			Ok(expiration) => expiration - now < time::Duration::days(validity_days as i64),
+			Err(_) => true,
+		} || read_certificate(&ca_certfile_path, permitted_names)
+		!= Some(vec![name_of(domain)]);
+
+	let mut params = rcgen::CertificateParams::default();
+	params.distinguished_name = rcgen::DistinguishedName::new();
+	params
+		.distinguished_name
+		.push(rcgen::DnType::CommonName, LOCAL_CA_NAME);
+	params.is_ca = rcgen::IsCa::Ca(rcgen::BasicConstraints::Constrained(0));
+	params.name_constraints = Some(rcgen::NameConstraints {
+		permitted_subtrees: vec![match domain.parse::<std::net::IpAddr>() {
+			Ok(ip) => rcgen::GeneralSubtree::IpAddress(rcgen::CidrSubnet::from_addr_prefix(
+				ip,
+				if ip.is_ipv4() { 32 } else { 128 },
+			)),
+			Err(_) => rcgen::GeneralSubtree::DnsName(String::from(domain)),
+		}],
+		excluded_subtrees: vec![],
+	});
+	params.key_usages = vec![
+		rcgen::KeyUsagePurpose::KeyCertSign,
+		rcgen::KeyUsagePurpose::CrlSign,
+		rcgen::KeyUsagePurpose::DigitalSignature,
+	];
+	params.not_before = now - time::Duration::days(1);
+	params.not_after = now + time::Duration::days(LOCAL_CA_VALIDITY_DAYS);
+	params.key_pair = key_pair;
+
+	let ca = rcgen::Certificate::from_params(params)
+		.map_err(|e| format!("can not generate local CA : {e}"))?;
+
+	if is_new_key {
+		write_key(&ca_keyfile_path, &ca.serialize_private_key_pem())?;
+	}
+	if needs_new_cert {
+		write_pem(
+			&ca_certfile_path,
+			&ca.serialize_pem()
+				.map_err(|e| format!("can not sign local CA : {e}"))?,
+		)?;
+	}
+
+	return Ok(ca);
+}
+
+fn write_pem(path: &std::path::Path, pem: &str) -> Result<(), String> {
+	return crate::http_server::utils::write_atomically(path, pem.as_bytes())
+		.map_err(|e| format!("can not write `{}` : {e}", path.to_string_lossy()));
+}
+
+fn write_key(path: &std::path::Path, pem: &str) -> Result<(), String> {
+	return crate::http_server::utils::write_private_atomically(path, pem.as_bytes())
+		.map_err(|e| format!("can not write `{}` : {e}", path.to_string_lossy()));
+}
+
+/// Applies `read` on the first certificate of this PEM file, if it can be parsed.
+fn read_certificate<T>(
+	certfile_path: &std::path::Path,
+	read: impl FnOnce(&x509_parser::certificate::X509Certificate) -> T,
+) -> Option<T> {
+	let certfile = std::fs::File::open(certfile_path).ok()?;
+	let der = rustls_pemfile::certs(&mut std::io::BufReader::new(certfile))
+		.ok()?
+		.into_iter()
+		.next()?;
+	let (_, certificate) = x509_parser::parse_x509_certificate(&der).ok()?;
+
+	return Some(read(&certificate));
+}
+
+fn subject_alt_names(certificate: &x509_parser::certificate::X509Certificate) -> Vec<String> {
+	return match certificate.subject_alternative_name() {
+		Ok(Some(extension)) => extension
+			.value
+			.general_names
+			.iter()
+			.filter_map(general_name)
+			.collect(),
+		_ => vec![],
+	};
+}
+
+/// Names which can be signed by this certificate authority.
+fn permitted_names(certificate: &x509_parser::certificate::X509Certificate) -> Vec<String> {
+	return match certificate.name_constraints() {
+		Ok(Some(extension)) => extension
+			.value
+			.permitted_subtrees
+			.iter()
+			.flatten()
+			.filter_map(|subtree| general_name(&subtree.base))
+			.collect(),
+		_ => vec![],
+	};
+}
+
+/// `domain` like [`general_name`] reads it from certificates.
+fn name_of(domain: &str) -> String {
+	return match domain.parse::<std::net::IpAddr>() {
+		Ok(ip) => ip.to_string(),
+		Err(_) => domain.to_lowercase(),
+	};
+}
+
+fn general_name(name: &x509_parser::extensions::GeneralName) -> Option<String> {
+	return match name {
+		x509_parser::extensions::GeneralName::DNSName(name) => Some(name.to_lowercase()),
+		// name constraints add the mask after the address
+		x509_parser::extensions::GeneralName::IPAddress(bytes) => match bytes.len() {
+			4 | 8 => <[u8; 4]>::try_from(&bytes[..4])
+				.ok()
+				.map(|ip| std::net::IpAddr::from(ip).to_string()),
+			16 | 32 => <[u8; 16]>::try_from(&bytes[..16])
+				.ok()
+				.map(|ip| std::net::IpAddr::from(ip).to_string()),
+			_ => None,
+		},
+		_ => None,
+	};
+}
+
+#[test]
+fn t7kq2vwx9mcn4rd() {
+	let workspace = tempfile::tempdir().unwrap();
+
+	let mut settings = crate::http_server::Settings::new(workspace.path().to_path_buf());
+	settings.domain = Some(String::from("127.0.0.1"));
+	let https = settings.https.as_mut().unwrap();
+	https.keyfile_path = String::new();
+	https.certfile_path = String::new();
+	https.self_signed = None;
+
+	print!("#010 : nothing is generated when it is not enabled ... ");
+	assert!(!renew_self_signed_certificate(&settings).unwrap());
+	assert!(!settings.self_signed_path().exists());
+	println!("OK");
+
+	settings.https.as_mut().unwrap().self_signed =
+		Some(crate::http_server::SettingsSelfSigned::default());
+	let https = settings.resolved_https().unwrap();
+	let certfile_path = std::path::PathBuf::from(&https.certfile_path);
+
+	print!("#020 : certificate is generated ... ");
+	assert!(renew_self_signed_certificate(&settings).unwrap());
+	assert!(super::load_certified_key(&https).is_ok());
+	let first_certificate = std::fs::read(&certfile_path).unwrap();
+	let first_expiration = crate::http_server::certificate_expiration(&certfile_path).unwrap();
+	assert!(first_expiration - time::OffsetDateTime::now_utc() > time::Duration::days(89));
+	assert_eq!(
+		read_certificate(&certfile_path, subject_alt_names),
+		Some(vec![String::from("127.0.0.1")])
+	);
+	#[cfg(unix)]
+	assert_eq!(
+		std::os::unix::fs::PermissionsExt::mode(
+			&std::fs::metadata(&https.keyfile_path)
+				.unwrap()
+				.permissions()
+		) & 0o777,
+		0o600
+	);
+	println!("OK");
+
+	print!("#030 : certificate is kept when it is valid ... ");
+	assert!(!renew_self_signed_certificate(&settings).unwrap());
+	assert_eq!(std::fs::read(&certfile_path).unwrap(), first_certificate);
+	println!("OK");
+
+	print!("#040 : certificate is renewed before its expiration ... ");
+	settings
+		.https
+		.as_mut()
+		.unwrap()
+		.min_certificate_validity_days = 100;
+	assert!(renew_self_signed_certificate(&settings).unwrap());
+	assert_ne!(std::fs::read(&certfile_path).unwrap(), first_certificate);
+	settings
+		.https
+		.as_mut()
+		.unwrap()
+		.min_certificate_validity_days = 7;
+	println!("OK");
+
+	print!("#050 : certificate is generated again when it can not be loaded ... ");
+	std::fs::write(&certfile_path, "not a certificate").unwrap();
+	assert!(renew_self_signed_certificate(&settings).unwrap());
+	assert!(super::load_certified_key(&https).is_ok());
+	println!("OK");
+
+	print!("#060 : certificate is signed by the local CA ... ");
+	settings.https.as_mut().unwrap().self_signed = Some(crate::http_server::SettingsSelfSigned {
+		local_ca: true,
+		..Default::default()
+	});
+	std::fs::remove_file(&certfile_path).unwrap();
+	assert!(renew_self_signed_certificate(&settings).unwrap());
+	let ca_certfile_path = settings.self_signed_path().join("ca_cert.pem");
+	let ca_certificate = std::fs::read(&ca_certfile_path).unwrap();
+	let ca_key = std::fs::read(settings.self_signed_path().join("ca_key.pem")).unwrap();
+
+	let issuer_and_subject = |path: &std::path::Path| {
+		let der = rustls_pemfile::certs(&mut std::io::BufReader::new(
+			std::fs::File::open(path).unwrap(),
+		))
+		.unwrap()
+		.remove(0);
+		let (_, certificate) = x509_parser::parse_x509_certificate(&der).unwrap();
+
+		(
+			certificate.issuer().to_string(),
+			certificate.subject().to_string(),
+		)
+	};
+	let (ca_issuer, ca_subject) = issuer_and_subject(&ca_certfile_path);
+	assert_eq!(ca_issuer, ca_subject);
+	assert_eq!(issuer_and_subject(&certfile_path).0, ca_subject);
+	assert_eq!(
+		read_certificate(&ca_certfile_path, permitted_names),
+		Some(vec![String::from("127.0.0.1")])
+	);
+	#[cfg(unix)]
+	assert_eq!(
+		std::os::unix::fs::PermissionsExt::mode(
+			&std::fs::metadata(settings.self_signed_path().join("ca_key.pem"))
+				.unwrap()
+				.permissions()
+		) & 0o777,
+		0o600
+	);
+	println!("OK");
+
+	print!("#070 : local CA is kept when the certificate is renewed ... ");
+	std::fs::remove_file(&certfile_path).unwrap();
+	assert!(renew_self_signed_certificate(&settings).unwrap());
+	assert_eq!(std::fs::read(&ca_certfile_path).unwrap(), ca_certificate);
+	assert_eq!(
+		std::fs::read(settings.self_signed_path().join("ca_key.pem")).unwrap(),
+		ca_key
+	);
+	assert_eq!(issuer_and_subject(&certfile_path).0, ca_subject);
+	println!("OK");
+
+	print!("#080 : certificate and local CA are generated again for another domain ... ");
+	settings.domain = Some(String::from("Example.localhost"));
+	assert!(renew_self_signed_certificate(&settings).unwrap());
+	assert_eq!(
+		read_certificate(&certfile_path, subject_alt_names),
+		Some(vec![String::from("example.localhost")])
+	);
+	assert_ne!(std::fs::read(&ca_certfile_path).unwrap(), ca_certificate);
+	assert_eq!(
+		read_certificate(&ca_certfile_path, permitted_names),
+		Some(vec![String::from("example.localhost")])
+	);
+	assert_eq!(
+		std::fs::read(settings.self_signed_path().join("ca_key.pem")).unwrap(),
+		ca_key
+	);
+	assert!(!renew_self_signed_certificate(&settings).unwrap());
+	println!("OK");
+
+	print!("#090 : files set in settings are never replaced ... ");
+	let https = settings.https.as_mut().unwrap();
+	https.keyfile_path = String::from(workspace.path().join("key.pem").to_string_lossy());
+	https.certfile_path = String::from(workspace.path().join("cert.pem").to_string_lossy());
+	assert!(!renew_self_signed_certificate(&settings).unwrap());
+	assert!(!workspace.path().join("cert.pem").exists());
+	println!("OK");
+}
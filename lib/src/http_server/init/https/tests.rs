@@ -0,0 +1,196 @@
+/// Private key of this PKCS#8 key in its own format : PKCS#1 for RSA keys, SEC1 for EC ones.
+fn unwrap_pkcs8(der: &[u8]) -> Vec<u8> {
+	let (_, pkcs8) = x509_parser::der_parser::parse_der(der).unwrap();
+
+	return pkcs8.as_sequence().unwrap()[2].as_slice().unwrap().to_vec();
+}
+
+fn pem(label: &str, der: &[u8]) -> String {
+	return format!(
+		"-----BEGIN {label}-----\n{}\n-----END {label}-----\n",
+		base64::encode(der)
+	);
+}
+
+fn new_certificate(
+	alg: &'static rcgen::SignatureAlgorithm,
+	key_pair: Option<rcgen::KeyPair>,
+) -> rcgen::Certificate {
+	let mut params = rcgen::CertificateParams::new(vec![String::from("localhost")]);
+	params.alg = alg;
+	params.key_pair = key_pair;
+
+	return rcgen::Certificate::from_params(params).unwrap();
+}
+
+#[test]
+fn q3mt8wc1vkh6zrd() {
+	let tempdir = tempfile::tempdir().unwrap();
+
+	let ec = new_certificate(&rcgen::PKCS_ECDSA_P256_SHA256, None);
+	let ec_cert = ec.serialize_pem().unwrap();
+	let ec_pkcs8 = ec.serialize_private_key_der();
+
+	let ed25519 = new_certificate(&rcgen::PKCS_ED25519, None);
+
+	// generated with `openssl genpkey -algorithm RSA -pkeyopt rsa_keygen_bits:2048`, because
+	// `rcgen` can not generate RSA keys
+	let rsa = new_certificate(
+		&rcgen::PKCS_RSA_SHA256,
+		Some(rcgen::KeyPair::from_pem(include_str!("test_rsa_key.pem")).unwrap()),
+	);
+	let rsa_cert = rsa.serialize_pem().unwrap();
+	let rsa_pkcs8 = rsa.serialize_private_key_der();
+
+	let mut ca_params = rcgen::CertificateParams::new(vec![]);
+	ca_params.is_ca = rcgen::IsCa::Ca(rcgen::BasicConstraints::Unconstrained);
+	let ca = rcgen::Certificate::from_params(ca_params).unwrap();
+	let leaf = new_certificate(&rcgen::PKCS_ECDSA_P256_SHA256, None);
+
+	let tests = vec![
+		(
+			010,
+			"ECDSA key in PKCS#8",
+			ec_cert.clone(),
+			Some(pem("PRIVATE KEY", &ec_pkcs8)),
+			Ok(1),
+		),
+		(
+			020,
+			"ECDSA key in SEC1",
+			ec_cert.clone(),
+			Some(pem("EC PRIVATE KEY", &unwrap_pkcs8(&ec_pkcs8))),
+			Ok(1),
+		),
+		(
+			030,
+			"Ed25519 key in PKCS#8",
+			ed25519.serialize_pem().unwrap(),
+			Some(pem("PRIVATE KEY", &ed25519.serialize_private_key_der())),
+			Ok(1),
+		),
+		(
+			040,
+			"RSA key in PKCS#8",
+			rsa_cert.clone(),
+			Some(pem("PRIVATE KEY", &rsa_pkcs8)),
+			Ok(1),
+		),
+		(
+			050,
+			"RSA key in PKCS#1",
+			rsa_cert.clone(),
+			Some(pem("RSA PRIVATE KEY", &unwrap_pkcs8(&rsa_pkcs8))),
+			Ok(1),
+		),
+		(
+			060,
+			"certificate with its intermediate one",
+			format!(
+				"{}{}",
+				leaf.serialize_pem_with_signer(&ca).unwrap(),
+				ca.serialize_pem().unwrap()
+			),
+			Some(leaf.serialize_private_key_pem()),
+			Ok(2),
+		),
+		(
+			070,
+			"certificate and key in the same file",
+			format!("{ec_cert}{}", pem("PRIVATE KEY", &ec_pkcs8)),
+			None,
+			Ok(1),
+		),
+		(
+			080,
+			"empty key file",
+			ec_cert.clone(),
+			Some(String::new()),
+			Err("no private key found in key file"),
+		),
+		(
+			090,
+			"certificate in key file",
+			ec_cert.clone(),
+			Some(ec_cert.clone()),
+			Err("no private key found in key file"),
+		),
+		(
+			100,
+			"key in cert file",
+			pem("PRIVATE KEY", &ec_pkcs8),
+			Some(pem("PRIVATE KEY", &ec_pkcs8)),
+			Err("it only contains a private key"),
+		),
+		(
+			110,
+			"empty cert file",
+			String::new(),
+			Some(pem("PRIVATE KEY", &ec_pkcs8)),
+			Err("no certificate found in cert file"),
+		),
+		(
+			120,
+			"incorrect PKCS#8 key",
+			ec_cert.clone(),
+			Some(pem("PRIVATE KEY", b"not a key")),
+			Err("can not load PKCS#8 private key"),
+		),
+		(
+			130,
+			"EC key as PKCS#1 RSA key",
+			ec_cert.clone(),
+			Some(pem("RSA PRIVATE KEY", &unwrap_pkcs8(&ec_pkcs8))),
+			Err("can not load PKCS#1 RSA private key"),
+		),
+		(
+			140,
+			"RSA key as SEC1 EC key",
+			rsa_cert,
+			Some(pem("EC PRIVATE KEY", &unwrap_pkcs8(&rsa_pkcs8))),
+			Err("can not load SEC1 EC private key"),
+		),
+	];
+
+	for test in tests {
+		print!("#{:03} : load {} ... ", test.0, test.1);
+
+		let certfile_path = tempdir.path().join(format!("cert_{:03}.pem", test.0));
+		std::fs::write(&certfile_path, &test.2).unwrap();
+		let keyfile_path = match &test.3 {
+			Some(key) => {
+				let keyfile_path = tempdir.path().join(format!("key_{:03}.pem", test.0));
+				std::fs::write(&keyfile_path, key).unwrap();
+				keyfile_path
+			}
+			None => certfile_path.clone(),
+		};
+
+		let result = super::load_certified_key(&crate::http_server::SettingsHTTPS {
+			keyfile_path: String::from(keyfile_path.to_string_lossy()),
+			certfile_path: String::from(certfile_path.to_string_lossy()),
+			..Default::default()
+		});
+
+		match test.4 {
+			Ok(chain_length) => assert_eq!(result.unwrap().cert.len(), chain_length),
+			Err(message) => {
+				let error = result.err().unwrap();
+				assert!(error.contains(message), "{error}");
+			}
+		}
+
+		println!("OK");
+	}
+
+	print!("#150 : load missing key file ... ");
+
+	let result = super::load_certified_key(&crate::http_server::SettingsHTTPS {
+		keyfile_path: String::from(tempdir.path().join("missing.pem").to_string_lossy()),
+		certfile_path: String::from(tempdir.path().join("cert_010.pem").to_string_lossy()),
+		..Default::default()
+	});
+	assert!(result.err().unwrap().starts_with("can not open key file"));
+
+	println!("OK");
+}
@@ -1,6 +1,7 @@
-use std::io::Write;
 use std::sync::{Arc, Mutex};
 
+/// Logger which prints logs in the console, and sends them to outputs of
+/// [`SettingsLogging`](super::SettingsLogging).
 pub fn load_or_create_logger(
 	settings: &super::Settings,
 	temp_logger: charlie_buffalo::Logger,
@@ -24,7 +25,9 @@ pub fn load_or_create_logger(
 		}
 	}
 
-	let logfile_path_for_dispatch = logfile_path.clone();
+	let outputs = settings.logging.clone().unwrap_or_default();
+	let mut logfile =
+		crate::http_server::logging::RotatingLogFile::new((*logfile_path).clone(), &outputs);
 
 	let new_logger = charlie_buffalo::concurrent_logger_from(charlie_buffalo::Logger::new(
 		charlie_buffalo::new_dispatcher(Box::from(move |log: charlie_buffalo::Log| {
@@ -37,6 +40,10 @@ pub fn load_or_create_logger(
 
 			if !is_whitespace {
 				let mut new_log = log;
+				let is_traced = new_log
+					.attributes
+					.remove(crate::http_server::logging::TRACED_FLAG)
+					.is_some();
 
 				let attributes: Vec<(String, String)> = vec![charlie_buffalo::Attr::new(
 					"time",
@@ -88,15 +95,15 @@ pub fn load_or_create_logger(
 					);
 				}
 
-				let mut result: Vec<u8> = vec![00u8, 30u8]; // 0x001D in Unicode : RECORD SEPARATOR
-				result.append(&mut rmp_serde::encode::to_vec(&new_log).unwrap());
-				let mut file = std::fs::File::options()
-					.create(true)
-					.append(true)
-					.open((*logfile_path_for_dispatch).clone())
-					.unwrap();
-				file.write_all(&result).unwrap();
-				file.flush().unwrap();
+				if outputs.tracing && !is_traced {
+					crate::http_server::logging::emit_tracing_event(&new_log);
+				}
+
+				if outputs.msgpack_file {
+					if let Err(e) = logfile.write(&new_log) {
+						eprintln!("❌ can not save log : {e}");
+					}
+				}
 			} else {
 				println!();
 			}
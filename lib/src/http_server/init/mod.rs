@@ -1,11 +1,13 @@
 mod database;
 mod https;
 mod logger;
+mod reload;
 mod settings;
 mod users;
 
 pub use database::*;
 pub use https::*;
 pub use logger::*;
+pub use reload::*;
 pub use settings::*;
 pub use users::*;
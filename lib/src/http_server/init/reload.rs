@@ -0,0 +1,322 @@
+use std::sync::{Arc, Mutex};
+
+/// Interval between checks of modification dates of files returned by [`watched_files`].
+const WATCH_INTERVAL: std::time::Duration = std::time::Duration::from_secs(2);
+
+/// Interval between checks of the expiration of the self-signed certificate, see
+/// [`renew_self_signed_certificate`](crate::http_server::renew_self_signed_certificate).
+const RENEWAL_INTERVAL: std::time::Duration = std::time::Duration::from_secs(60 * 60);
+
+/// Reloads settings, users and the TLS certificate (see [`reload`]) when their files change, and
+/// on `SIGHUP` signals on Unix.
+///
+/// The self-signed certificate is also renewed before its expiration, then reloaded as its files
+/// change.
+///
+/// It has to be called in an actix runtime, which listens to signals.
+pub fn spawn_reloader(
+	settings: Arc<Mutex<super::Settings>>,
+	users: Arc<Mutex<crate::http_server::Users>>,
+	program_state: Arc<Mutex<crate::http_server::ProgramState>>,
+	logger: Arc<Mutex<charlie_buffalo::Logger>>,
+) {
+	#[cfg(unix)]
+	{
+		let settings = settings.clone();
+		let users = users.clone();
+		let program_state = program_state.clone();
+		let logger = logger.clone();
+
+		actix_web::rt::spawn(async move {
+			let mut hangups = match actix_web::rt::signal::unix::signal(
+				actix_web::rt::signal::unix::SignalKind::hangup(),
+			) {
+				Ok(hangups) => hangups,
+				Err(e) => {
+					crate::http_server::logging::log(
+						&logger,
+						crate::http_server::logging::Level::Warning,
+						"setup",
+						&[("module", "reload")],
+						&format!("can not listen to SIGHUP signals : {e}"),
+					);
+
+					return;
+				}
+			};
+
+			while hangups.recv().await.is_some() {
+				reload(&settings, &users, &program_state, &logger);
+			}
+		});
+	}
+
+	std::thread::spawn(move || {
+		let mut dates = modification_dates(&watched_files(&settings.lock().unwrap()));
+		let mut last_renewal_check = std::time::Instant::now();
+
+		loop {
+			std::thread::sleep(WATCH_INTERVAL);
+
+			if last_renewal_check.elapsed() >= RENEWAL_INTERVAL {
+				renew_self_signed_certificate(&settings.lock().unwrap().clone(), &logger);
+				last_renewal_check = std::time::Instant::now();
+			}
+
+			let new_dates = modification_dates(&watched_files(&settings.lock().unwrap()));
+			if new_dates != dates {
+				reload(&settings, &users, &program_state, &logger);
+
+				// paths can change with settings
+				dates = modification_dates(&watched_files(&settings.lock().unwrap()));
+			}
+		}
+	});
+}
+
+/// The settings file, the users file, and key and cert files of HTTPS.
+pub fn watched_files(settings: &super::Settings) -> Vec<std::path::PathBuf> {
+	let mut result = vec![settings.settingsfile_path(), settings.userfile_path()];
+
+	if let Some(https) = settings.resolved_https() {
+		result.push(std::path::PathBuf::from(&https.keyfile_path));
+		result.push(std::path::PathBuf::from(&https.certfile_path));
+	}
+
+	return result;
+}
+
+fn modification_dates(paths: &[std::path::PathBuf]) -> Vec<Option<std::time::SystemTime>> {
+	return paths
+		.iter()
+		.map(|path| {
+			std::fs::metadata(path)
+				.and_then(|metadata| metadata.modified())
+				.ok()
+		})
+		.collect();
+}
+
+/// Reads again the settings file, the users file and the TLS certificate.
+///
+/// Changes of settings which are only read when servers start (like ports) are logged and kept
+/// for the next start, other ones are applied immediately. Files which can not be read or are not
+/// correct are logged and ignored, in order to keep the server running.
+pub fn reload(
+	settings: &Mutex<super::Settings>,
+	users: &Mutex<crate::http_server::Users>,
+	program_state: &Mutex<crate::http_server::ProgramState>,
+	logger: &Mutex<charlie_buffalo::Logger>,
+) {
+	let current_settings = settings.lock().unwrap().clone();
+
+	let new_settings = match read_settings(&current_settings.settingsfile_path()) {
+		Ok(mut new_settings) => {
+			new_settings.workspace_path = current_settings.workspace_path.clone();
+
+			for field in keep_fields_needing_restart(&current_settings, &mut new_settings) {
+				log(
+					logger,
+					crate::http_server::logging::Level::Warning,
+					&format!("`{field}` setting has changed, it will be applied after a restart"),
+				);
+			}
+
+			if toml::to_string(&new_settings).ok() != toml::to_string(&current_settings).ok() {
+				*settings.lock().unwrap() = new_settings.clone();
+
+				log(
+					logger,
+					crate::http_server::logging::Level::Info,
+					&format!(
+						"settings reloaded from `{}`",
+						new_settings.settingsfile_path().to_string_lossy()
+					),
+				);
+			}
+
+			new_settings
+		}
+		Err(e) => {
+			log(
+				logger,
+				crate::http_server::logging::Level::Error,
+				&format!("{e}, keeping current settings"),
+			);
+
+			current_settings
+		}
+	};
+
+	let users_path = new_settings.userfile_path();
+	// the file is read while users are locked, otherwise changes saved by handlers in the meantime
+	// would be replaced by the previous content of the file
+	{
+		let mut users = users.lock().unwrap();
+		users.set_password_hashing(new_settings.password_hashing.clone().unwrap_or_default());
+		match crate::http_server::Users::load(&users_path) {
+			Ok(mut new_users) => {
+				let format = crate::http_server::UsersFileFormat::from_path(&users_path);
+				// unless it is the file which has just been saved by the server itself
+				if new_users.serialize(format).ok() != users.serialize(format).ok() {
+					new_users.set_password_hashing(
+						new_settings.password_hashing.clone().unwrap_or_default(),
+					);
+					*users = new_users;
+
+					log(
+						logger,
+						crate::http_server::logging::Level::Info,
+						&format!("users reloaded from `{}`", users_path.to_string_lossy()),
+					);
+				}
+			}
+			Err(e) => {
+				log(
+					logger,
+					crate::http_server::logging::Level::Error,
+					&format!(
+						"can not reload users from `{}` : {e}, keeping current users",
+						users_path.to_string_lossy()
+					),
+				);
+			}
+		}
+	}
+
+	let certificate_resolver = program_state.lock().unwrap().certificate_resolver.clone();
+	if certificate_resolver.is_some() {
+		// self-signed certificates can be enabled or disabled by new settings
+		renew_self_signed_certificate(&new_settings, logger);
+	}
+	if let (Some(certificate_resolver), Some(https)) =
+		(certificate_resolver, new_settings.resolved_https())
+	{
+		match certificate_resolver.reload(&https) {
+			Ok(()) => {
+				let expiration = crate::http_server::certificate_expiration(std::path::Path::new(
+					&https.certfile_path,
+				))
+				.ok()
+				.and_then(|expiration| {
+					expiration
+						.format(&time::format_description::well_known::Rfc3339)
+						.ok()
+				})
+				.unwrap_or_else(|| String::from("an unknown date"));
+
+				log(
+					logger,
+					crate::http_server::logging::Level::Info,
+					&format!(
+						"certificate reloaded from `{}`, it expires on {expiration}",
+						https.certfile_path
+					),
+				);
+			}
+			Err(e) => {
+				log(
+					logger,
+					crate::http_server::logging::Level::Error,
+					&format!("can not reload certificate : {e}, keeping current certificate"),
+				);
+			}
+		}
+	}
+}
+
+fn renew_self_signed_certificate(
+	settings: &super::Settings,
+	logger: &Mutex<charlie_buffalo::Logger>,
+) {
+	match crate::http_server::renew_self_signed_certificate(settings) {
+		Ok(true) => log(
+			logger,
+			crate::http_server::logging::Level::Info,
+			&format!(
+				"self-signed certificate renewed in `{}`",
+				settings.self_signed_path().to_string_lossy()
+			),
+		),
+		Ok(false) => {}
+		Err(e) => log(
+			logger,
+			crate::http_server::logging::Level::Error,
+			&format!("can not renew self-signed certificate : {e}"),
+		),
+	}
+}
+
+fn read_settings(settings_path: &std::path::Path) -> Result<super::Settings, String> {
+	let bytes = std::fs::read(settings_path).map_err(|e| {
+		format!(
+			"can not read settings file `{}` : {e}",
+			settings_path.to_string_lossy()
+		)
+	})?;
+	let settings = toml::from_slice::<super::Settings>(&bytes)
+		.map_err(|e| format!("can not parse settings file : {e}"))?;
+
+	settings
+		.validate()
+		.map_err(|errors| format!("incorrect settings : {}", errors.join(", ")))?;
+
+	return Ok(settings);
+}
+
+/// Copies in `new_settings` the settings of `current_settings` which are only read when servers
+/// start, and returns names of the ones which have changed.
+fn keep_fields_needing_restart(
+	current_settings: &super::Settings,
+	new_settings: &mut super::Settings,
+) -> Vec<&'static str> {
+	let mut result = vec![];
+
+	if new_settings.port != current_settings.port {
+		result.push("port");
+		new_settings.port = current_settings.port;
+	}
+	if new_settings.domain != current_settings.domain {
+		result.push("domain");
+		new_settings.domain = current_settings.domain.clone();
+	}
+	if new_settings.metrics != current_settings.metrics {
+		result.push("metrics");
+		new_settings.metrics = current_settings.metrics.clone();
+	}
+	if new_settings.logging != current_settings.logging {
+		result.push("logging");
+		new_settings.logging = current_settings.logging.clone();
+	}
+
+	match (&mut new_settings.https, &current_settings.https) {
+		(Some(new_https), Some(current_https)) => {
+			if new_https.port != current_https.port {
+				result.push("https.port");
+				new_https.port = current_https.port;
+			}
+			if new_https.enable_hsts != current_https.enable_hsts {
+				result.push("https.enable_hsts");
+				new_https.enable_hsts = current_https.enable_hsts;
+			}
+		}
+		(None, None) => {}
+		_ => {
+			result.push("https");
+			new_settings.https = current_settings.https.clone();
+		}
+	}
+
+	return result;
+}
+
+fn log(
+	logger: &Mutex<charlie_buffalo::Logger>,
+	level: crate::http_server::logging::Level,
+	message: &str,
+) {
+	crate::http_server::logging::log(logger, level, "reload", &[("module", "reload")], message);
+}
+
+#[cfg(test)]
+mod tests;
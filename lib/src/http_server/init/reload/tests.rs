@@ -0,0 +1,131 @@
+use std::sync::{Arc, Mutex};
+
+fn new_logger() -> Arc<Mutex<charlie_buffalo::Logger>> {
+	return Arc::new(Mutex::new(charlie_buffalo::Logger::new(
+		charlie_buffalo::new_dispatcher(Box::from(move |log: charlie_buffalo::Log| {
+			println!("{:?} : {:?}", log.attributes, log.content);
+		})),
+		charlie_buffalo::new_dropper(Box::from(|_: &charlie_buffalo::Logger| {})),
+	)));
+}
+
+fn write_certificate(tempdir: &std::path::Path) -> Vec<u8> {
+	let certificate = rcgen::generate_simple_self_signed(vec![String::from("localhost")]).unwrap();
+	std::fs::write(
+		tempdir.join("cert.pem"),
+		certificate.serialize_pem().unwrap(),
+	)
+	.unwrap();
+	std::fs::write(
+		tempdir.join("key.pem"),
+		certificate.serialize_private_key_pem(),
+	)
+	.unwrap();
+
+	// each serialization is signed again, so it is read from the file
+	return rustls_pemfile::certs(&mut std::io::BufReader::new(
+		std::fs::File::open(tempdir.join("cert.pem")).unwrap(),
+	))
+	.unwrap()
+	.remove(0);
+}
+
+#[test]
+fn v5hm2xq8kdt3rbn() {
+	let tempdir = tempfile::tempdir().unwrap();
+	let logger = new_logger();
+
+	let mut settings = crate::http_server::Settings::new(tempdir.path().to_path_buf());
+	settings.https = None;
+	settings.save().unwrap();
+
+	let mut users = crate::http_server::Users::new();
+	users
+		.insert("user1", &mut String::from("password"))
+		.unwrap();
+	users.save(&settings.userfile_path()).unwrap();
+
+	let settings = Arc::new(Mutex::new(settings));
+	let users = Arc::new(Mutex::new(users));
+	let program_state = Arc::new(Mutex::new(crate::http_server::ProgramState::default()));
+
+	let previous_port = settings.lock().unwrap().port;
+	let mut new_settings = settings.lock().unwrap().clone();
+	new_settings.token_lifetime_seconds = Some(42);
+	new_settings.port = if previous_port == 80 { 81 } else { 80 };
+	new_settings.save().unwrap();
+
+	let mut new_users = users.lock().unwrap().clone();
+	new_users
+		.insert("user2", &mut String::from("password"))
+		.unwrap();
+	new_users.save(&new_settings.userfile_path()).unwrap();
+
+	super::reload(&settings, &users, &program_state, &logger);
+
+	assert_eq!(settings.lock().unwrap().token_lifetime_seconds, Some(42));
+	assert_eq!(settings.lock().unwrap().port, previous_port);
+	assert_eq!(users.lock().unwrap().get_usernames().len(), 2);
+
+	std::fs::write(
+		settings.lock().unwrap().settingsfile_path(),
+		"token_lifetime_seconds = 0",
+	)
+	.unwrap();
+	std::fs::write(settings.lock().unwrap().userfile_path(), "not users").unwrap();
+
+	super::reload(&settings, &users, &program_state, &logger);
+
+	assert_eq!(settings.lock().unwrap().token_lifetime_seconds, Some(42));
+	assert_eq!(users.lock().unwrap().get_usernames().len(), 2);
+}
+
+#[test]
+fn n9cw4tj7pzr2xkd() {
+	let tempdir = tempfile::tempdir().unwrap();
+	let logger = new_logger();
+
+	let mut settings = crate::http_server::Settings::new(tempdir.path().to_path_buf());
+	let https = settings.https.as_mut().unwrap();
+	https.keyfile_path = String::from(tempdir.path().join("key.pem").to_string_lossy());
+	https.certfile_path = String::from(tempdir.path().join("cert.pem").to_string_lossy());
+	settings.save().unwrap();
+
+	let mut users = crate::http_server::Users::new();
+	users
+		.insert("user1", &mut String::from("password"))
+		.unwrap();
+	users.save(&settings.userfile_path()).unwrap();
+
+	let first_certificate = write_certificate(tempdir.path());
+	let certificate_resolver = Arc::new(
+		crate::http_server::CertificateResolver::new(settings.https.as_ref().unwrap()).unwrap(),
+	);
+	let current_certificate = || {
+		certificate_resolver.certified_key().unwrap().cert[0]
+			.0
+			.clone()
+	};
+	assert_eq!(current_certificate(), first_certificate);
+
+	let settings = Arc::new(Mutex::new(settings));
+	let users = Arc::new(Mutex::new(users));
+	let program_state = Arc::new(Mutex::new(crate::http_server::ProgramState {
+		https_mode: true,
+		certificate_resolver: Some(certificate_resolver.clone()),
+		..Default::default()
+	}));
+
+	let second_certificate = write_certificate(tempdir.path());
+	assert_ne!(second_certificate, first_certificate);
+
+	super::reload(&settings, &users, &program_state, &logger);
+
+	assert_eq!(current_certificate(), second_certificate);
+
+	std::fs::write(tempdir.path().join("cert.pem"), "not a certificate").unwrap();
+
+	super::reload(&settings, &users, &program_state, &logger);
+
+	assert_eq!(current_certificate(), second_certificate);
+}
@@ -7,8 +7,10 @@ pub fn load_or_create_settings(
 	logger: &mut charlie_buffalo::Logger,
 ) -> Settings {
 	let settings = match std::fs::read(&settings_path) {
-		Ok(bytes) => match toml::from_slice(&bytes) {
-			Ok(settings) => {
+		Ok(bytes) => match toml::from_slice::<Settings>(&bytes) {
+			Ok(mut settings) => {
+				settings.workspace_path = settings_path.parent().unwrap().to_path_buf();
+
 				logger.push(
 					vec![
 						(String::from("event"), String::from("setup")),
@@ -121,10 +123,22 @@ pub struct Settings {
 	pub force_https: Option<bool>,
 	pub domain: Option<String>,
 	pub domain_suffix: Option<String>,
+	/// Other domains of `acct:` resources accepted by webfinger, like `example.com` for
+	/// `user@example.com` when this server is on `storage.example.com`.
+	pub domain_aliases: Option<Vec<String>>,
 	pub port: usize,
 	pub admin_email: String,
 	pub token_lifetime_seconds: Option<u64>,
-	pub oauth_wait_seconds: Option<u64>,
+	/// If set, only OAuth clients with one of these origins (like `https://app.example.com`)
+	/// can request an access to this server.
+	pub trusted_client_origins: Option<Vec<String>>,
+	pub oauth_throttling: Option<SettingsOauthThrottling>,
+	pub password_hashing: Option<SettingsPasswordHashing>,
+	pub auth_backend: Option<SettingsAuthBackend>,
+	pub cors: Option<SettingsCors>,
+	/// Serves `/metrics` if set.
+	pub metrics: Option<SettingsMetrics>,
+	pub logging: Option<SettingsLogging>,
 	pub https: Option<SettingsHTTPS>,
 }
 impl Settings {
@@ -135,12 +149,19 @@ impl Settings {
 			force_https: None,
 			domain,
 			domain_suffix: Some(String::new()),
+			domain_aliases: None,
 			port: random_port_generation(),
 			admin_email: String::new(),
 			token_lifetime_seconds: Some(60 * 60),
 			workspace_path,
 			https: Some(SettingsHTTPS::default()),
-			oauth_wait_seconds: Some(2),
+			trusted_client_origins: None,
+			oauth_throttling: Some(SettingsOauthThrottling::default()),
+			password_hashing: Some(SettingsPasswordHashing::default()),
+			auth_backend: Some(SettingsAuthBackend::default()),
+			cors: Some(SettingsCors::default()),
+			metrics: None,
+			logging: Some(SettingsLogging::default()),
 		}
 	}
 
@@ -152,10 +173,178 @@ impl Settings {
 		self.workspace_path.join("logs.msgpack")
 		// std::fs::File::create(&logfile_path).unwrap();
 	}
+	/// The first existing one of `users.toml`, `users.json` and `users.bin`, or `users.bin` if
+	/// there is none.
+	///
+	/// Its format is the one of its extension, see [`crate::http_server::UsersFileFormat`].
 	pub fn userfile_path(&self) -> PathBuf {
-		self.workspace_path.join("users.bin")
+		["users.toml", "users.json"]
+			.iter()
+			.map(|filename| self.workspace_path.join(filename))
+			.find(|path| path.exists())
+			.unwrap_or_else(|| self.workspace_path.join("users.bin"))
 		// std::fs::File::create(&userfile_path).unwrap();
 	}
+	pub fn settingsfile_path(&self) -> PathBuf {
+		self.workspace_path.join("settings.toml")
+	}
+	/// `true` if `/metrics` is served without access token on the ports of the API, see
+	/// [`SettingsMetrics`].
+	pub fn serves_public_metrics(&self) -> bool {
+		return matches!(
+			&self.metrics,
+			Some(SettingsMetrics {
+				admin_port: None,
+				public: true,
+			})
+		);
+	}
+	/// Folder of the self-signed certificate, see [`SettingsSelfSigned`].
+	pub fn self_signed_path(&self) -> PathBuf {
+		self.workspace_path.join("self_signed")
+	}
+	/// HTTPS settings, where key and cert files are the ones of the self-signed certificate if it
+	/// is enabled and they are not set.
+	pub fn resolved_https(&self) -> Option<SettingsHTTPS> {
+		let mut https = self.https.clone()?;
+
+		if https.self_signed.is_some()
+			&& https.keyfile_path.trim().is_empty()
+			&& https.certfile_path.trim().is_empty()
+		{
+			let self_signed_path = self.self_signed_path();
+			https.keyfile_path = self_signed_path
+				.join("key.pem")
+				.to_string_lossy()
+				.to_string();
+			https.certfile_path = self_signed_path
+				.join("cert.pem")
+				.to_string_lossy()
+				.to_string();
+		}
+
+		return Some(https);
+	}
+
+	pub fn save(&self) -> Result<(), String> {
+		crate::http_server::utils::write_atomically(
+			&self.settingsfile_path(),
+			&toml::to_vec(self).map_err(|e| e.to_string())?,
+		)
+	}
+
+	/// Returns all problems found in these settings, in order to show them to users.
+	pub fn validate(&self) -> Result<(), Vec<String>> {
+		let mut errors = vec![];
+
+		if let Some(domain) = &self.domain {
+			if domain.trim().is_empty() || domain.contains(|c: char| c.is_whitespace() || c == '/')
+			{
+				errors.push(format!("incorrect domain `{domain}`"));
+			}
+		}
+		for alias in self.domain_aliases.iter().flatten() {
+			if alias.trim().is_empty() || alias.contains(|c: char| c.is_whitespace() || c == '/') {
+				errors.push(format!("incorrect domain alias `{alias}`"));
+			}
+		}
+		if !(1..=65535).contains(&self.port) {
+			errors.push(format!("incorrect HTTP port {}", self.port));
+		}
+		if let Some(https) = &self.https {
+			if !(1..=65535).contains(&https.port) {
+				errors.push(format!("incorrect HTTPS port {}", https.port));
+			} else if https.port == self.port {
+				errors.push(String::from("HTTP and HTTPS ports should be different"));
+			}
+			if let Some(self_signed) = &https.self_signed {
+				if self_signed.validity_days <= https.min_certificate_validity_days {
+					errors.push(String::from(
+						"validity of self-signed certificates should be greater than the minimal validity of certificates",
+					));
+				}
+			}
+		}
+		if self.token_lifetime_seconds == Some(0) {
+			errors.push(String::from("token lifetime should be greater than 0"));
+		}
+		if let Some(trusted_client_origins) = &self.trusted_client_origins {
+			for origin in trusted_client_origins {
+				if crate::http_server::utils::origin_of(origin).is_none() {
+					errors.push(format!("incorrect trusted client origin `{origin}`"));
+				}
+			}
+		}
+		if let Some(password_hashing) = &self.password_hashing {
+			if let Err(e) = argon2::Params::new(
+				password_hashing.memory_kib,
+				password_hashing.iterations,
+				password_hashing.parallelism,
+				None,
+			) {
+				errors.push(format!("incorrect password hashing parameters : {e}"));
+			}
+		}
+
+		if let Some(cors) = &self.cors {
+			for origin in &cors.allowed_origins {
+				if origin != "*" && crate::http_server::utils::origin_of(origin).is_none() {
+					errors.push(format!("incorrect CORS allowed origin `{origin}`"));
+				}
+			}
+		}
+
+		if let Some(admin_port) = self.metrics.as_ref().and_then(|metrics| metrics.admin_port) {
+			if !(1..=65535).contains(&admin_port) {
+				errors.push(format!("incorrect metrics admin port {admin_port}"));
+			} else if admin_port == self.port
+				|| self.https.as_ref().map(|https| https.port) == Some(admin_port)
+			{
+				errors.push(String::from(
+					"metrics admin port should be different than HTTP and HTTPS ports",
+				));
+			}
+		}
+
+		if let Some(logging) = &self.logging {
+			if logging.max_file_size_kib == Some(0) {
+				errors.push(String::from(
+					"maximum size of log files should be greater than 0",
+				));
+			}
+			if logging.max_file_age_hours == Some(0) {
+				errors.push(String::from(
+					"maximum age of log files should be greater than 0",
+				));
+			}
+		}
+
+		match &self.auth_backend {
+			Some(SettingsAuthBackend::Htpasswd { path }) => {
+				if path.trim().is_empty() {
+					errors.push(String::from("htpasswd file path should not be empty"));
+				}
+			}
+			Some(SettingsAuthBackend::Ldap(ldap)) => {
+				if !["ldap://", "ldaps://", "ldapi://"]
+					.iter()
+					.any(|scheme| ldap.url.starts_with(scheme))
+				{
+					errors.push(format!("incorrect LDAP URL `{}`", ldap.url));
+				}
+				if !ldap.bind_dn.contains("{username}") {
+					errors.push(String::from("LDAP bind DN should contain `{username}`"));
+				}
+			}
+			Some(SettingsAuthBackend::UsersFile) | None => {}
+		}
+
+		if errors.is_empty() {
+			return Ok(());
+		} else {
+			return Err(errors);
+		}
+	}
 }
 
 #[derive(serde::Serialize, serde::Deserialize, Debug, Clone)]
@@ -165,6 +354,13 @@ pub struct SettingsHTTPS {
 	pub keyfile_path: String,
 	pub certfile_path: String,
 	pub enable_hsts: bool,
+	/// `/readyz` fails when the certificate expires in less than these days, in order to renew
+	/// it in time.
+	#[serde(default = "default_min_certificate_validity_days")]
+	pub min_certificate_validity_days: u64,
+	/// If set while `keyfile_path` and `certfile_path` are empty, a self-signed certificate for
+	/// `domain` is generated in the workspace.
+	pub self_signed: Option<SettingsSelfSigned>,
 }
 impl Default for SettingsHTTPS {
 	fn default() -> Self {
@@ -173,10 +369,192 @@ impl Default for SettingsHTTPS {
 			keyfile_path: String::new(),
 			certfile_path: String::new(),
 			enable_hsts: true,
+			min_certificate_validity_days: default_min_certificate_validity_days(),
+			self_signed: None,
 		}
 	}
 }
 
+/// Self-signed certificate of the workspace, for local networks and tests, see
+/// [`renew_self_signed_certificate`](crate::http_server::renew_self_signed_certificate).
+#[derive(serde::Serialize, serde::Deserialize, Debug, Clone, PartialEq, Eq)]
+#[serde(default)]
+pub struct SettingsSelfSigned {
+	/// Signs the certificate with a local certificate authority, which can be trusted by devices
+	/// once, instead of each new certificate.
+	pub local_ca: bool,
+	/// Validity of generated certificates, which are generated again when they expire in less than
+	/// `min_certificate_validity_days`.
+	pub validity_days: u64,
+}
+impl Default for SettingsSelfSigned {
+	fn default() -> Self {
+		Self {
+			local_ca: false,
+			validity_days: 90,
+		}
+	}
+}
+
+/// Limits of failed logins on the OAuth form, see [`LoginAttempts`](crate::http_server::LoginAttempts).
+#[derive(serde::Serialize, serde::Deserialize, Debug, Clone)]
+#[serde(default)]
+pub struct SettingsOauthThrottling {
+	/// Count of failed logins before slowing down next attempts.
+	pub free_attempts: u32,
+	/// Delay after the first slowed down attempt, doubled after each new failure.
+	pub backoff_base_seconds: u64,
+	pub backoff_max_seconds: u64,
+	/// Count of failed logins of an username before locking its account.
+	pub lockout_failures: u32,
+	pub lockout_seconds: u64,
+}
+impl Default for SettingsOauthThrottling {
+	fn default() -> Self {
+		Self {
+			free_attempts: 3,
+			backoff_base_seconds: 1,
+			backoff_max_seconds: 5 * 60,
+			lockout_failures: 10,
+			lockout_seconds: 15 * 60,
+		}
+	}
+}
+
+/// Cost parameters of Argon2id, used to hash passwords of users.
+#[derive(serde::Serialize, serde::Deserialize, Debug, Clone, PartialEq, Eq)]
+#[serde(default)]
+pub struct SettingsPasswordHashing {
+	pub memory_kib: u32,
+	pub iterations: u32,
+	pub parallelism: u32,
+}
+impl Default for SettingsPasswordHashing {
+	fn default() -> Self {
+		// recommended by OWASP
+		Self {
+			memory_kib: 19 * 1024,
+			iterations: 2,
+			parallelism: 1,
+		}
+	}
+}
+
+/// Where passwords of users are checked, see [`AuthBackend`](crate::http_server::AuthBackend).
+#[derive(serde::Serialize, serde::Deserialize, Debug, Clone, PartialEq, Eq, Default)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum SettingsAuthBackend {
+	/// The users file of the workspace.
+	#[default]
+	UsersFile,
+	/// Apache-style file, like created by `htpasswd -B`.
+	Htpasswd {
+		path: String,
+	},
+	Ldap(SettingsLdap),
+}
+
+/// Simple bind on a LDAP server, with the password of the user.
+#[derive(serde::Serialize, serde::Deserialize, Debug, Clone, PartialEq, Eq)]
+pub struct SettingsLdap {
+	/// Like `ldaps://ldap.example.com` or `ldap://localhost:389`.
+	pub url: String,
+	/// DN of users, where `{username}` is replaced, like `uid={username},ou=people,dc=example,dc=com`.
+	pub bind_dn: String,
+	#[serde(default)]
+	pub starttls: bool,
+	#[serde(default = "default_ldap_timeout_seconds")]
+	pub timeout_seconds: u64,
+}
+
+/// Cross-origin requests from browsers, see [`Cors`](crate::http_server::middlewares::Cors).
+#[derive(serde::Serialize, serde::Deserialize, Debug, Clone, PartialEq, Eq)]
+#[serde(default)]
+pub struct SettingsCors {
+	/// Origins of applications (like `https://app.example.com`) which can use this server, or
+	/// `*` for all of them, as expected by remoteStorage applications.
+	pub allowed_origins: Vec<String>,
+	/// How long browsers can cache answers to preflight requests.
+	pub max_age_seconds: u64,
+}
+impl Default for SettingsCors {
+	fn default() -> Self {
+		Self {
+			allowed_origins: vec![String::from("*")],
+			max_age_seconds: 60 * 60,
+		}
+	}
+}
+impl SettingsCors {
+	pub fn allows_all_origins(&self) -> bool {
+		self.allowed_origins.iter().any(|allowed| allowed == "*")
+	}
+
+	pub fn allows(&self, origin: &str) -> bool {
+		if self.allows_all_origins() {
+			return true;
+		}
+
+		let origin = crate::http_server::utils::origin_of(origin);
+
+		return origin.is_some()
+			&& self
+				.allowed_origins
+				.iter()
+				.any(|allowed| crate::http_server::utils::origin_of(allowed) == origin);
+	}
+}
+
+/// Metrics of the server in the text format of Prometheus, see
+/// [`Metrics`](crate::http_server::Metrics).
+#[derive(serde::Serialize, serde::Deserialize, Debug, Clone, PartialEq, Eq, Default)]
+#[serde(default)]
+pub struct SettingsMetrics {
+	/// If set, `/metrics` is only served on this port of `127.0.0.1`, instead of the ports of the
+	/// API, in order to keep it away from the Internet.
+	pub admin_port: Option<usize>,
+	/// Serves `/metrics` without access token on the ports of the API when `admin_port` is not
+	/// set, for servers which are not reachable from the Internet.
+	pub public: bool,
+}
+
+/// Outputs of logs, see [`load_or_create_logger`](crate::http_server::load_or_create_logger).
+#[derive(serde::Serialize, serde::Deserialize, Debug, Clone, PartialEq, Eq)]
+#[serde(default)]
+pub struct SettingsLogging {
+	/// The `logs.msgpack` file of the workspace.
+	pub msgpack_file: bool,
+	/// Events of `tracing`, for the subscriber of the program which embeds this library, of logs
+	/// pushed in the logger (logs of [`logging::log`](crate::http_server::logging::log) are always
+	/// sent as events, which subscribers can filter).
+	pub tracing: bool,
+	/// The `logs.msgpack` file is rotated when it would be bigger than this size.
+	pub max_file_size_kib: Option<u64>,
+	/// The `logs.msgpack` file is rotated when its first log is older than this age.
+	pub max_file_age_hours: Option<u64>,
+	/// Count of rotated files to keep, the older ones are removed.
+	pub retained_files: Option<usize>,
+}
+impl Default for SettingsLogging {
+	fn default() -> Self {
+		Self {
+			msgpack_file: true,
+			tracing: true,
+			max_file_size_kib: Some(10 * 1024),
+			max_file_age_hours: None,
+			retained_files: Some(10),
+		}
+	}
+}
+
+fn default_min_certificate_validity_days() -> u64 {
+	7
+}
+
+fn default_ldap_timeout_seconds() -> u64 {
+	5
+}
+
 fn random_port_generation() -> usize {
 	let mut rng = rand::thread_rng();
 
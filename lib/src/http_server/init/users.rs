@@ -6,13 +6,30 @@ pub fn load_or_create_users(
 ) -> crate::http_server::Users {
 	let users_path = settings.userfile_path();
 
+	let password_hashing = settings.password_hashing.clone().unwrap_or_default();
+
 	let users = {
-		let userlist = match std::fs::read(&users_path) {
-			Ok(bytes) => match bincode::deserialize::<crate::http_server::Users>(&bytes) {
-				Ok(users) => Ok(users),
-				Err(e) => Err(format!("can not parse users file : {}", e)),
-			},
-			Err(e) => Err(format!("can not read users file : {}", e)),
+		let userlist = match crate::http_server::Users::load(&users_path) {
+			Ok(mut users) => {
+				users.set_password_hashing(password_hashing.clone());
+				Ok(users)
+			}
+			Err(e) if e.kind() == std::io::ErrorKind::NotFound => {
+				Err(format!("can not read users file : {}", e))
+			}
+			Err(e) => {
+				// creating a new users list would overwrite the existing one
+				logger.lock().unwrap().push(
+					vec![
+						(String::from("event"), String::from("setup")),
+						(String::from("module"), String::from("users_list")),
+						(String::from("level"), String::from("ERROR")),
+					],
+					Some(&format!("{e}, please fix or remove it")),
+				);
+
+				panic!("{}", e);
+			}
 		};
 
 		match userlist {
@@ -98,6 +115,7 @@ pub fn load_or_create_users(
 				}
 
 				let mut users = crate::http_server::Users::new();
+				users.set_password_hashing(password_hashing);
 				if let Err(e) = users.insert(&admin_username, &mut admin_password) {
 					logger.lock().unwrap().push(
 						vec![
@@ -160,7 +178,7 @@ pub fn load_or_create_users(
 						);
 					}
 				}
-				if let Err(e) = std::fs::write(users_path, bincode::serialize(&users).unwrap()) {
+				if let Err(e) = users.save(&users_path) {
 					logger.lock().unwrap().push(
 						vec![
 							(String::from("event"), String::from("setup")),
@@ -0,0 +1,404 @@
+use std::io::Write;
+
+/// Bytes before each log in log files.
+const RECORD_SEPARATOR: [u8; 2] = [0, 30];
+
+/// The `logs.msgpack` file of the workspace, which is renamed (like
+/// `logs.20261019T120000.000000.msgpack`) when it is too big or too old, see
+/// [`SettingsLogging`](crate::http_server::SettingsLogging).
+pub struct RotatingLogFile {
+	path: std::path::PathBuf,
+	max_size_bytes: Option<u64>,
+	max_age: Option<std::time::Duration>,
+	retained_files: Option<usize>,
+	/// When the first log of the current file has been written.
+	started_at: Option<std::time::SystemTime>,
+}
+impl RotatingLogFile {
+	pub fn new(path: std::path::PathBuf, settings: &crate::http_server::SettingsLogging) -> Self {
+		let started_at = std::fs::metadata(&path)
+			.ok()
+			.map(|metadata| metadata.created().or_else(|_| metadata.modified()))
+			.and_then(Result::ok);
+
+		Self {
+			path,
+			max_size_bytes: settings.max_file_size_kib.map(|size| size * 1024),
+			max_age: settings
+				.max_file_age_hours
+				.map(|hours| std::time::Duration::from_secs(hours * 60 * 60)),
+			retained_files: settings.retained_files,
+			started_at,
+		}
+	}
+
+	pub fn write(&mut self, log: &charlie_buffalo::Log) -> Result<(), String> {
+		let mut record = RECORD_SEPARATOR.to_vec();
+		record.append(&mut rmp_serde::encode::to_vec(log).map_err(|e| e.to_string())?);
+
+		if self.should_rotate(record.len() as u64) {
+			self.rotate()?;
+		}
+
+		let mut file = std::fs::File::options()
+			.create(true)
+			.append(true)
+			.open(&self.path)
+			.map_err(|e| format!("can not open `{}` : {}", self.path.to_string_lossy(), e))?;
+		file.write_all(&record)
+			.and_then(|_| file.flush())
+			.map_err(|e| format!("can not write in `{}` : {}", self.path.to_string_lossy(), e))?;
+
+		if self.started_at.is_none() {
+			self.started_at = Some(std::time::SystemTime::now());
+		}
+
+		return Ok(());
+	}
+
+	fn should_rotate(&self, new_bytes: u64) -> bool {
+		let size = match std::fs::metadata(&self.path) {
+			Ok(metadata) => metadata.len(),
+			Err(_) => return false,
+		};
+		if size == 0 {
+			return false;
+		}
+
+		let is_too_big = self
+			.max_size_bytes
+			.map(|max_size| size + new_bytes > max_size)
+			.unwrap_or(false);
+		let is_too_old = match (self.max_age, self.started_at) {
+			(Some(max_age), Some(started_at)) => started_at
+				.elapsed()
+				.map(|age| age >= max_age)
+				.unwrap_or(false),
+			_ => false,
+		};
+
+		return is_too_big || is_too_old;
+	}
+
+	fn rotate(&mut self) -> Result<(), String> {
+		let format = time::format_description::parse_borrowed::<2>(
+			"[year][month][day]T[hour][minute][second].[subsecond digits:6]",
+		)
+		.map_err(|e| e.to_string())?;
+		let suffix = time::OffsetDateTime::now_utc()
+			.format(&format)
+			.map_err(|e| e.to_string())?;
+		let rotated_path =
+			self.path
+				.with_file_name(format!("{}.{}.msgpack", file_stem(&self.path), suffix));
+
+		std::fs::rename(&self.path, &rotated_path).map_err(|e| {
+			format!(
+				"can not rename `{}` to `{}` : {}",
+				self.path.to_string_lossy(),
+				rotated_path.to_string_lossy(),
+				e
+			)
+		})?;
+		self.started_at = None;
+
+		if let Some(retained_files) = self.retained_files {
+			let rotated_files = log_files(&self.path);
+			// the last one is the current file, which does not exist anymore
+			let rotated_files = &rotated_files[..rotated_files.len() - 1];
+
+			for old_file in rotated_files
+				.iter()
+				.take(rotated_files.len().saturating_sub(retained_files))
+			{
+				std::fs::remove_file(old_file).map_err(|e| {
+					format!("can not remove `{}` : {}", old_file.to_string_lossy(), e)
+				})?;
+			}
+		}
+
+		return Ok(());
+	}
+}
+
+fn file_stem(path: &std::path::Path) -> String {
+	return path
+		.file_stem()
+		.map(|stem| stem.to_string_lossy().to_string())
+		.unwrap_or_default();
+}
+
+/// Rotated files of this log file, from the oldest to the newest, then this log file.
+pub fn log_files(path: &std::path::Path) -> Vec<std::path::PathBuf> {
+	let prefix = format!("{}.", file_stem(path));
+
+	let mut result: Vec<std::path::PathBuf> = path
+		.parent()
+		.and_then(|parent| std::fs::read_dir(parent).ok())
+		.map(|entries| {
+			entries
+				.filter_map(Result::ok)
+				.map(|entry| entry.path())
+				.filter(|entry_path| {
+					let name = entry_path
+						.file_name()
+						.map(|name| name.to_string_lossy().to_string())
+						.unwrap_or_default();
+
+					name.starts_with(&prefix)
+						&& name.ends_with(".msgpack")
+						&& entry_path.as_path() != path
+				})
+				.collect()
+		})
+		.unwrap_or_default();
+	// suffixes are dates, so the alphabetical order is the chronological one
+	result.sort();
+
+	result.push(path.to_path_buf());
+
+	return result;
+}
+
+/// Logs of these bytes of a log file, and the count of read bytes : the last log can be
+/// incomplete if it is being written.
+pub fn decode_logs(bytes: &[u8]) -> (Vec<charlie_buffalo::Log>, usize) {
+	let mut logs = vec![];
+	let mut position = 0;
+
+	while bytes.len() > position + RECORD_SEPARATOR.len()
+		&& bytes[position..].starts_with(&RECORD_SEPARATOR)
+	{
+		let mut cursor = std::io::Cursor::new(&bytes[position + RECORD_SEPARATOR.len()..]);
+
+		match rmp_serde::decode::from_read::<_, charlie_buffalo::Log>(&mut cursor) {
+			Ok(log) => {
+				logs.push(log);
+				position += RECORD_SEPARATOR.len() + cursor.position() as usize;
+			}
+			Err(_) => break,
+		}
+	}
+
+	return (logs, position);
+}
+
+/// Filters of logs, where `None` accepts all of them.
+#[derive(Debug, Default, Clone)]
+pub struct LogFilter {
+	/// Logs of this level or a more severe one, where logs without level are `INFO` ones.
+	pub level: Option<String>,
+	pub event: Option<String>,
+	pub module: Option<String>,
+	pub since: Option<time::OffsetDateTime>,
+	pub until: Option<time::OffsetDateTime>,
+	pub client_ip: Option<String>,
+}
+impl LogFilter {
+	pub fn matches(&self, log: &charlie_buffalo::Log) -> bool {
+		let attribute = |key: &str| log.attributes.get(key).map(|value| value.as_str());
+
+		if let Some(level) = &self.level {
+			if severity(attribute("level").unwrap_or("INFO")) < severity(level) {
+				return false;
+			}
+		}
+
+		for (key, expected) in [("event", &self.event), ("module", &self.module)] {
+			if let Some(expected) = expected {
+				if !attribute(key)
+					.map(|value| value.eq_ignore_ascii_case(expected))
+					.unwrap_or(false)
+				{
+					return false;
+				}
+			}
+		}
+
+		if let Some(client_ip) = &self.client_ip {
+			if attribute("client_ip") != Some(client_ip.as_str()) {
+				return false;
+			}
+		}
+
+		if self.since.is_some() || self.until.is_some() {
+			let time = match attribute("time").and_then(parse_log_time) {
+				Some(time) => time,
+				None => return false,
+			};
+
+			if self.since.map(|since| time < since).unwrap_or(false)
+				|| self.until.map(|until| time > until).unwrap_or(false)
+			{
+				return false;
+			}
+		}
+
+		return true;
+	}
+}
+
+fn severity(level: &str) -> u8 {
+	match level.trim().to_uppercase().as_str() {
+		"DEBUG" => 0,
+		"WARNING" => 2,
+		"ERROR" => 3,
+		_ => 1,
+	}
+}
+
+/// Parses the `time` attribute of logs, like `"2026-10-19 12:00:00.0 +00:00:00"` (with quotes,
+/// added by [`charlie_buffalo::Attr`]), or a RFC 3339 date.
+pub fn parse_log_time(value: &str) -> Option<time::OffsetDateTime> {
+	let value = value.trim().trim_matches('"');
+
+	let display_format = time::format_description::parse_borrowed::<2>(
+		"[year]-[month]-[day] [hour]:[minute]:[second].[subsecond] [offset_hour sign:mandatory]:[offset_minute]:[offset_second]",
+	)
+	.ok()?;
+
+	return time::OffsetDateTime::parse(value, &display_format)
+		.or_else(|_| {
+			time::OffsetDateTime::parse(value, &time::format_description::well_known::Rfc3339)
+		})
+		.ok();
+}
+
+#[test]
+fn k7bq2mw9xft4zhc() {
+	let tempdir = tempfile::tempdir().unwrap();
+	let path = tempdir.path().join("logs.msgpack");
+
+	let mut file = RotatingLogFile::new(
+		path.clone(),
+		&crate::http_server::SettingsLogging {
+			max_file_size_kib: Some(1),
+			max_file_age_hours: None,
+			retained_files: Some(2),
+			..Default::default()
+		},
+	);
+
+	for i in 0..100 {
+		file.write(&charlie_buffalo::Log::from((
+			vec![(String::from("event"), format!("event_{i:03}"))],
+			Some("0123456789".repeat(5).as_str()),
+		)))
+		.unwrap();
+	}
+
+	let files = log_files(&path);
+	assert_eq!(files.len(), 3);
+	assert_eq!(files.last(), Some(&path));
+
+	let mut events = vec![];
+	for file in &files {
+		let bytes = std::fs::read(file).unwrap();
+		assert!(bytes.len() <= 1024);
+
+		let (logs, read_bytes) = decode_logs(&bytes);
+		assert_eq!(read_bytes, bytes.len());
+		events.extend(logs.into_iter().map(|log| log.attributes["event"].clone()));
+	}
+	assert_eq!(events.last().unwrap(), "event_099");
+	assert!(events.windows(2).all(|pair| pair[0] < pair[1]));
+	assert!(!events.contains(&String::from("event_000")));
+
+	let bytes = std::fs::read(&path).unwrap();
+	let (logs, read_bytes) = decode_logs(&bytes[..bytes.len() - 1]);
+	assert_eq!(logs.len(), decode_logs(&bytes).0.len() - 1);
+	assert!(read_bytes < bytes.len());
+}
+
+#[test]
+fn s3fn8ck1vyr6pdw() {
+	let log = charlie_buffalo::Log::from((
+		vec![
+			(String::from("event"), String::from("http_access")),
+			(String::from("client_ip"), String::from("127.0.0.1")),
+			charlie_buffalo::Attr::new(
+				"time",
+				format!(
+					"{}",
+					time::OffsetDateTime::parse(
+						"2026-10-19T12:00:00.5Z",
+						&time::format_description::well_known::Rfc3339
+					)
+					.unwrap()
+				),
+			)
+			.into(),
+		],
+		None,
+	));
+
+	let tests = vec![
+		(010, LogFilter::default(), true),
+		(
+			020,
+			LogFilter {
+				level: Some(String::from("info")),
+				..Default::default()
+			},
+			true,
+		),
+		(
+			030,
+			LogFilter {
+				level: Some(String::from("WARNING")),
+				..Default::default()
+			},
+			false,
+		),
+		(
+			040,
+			LogFilter {
+				event: Some(String::from("HTTP_ACCESS")),
+				client_ip: Some(String::from("127.0.0.1")),
+				..Default::default()
+			},
+			true,
+		),
+		(
+			050,
+			LogFilter {
+				module: Some(String::from("https")),
+				..Default::default()
+			},
+			false,
+		),
+		(
+			060,
+			LogFilter {
+				client_ip: Some(String::from("127.0.0.2")),
+				..Default::default()
+			},
+			false,
+		),
+		(
+			070,
+			LogFilter {
+				since: parse_log_time("2026-10-19T12:00:00Z"),
+				until: parse_log_time("2026-10-19T14:00:00+01:00"),
+				..Default::default()
+			},
+			true,
+		),
+		(
+			080,
+			LogFilter {
+				since: parse_log_time("2026-10-19T12:00:01Z"),
+				..Default::default()
+			},
+			false,
+		),
+	];
+
+	for test in tests {
+		print!("#{:03} : filter logs with {:?} ... ", test.0, test.1);
+
+		assert_eq!(test.1.matches(&log), test.2);
+
+		println!("OK");
+	}
+}
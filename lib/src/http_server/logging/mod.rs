@@ -0,0 +1,147 @@
+use std::sync::{Arc, Mutex};
+
+mod file;
+
+pub use file::{decode_logs, log_files, parse_log_time, LogFilter, RotatingLogFile};
+
+/// Header of the id of each request, which is sent back in responses. It can be set by clients or
+/// reverse proxies, in order to follow a request across them.
+pub const REQUEST_ID_HEADER: &str = "x-request-id";
+
+/// Logger which only sends logs to the subscriber of `tracing`, for programs which embed this
+/// library (unlike [`load_or_create_logger`](crate::http_server::load_or_create_logger), which
+/// also writes them in the `logs.msgpack` file of the workspace).
+pub fn new_tracing_logger() -> Arc<Mutex<charlie_buffalo::Logger>> {
+	return charlie_buffalo::concurrent_logger_from(charlie_buffalo::Logger::new(
+		charlie_buffalo::new_dispatcher(Box::from(|log: charlie_buffalo::Log| {
+			if !log.attributes.contains_key(TRACED_FLAG) {
+				emit_tracing_event(&log);
+			}
+		})),
+		None,
+	));
+}
+
+/// Sends this log as an event of `tracing`, in the current span (like the one of the HTTP request,
+/// with its id).
+///
+/// Its `level` attribute is the level of the event, `event` and `module` attributes are fields of
+/// the event, and other ones are in its `attributes` field.
+pub fn emit_tracing_event(log: &charlie_buffalo::Log) {
+	let content = log.content.clone().unwrap_or_default();
+	if content.trim().to_uppercase() == "*CONSOLE_WHITESPACE*" {
+		return;
+	}
+
+	let empty = String::new();
+	let event = log.attributes.get("event").unwrap_or(&empty);
+	let module = log.attributes.get("module").unwrap_or(&empty);
+	let attributes = log
+		.attributes
+		.iter()
+		.filter(|(key, _)| !["event", "module", "level", "time"].contains(&key.as_str()))
+		.map(|(key, value)| {
+			if value.is_empty() {
+				key.clone()
+			} else {
+				format!("{key}={value:?}")
+			}
+		})
+		.collect::<Vec<String>>()
+		.join(" ");
+
+	// levels of events have to be known at compile time
+	macro_rules! emit {
+		($level:expr) => {
+			tracing::event!(
+				$level,
+				event = event.as_str(),
+				module = module.as_str(),
+				attributes = attributes.as_str(),
+				"{}",
+				content
+			)
+		};
+	}
+
+	match log
+		.attributes
+		.get("level")
+		.map(|level| level.trim().to_uppercase())
+		.as_deref()
+	{
+		Some("ERROR") => emit!(tracing::Level::ERROR),
+		Some("WARNING") => emit!(tracing::Level::WARN),
+		Some("DEBUG") => emit!(tracing::Level::DEBUG),
+		_ => emit!(tracing::Level::INFO),
+	}
+}
+
+/// Flag of logs which are already sent to `tracing` by [`log`], so loggers do not send them again.
+pub const TRACED_FLAG: &str = "TRACED";
+
+/// Level of a log, in its `level` attribute.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Level {
+	Debug,
+	Info,
+	Warning,
+	Error,
+}
+impl Level {
+	pub fn as_str(&self) -> &'static str {
+		match self {
+			Self::Debug => "DEBUG",
+			Self::Info => "INFO",
+			Self::Warning => "WARNING",
+			Self::Error => "ERROR",
+		}
+	}
+}
+
+/// Sends a log as an event of `tracing`, in the current span (like the one of the HTTP request),
+/// then to `logger`, which only writes it in its other outputs (console, `logs.msgpack` file).
+pub fn log(
+	logger: &Mutex<charlie_buffalo::Logger>,
+	level: Level,
+	event: &str,
+	attributes: &[(&str, &str)],
+	message: &str,
+) {
+	let mut log_attributes = vec![
+		(String::from("event"), String::from(event)),
+		(String::from("level"), String::from(level.as_str())),
+	];
+	for (key, value) in attributes {
+		log_attributes.push((String::from(*key), String::from(*value)));
+	}
+
+	emit_tracing_event(&charlie_buffalo::Log::from((
+		log_attributes.clone(),
+		Some(message),
+	)));
+
+	log_attributes.push(charlie_buffalo::Flag::from(TRACED_FLAG).into());
+	logger.lock().unwrap().push(log_attributes, Some(message));
+}
+
+/// Id of the request in its [`REQUEST_ID_HEADER`] header if it is a sane one, or a new one.
+pub fn request_id(service_request: &actix_web::dev::ServiceRequest) -> String {
+	return service_request
+		.headers()
+		.get(REQUEST_ID_HEADER)
+		.and_then(|value| value.to_str().ok())
+		.map(|value| value.trim())
+		.filter(|value| {
+			!value.is_empty()
+				&& value.len() <= 128
+				&& value
+					.chars()
+					.all(|c| c.is_ascii_alphanumeric() || ['-', '_', '.'].contains(&c))
+		})
+		.map(String::from)
+		.unwrap_or_else(|| ulid::Ulid::new().to_string());
+}
+
+#[cfg(test)]
+mod tests;
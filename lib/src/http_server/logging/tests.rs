@@ -0,0 +1,341 @@
+use std::collections::BTreeMap;
+use std::sync::{Arc, Mutex};
+
+/// Keeps events of `tracing`, with fields of their spans.
+#[derive(Clone, Default)]
+struct CaptureSubscriber {
+	spans: Arc<Mutex<Vec<BTreeMap<String, String>>>>,
+	entered_spans: Arc<Mutex<Vec<u64>>>,
+	events: Arc<Mutex<Vec<CapturedEvent>>>,
+}
+struct CapturedEvent {
+	level: tracing::Level,
+	fields: BTreeMap<String, String>,
+	span_fields: BTreeMap<String, String>,
+}
+
+struct FieldsVisitor<'a>(&'a mut BTreeMap<String, String>);
+impl tracing::field::Visit for FieldsVisitor<'_> {
+	fn record_str(&mut self, field: &tracing::field::Field, value: &str) {
+		self.0
+			.insert(String::from(field.name()), String::from(value));
+	}
+	fn record_debug(&mut self, field: &tracing::field::Field, value: &dyn std::fmt::Debug) {
+		self.0
+			.insert(String::from(field.name()), format!("{value:?}"));
+	}
+}
+
+impl tracing::Subscriber for CaptureSubscriber {
+	fn enabled(&self, _: &tracing::Metadata<'_>) -> bool {
+		true
+	}
+	fn new_span(&self, span: &tracing::span::Attributes<'_>) -> tracing::span::Id {
+		let mut fields = BTreeMap::new();
+		span.record(&mut FieldsVisitor(&mut fields));
+
+		let mut spans = self.spans.lock().unwrap();
+		spans.push(fields);
+		tracing::span::Id::from_u64(spans.len() as u64)
+	}
+	fn record(&self, _: &tracing::span::Id, _: &tracing::span::Record<'_>) {}
+	fn record_follows_from(&self, _: &tracing::span::Id, _: &tracing::span::Id) {}
+	fn event(&self, event: &tracing::Event<'_>) {
+		let mut fields = BTreeMap::new();
+		event.record(&mut FieldsVisitor(&mut fields));
+
+		let spans = self.spans.lock().unwrap();
+		let mut span_fields = BTreeMap::new();
+		for id in self.entered_spans.lock().unwrap().iter() {
+			span_fields.extend(spans[(*id - 1) as usize].clone());
+		}
+
+		self.events.lock().unwrap().push(CapturedEvent {
+			level: *event.metadata().level(),
+			fields,
+			span_fields,
+		});
+	}
+	fn enter(&self, span: &tracing::span::Id) {
+		self.entered_spans.lock().unwrap().push(span.into_u64());
+	}
+	fn exit(&self, _: &tracing::span::Id) {
+		self.entered_spans.lock().unwrap().pop();
+	}
+}
+
+#[test]
+fn w8dk3pz6qjm1hvt() {
+	let subscriber = CaptureSubscriber::default();
+
+	let tests = vec![
+		(
+			010,
+			Some("ERROR"),
+			Some("can not read file"),
+			Some(tracing::Level::ERROR),
+		),
+		(
+			020,
+			Some("WARNING"),
+			Some("can not read file"),
+			Some(tracing::Level::WARN),
+		),
+		(
+			030,
+			Some("INFO"),
+			Some("can not read file"),
+			Some(tracing::Level::INFO),
+		),
+		(
+			040,
+			Some("DEBUG"),
+			Some("can not read file"),
+			Some(tracing::Level::DEBUG),
+		),
+		(
+			050,
+			None,
+			Some("can not read file"),
+			Some(tracing::Level::INFO),
+		),
+		(060, Some("INFO"), Some("*CONSOLE_WHITESPACE*"), None),
+		(070, Some("ERROR"), None, Some(tracing::Level::ERROR)),
+	];
+
+	for test in tests {
+		print!(
+			"#{:03} : log with level {:?} and content {:?} ... ",
+			test.0, test.1, test.2
+		);
+
+		let mut attributes = vec![
+			(String::from("event"), String::from("setup")),
+			(String::from("module"), String::from("https")),
+			(String::from("username"), String::from("user")),
+			charlie_buffalo::Flag::from("STOP").into(),
+		];
+		if let Some(level) = test.1 {
+			attributes.push((String::from("level"), String::from(level)));
+		}
+		let log = charlie_buffalo::Log::from((attributes, test.2));
+
+		subscriber.events.lock().unwrap().clear();
+		tracing::subscriber::with_default(subscriber.clone(), || {
+			super::emit_tracing_event(&log);
+		});
+
+		let events = subscriber.events.lock().unwrap();
+		match test.3 {
+			Some(level) => {
+				assert_eq!(events.len(), 1);
+				assert_eq!(events[0].level, level);
+				assert_eq!(events[0].fields["event"], "setup");
+				assert_eq!(events[0].fields["module"], "https");
+				assert_eq!(events[0].fields["attributes"], "STOP username=\"user\"");
+				assert_eq!(events[0].fields["message"], test.2.unwrap_or_default());
+			}
+			None => assert!(events.is_empty()),
+		}
+
+		println!("OK");
+	}
+}
+
+#[test]
+fn q7hn3ve5ckz2wyd() {
+	let subscriber = CaptureSubscriber::default();
+
+	let logs = Arc::new(Mutex::new(vec![]));
+	let logs_for_dispatcher = logs.clone();
+	let logger = charlie_buffalo::concurrent_logger_from(charlie_buffalo::Logger::new(
+		charlie_buffalo::new_dispatcher(Box::from(move |log: charlie_buffalo::Log| {
+			logs_for_dispatcher.lock().unwrap().push(log);
+		})),
+		None,
+	));
+
+	tracing::subscriber::with_default(subscriber.clone(), || {
+		super::log(
+			&logger,
+			super::Level::Warning,
+			"account",
+			&[("username", "user")],
+			"password changed",
+		);
+		// the logger does not send it again to `tracing`
+		super::log(
+			&super::new_tracing_logger(),
+			super::Level::Info,
+			"reload",
+			&[],
+			"settings reloaded",
+		);
+	});
+
+	let events = subscriber.events.lock().unwrap();
+	assert_eq!(events.len(), 2);
+	assert_eq!(events[0].level, tracing::Level::WARN);
+	assert_eq!(events[0].fields["event"], "account");
+	assert_eq!(events[0].fields["attributes"], "username=\"user\"");
+	assert_eq!(events[0].fields["message"], "password changed");
+	assert_eq!(events[1].level, tracing::Level::INFO);
+	assert_eq!(events[1].fields["event"], "reload");
+
+	let logs = logs.lock().unwrap();
+	assert_eq!(logs.len(), 1);
+	assert_eq!(logs[0].attributes["event"], "account");
+	assert_eq!(logs[0].attributes["level"], "WARNING");
+	assert_eq!(logs[0].attributes["username"], "user");
+	assert!(logs[0].attributes.contains_key(super::TRACED_FLAG));
+	assert_eq!(logs[0].content.as_deref(), Some("password changed"));
+}
+
+#[actix_web::get("/log")]
+async fn log_in_handler(
+	logger: actix_web::web::Data<Arc<Mutex<charlie_buffalo::Logger>>>,
+) -> impl actix_web::Responder {
+	logger.lock().unwrap().push(
+		vec![
+			(String::from("event"), String::from("test")),
+			(String::from("level"), String::from("INFO")),
+		],
+		Some("inside handler"),
+	);
+
+	return actix_web::HttpResponse::Ok().finish();
+}
+
+#[actix_rt::test]
+async fn c4tj9xr2nvb7mpe() {
+	let subscriber = CaptureSubscriber::default();
+	let _guard = tracing::subscriber::set_default(subscriber.clone());
+
+	let logger = super::new_tracing_logger();
+
+	let app = actix_web::test::init_service(
+		actix_web::App::new()
+			.app_data(actix_web::web::Data::new(logger.clone()))
+			.wrap(crate::http_server::middlewares::Logger { logger })
+			.service(log_in_handler),
+	)
+	.await;
+
+	let tests = vec![
+		(010, None, None),
+		(
+			020,
+			Some("0af7651916cd43dd-8448eb211c80319c"),
+			Some("0af7651916cd43dd-8448eb211c80319c"),
+		),
+		(030, Some("with spaces"), None),
+		(040, Some(""), None),
+	];
+
+	for test in tests {
+		print!("#{:03} : request with id {:?} ... ", test.0, test.1);
+
+		subscriber.events.lock().unwrap().clear();
+
+		let mut request = actix_web::test::TestRequest::get().uri("/log");
+		if let Some(request_id) = test.1 {
+			request = request.insert_header((super::REQUEST_ID_HEADER, request_id));
+		}
+		let response = actix_web::test::call_service(&app, request.to_request()).await;
+		assert_eq!(response.status(), actix_web::http::StatusCode::OK);
+
+		let request_id = response
+			.headers()
+			.get(super::REQUEST_ID_HEADER)
+			.unwrap()
+			.to_str()
+			.unwrap();
+		match test.2 {
+			Some(expected) => assert_eq!(request_id, expected),
+			None => assert!(ulid::Ulid::from_string(request_id).is_ok()),
+		}
+
+		let events = subscriber.events.lock().unwrap();
+
+		let handler_event = events
+			.iter()
+			.find(|event| event.fields["event"] == "test")
+			.unwrap();
+		assert_eq!(handler_event.fields["message"], "inside handler");
+		assert_eq!(handler_event.span_fields["request_id"], request_id);
+		assert_eq!(handler_event.span_fields["path"], "/log");
+
+		let access_event = events
+			.iter()
+			.find(|event| event.fields["event"] == "http_access")
+			.unwrap();
+		assert_eq!(access_event.span_fields["request_id"], request_id);
+		assert!(access_event.fields["attributes"].contains(&format!("request_id={request_id:?}")));
+
+		println!("OK");
+	}
+}
+
+#[actix_web::get("/cookie")]
+async fn set_cookie_in_handler() -> impl actix_web::Responder {
+	return actix_web::HttpResponse::Ok()
+		.cookie(actix_web::cookie::Cookie::new("session", "response_secret"))
+		.finish();
+}
+
+#[actix_rt::test]
+async fn k2wp7dx4rcm9vzq() {
+	let subscriber = CaptureSubscriber::default();
+	let _guard = tracing::subscriber::set_default(subscriber.clone());
+
+	let app = actix_web::test::init_service(
+		actix_web::App::new()
+			.wrap(crate::http_server::middlewares::Logger {
+				logger: super::new_tracing_logger(),
+			})
+			.service(set_cookie_in_handler),
+	)
+	.await;
+
+	let request = actix_web::test::TestRequest::get()
+		.uri("/cookie")
+		.insert_header((
+			actix_web::http::header::AUTHORIZATION,
+			"Basic YWRtaW46cmVxdWVzdF9zZWNyZXQ=",
+		))
+		.insert_header((actix_web::http::header::COOKIE, "session=request_secret"))
+		.insert_header((actix_web::http::header::ACCEPT, "text/plain"))
+		.to_request();
+	let response = actix_web::test::call_service(&app, request).await;
+	assert_eq!(response.status(), actix_web::http::StatusCode::OK);
+
+	let events = subscriber.events.lock().unwrap();
+	let attributes = &events
+		.iter()
+		.find(|event| event.fields["event"] == "http_access")
+		.unwrap()
+		.fields["attributes"];
+
+	assert!(
+		!attributes.contains("YWRtaW46cmVxdWVzdF9zZWNyZXQ="),
+		"{attributes}"
+	);
+	assert!(!attributes.contains("request_secret"), "{attributes}");
+	assert!(!attributes.contains("response_secret"), "{attributes}");
+	assert!(
+		attributes.contains("request_header:authorization=\"*REDACTED*\""),
+		"{attributes}"
+	);
+	assert!(
+		attributes.contains("request_header:cookie=\"*REDACTED*\""),
+		"{attributes}"
+	);
+	assert!(
+		attributes.contains("response_header:set-cookie=\"*REDACTED*\""),
+		"{attributes}"
+	);
+	assert!(
+		attributes.contains("request_header:accept=\"text/plain\""),
+		"{attributes}"
+	);
+}
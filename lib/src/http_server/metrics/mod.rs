@@ -0,0 +1,350 @@
+use std::sync::{Arc, Mutex};
+
+/// Upper bounds (in seconds) of buckets of durations.
+const DURATION_BUCKETS: [f64; 11] = [
+	0.005, 0.01, 0.025, 0.05, 0.1, 0.25, 0.5, 1.0, 2.5, 5.0, 10.0,
+];
+
+/// Counters of the activity of the server, served in the text format of Prometheus by
+/// [`get_metrics`].
+///
+/// HTTP requests are recorded by the [`Logger`](crate::http_server::middlewares::Logger)
+/// middleware, and calls to the database by [`MeasuredSource`].
+#[derive(Debug, Default)]
+pub struct Metrics {
+	/// By method (see [`method_label`]) and status.
+	requests: std::collections::BTreeMap<(&'static str, u16), Histogram>,
+	request_bytes: u64,
+	response_bytes: u64,
+	oauth_login_successes: u64,
+	oauth_login_failures: u64,
+	/// By function of [`DataSource`](crate::database::sources::DataSource).
+	database_operations: std::collections::BTreeMap<&'static str, Histogram>,
+	events_queued: u64,
+	events_written: u64,
+}
+impl Metrics {
+	pub fn new() -> Self {
+		Self::default()
+	}
+
+	pub fn record_request(
+		&mut self,
+		method: &actix_web::http::Method,
+		status: actix_web::http::StatusCode,
+		duration: std::time::Duration,
+		request_bytes: u64,
+		response_bytes: u64,
+	) {
+		self.requests
+			.entry((method_label(method), status.as_u16()))
+			.or_default()
+			.observe(duration);
+		self.request_bytes += request_bytes;
+		self.response_bytes += response_bytes;
+	}
+
+	pub fn record_oauth_login(&mut self, is_success: bool) {
+		if is_success {
+			self.oauth_login_successes += 1;
+		} else {
+			self.oauth_login_failures += 1;
+		}
+	}
+
+	pub fn record_database_operation(
+		&mut self,
+		operation: &'static str,
+		duration: std::time::Duration,
+	) {
+		self.database_operations
+			.entry(operation)
+			.or_default()
+			.observe(duration);
+	}
+
+	/// When a [`DbEvent`](crate::http_server::DbEvent) is sent to the thread which saves them.
+	pub fn record_event_queued(&mut self) {
+		self.events_queued += 1;
+	}
+
+	/// When a [`DbEvent`](crate::http_server::DbEvent) is saved in the `events.bin` file.
+	pub fn record_event_written(&mut self) {
+		self.events_written += 1;
+	}
+
+	/// Text format of Prometheus, version 0.0.4.
+	pub fn render(&self, active_tokens: usize) -> String {
+		let mut result = String::new();
+
+		write_header(
+			&mut result,
+			"pontus_onyx_http_requests_total",
+			"counter",
+			"HTTP requests, by method and status.",
+		);
+		for ((method, status), histogram) in &self.requests {
+			result += &format!(
+				"pontus_onyx_http_requests_total{{method=\"{}\",status=\"{status}\"}} {}\n",
+				escape_label(method),
+				histogram.count
+			);
+		}
+
+		write_header(
+			&mut result,
+			"pontus_onyx_http_request_duration_seconds",
+			"histogram",
+			"Time to answer HTTP requests, by method and status.",
+		);
+		for ((method, status), histogram) in &self.requests {
+			histogram.write(
+				&mut result,
+				"pontus_onyx_http_request_duration_seconds",
+				&format!("method=\"{}\",status=\"{status}\"", escape_label(method)),
+			);
+		}
+
+		write_header(
+			&mut result,
+			"pontus_onyx_http_request_bytes_total",
+			"counter",
+			"Bytes received in bodies of HTTP requests.",
+		);
+		result += &format!(
+			"pontus_onyx_http_request_bytes_total {}\n",
+			self.request_bytes
+		);
+
+		write_header(
+			&mut result,
+			"pontus_onyx_http_response_bytes_total",
+			"counter",
+			"Bytes sent in bodies of HTTP responses.",
+		);
+		result += &format!(
+			"pontus_onyx_http_response_bytes_total {}\n",
+			self.response_bytes
+		);
+
+		write_header(
+			&mut result,
+			"pontus_onyx_access_tokens",
+			"gauge",
+			"Access tokens which are not expired.",
+		);
+		result += &format!("pontus_onyx_access_tokens {active_tokens}\n");
+
+		write_header(
+			&mut result,
+			"pontus_onyx_oauth_logins_total",
+			"counter",
+			"Logins on the OAuth form, by result.",
+		);
+		result += &format!(
+			"pontus_onyx_oauth_logins_total{{result=\"success\"}} {}\n",
+			self.oauth_login_successes
+		);
+		result += &format!(
+			"pontus_onyx_oauth_logins_total{{result=\"failure\"}} {}\n",
+			self.oauth_login_failures
+		);
+
+		write_header(
+			&mut result,
+			"pontus_onyx_database_operation_duration_seconds",
+			"histogram",
+			"Time of operations on the database, by operation.",
+		);
+		for (operation, histogram) in &self.database_operations {
+			histogram.write(
+				&mut result,
+				"pontus_onyx_database_operation_duration_seconds",
+				&format!("operation=\"{operation}\""),
+			);
+		}
+
+		write_header(
+			&mut result,
+			"pontus_onyx_events_queue_depth",
+			"gauge",
+			"Events of the database which are not saved yet.",
+		);
+		result += &format!(
+			"pontus_onyx_events_queue_depth {}\n",
+			self.events_queued.saturating_sub(self.events_written)
+		);
+
+		return result;
+	}
+}
+
+#[derive(Debug, Default, Clone)]
+struct Histogram {
+	/// Cumulative, like in Prometheus : each one also counts values of previous buckets.
+	buckets: [u64; DURATION_BUCKETS.len()],
+	sum: f64,
+	count: u64,
+}
+impl Histogram {
+	fn observe(&mut self, duration: std::time::Duration) {
+		let seconds = duration.as_secs_f64();
+
+		for (bucket, upper_bound) in self.buckets.iter_mut().zip(DURATION_BUCKETS) {
+			if seconds <= upper_bound {
+				*bucket += 1;
+			}
+		}
+		self.sum += seconds;
+		self.count += 1;
+	}
+
+	fn write(&self, result: &mut String, name: &str, labels: &str) {
+		for (bucket, upper_bound) in self.buckets.iter().zip(DURATION_BUCKETS) {
+			*result += &format!("{name}_bucket{{{labels},le=\"{upper_bound}\"}} {bucket}\n");
+		}
+		*result += &format!("{name}_bucket{{{labels},le=\"+Inf\"}} {}\n", self.count);
+		*result += &format!("{name}_sum{{{labels}}} {}\n", self.sum);
+		*result += &format!("{name}_count{{{labels}}} {}\n", self.count);
+	}
+}
+
+fn write_header(result: &mut String, name: &str, metric_type: &str, help: &str) {
+	*result += &format!("# HELP {name} {help}\n");
+	*result += &format!("# TYPE {name} {metric_type}\n");
+}
+
+fn escape_label(value: &str) -> String {
+	value
+		.replace('\\', "\\\\")
+		.replace('"', "\\\"")
+		.replace('\n', "\\n")
+}
+
+/// Records the time of each call to `source` in `metrics`.
+#[derive(Debug)]
+pub struct MeasuredSource {
+	pub source: Box<dyn crate::database::sources::DataSource>,
+	pub metrics: Arc<Mutex<Metrics>>,
+}
+impl crate::database::sources::DataSource for MeasuredSource {
+	fn get(
+		&self,
+		path: &crate::item::ItemPath,
+		if_match: &crate::item::Etag,
+		if_none_match: &[&crate::item::Etag],
+		get_content: bool,
+	) -> Result<crate::item::Item, Box<dyn std::error::Error>> {
+		let start = std::time::Instant::now();
+		let result = self.source.get(path, if_match, if_none_match, get_content);
+		self.metrics
+			.lock()
+			.unwrap()
+			.record_database_operation("get", start.elapsed());
+
+		return result;
+	}
+
+	fn put(
+		&mut self,
+		path: &crate::item::ItemPath,
+		if_match: &crate::item::Etag,
+		if_none_match: &[&crate::item::Etag],
+		new_item: crate::item::Item,
+	) -> crate::database::PutResult {
+		let start = std::time::Instant::now();
+		let result = self.source.put(path, if_match, if_none_match, new_item);
+		self.metrics
+			.lock()
+			.unwrap()
+			.record_database_operation("put", start.elapsed());
+
+		return result;
+	}
+
+	fn delete(
+		&mut self,
+		path: &crate::item::ItemPath,
+		if_match: &crate::item::Etag,
+	) -> Result<crate::item::Etag, Box<dyn std::error::Error>> {
+		let start = std::time::Instant::now();
+		let result = self.source.delete(path, if_match);
+		self.metrics
+			.lock()
+			.unwrap()
+			.record_database_operation("delete", start.elapsed());
+
+		return result;
+	}
+}
+
+#[actix_web::get("/metrics")]
+pub async fn get_metrics(
+	metrics: actix_web::web::Data<Arc<Mutex<Metrics>>>,
+	access_tokens: actix_web::web::Data<Arc<Mutex<Vec<crate::http_server::AccessBearer>>>>,
+	settings: actix_web::web::Data<Arc<Mutex<crate::http_server::Settings>>>,
+) -> impl actix_web::Responder {
+	let token_lifetime = std::time::Duration::from_secs(
+		settings
+			.lock()
+			.unwrap()
+			.token_lifetime_seconds
+			.unwrap_or_else(|| {
+				crate::http_server::Settings::new(std::path::PathBuf::from("."))
+					.token_lifetime_seconds
+					.unwrap()
+			}),
+	);
+	let active_tokens = access_tokens
+		.lock()
+		.unwrap()
+		.iter()
+		.filter(|token| token.get_emit_time().elapsed() < token_lifetime)
+		.count();
+
+	let body = metrics.lock().unwrap().render(active_tokens);
+
+	return actix_web::HttpResponse::Ok()
+		.insert_header((
+			actix_web::http::header::CONTENT_TYPE,
+			"text/plain; version=0.0.4; charset=utf-8",
+		))
+		.insert_header((actix_web::http::header::CACHE_CONTROL, "no-cache"))
+		.body(body);
+}
+
+/// Serves only [`get_metrics`], on the admin port of
+/// [`SettingsMetrics`](crate::http_server::SettingsMetrics).
+/// Clients can send any extension method, which would add as many series, so they are all
+/// counted as `OTHER`.
+fn method_label(method: &actix_web::http::Method) -> &'static str {
+	return match *method {
+		actix_web::http::Method::GET => "GET",
+		actix_web::http::Method::HEAD => "HEAD",
+		actix_web::http::Method::POST => "POST",
+		actix_web::http::Method::PUT => "PUT",
+		actix_web::http::Method::DELETE => "DELETE",
+		actix_web::http::Method::OPTIONS => "OPTIONS",
+		actix_web::http::Method::PATCH => "PATCH",
+		actix_web::http::Method::CONNECT => "CONNECT",
+		actix_web::http::Method::TRACE => "TRACE",
+		_ => "OTHER",
+	};
+}
+
+pub fn configure_metrics_server(
+	settings: Arc<Mutex<crate::http_server::Settings>>,
+	access_tokens: Arc<Mutex<Vec<crate::http_server::AccessBearer>>>,
+	metrics: Arc<Mutex<Metrics>>,
+) -> impl FnOnce(&mut actix_web::web::ServiceConfig) {
+	return move |config: &mut actix_web::web::ServiceConfig| {
+		config
+			.app_data(actix_web::web::Data::new(settings))
+			.app_data(actix_web::web::Data::new(access_tokens))
+			.app_data(actix_web::web::Data::new(metrics))
+			.service(get_metrics);
+	};
+}
+
+#[cfg(test)]
+mod tests;
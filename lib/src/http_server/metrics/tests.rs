@@ -0,0 +1,151 @@
+use std::sync::{Arc, Mutex};
+
+#[test]
+fn m9tc4wq2hzx7dkf() {
+	let metrics = Arc::new(Mutex::new(super::Metrics::new()));
+
+	metrics.lock().unwrap().record_request(
+		&actix_web::http::Method::GET,
+		actix_web::http::StatusCode::OK,
+		std::time::Duration::from_millis(20),
+		0,
+		100,
+	);
+	metrics.lock().unwrap().record_request(
+		&actix_web::http::Method::PUT,
+		actix_web::http::StatusCode::CREATED,
+		std::time::Duration::from_secs(20),
+		50,
+		10,
+	);
+	for method in ["FOO1", "FOO2"] {
+		metrics.lock().unwrap().record_request(
+			&actix_web::http::Method::from_bytes(method.as_bytes()).unwrap(),
+			actix_web::http::StatusCode::METHOD_NOT_ALLOWED,
+			std::time::Duration::from_millis(1),
+			0,
+			0,
+		);
+	}
+	metrics.lock().unwrap().record_oauth_login(true);
+	metrics.lock().unwrap().record_oauth_login(false);
+	metrics.lock().unwrap().record_oauth_login(false);
+	metrics.lock().unwrap().record_event_queued();
+	metrics.lock().unwrap().record_event_queued();
+	metrics.lock().unwrap().record_event_written();
+
+	let mut database = crate::database::Database::new(Box::new(super::MeasuredSource {
+		source: Box::new(crate::database::sources::MemoryStorage {
+			root_item: crate::item::Item::new_folder(vec![]),
+		}),
+		metrics: metrics.clone(),
+	}));
+	database
+		.put(
+			&crate::item::ItemPath::from("user/a/document"),
+			crate::item::Item::new_doc(b"HELLO", "text/plain"),
+			&crate::item::Etag::from(""),
+			&[],
+		)
+		.unwrap();
+	database
+		.get(
+			&crate::item::ItemPath::from("user/a/document"),
+			&crate::item::Etag::from(""),
+			&[],
+		)
+		.unwrap();
+
+	let result = metrics.lock().unwrap().render(4);
+
+	for expected in [
+		"# TYPE pontus_onyx_http_requests_total counter\n",
+		"pontus_onyx_http_requests_total{method=\"GET\",status=\"200\"} 1\n",
+		"pontus_onyx_http_requests_total{method=\"PUT\",status=\"201\"} 1\n",
+		"# TYPE pontus_onyx_http_request_duration_seconds histogram\n",
+		"pontus_onyx_http_request_duration_seconds_bucket{method=\"GET\",status=\"200\",le=\"0.01\"} 0\n",
+		"pontus_onyx_http_request_duration_seconds_bucket{method=\"GET\",status=\"200\",le=\"0.025\"} 1\n",
+		"pontus_onyx_http_request_duration_seconds_bucket{method=\"GET\",status=\"200\",le=\"10\"} 1\n",
+		"pontus_onyx_http_request_duration_seconds_bucket{method=\"PUT\",status=\"201\",le=\"10\"} 0\n",
+		"pontus_onyx_http_request_duration_seconds_bucket{method=\"PUT\",status=\"201\",le=\"+Inf\"} 1\n",
+		"pontus_onyx_http_request_duration_seconds_sum{method=\"PUT\",status=\"201\"} 20\n",
+		"pontus_onyx_http_request_duration_seconds_count{method=\"PUT\",status=\"201\"} 1\n",
+		"pontus_onyx_http_requests_total{method=\"OTHER\",status=\"405\"} 2\n",
+		"pontus_onyx_http_request_bytes_total 50\n",
+		"pontus_onyx_http_response_bytes_total 110\n",
+		"pontus_onyx_access_tokens 4\n",
+		"pontus_onyx_oauth_logins_total{result=\"success\"} 1\n",
+		"pontus_onyx_oauth_logins_total{result=\"failure\"} 2\n",
+		"pontus_onyx_database_operation_duration_seconds_count{operation=\"get\"} 1\n",
+		"pontus_onyx_database_operation_duration_seconds_count{operation=\"put\"} 1\n",
+		"pontus_onyx_events_queue_depth 1\n",
+	] {
+		assert!(result.contains(expected), "`{expected}` not found in :\n{result}");
+	}
+	assert!(!result.contains("operation=\"delete\""));
+	assert!(!result.contains("FOO"));
+}
+
+#[actix_rt::test]
+async fn f5yr8nb3kqj1wvc() {
+	let mut settings = crate::http_server::Settings::new(std::path::PathBuf::from("."));
+	settings.token_lifetime_seconds = Some(60);
+	let settings = Arc::new(Mutex::new(settings));
+
+	let access_tokens = Arc::new(Mutex::new(vec![crate::http_server::AccessBearer::new(
+		vec![],
+		"test",
+		"",
+		"user",
+	)]));
+	let metrics = Arc::new(Mutex::new(super::Metrics::new()));
+
+	let logger = Arc::new(Mutex::new(charlie_buffalo::Logger::new(
+		charlie_buffalo::new_dispatcher(Box::new(|_| {})),
+		None,
+	)));
+
+	let app = actix_web::test::init_service(
+		actix_web::App::new()
+			.wrap(crate::http_server::middlewares::Logger { logger })
+			.app_data(actix_web::web::Data::new(settings.clone()))
+			.app_data(actix_web::web::Data::new(access_tokens.clone()))
+			.app_data(actix_web::web::Data::new(metrics.clone()))
+			.service(crate::http_server::get_favicon)
+			.service(super::get_metrics),
+	)
+	.await;
+
+	for _ in 0..2 {
+		let request = actix_web::test::TestRequest::get()
+			.uri("/favicon.ico")
+			.to_request();
+		let response = actix_web::test::call_service(&app, request).await;
+		assert_eq!(response.status(), actix_web::http::StatusCode::OK);
+	}
+	let request = actix_web::test::TestRequest::get()
+		.uri("/RANDOM")
+		.to_request();
+	let response = actix_web::test::call_service(&app, request).await;
+	assert_eq!(response.status(), actix_web::http::StatusCode::NOT_FOUND);
+
+	let request = actix_web::test::TestRequest::get()
+		.uri("/metrics")
+		.to_request();
+	let response = actix_web::test::call_service(&app, request).await;
+	assert_eq!(response.status(), actix_web::http::StatusCode::OK);
+	assert_eq!(
+		response
+			.headers()
+			.get(actix_web::http::header::CONTENT_TYPE)
+			.unwrap(),
+		"text/plain; version=0.0.4; charset=utf-8"
+	);
+
+	let body = actix_web::test::read_body(response).await;
+	let body = String::from_utf8_lossy(&body);
+	assert!(body.contains("pontus_onyx_http_requests_total{method=\"GET\",status=\"200\"} 2\n"));
+	assert!(body.contains("pontus_onyx_http_requests_total{method=\"GET\",status=\"404\"} 1\n"));
+	assert!(body.contains("pontus_onyx_access_tokens 1\n"));
+	assert!(!body.contains("pontus_onyx_http_response_bytes_total 0\n"));
+}
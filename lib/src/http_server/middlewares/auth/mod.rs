@@ -57,14 +57,40 @@ where
 	fn call(&self, service_request: actix_web::dev::ServiceRequest) -> Self::Future {
 		let request_method = service_request.method().clone();
 
-		match service_request.head().headers().get("Authorization") {
-			Some(auth_value) => {
-				let search_token = auth_value
-					.to_str()
-					.unwrap()
-					.strip_prefix("Bearer ")
-					.unwrap()
-					.trim();
+		if service_request.path().starts_with("/admin/") {
+			// admin API checks credentials of users by itself
+			let future = self.service.call(service_request);
+			return Box::pin(future);
+		}
+
+		if service_request.path() == "/healthz" || service_request.path() == "/readyz" {
+			// probes of orchestrators do not have any token
+			let future = self.service.call(service_request);
+			return Box::pin(future);
+		}
+
+		let (bearer, is_query_token) = match service_request.head().headers().get("Authorization") {
+			Some(auth_value) => (
+				Some(String::from(
+					auth_value
+						.to_str()
+						.unwrap_or_default()
+						.strip_prefix("Bearer ")
+						.unwrap_or_default()
+						.trim(),
+				)),
+				false,
+			),
+			None => {
+				let query_token = query_access_token(&service_request);
+				let is_query_token = query_token.is_some();
+				(query_token, is_query_token)
+			}
+		};
+
+		match bearer {
+			Some(search_token) => {
+				let search_token = search_token.as_str();
 				let tokens = service_request
 					.app_data::<actix_web::web::Data<
 						std::sync::Arc<std::sync::Mutex<Vec<crate::http_server::AccessBearer>>>,
@@ -93,11 +119,33 @@ where
 							}),
 							service_request.method(),
 							service_request.path(),
+							service_request
+								.headers()
+								.get(actix_web::http::header::ORIGIN)
+								.and_then(|origin| origin.to_str().ok()),
 						) {
 							Ok(allowed) => {
-								if allowed {
+								// OPTIONS requests answer with the methods allowed by the token,
+								// even if there is none apart from OPTIONS
+								if allowed
+									|| service_request.method() == actix_web::http::Method::OPTIONS
+								{
 									let future = self.service.call(service_request);
-									Box::pin(async move { future.await })
+									Box::pin(async move {
+										let mut response = future.await?;
+
+										// RFC 6750, section 2.3
+										if is_query_token && response.status().is_success() {
+											response.headers_mut().insert(
+												actix_web::http::header::CACHE_CONTROL,
+												actix_web::http::header::HeaderValue::from_static(
+													"private, no-cache",
+												),
+											);
+										}
+
+										Ok(response)
+									})
 								} else {
 									self.logger.lock().unwrap().push(
 										vec![
@@ -109,21 +157,9 @@ where
 									);
 
 									Box::pin(async move {
-										// TODO : check security issue about this ?
-										let all_origins =
-											actix_web::http::header::HeaderValue::from_bytes(b"*")
-												.unwrap();
-										let headers = service_request.headers().clone();
-										let origin = headers
-											.get(actix_web::http::header::ORIGIN)
-											.unwrap_or(&all_origins)
-											.to_str()
-											.unwrap();
-
 										Ok(actix_web::dev::ServiceResponse::new(
 											service_request.into_parts().0,
 											crate::database::build_http_json_response(
-												origin,
 												&request_method,
 												actix_web::http::StatusCode::FORBIDDEN,
 												None,
@@ -146,21 +182,9 @@ where
 								);
 
 								Box::pin(async move {
-									// TODO : check security issue about this ?
-									let all_origins =
-										actix_web::http::header::HeaderValue::from_bytes(b"*")
-											.unwrap();
-									let headers = service_request.headers().clone();
-									let origin = headers
-										.get(actix_web::http::header::ORIGIN)
-										.unwrap_or(&all_origins)
-										.to_str()
-										.unwrap();
-
 									Ok(actix_web::dev::ServiceResponse::new(
 										service_request.into_parts().0,
 										crate::database::build_http_json_response(
-											origin,
 											&request_method,
 											actix_web::http::StatusCode::FORBIDDEN,
 											None,
@@ -173,32 +197,19 @@ where
 							}
 						}
 					}
-					None => {
-						Box::pin(async move {
-							// TODO : check security issue about this ?
-							let all_origins =
-								actix_web::http::header::HeaderValue::from_bytes(b"*").unwrap();
-							let headers = service_request.headers().clone();
-							let origin = headers
-								.get(actix_web::http::header::ORIGIN)
-								.unwrap_or(&all_origins)
-								.to_str()
-								.unwrap();
-
-							Ok(actix_web::dev::ServiceResponse::new(
-								service_request.into_parts().0,
-								crate::database::build_http_json_response(
-									origin,
-									&request_method,
-									actix_web::http::StatusCode::UNAUTHORIZED,
-									None,
-									None,
-									None,
-									true,
-								),
-							))
-						})
-					}
+					None => Box::pin(async move {
+						Ok(actix_web::dev::ServiceResponse::new(
+							service_request.into_parts().0,
+							crate::database::build_http_json_response(
+								&request_method,
+								actix_web::http::StatusCode::UNAUTHORIZED,
+								None,
+								None,
+								None,
+								true,
+							),
+						))
+					}),
 				}
 			}
 			None => {
@@ -228,6 +239,12 @@ where
 				{
 					let future = self.service.call(service_request);
 					Box::pin(async move { future.await })
+				} else if service_request.path().starts_with("/account/")
+					&& (service_request.method() == actix_web::http::Method::GET
+						|| service_request.method() == actix_web::http::Method::POST)
+				{
+					let future = self.service.call(service_request);
+					Box::pin(async move { future.await })
 				} else if service_request.path() == "/favicon.ico"
 					&& service_request.method() == actix_web::http::Method::GET
 				{
@@ -243,25 +260,25 @@ where
 				{
 					let future = self.service.call(service_request);
 					Box::pin(async move { future.await })
+				} else if service_request.path() == "/metrics"
+					&& service_request.method() == actix_web::http::Method::GET
+					&& service_request
+						.app_data::<actix_web::web::Data<
+							std::sync::Arc<std::sync::Mutex<crate::http_server::Settings>>,
+						>>()
+						.map(|settings| settings.lock().unwrap().serves_public_metrics())
+						.unwrap_or(false)
+				{
+					let future = self.service.call(service_request);
+					Box::pin(async move { future.await })
 				} else if service_request.method() == actix_web::http::Method::OPTIONS {
 					let future = self.service.call(service_request);
 					Box::pin(async move { future.await })
 				} else {
 					Box::pin(async move {
-						// TODO : check security issue about this ?
-						let all_origins =
-							actix_web::http::header::HeaderValue::from_bytes(b"*").unwrap();
-						let headers = service_request.headers().clone();
-						let origin = headers
-							.get(actix_web::http::header::ORIGIN)
-							.unwrap_or(&all_origins)
-							.to_str()
-							.unwrap();
-
 						Ok(actix_web::dev::ServiceResponse::new(
 							service_request.into_parts().0,
 							crate::database::build_http_json_response(
-								origin,
 								&request_method,
 								actix_web::http::StatusCode::UNAUTHORIZED,
 								None,
@@ -276,3 +293,19 @@ where
 		}
 	}
 }
+
+/// Access token of the `access_token` query parameter (RFC 6750, section 2.3), only for GET and
+/// HEAD requests of the storage, in order to use documents in `<img>` or `<audio>` tags of
+/// applications, which can not add the `Authorization` header.
+fn query_access_token(service_request: &actix_web::dev::ServiceRequest) -> Option<String> {
+	if !service_request.path().starts_with("/storage/")
+		|| (service_request.method() != actix_web::http::Method::GET
+			&& service_request.method() != actix_web::http::Method::HEAD)
+	{
+		return None;
+	}
+
+	url::form_urlencoded::parse(service_request.query_string().as_bytes())
+		.find(|(key, _)| key == "access_token")
+		.map(|(_, value)| String::from(value.trim()))
+}
@@ -42,6 +42,7 @@ async fn hsv5femo2qgu80gbad0ov5() {
 		(110, "/remotestorage.svg", false),
 		(120, "/", false),
 		(130, "/events/all", true),
+		(140, "/metrics", true),
 	];
 
 	for test in tests {
@@ -58,6 +59,20 @@ async fn hsv5femo2qgu80gbad0ov5() {
 
 		println!("OK");
 	}
+
+	print!("#150 : GET request to /metrics when they are public ... ");
+
+	settings.lock().unwrap().metrics = Some(crate::http_server::SettingsMetrics {
+		admin_port: None,
+		public: true,
+	});
+	let request = actix_web::test::TestRequest::get()
+		.uri("/metrics")
+		.to_request();
+	let response = actix_web::test::call_service(&app, request).await;
+	assert_ne!(response.status(), actix_web::http::StatusCode::UNAUTHORIZED);
+
+	println!("OK");
 }
 
 #[actix_rt::test]
@@ -77,6 +92,7 @@ async fn kp6m20xdwvw6v4t3yxq() {
 			},
 		],
 		"test",
+		"",
 		"user",
 	);
 	access_tokens.lock().unwrap().push(token.clone());
@@ -200,6 +216,8 @@ async fn kp6m20xdwvw6v4t3yxq() {
 	)
 	.await;
 
+	let query_token = pct_str::PctString::encode(token.get_name().chars(), pct_str::URIReserved);
+
 	let tests: Vec<(
 		usize,
 		actix_web::test::TestRequest,
@@ -455,6 +473,40 @@ async fn kp6m20xdwvw6v4t3yxq() {
 			actix_web::test::TestRequest::get().uri("/events/all"),
 			actix_web::http::StatusCode::UNAUTHORIZED,
 		),
+		(
+			240,
+			actix_web::test::TestRequest::get().uri(&format!(
+				"/storage/user/folder_read/a?access_token={query_token}"
+			)),
+			actix_web::http::StatusCode::OK,
+		),
+		(
+			250,
+			actix_web::test::TestRequest::get()
+				.uri(&format!("/storage/user/other/a?access_token={query_token}")),
+			actix_web::http::StatusCode::FORBIDDEN,
+		),
+		(
+			260,
+			actix_web::test::TestRequest::get()
+				.uri("/storage/user/folder_read/a?access_token=RANDOM_BEARER"),
+			actix_web::http::StatusCode::UNAUTHORIZED,
+		),
+		(
+			270,
+			actix_web::test::TestRequest::put()
+				.uri(&format!(
+					"/storage/user/folder_write/c?access_token={query_token}"
+				))
+				.set_json(&serde_json::json!({"value": "HELLO"})),
+			actix_web::http::StatusCode::UNAUTHORIZED,
+		),
+		(
+			280,
+			actix_web::test::TestRequest::get()
+				.uri(&format!("/events/all?access_token={query_token}")),
+			actix_web::http::StatusCode::UNAUTHORIZED,
+		),
 	];
 
 	for test in tests {
@@ -480,4 +532,124 @@ async fn kp6m20xdwvw6v4t3yxq() {
 
 		println!("OK");
 	}
+
+	// responses to tokens in the URI should not be kept by shared caches (RFC 6750, section 2.3)
+	let request = actix_web::test::TestRequest::get()
+		.uri(&format!(
+			"/storage/user/folder_read/a?access_token={query_token}"
+		))
+		.to_request();
+	let response = actix_web::test::call_service(&mut app, request).await;
+	assert_eq!(
+		response
+			.headers()
+			.get(actix_web::http::header::CACHE_CONTROL)
+			.unwrap(),
+		"private, no-cache"
+	);
+}
+
+#[actix_rt::test]
+async fn b7tqe0wzsf3vmk1n() {
+	let access_tokens: std::sync::Arc<std::sync::Mutex<Vec<crate::http_server::AccessBearer>>> =
+		std::sync::Arc::new(std::sync::Mutex::new(vec![]));
+
+	let token = crate::http_server::AccessBearer::new(
+		vec![crate::scope::Scope {
+			module: String::from("documents"),
+			right_type: crate::scope::ScopeRightType::ReadWrite,
+		}],
+		"https://app.example.com",
+		"https://app.example.com/callback/",
+		"user",
+	);
+	access_tokens.lock().unwrap().push(token.clone());
+
+	let database =
+		crate::database::Database::new(Box::new(crate::database::sources::MemoryStorage {
+			root_item: crate::item::Item::new_folder(vec![(
+				"user",
+				crate::item::Item::new_folder(vec![(
+					"documents",
+					crate::item::Item::new_folder(vec![(
+						"a",
+						crate::item::Item::new_doc(b"HELLO", "text/plain"),
+					)]),
+				)]),
+			)]),
+		}));
+	let database = std::sync::Arc::new(std::sync::Mutex::new(database));
+
+	let settings = std::sync::Arc::new(std::sync::Mutex::new(crate::http_server::Settings::new(
+		tempfile::tempdir().unwrap().into_path(),
+	)));
+
+	let logger = Arc::new(Mutex::new(charlie_buffalo::Logger::new(
+		charlie_buffalo::new_dispatcher(Box::new(|_| {})),
+		None,
+	)));
+
+	let app = actix_web::test::init_service(
+		actix_web::App::new()
+			.app_data(actix_web::web::Data::new(database))
+			.app_data(actix_web::web::Data::new(access_tokens))
+			.app_data(actix_web::web::Data::new(settings))
+			.app_data(actix_web::web::Data::new(logger.clone()))
+			.wrap(super::Auth { logger })
+			.service(crate::http_server::api::get_item),
+	)
+	.await;
+
+	let tests = vec![
+		(010, None, actix_web::http::StatusCode::OK),
+		(
+			020,
+			Some("https://app.example.com"),
+			actix_web::http::StatusCode::OK,
+		),
+		(
+			030,
+			Some("https://app.example.com:443"),
+			actix_web::http::StatusCode::OK,
+		),
+		(
+			040,
+			Some("https://evil.example.com"),
+			actix_web::http::StatusCode::FORBIDDEN,
+		),
+		(
+			050,
+			Some("http://app.example.com"),
+			actix_web::http::StatusCode::FORBIDDEN,
+		),
+		(
+			060,
+			Some("https://app.example.com:8443"),
+			actix_web::http::StatusCode::FORBIDDEN,
+		),
+		(070, Some("null"), actix_web::http::StatusCode::FORBIDDEN),
+	];
+
+	for test in tests {
+		print!(
+			"#{:03} : GET request to /storage/user/documents/a with Origin = {:?} ... ",
+			test.0, test.1
+		);
+
+		let mut request = actix_web::test::TestRequest::get()
+			.uri("/storage/user/documents/a")
+			.insert_header((
+				actix_web::http::header::AUTHORIZATION,
+				format!("Bearer {}", token.get_name()),
+			));
+		if let Some(origin) = test.1 {
+			request = request.insert_header((actix_web::http::header::ORIGIN, origin));
+		}
+
+		let response = actix_web::test::call_service(&app, request.to_request()).await;
+
+		assert_eq!(response.status(), test.2);
+
+		println!("OK");
+	}
 }
@@ -40,8 +40,12 @@ impl OauthFormToken {
 		&self.value
 	}
 
+	pub fn is_from(&self, ip: &std::net::SocketAddr) -> bool {
+		self.ip.ip() == ip.ip()
+	}
+
 	pub fn has_expirated(&self) -> bool {
-		(std::time::Instant::now() - self.forged) == std::time::Duration::from_secs(5 * 60)
+		(std::time::Instant::now() - self.forged) >= std::time::Duration::from_secs(5 * 60)
 	}
 
 	pub fn should_be_cleaned(&self, ip: &std::net::SocketAddr) -> bool {
@@ -0,0 +1,185 @@
+/// Request headers which browsers can send from other origins, like the `Authorization` one for
+/// access tokens, and conditional requests of the storage.
+const ALLOWED_HEADERS: &str =
+	"Authorization, Content-Length, Content-Type, Origin, X-Requested-With, If-Match, If-None-Match";
+/// Response headers which can be read by applications.
+const EXPOSED_HEADERS: &str = "Content-Length, Content-Type, ETag, Last-Modified";
+
+/// Cross-origin resource sharing, as set in [`SettingsCors`](crate::http_server::SettingsCors).
+///
+/// Preflight requests are answered here, before [`Auth`](super::Auth), because browsers never send
+/// credentials with them. It should wrap all other middlewares except the
+/// [`Logger`](super::Logger), so that their errors also have these headers.
+pub struct Cors;
+
+impl<S> actix_web::dev::Transform<S, actix_web::dev::ServiceRequest> for Cors
+where
+	S: actix_web::dev::Service<
+		actix_web::dev::ServiceRequest,
+		Response = actix_web::dev::ServiceResponse<actix_web::body::BoxBody>,
+		Error = actix_web::Error,
+	>,
+	S::Future: 'static,
+{
+	type Response = actix_web::dev::ServiceResponse<actix_web::body::BoxBody>;
+	type Error = actix_web::Error;
+	type InitError = ();
+	type Transform = CorsMiddleware<S>;
+	type Future = futures::future::Ready<Result<Self::Transform, Self::InitError>>;
+
+	fn new_transform(&self, service: S) -> Self::Future {
+		futures::future::ok(Self::Transform { service })
+	}
+}
+
+pub struct CorsMiddleware<S> {
+	service: S,
+}
+
+impl<S> actix_web::dev::Service<actix_web::dev::ServiceRequest> for CorsMiddleware<S>
+where
+	S: actix_web::dev::Service<
+		actix_web::dev::ServiceRequest,
+		Response = actix_web::dev::ServiceResponse<actix_web::body::BoxBody>,
+		Error = actix_web::Error,
+	>,
+	S::Future: 'static,
+{
+	type Response = actix_web::dev::ServiceResponse<actix_web::body::BoxBody>;
+	type Error = actix_web::Error;
+	type Future =
+		futures_util::future::LocalBoxFuture<'static, Result<Self::Response, Self::Error>>;
+
+	actix_web::dev::forward_ready!(service);
+
+	fn call(&self, service_request: actix_web::dev::ServiceRequest) -> Self::Future {
+		let settings = service_request
+			.app_data::<actix_web::web::Data<
+				std::sync::Arc<std::sync::Mutex<crate::http_server::Settings>>,
+			>>()
+			.and_then(|settings| settings.lock().unwrap().cors.clone())
+			.unwrap_or_default();
+
+		let origin = service_request
+			.headers()
+			.get(actix_web::http::header::ORIGIN)
+			.and_then(|origin| origin.to_str().ok())
+			.map(String::from);
+
+		if let Some(origin) = &origin {
+			if service_request.method() == actix_web::http::Method::OPTIONS
+				&& service_request
+					.headers()
+					.contains_key(actix_web::http::header::ACCESS_CONTROL_REQUEST_METHOD)
+			{
+				let response = build_preflight_response(&settings, origin, service_request.path());
+
+				return Box::pin(async move {
+					Ok(actix_web::dev::ServiceResponse::new(
+						service_request.into_parts().0,
+						response,
+					))
+				});
+			}
+		}
+
+		let future = self.service.call(service_request);
+		Box::pin(async move {
+			let mut response = future.await?;
+			insert_cors_headers(&settings, origin.as_deref(), response.headers_mut());
+
+			Ok(response)
+		})
+	}
+}
+
+fn build_preflight_response(
+	settings: &crate::http_server::SettingsCors,
+	origin: &str,
+	path: &str,
+) -> actix_web::HttpResponse {
+	if !settings.allows(origin) {
+		let mut response = actix_web::HttpResponse::Forbidden().finish();
+		insert_cors_headers(settings, Some(origin), response.headers_mut());
+
+		return response;
+	}
+
+	let mut response = actix_web::HttpResponse::Ok();
+	response.insert_header((
+		actix_web::http::header::ACCESS_CONTROL_ALLOW_METHODS,
+		crate::http_server::join_methods(&allowed_methods(path)),
+	));
+	response.insert_header((
+		actix_web::http::header::ACCESS_CONTROL_ALLOW_HEADERS,
+		ALLOWED_HEADERS,
+	));
+	response.insert_header((
+		actix_web::http::header::ACCESS_CONTROL_MAX_AGE,
+		settings.max_age_seconds.to_string(),
+	));
+
+	let mut response = response.finish();
+	insert_cors_headers(settings, Some(origin), response.headers_mut());
+
+	return response;
+}
+
+/// Methods which can be used on `path` with enough rights, because the token of the request is
+/// not sent with preflight requests.
+fn allowed_methods(path: &str) -> Vec<actix_web::http::Method> {
+	if path.starts_with("/storage/") {
+		crate::http_server::storage_methods(path)
+	} else if path == "/oauth/token" {
+		vec![
+			actix_web::http::Method::OPTIONS,
+			actix_web::http::Method::POST,
+		]
+	} else {
+		vec![
+			actix_web::http::Method::OPTIONS,
+			actix_web::http::Method::GET,
+			actix_web::http::Method::HEAD,
+		]
+	}
+}
+
+fn insert_cors_headers(
+	settings: &crate::http_server::SettingsCors,
+	origin: Option<&str>,
+	headers: &mut actix_web::http::header::HeaderMap,
+) {
+	let allowed_origin = match origin {
+		Some(origin) => {
+			// the answer depends on the origin, so it should not be cached for other ones
+			headers.append(
+				actix_web::http::header::VARY,
+				actix_web::http::header::HeaderValue::from_static("Origin"),
+			);
+
+			if settings.allows(origin) {
+				actix_web::http::header::HeaderValue::from_str(origin).ok()
+			} else {
+				None
+			}
+		}
+		None if settings.allows_all_origins() => {
+			Some(actix_web::http::header::HeaderValue::from_static("*"))
+		}
+		None => None,
+	};
+
+	if let Some(allowed_origin) = allowed_origin {
+		headers.insert(
+			actix_web::http::header::ACCESS_CONTROL_ALLOW_ORIGIN,
+			allowed_origin,
+		);
+		headers.insert(
+			actix_web::http::header::ACCESS_CONTROL_EXPOSE_HEADERS,
+			actix_web::http::header::HeaderValue::from_static(EXPOSED_HEADERS),
+		);
+	}
+}
+
+#[cfg(test)]
+mod tests;
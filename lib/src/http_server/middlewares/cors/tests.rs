@@ -0,0 +1,164 @@
+#[actix_rt::test]
+async fn x3mf8qz1ckw6npd() {
+	let mut settings = crate::http_server::Settings::new(std::path::PathBuf::from("."));
+	settings.cors = Some(crate::http_server::SettingsCors {
+		allowed_origins: vec![String::from("https://app.example.com")],
+		max_age_seconds: 600,
+	});
+	let settings = std::sync::Arc::new(std::sync::Mutex::new(settings));
+
+	let app = actix_web::test::init_service(
+		actix_web::App::new()
+			.app_data(actix_web::web::Data::new(settings.clone()))
+			.wrap(super::Cors)
+			.service(crate::http_server::get_favicon)
+			.service(crate::http_server::options_favicon),
+	)
+	.await;
+
+	let tests = vec![
+		(
+			010,
+			"GET",
+			Some("https://app.example.com"),
+			false,
+			200,
+			true,
+		),
+		(
+			020,
+			"GET",
+			Some("https://other.example.com"),
+			false,
+			200,
+			false,
+		),
+		(030, "GET", None, false, 200, false),
+		(
+			040,
+			"OPTIONS",
+			Some("https://app.example.com"),
+			true,
+			200,
+			true,
+		),
+		(
+			050,
+			"OPTIONS",
+			Some("https://other.example.com"),
+			true,
+			403,
+			false,
+		),
+		(
+			060,
+			"OPTIONS",
+			Some("https://app.example.com"),
+			false,
+			200,
+			true,
+		),
+	];
+
+	for test in tests {
+		print!(
+			"#{:03} : {} request from {:?} (preflight : {}) ... ",
+			test.0, test.1, test.2, test.3
+		);
+
+		let mut request = actix_web::test::TestRequest::default()
+			.method(actix_web::http::Method::from_bytes(test.1.as_bytes()).unwrap())
+			.uri("/favicon.ico");
+		if let Some(origin) = test.2 {
+			request = request.insert_header((actix_web::http::header::ORIGIN, origin));
+		}
+		if test.3 {
+			request = request.insert_header((
+				actix_web::http::header::ACCESS_CONTROL_REQUEST_METHOD,
+				"PUT",
+			));
+		}
+
+		let response = actix_web::test::call_service(&app, request.to_request()).await;
+
+		assert_eq!(response.status().as_u16(), test.4);
+		assert_eq!(
+			response
+				.headers()
+				.get(actix_web::http::header::ACCESS_CONTROL_ALLOW_ORIGIN)
+				.and_then(|origin| origin.to_str().ok()),
+			if test.5 { test.2 } else { None }
+		);
+		assert_eq!(
+			response
+				.headers()
+				.contains_key(actix_web::http::header::ACCESS_CONTROL_MAX_AGE),
+			test.3 && test.5
+		);
+		if test.2.is_some() {
+			assert_eq!(
+				response
+					.headers()
+					.get(actix_web::http::header::VARY)
+					.unwrap(),
+				"Origin"
+			);
+		}
+
+		println!("OK");
+	}
+
+	settings.lock().unwrap().cors = Some(crate::http_server::SettingsCors::default());
+
+	let request = actix_web::test::TestRequest::get()
+		.uri("/favicon.ico")
+		.to_request();
+	let response = actix_web::test::call_service(&app, request).await;
+	assert_eq!(
+		response
+			.headers()
+			.get(actix_web::http::header::ACCESS_CONTROL_ALLOW_ORIGIN)
+			.unwrap(),
+		"*"
+	);
+
+	let request = actix_web::test::TestRequest::default()
+		.method(actix_web::http::Method::OPTIONS)
+		.uri("/favicon.ico")
+		.insert_header((actix_web::http::header::ORIGIN, "https://other.example.com"))
+		.insert_header((
+			actix_web::http::header::ACCESS_CONTROL_REQUEST_METHOD,
+			"PUT",
+		))
+		.insert_header((
+			actix_web::http::header::ACCESS_CONTROL_REQUEST_HEADERS,
+			"authorization, if-match",
+		))
+		.to_request();
+	let response = actix_web::test::call_service(&app, request).await;
+	assert_eq!(response.status(), actix_web::http::StatusCode::OK);
+	assert_eq!(
+		response
+			.headers()
+			.get(actix_web::http::header::ACCESS_CONTROL_ALLOW_ORIGIN)
+			.unwrap(),
+		"https://other.example.com"
+	);
+	assert_eq!(
+		response
+			.headers()
+			.get(actix_web::http::header::ACCESS_CONTROL_MAX_AGE)
+			.unwrap(),
+		"3600"
+	);
+	let allowed_headers = response
+		.headers()
+		.get(actix_web::http::header::ACCESS_CONTROL_ALLOW_HEADERS)
+		.unwrap()
+		.to_str()
+		.unwrap()
+		.to_lowercase();
+	assert!(allowed_headers.contains("authorization"));
+	assert!(allowed_headers.contains("if-match"));
+	assert!(allowed_headers.contains("content-type"));
+}
@@ -12,7 +12,7 @@ where
 		Error = actix_web::Error,
 	>,
 	S::Future: 'static,
-	B: 'static,
+	B: actix_web::body::MessageBody + 'static,
 {
 	type Response = actix_web::dev::ServiceResponse<B>;
 	type Error = actix_web::Error;
@@ -41,7 +41,7 @@ where
 		Error = actix_web::Error,
 	>,
 	S::Future: 'static,
-	B: 'static,
+	B: actix_web::body::MessageBody + 'static,
 {
 	type Response = actix_web::dev::ServiceResponse<B>;
 	type Error = actix_web::Error;
@@ -51,8 +51,18 @@ where
 	actix_web::dev::forward_ready!(service);
 
 	fn call(&self, service_request: actix_web::dev::ServiceRequest) -> Self::Future {
+		let request_id = crate::http_server::logging::request_id(&service_request);
+		// logs of next middlewares and handlers are events of this span
+		let span = tracing::info_span!(
+			"http_request",
+			request_id = request_id.as_str(),
+			method = service_request.method().as_str(),
+			path = service_request.path(),
+		);
+
 		let mut attributes = vec![
 			(String::from("event"), String::from("http_access")),
+			(String::from("request_id"), request_id.clone()),
 			(
 				String::from("method"),
 				format!("{}", service_request.method()),
@@ -60,7 +70,7 @@ where
 			(String::from("path"), String::from(service_request.path())),
 			(
 				String::from("query"),
-				String::from(service_request.query_string()),
+				without_access_token(service_request.query_string()),
 			),
 		];
 		if let Some(peer_addr) = service_request.connection_info().realip_remote_addr() {
@@ -69,7 +79,7 @@ where
 		for (header_name, header_value) in service_request.headers() {
 			attributes.push((
 				format!("request_header:{}", header_name),
-				String::from(header_value.to_str().unwrap_or_default()),
+				logged_header_value(header_name, header_value),
 			));
 		}
 		attributes.push((
@@ -77,29 +87,117 @@ where
 			String::from(service_request.uri().scheme_str().unwrap_or("http?")),
 		));
 
-		let future = self.service.call(service_request);
+		let start = std::time::Instant::now();
+		let method = service_request.method().clone();
+		let request_bytes = service_request
+			.headers()
+			.get(actix_web::http::header::CONTENT_LENGTH)
+			.and_then(|value| value.to_str().ok())
+			.and_then(|value| value.parse::<u64>().ok())
+			.unwrap_or(0);
+		let metrics = service_request
+			.app_data::<actix_web::web::Data<Arc<Mutex<crate::http_server::Metrics>>>>()
+			.cloned();
+
+		let future = span.in_scope(|| self.service.call(service_request));
 
 		let logger_for_response = self.logger.clone();
-		Box::pin(async move {
-			let res = future.await;
+		Box::pin(tracing::Instrument::instrument(
+			async move {
+				let mut res = future.await;
+
+				if let Ok(response) = &mut res {
+					if let Ok(request_id) =
+						actix_web::http::header::HeaderValue::from_str(&request_id)
+					{
+						response.headers_mut().insert(
+							actix_web::http::header::HeaderName::from_static(
+								crate::http_server::logging::REQUEST_ID_HEADER,
+							),
+							request_id,
+						);
+					}
+
+					if let Some(metrics) = metrics {
+						// streamed bodies are not counted
+						let response_bytes = match response.response().body().size() {
+							actix_web::body::BodySize::Sized(size) => size,
+							_ => 0,
+						};
 
-			if let Ok(response) = &res {
-				attributes.push((
-					String::from("response_code"),
-					String::from(response.status().as_str()),
-				));
+						metrics.lock().unwrap().record_request(
+							&method,
+							response.status(),
+							start.elapsed(),
+							request_bytes,
+							response_bytes,
+						);
+					}
 
-				for (header_name, header_value) in response.headers() {
 					attributes.push((
-						format!("response_header:{}", header_name),
-						String::from(header_value.to_str().unwrap_or_default()),
+						String::from("response_code"),
+						String::from(response.status().as_str()),
 					));
+
+					for (header_name, header_value) in response.headers() {
+						attributes.push((
+							format!("response_header:{}", header_name),
+							logged_header_value(header_name, header_value),
+						));
+					}
 				}
-			}
 
-			charlie_buffalo::push(&logger_for_response, attributes, None);
+				charlie_buffalo::push(&logger_for_response, attributes, None);
 
-			res
-		})
+				res
+			},
+			span,
+		))
 	}
 }
+
+/// Credentials (like passwords of HTTP Basic authentication, bearer tokens or session cookies)
+/// are replaced, in order to not be saved in logs.
+fn logged_header_value(
+	header_name: &actix_web::http::header::HeaderName,
+	header_value: &actix_web::http::header::HeaderValue,
+) -> String {
+	if [
+		actix_web::http::header::AUTHORIZATION,
+		actix_web::http::header::PROXY_AUTHORIZATION,
+		actix_web::http::header::COOKIE,
+		actix_web::http::header::SET_COOKIE,
+	]
+	.contains(header_name)
+	{
+		return String::from("*REDACTED*");
+	}
+
+	return String::from(header_value.to_str().unwrap_or_default());
+}
+
+/// Access tokens can be sent in the query (see [`super::Auth`]), but should not be saved in logs.
+///
+/// Keys are compared once decoded, like [`super::Auth`] does, so the query is encoded again.
+fn without_access_token(query_string: &str) -> String {
+	url::form_urlencoded::Serializer::new(String::new())
+		.extend_pairs(
+			url::form_urlencoded::parse(query_string.as_bytes())
+				.filter(|(key, _)| key != "access_token"),
+		)
+		.finish()
+}
+
+#[test]
+fn r4xn7wc1pdq9mkt() {
+	assert_eq!(without_access_token(""), "");
+	assert_eq!(without_access_token("access_token=abc"), "");
+	assert_eq!(without_access_token("a=1&access_token=abc&b=2"), "a=1&b=2");
+	assert_eq!(without_access_token("access_token&b=2"), "b=2");
+	assert_eq!(without_access_token("access%5Ftoken=abc&b=2"), "b=2");
+	assert_eq!(without_access_token("%61ccess_token=abc"), "");
+	assert_eq!(
+		without_access_token("my_access_token=abc"),
+		"my_access_token=abc"
+	);
+}
@@ -1,7 +1,9 @@
 mod auth;
+mod cors;
 mod hsts;
 mod logger;
 
 pub use auth::*;
+pub use cors::*;
 pub use hsts::*;
 pub use logger::*;
@@ -1,25 +1,34 @@
 mod api;
+mod auth_backends;
+mod health;
 mod init;
+mod metrics;
+mod throttling;
 mod tokens;
 mod users;
 mod utils;
 mod webfinger;
 
-use users::*;
 use utils::build_server_address;
 
 use std::sync::{Arc, Mutex};
 
+pub mod logging;
 pub mod middlewares;
 
 pub use api::*;
+pub use auth_backends::{authenticate, AuthBackend, HtpasswdBackend, LdapBackend};
+pub use health::{certificate_expiration, get_healthz, get_readyz};
 pub use init::*;
+pub use metrics::{configure_metrics_server, get_metrics, MeasuredSource, Metrics};
+pub use throttling::*;
 pub use tokens::*;
-pub use users::Users;
-pub use webfinger::webfinger_handle;
+pub use users::{
+	PasswordCheck, SecondFactorCheck, UserGrant, UserRight, Users, UsersError, UsersFileFormat,
+};
+pub use webfinger::{options_webfinger, webfinger_handle};
 
 const FORM_TOKEN_ALPHABET: &str = "abcdefghijklmnopqrstuvwxyz-0123456789_ABCDEFGHIJKLMNOPQRSTUVWXYZ?,;.:/!§*µù%$£¤=+{}[]()°à@çè|#é~&";
-const PASSWORD_HASH_ALPHABET: &str = "abcdefghijklmnopqrstuvwxyz-0123456789_ABCDEFGHIJKLMNOPQRSTUVWXYZ?,;.:/!§*µù%$£¤=+{}[]()°à@çè|#é~&";
 const ACCESS_TOKEN_ALPHABET: &str =
 	"abcdefghijklmnopqrstuvwxyz-0123456789_ABCDEFGHIJKLMNOPQRSTUVWXYZ!+*";
 
@@ -44,23 +53,33 @@ pub fn configure_server(
 	settings: Arc<Mutex<crate::http_server::Settings>>,
 	database: Arc<Mutex<crate::database::Database>>,
 	access_tokens: Arc<Mutex<Vec<crate::http_server::AccessBearer>>>,
+	authorization_codes: Arc<Mutex<Vec<crate::http_server::AuthorizationCode>>>,
 	oauth_form_tokens: Arc<Mutex<Vec<crate::http_server::middlewares::OauthFormToken>>>,
+	sessions: Arc<Mutex<Vec<crate::http_server::Session>>>,
+	login_attempts: Arc<Mutex<crate::http_server::LoginAttempts>>,
 	users: Arc<Mutex<crate::http_server::Users>>,
 	program_state: Arc<Mutex<ProgramState>>,
+	metrics: Arc<Mutex<crate::http_server::Metrics>>,
 	logger: Arc<Mutex<charlie_buffalo::Logger>>,
 	workspace_path: &std::path::Path,
 	dbevent_sender: Option<std::sync::mpsc::Sender<DbEvent>>,
 ) -> impl FnOnce(&mut actix_web::web::ServiceConfig) {
 	let workspace_path_clone = workspace_path.to_path_buf();
+	// otherwise, it is served on its own port, or not at all
+	let serve_metrics = settings.lock().unwrap().serves_public_metrics();
 
 	return move |config: &mut actix_web::web::ServiceConfig| {
 		config
 			.app_data(actix_web::web::Data::new(database.clone()))
 			.app_data(actix_web::web::Data::new(oauth_form_tokens.clone()))
+			.app_data(actix_web::web::Data::new(sessions.clone()))
 			.app_data(actix_web::web::Data::new(access_tokens.clone()))
+			.app_data(actix_web::web::Data::new(authorization_codes.clone()))
+			.app_data(actix_web::web::Data::new(login_attempts.clone()))
 			.app_data(actix_web::web::Data::new(users.clone()))
 			.app_data(actix_web::web::Data::new(settings.clone()))
 			.app_data(actix_web::web::Data::new(program_state.clone()))
+			.app_data(actix_web::web::Data::new(metrics.clone()))
 			.app_data(actix_web::web::Data::new(workspace_path_clone))
 			.app_data(actix_web::web::Data::new(logger));
 
@@ -68,12 +87,36 @@ pub fn configure_server(
 			config.app_data(actix_web::web::Data::new(dbevent_sender));
 		}
 
+		if serve_metrics {
+			config.service(get_metrics);
+		}
+
 		config
+			.service(get_healthz)
+			.service(get_readyz)
 			.service(options_favicon)
 			.service(get_favicon)
 			.service(get_oauth)
 			.service(post_oauth)
+			.service(post_oauth_token)
 			.service(webfinger_handle)
+			.service(options_webfinger)
+			.service(get_account)
+			.service(post_account)
+			.service(get_admin_users)
+			.service(post_admin_users)
+			.service(delete_admin_user)
+			.service(put_admin_user_password)
+			.service(put_admin_user_right)
+			.service(delete_admin_user_right)
+			.service(get_admin_dashboard)
+			.service(get_admin_me)
+			.service(get_admin_tokens)
+			.service(delete_admin_token)
+			.service(get_admin_events)
+			.service(get_admin_storage)
+			.service(get_admin_settings)
+			.service(put_admin_settings)
 			.service(get_item)
 			.service(head_item)
 			.service(options_item)
@@ -89,17 +132,14 @@ pub fn configure_server(
 pub async fn options_favicon() -> impl actix_web::Responder {
 	let mut res = actix_web::HttpResponse::Ok();
 	res.insert_header((actix_web::http::header::ALLOW, "OPTIONS, GET"));
-	res.insert_header((actix_web::http::header::ACCESS_CONTROL_ALLOW_ORIGIN, "*"));
 
 	return res;
 }
 
 #[actix_web::get("/favicon.ico")]
 pub async fn get_favicon() -> impl actix_web::Responder {
-	let mut res = actix_web::HttpResponse::Ok();
-	res.insert_header((actix_web::http::header::ACCESS_CONTROL_ALLOW_ORIGIN, "*"));
-
-	return res.body(actix_web::web::Bytes::from_static(crate::assets::ICON));
+	return actix_web::HttpResponse::Ok()
+		.body(actix_web::web::Bytes::from_static(crate::assets::ICON));
 }
 
 #[actix_web::get("/remotestorage.svg")]
@@ -109,6 +149,26 @@ pub async fn remotestoragesvg() -> impl actix_web::Responder {
 	));
 }
 
+/// Reads all events saved in the `events.bin` file of the workspace, from the oldest to the newest.
+pub fn read_events(workspace_path: &std::path::Path) -> Result<Vec<DbEvent>, String> {
+	let file_content = match std::fs::read(workspace_path.join("events.bin")) {
+		Ok(file_content) => file_content,
+		Err(e) if e.kind() == std::io::ErrorKind::NotFound => return Ok(vec![]),
+		Err(e) => return Err(e.to_string()),
+	};
+
+	let mut file_content_stream = vec![b'['];
+	file_content_stream
+		.extend_from_slice(file_content.strip_suffix(b",\n").unwrap_or(&file_content));
+	file_content_stream.push(b']');
+
+	let file_content_stream = String::from_utf8_lossy(&file_content_stream);
+	let file_content_stream =
+		file_content_stream.replace(r#""method":"Put""#, r#""method":"Update""#); // backward compatibility
+
+	return serde_json::from_str(&file_content_stream).map_err(|e| e.to_string());
+}
+
 #[actix_web::get("/events/all")]
 pub async fn server_events(
 	workspace_path: actix_web::web::Data<std::path::PathBuf>,
@@ -119,32 +179,20 @@ pub async fn server_events(
 	request: actix_web::HttpRequest,
 	settings: actix_web::web::Data<std::sync::Arc<std::sync::Mutex<crate::http_server::Settings>>>,
 ) -> impl actix_web::Responder {
-	let mut file_content = std::fs::read(workspace_path.join("events.bin")).unwrap();
-	file_content = file_content[..(file_content.len() - b",\n".len())].to_vec();
-	let events: Vec<DbEvent> = {
-		let mut file_content_stream = vec![b'['];
-		file_content_stream.append(&mut file_content);
-		file_content_stream.push(b']');
-
-		let file_content_stream = String::from_utf8_lossy(&file_content_stream);
-		let file_content_stream =
-			file_content_stream.replace(r#""method":"Put""#, r#""method":"Update""#); // backward compatibility
-
-		match serde_json::from_str(&file_content_stream) {
-			Ok(events) => events,
-			Err(err) => {
-				logger.lock().unwrap().push(
-					vec![
-						(String::from("level"), String::from("ERROR")),
-						(String::from("module"), String::from("https?")),
-						(String::from("method"), String::from("GET")),
-						(String::from("path"), String::from("/events/all")),
-					],
-					Some(&err.to_string()),
-				);
-
-				vec![]
-			}
+	let events = match read_events(&workspace_path) {
+		Ok(events) => events,
+		Err(err) => {
+			logger.lock().unwrap().push(
+				vec![
+					(String::from("level"), String::from("ERROR")),
+					(String::from("module"), String::from("https?")),
+					(String::from("method"), String::from("GET")),
+					(String::from("path"), String::from("/events/all")),
+				],
+				Some(&err),
+			);
+
+			vec![]
 		}
 	};
 
@@ -204,6 +252,10 @@ pub async fn server_events(
 					max_token_lifetime_seconds,
 					&actix_web::http::Method::GET,
 					&event.path,
+					request
+						.headers()
+						.get(actix_web::http::header::ORIGIN)
+						.and_then(|origin| origin.to_str().ok()),
 				)
 				.unwrap_or(false)
 		} else {
@@ -232,7 +284,6 @@ pub async fn server_events(
 	}
 
 	let mut res = actix_web::HttpResponse::Ok();
-	res.insert_header((actix_web::http::header::ACCESS_CONTROL_ALLOW_ORIGIN, "*"));
 	res.insert_header((actix_web::http::header::CONTENT_TYPE, "text/event-stream"));
 	res.insert_header((actix_web::http::header::CACHE_CONTROL, "no-cache"));
 
@@ -253,4 +304,8 @@ pub async fn index() -> impl actix_web::Responder {
 #[derive(Debug, Clone, Default)]
 pub struct ProgramState {
 	pub https_mode: bool,
+	/// Certificate of the HTTPS server, in order to reload it.
+	pub certificate_resolver: Option<Arc<CertificateResolver>>,
+	/// Time and result of the last probe of the database by `/readyz`.
+	pub database_probe: Option<(std::time::Instant, Result<(), String>)>,
 }
@@ -0,0 +1,395 @@
+/// Failed logins on the OAuth form, counted by IP address and by username, in order to slow down
+/// brute-force attacks.
+#[derive(Debug, Default)]
+pub struct LoginAttempts {
+	by_ip: std::collections::HashMap<std::net::IpAddr, Failures>,
+	by_username: std::collections::HashMap<String, Failures>,
+}
+
+#[derive(Debug, Clone)]
+struct Failures {
+	count: u32,
+	/// Attempts which are being checked, counted as failures for the lockout until they are
+	/// settled.
+	pending: u32,
+	last: std::time::Instant,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum LoginRefusal {
+	TooManyAttempts(std::time::Duration),
+	AccountLocked(std::time::Duration),
+}
+
+impl LoginAttempts {
+	pub fn new() -> Self {
+		Self::default()
+	}
+
+	/// Checks if a new login attempt can be done now, without checking credentials, and reserves
+	/// it.
+	///
+	/// Reserved attempts are counted as failures for the lockout until they are settled with
+	/// [`register_failure`](Self::register_failure) or [`register_success`](Self::register_success),
+	/// otherwise concurrent attempts would all be allowed while credentials are checked. They do
+	/// not slow down other attempts, so concurrent logins of legitimate users are not refused.
+	pub fn reserve(
+		&mut self,
+		ip: Option<std::net::IpAddr>,
+		username: &str,
+		settings: &crate::http_server::SettingsOauthThrottling,
+	) -> Result<(), LoginRefusal> {
+		self.reserve_at(ip, username, settings, std::time::Instant::now())
+	}
+
+	fn reserve_at(
+		&mut self,
+		ip: Option<std::net::IpAddr>,
+		username: &str,
+		settings: &crate::http_server::SettingsOauthThrottling,
+		now: std::time::Instant,
+	) -> Result<(), LoginRefusal> {
+		let forget_after = std::time::Duration::from_secs(
+			settings.lockout_seconds.max(settings.backoff_max_seconds),
+		);
+		let is_remembered = |failures: &Failures| {
+			failures.pending > 0 || now.duration_since(failures.last) < forget_after
+		};
+		self.by_ip.retain(|_, failures| is_remembered(failures));
+		self.by_username
+			.retain(|_, failures| is_remembered(failures));
+
+		if let Some(failures) = self.by_username.get(username) {
+			if failures.count.saturating_add(failures.pending) >= settings.lockout_failures {
+				let unlock =
+					failures.last + std::time::Duration::from_secs(settings.lockout_seconds);
+				if unlock > now {
+					return Err(LoginRefusal::AccountLocked(unlock - now));
+				}
+			}
+		}
+
+		let mut wait = std::time::Duration::ZERO;
+		for failures in [
+			ip.and_then(|ip| self.by_ip.get(&ip)),
+			self.by_username.get(username),
+		]
+		.into_iter()
+		.flatten()
+		{
+			let next_attempt = failures.last + backoff(failures.count, settings);
+			if next_attempt > now {
+				wait = wait.max(next_attempt - now);
+			}
+		}
+
+		if wait > std::time::Duration::ZERO {
+			return Err(LoginRefusal::TooManyAttempts(wait));
+		}
+
+		let reserve = |failures: &mut Failures| {
+			failures.pending = failures.pending.saturating_add(1);
+		};
+		if let Some(ip) = ip {
+			reserve(self.by_ip.entry(ip).or_insert(Failures {
+				count: 0,
+				pending: 0,
+				last: now,
+			}));
+		}
+		reserve(
+			self.by_username
+				.entry(String::from(username))
+				.or_insert(Failures {
+					count: 0,
+					pending: 0,
+					last: now,
+				}),
+		);
+
+		return Ok(());
+	}
+
+	/// Settles a reserved attempt as a failed login, and returns the count of failures for this IP address and for this username.
+	pub fn register_failure(&mut self, ip: Option<std::net::IpAddr>, username: &str) -> (u32, u32) {
+		self.register_failure_at(ip, username, std::time::Instant::now())
+	}
+
+	fn register_failure_at(
+		&mut self,
+		ip: Option<std::net::IpAddr>,
+		username: &str,
+		now: std::time::Instant,
+	) -> (u32, u32) {
+		let increment = |failures: &mut Failures| {
+			failures.pending = failures.pending.saturating_sub(1);
+			failures.count = failures.count.saturating_add(1);
+			failures.last = now;
+			failures.count
+		};
+
+		let ip_count = match ip {
+			Some(ip) => increment(self.by_ip.entry(ip).or_insert(Failures {
+				count: 0,
+				pending: 0,
+				last: now,
+			})),
+			None => 0,
+		};
+		let username_count = increment(self.by_username.entry(String::from(username)).or_insert(
+			Failures {
+				count: 0,
+				pending: 0,
+				last: now,
+			},
+		));
+
+		return (ip_count, username_count);
+	}
+
+	/// Settles a reserved attempt as a successful login, which forgets previous failures once no
+	/// other attempt is still checked (their failures have to be counted from the previous ones).
+	pub fn register_success(&mut self, ip: Option<std::net::IpAddr>, username: &str) {
+		let settle = |failures: &mut Failures| {
+			failures.pending = failures.pending.saturating_sub(1);
+			if failures.pending == 0 {
+				failures.count = 0;
+			}
+		};
+
+		if let Some(failures) = ip.and_then(|ip| self.by_ip.get_mut(&ip)) {
+			settle(failures);
+		}
+		if let Some(failures) = self.by_username.get_mut(username) {
+			settle(failures);
+		}
+	}
+}
+
+/// Delay to wait after `failures` failed logins : nothing for the first free attempts, and then
+/// doubled on each new failure.
+fn backoff(
+	failures: u32,
+	settings: &crate::http_server::SettingsOauthThrottling,
+) -> std::time::Duration {
+	if failures < settings.free_attempts {
+		return std::time::Duration::ZERO;
+	}
+
+	let factor = 1_u64
+		.checked_shl(failures - settings.free_attempts)
+		.unwrap_or(u64::MAX);
+
+	return std::time::Duration::from_secs(
+		settings
+			.backoff_base_seconds
+			.saturating_mul(factor)
+			.min(settings.backoff_max_seconds),
+	);
+}
+
+#[test]
+fn n4vr7kq2bxw0sfe() {
+	let settings = crate::http_server::SettingsOauthThrottling {
+		free_attempts: 2,
+		backoff_base_seconds: 10,
+		backoff_max_seconds: 60,
+		lockout_failures: 100,
+		lockout_seconds: 600,
+	};
+
+	assert_eq!(backoff(0, &settings), std::time::Duration::ZERO);
+	assert_eq!(backoff(1, &settings), std::time::Duration::ZERO);
+	assert_eq!(backoff(2, &settings), std::time::Duration::from_secs(10));
+	assert_eq!(backoff(3, &settings), std::time::Duration::from_secs(20));
+	assert_eq!(backoff(4, &settings), std::time::Duration::from_secs(40));
+	assert_eq!(backoff(5, &settings), std::time::Duration::from_secs(60));
+	assert_eq!(backoff(200, &settings), std::time::Duration::from_secs(60));
+}
+
+#[test]
+fn jy8c1hd5mwaqz6p() {
+	let settings = crate::http_server::SettingsOauthThrottling {
+		free_attempts: 2,
+		backoff_base_seconds: 10,
+		backoff_max_seconds: 60,
+		lockout_failures: 100,
+		lockout_seconds: 600,
+	};
+	let ip: std::net::IpAddr = "192.168.0.1".parse().unwrap();
+	let other_ip: std::net::IpAddr = "192.168.0.2".parse().unwrap();
+	let start = std::time::Instant::now();
+
+	let mut attempts = LoginAttempts::new();
+
+	assert_eq!(
+		attempts.reserve_at(Some(ip), "user", &settings, start),
+		Ok(())
+	);
+	assert_eq!(
+		attempts.register_failure_at(Some(ip), "user", start),
+		(1, 1)
+	);
+	assert_eq!(
+		attempts.reserve_at(Some(ip), "other", &settings, start),
+		Ok(())
+	);
+	assert_eq!(
+		attempts.register_failure_at(Some(ip), "other", start),
+		(2, 1)
+	);
+
+	// IP address is slowed down, even for other usernames
+	assert_eq!(
+		attempts.reserve_at(Some(ip), "third", &settings, start),
+		Err(LoginRefusal::TooManyAttempts(
+			std::time::Duration::from_secs(10)
+		))
+	);
+	assert_eq!(
+		attempts.reserve_at(
+			Some(ip),
+			"third",
+			&settings,
+			start + std::time::Duration::from_secs(10)
+		),
+		Ok(())
+	);
+	assert_eq!(
+		attempts.register_failure_at(Some(ip), "third", start),
+		(3, 1)
+	);
+
+	// username is slowed down, even from other IP addresses
+	assert_eq!(
+		attempts.reserve_at(Some(other_ip), "user", &settings, start),
+		Ok(())
+	);
+	assert_eq!(
+		attempts.register_failure_at(Some(other_ip), "user", start),
+		(1, 2)
+	);
+	assert_eq!(
+		attempts.reserve_at(Some(other_ip), "user", &settings, start),
+		Err(LoginRefusal::TooManyAttempts(
+			std::time::Duration::from_secs(10)
+		))
+	);
+
+	attempts.register_success(Some(ip), "user");
+	assert_eq!(
+		attempts.reserve_at(Some(ip), "user", &settings, start),
+		Ok(())
+	);
+	assert_eq!(
+		attempts.reserve_at(Some(other_ip), "user", &settings, start),
+		Ok(())
+	);
+}
+
+#[test]
+fn w3fz8kc5nqd1xhr() {
+	let settings = crate::http_server::SettingsOauthThrottling {
+		free_attempts: 2,
+		backoff_base_seconds: 10,
+		backoff_max_seconds: 60,
+		lockout_failures: 3,
+		lockout_seconds: 600,
+	};
+	let ip: std::net::IpAddr = "192.168.0.1".parse().unwrap();
+	let start = std::time::Instant::now();
+
+	let mut attempts = LoginAttempts::new();
+
+	// concurrent attempts, whose credentials are still checked, only count for the lockout
+	for _ in 0..3 {
+		assert_eq!(
+			attempts.reserve_at(Some(ip), "user", &settings, start),
+			Ok(())
+		);
+	}
+	assert_eq!(
+		attempts.reserve_at(Some(ip), "user", &settings, start),
+		Err(LoginRefusal::AccountLocked(std::time::Duration::from_secs(
+			600
+		)))
+	);
+
+	// a successful attempt does not forget failures of the other ones
+	assert_eq!(
+		attempts.register_failure_at(Some(ip), "user", start),
+		(1, 1)
+	);
+	attempts.register_success(Some(ip), "user");
+	assert_eq!(
+		attempts.register_failure_at(Some(ip), "user", start),
+		(2, 2)
+	);
+	assert_eq!(
+		attempts.reserve_at(Some(ip), "user", &settings, start),
+		Err(LoginRefusal::TooManyAttempts(
+			std::time::Duration::from_secs(10)
+		))
+	);
+
+	// pending attempts are not forgotten, and count for the lockout
+	let later = start + std::time::Duration::from_secs(3600);
+	assert_eq!(attempts.reserve_at(None, "other", &settings, later), Ok(()));
+	assert_eq!(attempts.reserve_at(None, "other", &settings, later), Ok(()));
+	assert_eq!(
+		attempts.reserve_at(
+			None,
+			"other",
+			&settings,
+			later + std::time::Duration::from_secs(3600)
+		),
+		Ok(())
+	);
+	assert_eq!(
+		attempts.reserve_at(None, "other", &settings, later),
+		Err(LoginRefusal::AccountLocked(std::time::Duration::from_secs(
+			600
+		)))
+	);
+}
+
+#[test]
+fn e0ukd6ghz3tl5ba() {
+	let settings = crate::http_server::SettingsOauthThrottling {
+		free_attempts: 100,
+		backoff_base_seconds: 0,
+		backoff_max_seconds: 0,
+		lockout_failures: 3,
+		lockout_seconds: 600,
+	};
+	let start = std::time::Instant::now();
+
+	let mut attempts = LoginAttempts::new();
+
+	for _ in 0..3 {
+		assert_eq!(attempts.reserve_at(None, "user", &settings, start), Ok(()));
+		attempts.register_failure_at(None, "user", start);
+	}
+
+	assert_eq!(
+		attempts.reserve_at(
+			None,
+			"user",
+			&settings,
+			start + std::time::Duration::from_secs(60)
+		),
+		Err(LoginRefusal::AccountLocked(std::time::Duration::from_secs(
+			540
+		)))
+	);
+	assert_eq!(attempts.reserve_at(None, "other", &settings, start), Ok(()));
+	assert_eq!(
+		attempts.reserve_at(
+			None,
+			"user",
+			&settings,
+			start + std::time::Duration::from_secs(600)
+		),
+		Ok(())
+	);
+}
@@ -6,11 +6,19 @@ pub struct AccessBearer {
 	name: String,
 	scopes: Vec<crate::scope::Scope>,
 	client_id: String,
+	client_origin: Option<String>,
 	username: String,
 	emit_time: std::time::Instant,
 }
 impl AccessBearer {
-	pub fn new(scopes: Vec<crate::scope::Scope>, client_id: &str, username: &str) -> Self {
+	/// `redirect_uri` is the one requested by the client when this token is issued :
+	/// its origin is recorded in order to refuse browser requests from other web sites.
+	pub fn new(
+		scopes: Vec<crate::scope::Scope>,
+		client_id: &str,
+		redirect_uri: &str,
+		username: &str,
+	) -> Self {
 		let mut name = String::new();
 
 		let mut rng_limit = rand::thread_rng();
@@ -29,6 +37,7 @@ impl AccessBearer {
 			name,
 			scopes,
 			client_id: String::from(client_id),
+			client_origin: crate::http_server::utils::origin_of(redirect_uri),
 			username: String::from(username),
 			emit_time: std::time::Instant::now(),
 		}
@@ -40,11 +49,12 @@ impl AccessBearer {
 	pub fn get_scopes(&self) -> &[crate::scope::Scope] {
 		&self.scopes
 	}
-	/*
 	pub fn get_client_id(&self) -> &str {
 		&self.client_id
 	}
-	*/
+	pub fn get_client_origin(&self) -> Option<&str> {
+		self.client_origin.as_deref()
+	}
 	pub fn get_username(&self) -> &str {
 		&self.username
 	}
@@ -57,12 +67,26 @@ impl AccessBearer {
 		max_token_lifetime_seconds: u64,
 		method: &actix_web::http::Method,
 		path: impl Into<String>,
+		request_origin: Option<&str>,
 	) -> Result<bool, String> {
-		// TODO : check token validity with client_id
-
 		if (std::time::Instant::now() - *self.get_emit_time())
 			< std::time::Duration::from_secs(max_token_lifetime_seconds)
 		{
+			// requests without `Origin` header does not come from a browser, so they can not be
+			// forged by another web site.
+			if let (Some(client_origin), Some(request_origin)) =
+				(self.get_client_origin(), request_origin)
+			{
+				if crate::http_server::utils::origin_of(request_origin).as_deref()
+					!= Some(client_origin)
+				{
+					return Err(format!(
+						"token of `{}` used from another origin : `{}`",
+						client_origin, request_origin
+					));
+				}
+			}
+
 			let path = path.into();
 
 			Ok(self
@@ -75,10 +99,162 @@ impl AccessBearer {
 	}
 }
 
+/// Short-lived code issued by the authorization code flow (`response_type=code`),
+/// which can be exchanged once for an [`AccessBearer`] on the token endpoint.
+///
+/// Only PKCE with the `S256` method is supported.
+#[derive(Debug, Clone)]
+pub struct AuthorizationCode {
+	value: String,
+	scopes: Vec<crate::scope::Scope>,
+	client_id: String,
+	redirect_uri: String,
+	username: String,
+	code_challenge: String,
+	emit_time: std::time::Instant,
+}
+impl AuthorizationCode {
+	pub fn new(
+		scopes: Vec<crate::scope::Scope>,
+		client_id: &str,
+		redirect_uri: &str,
+		username: &str,
+		code_challenge: &str,
+	) -> Self {
+		let mut value = String::new();
+
+		let mut rng_limit = rand::thread_rng();
+		for _ in 1..rng_limit.gen_range(64..96) {
+			let mut rng_item = rand::thread_rng();
+			value.push(
+				crate::http_server::ACCESS_TOKEN_ALPHABET
+					.chars()
+					.choose(&mut rng_item)
+					.unwrap(),
+			);
+		}
+
+		Self {
+			value,
+			scopes,
+			client_id: String::from(client_id),
+			redirect_uri: String::from(redirect_uri),
+			username: String::from(username),
+			code_challenge: String::from(code_challenge),
+			emit_time: std::time::Instant::now(),
+		}
+	}
+
+	pub fn get_value(&self) -> &str {
+		&self.value
+	}
+	pub fn get_scopes(&self) -> &[crate::scope::Scope] {
+		&self.scopes
+	}
+	pub fn get_client_id(&self) -> &str {
+		&self.client_id
+	}
+	pub fn get_redirect_uri(&self) -> &str {
+		&self.redirect_uri
+	}
+	pub fn get_username(&self) -> &str {
+		&self.username
+	}
+
+	pub fn has_expirated(&self) -> bool {
+		(std::time::Instant::now() - self.emit_time) > std::time::Duration::from_secs(60)
+	}
+
+	/// Checks `code_verifier` against the `code_challenge` sent in the authorization request,
+	/// as described in [RFC 7636 section 4.6](https://datatracker.ietf.org/doc/html/rfc7636#section-4.6).
+	pub fn check_code_verifier(&self, code_verifier: &str) -> bool {
+		let hash = <sha2::Sha256 as sha2::Digest>::digest(code_verifier.as_bytes());
+
+		base64::encode_config(hash, base64::URL_SAFE_NO_PAD) == self.code_challenge
+	}
+
+	pub fn into_access_bearer(self) -> AccessBearer {
+		AccessBearer::new(
+			self.scopes,
+			&self.client_id,
+			&self.redirect_uri,
+			&self.username,
+		)
+	}
+}
+
+/// Session of an user, opened after it typed its password, which allows to use the account page
+/// and skip the consent of already allowed applications without asking its password again.
+///
+/// Its value is only sent in an `HttpOnly` cookie, so it is never written in pages (unlike
+/// [`OauthFormToken`](crate::http_server::middlewares::OauthFormToken)s, which should not give
+/// access to a session if a page leaks).
+#[derive(Debug, Clone)]
+pub struct Session {
+	value: String,
+	username: String,
+	ip: std::net::SocketAddr,
+	emit_time: std::time::Instant,
+}
+impl Session {
+	pub fn new(ip: std::net::SocketAddr, username: &str) -> Self {
+		let mut value = String::new();
+
+		let mut rng_limit = rand::thread_rng();
+		for _ in 1..rng_limit.gen_range(64..128) {
+			let mut rng_item = rand::thread_rng();
+			value.push(
+				crate::http_server::ACCESS_TOKEN_ALPHABET
+					.chars()
+					.choose(&mut rng_item)
+					.unwrap(),
+			);
+		}
+
+		Self {
+			value,
+			username: String::from(username),
+			ip,
+			emit_time: std::time::Instant::now(),
+		}
+	}
+
+	pub fn get_value(&self) -> &str {
+		&self.value
+	}
+	pub fn get_username(&self) -> &str {
+		&self.username
+	}
+
+	pub fn is_from(&self, ip: &std::net::SocketAddr) -> bool {
+		self.ip.ip() == ip.ip()
+	}
+
+	pub fn has_expirated(&self) -> bool {
+		(std::time::Instant::now() - self.emit_time) >= std::time::Duration::from_secs(30 * 60)
+	}
+}
+
 #[cfg(test)]
 mod tests {
 	use crate::scope::{ScopeParsingError, ScopeRightType};
 
+	#[test]
+	fn ql3vuh0x2z7ge() {
+		// example from RFC 7636, appendix B
+		let code = super::AuthorizationCode::new(
+			vec![],
+			"https://app.example.com",
+			"https://app.example.com/",
+			"user",
+			"E9Melhoa2OwvFrEMTJguCHaoeK1t8URWbuGJSstw-cM",
+		);
+
+		assert!(code.check_code_verifier("dBjftJeZ4CVP-mB92K27uhbUJU1p1r_wW1gFWFOEjXk"));
+		assert!(!code.check_code_verifier("dBjftJeZ4CVP-mB92K27uhbUJU1p1r_wW1gFWFOEjXK"));
+		assert!(!code.check_code_verifier("E9Melhoa2OwvFrEMTJguCHaoeK1t8URWbuGJSstw-cM"));
+	}
+
 	#[test]
 	fn c0ok0eil7m3() {
 		assert_eq!(
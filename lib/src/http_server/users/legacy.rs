@@ -0,0 +1,102 @@
+/// Users list as saved by the previous versions, without header in the file.
+#[derive(serde::Deserialize)]
+pub struct LegacyUsers {
+	salt: String,
+	list: Vec<LegacyUser>,
+}
+
+#[derive(serde::Deserialize)]
+struct LegacyUser {
+	name: String,
+	rights: Vec<super::UserRight>,
+	hashed_password: Vec<u8>,
+}
+
+impl From<LegacyUsers> for super::Users {
+	fn from(legacy: LegacyUsers) -> Self {
+		let mut result = Self::new();
+		result.legacy_salt = legacy.salt;
+		result.list = legacy
+			.list
+			.into_iter()
+			.map(|user| super::User {
+				name: user.name,
+				rights: user.rights,
+				password: super::UserPassword::Legacy(user.hashed_password),
+				grants: vec![],
+				totp: None,
+			})
+			.collect();
+
+		result
+	}
+}
+
+/// Users list as saved in the version 1 of the users file, before grants of applications.
+#[derive(serde::Deserialize)]
+pub struct UsersV1 {
+	legacy_salt: String,
+	list: Vec<UserV1>,
+}
+
+#[derive(serde::Deserialize)]
+struct UserV1 {
+	name: String,
+	rights: Vec<super::UserRight>,
+	password: super::UserPassword,
+}
+
+impl From<UsersV1> for super::Users {
+	fn from(users: UsersV1) -> Self {
+		let mut result = Self::new();
+		result.legacy_salt = users.legacy_salt;
+		result.list = users
+			.list
+			.into_iter()
+			.map(|user| super::User {
+				name: user.name,
+				rights: user.rights,
+				password: user.password,
+				grants: vec![],
+				totp: None,
+			})
+			.collect();
+
+		result
+	}
+}
+
+/// Users list as saved in the version 2 of the users file, before two-factor authentication.
+#[derive(serde::Deserialize)]
+pub struct UsersV2 {
+	legacy_salt: String,
+	list: Vec<UserV2>,
+}
+
+#[derive(serde::Deserialize)]
+struct UserV2 {
+	name: String,
+	rights: Vec<super::UserRight>,
+	password: super::UserPassword,
+	grants: Vec<super::UserGrant>,
+}
+
+impl From<UsersV2> for super::Users {
+	fn from(users: UsersV2) -> Self {
+		let mut result = Self::new();
+		result.legacy_salt = users.legacy_salt;
+		result.list = users
+			.list
+			.into_iter()
+			.map(|user| super::User {
+				name: user.name,
+				rights: user.rights,
+				password: user.password,
+				grants: user.grants,
+				totp: None,
+			})
+			.collect();
+
+		result
+	}
+}
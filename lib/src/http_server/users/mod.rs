@@ -1,97 +1,639 @@
+mod legacy;
+mod password;
+pub(crate) mod totp;
 mod user;
 
-use user::User;
-pub use user::UserRight;
+use user::{User, UserPassword, UserTotp};
+pub use user::{UserGrant, UserRight};
 
-use rand::seq::IteratorRandom;
-use rand::Rng;
+/// Header of the users file, followed by its format version and then the bincode of [`Users`].
+///
+/// Files without this header are from previous versions, see [`legacy::LegacyUsers`].
+const USERS_FILE_MAGIC: &[u8] = b"PONTUS_ONYX_USERS";
+const USERS_FILE_VERSION: u8 = 3;
 
-#[derive(serde::Deserialize, serde::Serialize)]
+#[derive(serde::Deserialize, serde::Serialize, Clone)]
 pub struct Users {
-	salt: String,
+	/// Only used to check passwords of users who did not log in since previous versions.
+	legacy_salt: String,
 	list: Vec<User>,
+	#[serde(skip)]
+	password_hashing: crate::http_server::SettingsPasswordHashing,
 }
+
+/// Format of the users file, from its extension.
+///
+/// TOML and JSON ones can be edited by hand : they contain the same data than the bincode one,
+/// with bytes (like hashes) as hexadecimal strings.
+#[derive(Debug, PartialEq, Eq, Clone, Copy)]
+pub enum UsersFileFormat {
+	Bincode,
+	Toml,
+	Json,
+}
+impl UsersFileFormat {
+	pub fn from_path(path: &std::path::Path) -> Self {
+		match path.extension().and_then(|extension| extension.to_str()) {
+			Some("toml") => Self::Toml,
+			Some("json") => Self::Json,
+			_ => Self::Bincode,
+		}
+	}
+
+	pub fn extension(&self) -> &'static str {
+		match self {
+			Self::Bincode => "bin",
+			Self::Toml => "toml",
+			Self::Json => "json",
+		}
+	}
+}
+impl std::str::FromStr for UsersFileFormat {
+	type Err = String;
+
+	fn from_str(value: &str) -> Result<Self, Self::Err> {
+		match value {
+			"bin" | "bincode" => Ok(Self::Bincode),
+			"toml" => Ok(Self::Toml),
+			"json" => Ok(Self::Json),
+			_ => Err(format!(
+				"unknown users file format `{value}`, expected `toml`, `json` or `bin`"
+			)),
+		}
+	}
+}
+
+/// Reasons why a change of [`Users`] is refused.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum UsersError {
+	UserNotFound,
+	UserAlreadyExists,
+	RightAlreadyGranted,
+	RightNotGranted,
+	GrantNotFound,
+	TotpAlreadyEnabled,
+	TotpNotStarted,
+	WrongTotpCode,
+	/// The new password can not be hashed, for example with wrong `password_hashing` settings.
+	PasswordHashing(String),
+}
+impl std::fmt::Display for UsersError {
+	fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+		match self {
+			Self::UserNotFound => write!(f, "user not found"),
+			Self::UserAlreadyExists => write!(f, "this username already exists"),
+			Self::RightAlreadyGranted => write!(f, "user have already this right"),
+			Self::RightNotGranted => write!(f, "user does not have already this right"),
+			Self::GrantNotFound => write!(f, "grant not found"),
+			Self::TotpAlreadyEnabled => write!(f, "two-factor authentication already enabled"),
+			Self::TotpNotStarted => write!(f, "two-factor authentication not started"),
+			Self::WrongTotpCode => write!(f, "wrong code"),
+			Self::PasswordHashing(e) => write!(f, "can not hash password : {e}"),
+		}
+	}
+}
+
+#[derive(Debug, PartialEq, Eq)]
+pub enum PasswordCheck {
+	Wrong,
+	Correct,
+	/// The password is correct, and its hash has been upgraded, so the users list should be saved.
+	Rehashed,
+}
+impl PasswordCheck {
+	pub fn is_correct(&self) -> bool {
+		*self != Self::Wrong
+	}
+}
+
+/// Copy of the password of a user, in order to verify it without locking [`Users`], see
+/// [`Users::check_shared`].
+struct PasswordVerification {
+	username: String,
+	/// `None` for unknown users.
+	password: Option<UserPassword>,
+	legacy_salt: String,
+	password_hashing: crate::http_server::SettingsPasswordHashing,
+}
+impl PasswordVerification {
+	fn verify(self, password: &mut String) -> VerificationOutcome {
+		let (is_correct, needs_rehash) = match &self.password {
+			Some(UserPassword::Legacy(hashed_password)) => (
+				*hashed_password == password::legacy_hash(&self.legacy_salt, password),
+				true,
+			),
+			Some(UserPassword::Argon2(password_hash)) => (
+				password::verify(password, password_hash),
+				password::needs_rehash(password_hash, &self.password_hashing),
+			),
+			None => {
+				// takes the same time than for known users, so usernames can not be guessed
+				password::verify(password, &password::dummy_hash(&self.password_hashing));
+				(false, false)
+			}
+		};
+
+		let result = match self.password {
+			_ if !is_correct => VerificationOutcome::Checked(PasswordCheck::Wrong),
+			Some(previous_password) if needs_rehash => {
+				match password::hash(password, &self.password_hashing) {
+					Ok(password_hash) => VerificationOutcome::Rehashed {
+						username: self.username,
+						previous_password,
+						password_hash,
+					},
+					Err(_) => VerificationOutcome::Checked(PasswordCheck::Correct),
+				}
+			}
+			_ => VerificationOutcome::Checked(PasswordCheck::Correct),
+		};
+
+		zeroize::Zeroize::zeroize(password);
+
+		result
+	}
+}
+
+enum VerificationOutcome {
+	Checked(PasswordCheck),
+	/// The password is correct, and its new hash should replace `previous_password`.
+	Rehashed {
+		username: String,
+		previous_password: UserPassword,
+		password_hash: String,
+	},
+}
+
+#[derive(Debug, PartialEq, Eq)]
+pub enum SecondFactorCheck {
+	Wrong,
+	/// This user does not use two-factor authentication.
+	Disabled,
+	/// The code (or a recovery code) is correct and can not be used again, so the users list
+	/// should be saved.
+	Correct,
+}
+impl SecondFactorCheck {
+	pub fn is_correct(&self) -> bool {
+		*self != Self::Wrong
+	}
+}
+
 impl Users {
 	pub fn new() -> Self {
-		let mut salt = String::new();
-		let mut rng_limit = rand::thread_rng();
-		for _ in 1..rng_limit.gen_range(16..32) {
-			let mut rng_item = rand::thread_rng();
-			salt.push(
-				crate::http_server::PASSWORD_HASH_ALPHABET
-					.chars()
-					.choose(&mut rng_item)
-					.unwrap(),
-			);
+		Self {
+			legacy_salt: String::new(),
+			list: vec![],
+			password_hashing: crate::http_server::SettingsPasswordHashing::default(),
 		}
+	}
 
-		Self { salt, list: vec![] }
+	pub fn from_bytes(bytes: &[u8]) -> Result<Self, String> {
+		match bytes.strip_prefix(USERS_FILE_MAGIC) {
+			Some(content) => match content.split_first() {
+				Some((&USERS_FILE_VERSION, content)) => {
+					bincode::deserialize::<Self>(content).map_err(|e| e.to_string())
+				}
+				Some((2, content)) => bincode::deserialize::<legacy::UsersV2>(content)
+					.map(Self::from)
+					.map_err(|e| e.to_string()),
+				Some((1, content)) => bincode::deserialize::<legacy::UsersV1>(content)
+					.map(Self::from)
+					.map_err(|e| e.to_string()),
+				Some((version, _)) => Err(format!("unsupported users file version {version}")),
+				None => Err(String::from("missing users file version")),
+			},
+			None => bincode::deserialize::<legacy::LegacyUsers>(bytes)
+				.map(Self::from)
+				.map_err(|e| e.to_string()),
+		}
 	}
 
-	pub fn check(&self, username: &str, password: &mut String) -> bool {
-		let mut hasher = hmac_sha512::Hash::new();
-		hasher.update(self.salt.as_bytes());
-		hasher.update(password.as_bytes());
-		hasher.update(self.salt.as_bytes());
-		let hashed_password = hasher.finalize().to_vec();
+	pub fn to_bytes(&self) -> Vec<u8> {
+		let mut result = USERS_FILE_MAGIC.to_vec();
+		result.push(USERS_FILE_VERSION);
+		result.append(&mut bincode::serialize(self).unwrap());
 
-		zeroize::Zeroize::zeroize(password);
+		result
+	}
+
+	/// Errors of human-readable formats contain the line and column of the issue.
+	pub fn parse(bytes: &[u8], format: UsersFileFormat) -> Result<Self, String> {
+		match format {
+			UsersFileFormat::Bincode => Self::from_bytes(bytes),
+			UsersFileFormat::Toml => std::str::from_utf8(bytes)
+				.map_err(|e| e.to_string())
+				.and_then(|content| toml::from_str(content).map_err(|e| e.to_string())),
+			UsersFileFormat::Json => serde_json::from_slice(bytes).map_err(|e| e.to_string()),
+		}
+	}
+
+	pub fn serialize(&self, format: UsersFileFormat) -> Result<Vec<u8>, String> {
+		match format {
+			UsersFileFormat::Bincode => Ok(self.to_bytes()),
+			UsersFileFormat::Toml => toml::to_string_pretty(self)
+				.map(String::into_bytes)
+				.map_err(|e| e.to_string()),
+			UsersFileFormat::Json => serde_json::to_vec_pretty(self).map_err(|e| e.to_string()),
+		}
+	}
+
+	/// Reads the users file at `path`, in the format of its extension.
+	///
+	/// The error is [`std::io::ErrorKind::NotFound`] only if this file does not exist.
+	pub fn load(path: &std::path::Path) -> Result<Self, std::io::Error> {
+		let bytes = std::fs::read(path)?;
+
+		Self::parse(&bytes, UsersFileFormat::from_path(path)).map_err(|e| {
+			std::io::Error::new(
+				std::io::ErrorKind::InvalidData,
+				format!("can not parse `{}` : {}", path.to_string_lossy(), e),
+			)
+		})
+	}
+
+	/// Writes the users file at `path`, in the format of its extension.
+	pub fn save(&self, path: &std::path::Path) -> Result<(), String> {
+		crate::http_server::utils::write_atomically(
+			path,
+			&self.serialize(UsersFileFormat::from_path(path))?,
+		)
+	}
+
+	/// Sets cost parameters of new password hashes.
+	///
+	/// Hashes with other parameters are upgraded on next successful login of their user.
+	pub fn set_password_hashing(
+		&mut self,
+		password_hashing: crate::http_server::SettingsPasswordHashing,
+	) {
+		self.password_hashing = password_hashing;
+	}
+
+	pub fn check(&mut self, username: &str, password: &mut String) -> PasswordCheck {
+		let outcome = self.password_verification(username).verify(password);
+
+		return self.apply_verification(outcome);
+	}
+
+	/// Same as [`check`](Self::check), but `users` are only locked to copy the password hash, and
+	/// to save it if it is upgraded, not while it is verified (which is slow on purpose).
+	pub fn check_shared(
+		users: &std::sync::Mutex<Self>,
+		username: &str,
+		password: &mut String,
+	) -> PasswordCheck {
+		let verification = users.lock().unwrap().password_verification(username);
+
+		return match verification.verify(password) {
+			VerificationOutcome::Checked(result) => result,
+			outcome => users.lock().unwrap().apply_verification(outcome),
+		};
+	}
+
+	fn password_verification(&self, username: &str) -> PasswordVerification {
+		PasswordVerification {
+			username: String::from(username),
+			password: self
+				.list
+				.iter()
+				.find(|user| user.name == username)
+				.map(|user| user.password.clone()),
+			legacy_salt: self.legacy_salt.clone(),
+			password_hashing: self.password_hashing.clone(),
+		}
+	}
+
+	fn apply_verification(&mut self, outcome: VerificationOutcome) -> PasswordCheck {
+		let (username, previous_password, password_hash) = match outcome {
+			VerificationOutcome::Checked(result) => return result,
+			VerificationOutcome::Rehashed {
+				username,
+				previous_password,
+				password_hash,
+			} => (username, previous_password, password_hash),
+		};
+
+		match self.list.iter_mut().find(|user| user.name == username) {
+			// otherwise, the password has been changed while it was verified
+			Some(user) if user.password == previous_password => {
+				user.password = UserPassword::Argon2(password_hash);
+			}
+			_ => return PasswordCheck::Correct,
+		}
 
-		match self.list.iter().find(|user| user.name == username) {
-			Some(user) => user.hashed_password == hashed_password,
-			None => false,
+		if !self
+			.list
+			.iter()
+			.any(|user| matches!(user.password, UserPassword::Legacy(_)))
+		{
+			self.legacy_salt.clear();
 		}
+
+		PasswordCheck::Rehashed
 	}
 
 	pub fn get_usernames(&self) -> Vec<&String> {
 		self.list.iter().map(|user| &user.name).collect()
 	}
 
-	pub fn insert(&mut self, username: &str, password: &mut String) -> Result<(), String> {
-		let mut hasher = hmac_sha512::Hash::new();
-		hasher.update(self.salt.as_bytes());
-		hasher.update(password.as_bytes());
-		hasher.update(self.salt.as_bytes());
-
-		zeroize::Zeroize::zeroize(password);
+	pub fn get_rights(&self, username: &str) -> Option<&Vec<UserRight>> {
+		self.list
+			.iter()
+			.find(|user| user.name == username)
+			.map(|user| &user.rights)
+	}
 
+	pub fn insert(&mut self, username: &str, password: &mut String) -> Result<(), UsersError> {
 		if self.list.iter().any(|user| user.name == username) {
-			return Err(String::from("this username already exists"));
+			zeroize::Zeroize::zeroize(password);
+			return Err(UsersError::UserAlreadyExists);
 		}
 
+		let password_hash = password::hash(password, &self.password_hashing);
+		zeroize::Zeroize::zeroize(password);
+
 		self.list.push(User {
 			name: String::from(username),
 			rights: vec![],
-			hashed_password: hasher.finalize().to_vec(),
+			password: UserPassword::Argon2(password_hash.map_err(UsersError::PasswordHashing)?),
+			grants: vec![],
+			totp: None,
 		});
 
 		return Ok(());
 	}
 
-	pub fn add_right(&mut self, username: &str, right: UserRight) -> Result<(), String> {
+	pub fn remove(&mut self, username: &str) -> Result<(), UsersError> {
+		match self.list.iter().position(|user| user.name == username) {
+			Some(position) => {
+				self.list.remove(position);
+				Ok(())
+			}
+			None => Err(UsersError::UserNotFound),
+		}
+	}
+
+	pub fn set_password(
+		&mut self,
+		username: &str,
+		password: &mut String,
+	) -> Result<(), UsersError> {
+		let password_hash = password::hash(password, &self.password_hashing);
+		zeroize::Zeroize::zeroize(password);
+
+		match self.list.iter_mut().find(|user| user.name == username) {
+			Some(user) => {
+				user.password =
+					UserPassword::Argon2(password_hash.map_err(UsersError::PasswordHashing)?);
+				Ok(())
+			}
+			None => Err(UsersError::UserNotFound),
+		}
+	}
+
+	pub fn add_right(&mut self, username: &str, right: UserRight) -> Result<(), UsersError> {
 		match self.list.iter_mut().find(|user| user.name == username) {
 			Some(user) => {
 				if !user.rights.contains(&right) {
 					user.rights.push(right);
 					Ok(())
 				} else {
-					Err(String::from("user have already this right"))
+					Err(UsersError::RightAlreadyGranted)
 				}
 			}
-			None => Err(String::from("user not found")),
+			None => Err(UsersError::UserNotFound),
 		}
 	}
 
-	pub fn remove_right(&mut self, username: &str, right: UserRight) -> Result<(), String> {
+	pub fn remove_right(&mut self, username: &str, right: UserRight) -> Result<(), UsersError> {
 		match self.list.iter_mut().find(|user| user.name == username) {
-			Some(user) => match user.rights.binary_search(&right) {
-				Ok(position) => {
+			Some(user) => match user
+				.rights
+				.iter()
+				.position(|user_right| *user_right == right)
+			{
+				Some(position) => {
 					user.rights.remove(position);
 					Ok(())
 				}
-				Err(_) => Err(String::from("user does not have already this right")),
+				None => Err(UsersError::RightNotGranted),
 			},
-			None => Err(String::from("user not found")),
+			None => Err(UsersError::UserNotFound),
+		}
+	}
+
+	pub fn get_grants(&self, username: &str) -> Option<&Vec<UserGrant>> {
+		self.list
+			.iter()
+			.find(|user| user.name == username)
+			.map(|user| &user.grants)
+	}
+
+	/// Returns `true` if `username` already allowed all these `scopes` to this client.
+	pub fn is_granted(
+		&self,
+		username: &str,
+		client_id: &str,
+		scopes: &[crate::scope::Scope],
+	) -> bool {
+		self.get_grants(username)
+			.and_then(|grants| grants.iter().find(|grant| grant.client_id == client_id))
+			.map(|grant| grant.covers(scopes))
+			.unwrap_or(false)
+	}
+
+	/// Remembers that `username` allowed these `scopes` to this client, in addition to the ones it
+	/// already allowed.
+	pub fn add_grant(
+		&mut self,
+		username: &str,
+		client_id: &str,
+		scopes: &[crate::scope::Scope],
+	) -> Result<(), UsersError> {
+		match self.list.iter_mut().find(|user| user.name == username) {
+			Some(user) => {
+				let position = match user
+					.grants
+					.iter()
+					.position(|grant| grant.client_id == client_id)
+				{
+					Some(position) => position,
+					None => {
+						user.grants.push(UserGrant {
+							client_id: String::from(client_id),
+							scopes: vec![],
+						});
+						user.grants.len() - 1
+					}
+				};
+
+				let grant = &mut user.grants[position];
+				let mut granted_scopes = grant.get_scopes();
+				for scope in scopes {
+					if !granted_scopes
+						.iter()
+						.any(|granted_scope| granted_scope.includes(scope))
+					{
+						granted_scopes.retain(|granted_scope| !scope.includes(granted_scope));
+						granted_scopes.push(scope.clone());
+					}
+				}
+				grant.scopes = granted_scopes
+					.iter()
+					.map(|scope| scope.to_string())
+					.collect();
+
+				Ok(())
+			}
+			None => Err(UsersError::UserNotFound),
+		}
+	}
+
+	pub fn remove_grant(&mut self, username: &str, client_id: &str) -> Result<(), UsersError> {
+		match self.list.iter_mut().find(|user| user.name == username) {
+			Some(user) => match user
+				.grants
+				.iter()
+				.position(|grant| grant.client_id == client_id)
+			{
+				Some(position) => {
+					user.grants.remove(position);
+					Ok(())
+				}
+				None => Err(UsersError::GrantNotFound),
+			},
+			None => Err(UsersError::UserNotFound),
+		}
+	}
+
+	/// Returns `true` if `username` confirmed the setup of its two-factor authentication.
+	pub fn has_totp(&self, username: &str) -> bool {
+		self.list
+			.iter()
+			.find(|user| user.name == username)
+			.and_then(|user| user.totp.as_ref())
+			.map(|totp| totp.confirmed)
+			.unwrap_or(false)
+	}
+
+	/// Address of the secret for the authenticator application of `username`, only available
+	/// until the setup is confirmed.
+	pub fn get_totp_setup_uri(&self, username: &str, issuer: &str) -> Option<String> {
+		self.list
+			.iter()
+			.find(|user| user.name == username)
+			.and_then(|user| user.totp.as_ref())
+			.filter(|totp| !totp.confirmed)
+			.map(|totp| totp::uri(&totp.secret, issuer, username))
+	}
+
+	pub fn get_recovery_codes_count(&self, username: &str) -> usize {
+		self.list
+			.iter()
+			.find(|user| user.name == username)
+			.and_then(|user| user.totp.as_ref())
+			.map(|totp| totp.recovery_codes.len())
+			.unwrap_or_default()
+	}
+
+	/// Generates a new secret for the two-factor authentication of `username`, which is only
+	/// required after being confirmed with [`Users::confirm_totp`].
+	pub fn start_totp(&mut self, username: &str) -> Result<(), UsersError> {
+		match self.list.iter_mut().find(|user| user.name == username) {
+			Some(user) => {
+				if user
+					.totp
+					.as_ref()
+					.map(|totp| totp.confirmed)
+					.unwrap_or(false)
+				{
+					return Err(UsersError::TotpAlreadyEnabled);
+				}
+
+				user.totp = Some(UserTotp {
+					secret: totp::new_secret(),
+					confirmed: false,
+					last_step: 0,
+					recovery_codes: vec![],
+				});
+
+				Ok(())
+			}
+			None => Err(UsersError::UserNotFound),
+		}
+	}
+
+	/// Enables the two-factor authentication of `username` if `code` is correct, and returns its
+	/// new recovery codes, which can not be retrieved later.
+	pub fn confirm_totp(&mut self, username: &str, code: &str) -> Result<Vec<String>, UsersError> {
+		match self.list.iter_mut().find(|user| user.name == username) {
+			Some(user) => match user.totp.as_mut().filter(|totp| !totp.confirmed) {
+				Some(totp) => match totp::find_step(&totp.secret, code, totp::current_step()) {
+					Some(step) => {
+						let recovery_codes = totp::new_recovery_codes();
+
+						totp.confirmed = true;
+						totp.last_step = step;
+						totp.recovery_codes = recovery_codes
+							.iter()
+							.map(|recovery_code| totp::hash_recovery_code(recovery_code))
+							.collect();
+
+						Ok(recovery_codes)
+					}
+					None => Err(UsersError::WrongTotpCode),
+				},
+				None => Err(UsersError::TotpNotStarted),
+			},
+			None => Err(UsersError::UserNotFound),
+		}
+	}
+
+	/// Checks `code` (or a recovery code) if `username` enabled two-factor authentication.
+	pub fn check_totp(&mut self, username: &str, code: &str) -> SecondFactorCheck {
+		let totp = match self
+			.list
+			.iter_mut()
+			.find(|user| user.name == username)
+			.and_then(|user| user.totp.as_mut())
+			.filter(|totp| totp.confirmed)
+		{
+			Some(totp) => totp,
+			None => return SecondFactorCheck::Disabled,
+		};
+
+		if let Some(step) = totp::find_step(&totp.secret, code, totp::current_step()) {
+			if step > totp.last_step {
+				totp.last_step = step;
+				return SecondFactorCheck::Correct;
+			} else {
+				return SecondFactorCheck::Wrong;
+			}
+		}
+
+		let hash = totp::hash_recovery_code(code);
+		match totp
+			.recovery_codes
+			.iter()
+			.position(|recovery_code| *recovery_code == hash)
+		{
+			Some(position) => {
+				totp.recovery_codes.remove(position);
+				SecondFactorCheck::Correct
+			}
+			None => SecondFactorCheck::Wrong,
+		}
+	}
+
+	/// Disables the two-factor authentication of `username`, or cancels its setup.
+	pub fn reset_totp(&mut self, username: &str) -> Result<(), UsersError> {
+		match self.list.iter_mut().find(|user| user.name == username) {
+			Some(user) => {
+				user.totp = None;
+				Ok(())
+			}
+			None => Err(UsersError::UserNotFound),
 		}
 	}
 }
@@ -102,7 +644,7 @@ fn qsaeipdjit2zwcqlpx() {
 	assert_eq!(users.insert("user", &mut String::from("password")), Ok(()));
 	assert_eq!(
 		users.insert("user", &mut String::from("password")),
-		Err(String::from("this username already exists"))
+		Err(UsersError::UserAlreadyExists)
 	);
 }
 
@@ -112,11 +654,484 @@ fn w0rls5kmnz() {
 	assert_eq!(users.insert("user", &mut String::from("password")), Ok(()));
 	assert_eq!(
 		users.add_right("RANDOM", UserRight::ManageUsers),
-		Err(String::from("user not found"))
+		Err(UsersError::UserNotFound)
 	);
 	assert_eq!(users.add_right("user", UserRight::ManageUsers), Ok(()));
 	assert_eq!(
 		users.add_right("user", UserRight::ManageUsers),
-		Err(String::from("user have already this right"))
+		Err(UsersError::RightAlreadyGranted)
+	);
+}
+
+#[test]
+fn kq0w3ztp6yfhxm() {
+	let mut users = Users::new();
+	assert_eq!(users.insert("user", &mut String::from("password")), Ok(()));
+	assert_eq!(users.add_right("user", UserRight::ManageUsers), Ok(()));
+	assert_eq!(
+		users.add_right("user", UserRight::ManageApplications),
+		Ok(())
+	);
+
+	assert_eq!(
+		users.remove_right("user", UserRight::ManageServerSettings),
+		Err(UsersError::RightNotGranted)
+	);
+	assert_eq!(users.remove_right("user", UserRight::ManageUsers), Ok(()));
+	assert_eq!(
+		users.get_rights("user"),
+		Some(&vec![UserRight::ManageApplications])
+	);
+
+	assert_eq!(
+		users.set_password("RANDOM", &mut String::from("new_password")),
+		Err(UsersError::UserNotFound)
+	);
+	assert_eq!(
+		users.set_password("user", &mut String::from("new_password")),
+		Ok(())
+	);
+	assert!(!users
+		.check("user", &mut String::from("password"))
+		.is_correct());
+	assert!(users
+		.check("user", &mut String::from("new_password"))
+		.is_correct());
+
+	assert_eq!(users.remove("RANDOM"), Err(UsersError::UserNotFound));
+	assert_eq!(users.remove("user"), Ok(()));
+	assert!(users.get_usernames().is_empty());
+}
+
+#[test]
+fn d2hxq8m5ztvak() {
+	let mut users = Users::new();
+	users.set_password_hashing(crate::http_server::SettingsPasswordHashing {
+		memory_kib: 64,
+		iterations: 1,
+		parallelism: 1,
+	});
+	users.insert("user", &mut String::from("password")).unwrap();
+
+	let mut users = Users::from_bytes(&users.to_bytes()).unwrap();
+	users.set_password_hashing(crate::http_server::SettingsPasswordHashing {
+		memory_kib: 64,
+		iterations: 1,
+		parallelism: 1,
+	});
+	assert_eq!(users.get_usernames(), vec!["user"]);
+	assert_eq!(
+		users.check("user", &mut String::from("WRONG")),
+		PasswordCheck::Wrong
+	);
+	assert_eq!(
+		users.check("RANDOM", &mut String::from("password")),
+		PasswordCheck::Wrong
+	);
+	assert_eq!(
+		users.check("user", &mut String::from("password")),
+		PasswordCheck::Correct
+	);
+
+	users.set_password_hashing(crate::http_server::SettingsPasswordHashing {
+		memory_kib: 128,
+		iterations: 1,
+		parallelism: 1,
+	});
+	assert_eq!(
+		users.check("user", &mut String::from("password")),
+		PasswordCheck::Rehashed
+	);
+	assert_eq!(
+		users.check("user", &mut String::from("password")),
+		PasswordCheck::Correct
+	);
+
+	// password changed while the previous one was verified
+	users.set_password_hashing(crate::http_server::SettingsPasswordHashing {
+		memory_kib: 64,
+		iterations: 1,
+		parallelism: 1,
+	});
+	let outcome = users
+		.password_verification("user")
+		.verify(&mut String::from("password"));
+	assert!(matches!(outcome, VerificationOutcome::Rehashed { .. }));
+	users
+		.set_password("user", &mut String::from("new_password"))
+		.unwrap();
+	assert_eq!(users.apply_verification(outcome), PasswordCheck::Correct);
+
+	let users = std::sync::Mutex::new(users);
+	assert_eq!(
+		Users::check_shared(&users, "user", &mut String::from("new_password")),
+		PasswordCheck::Correct
+	);
+	assert_eq!(
+		Users::check_shared(&users, "RANDOM", &mut String::from("new_password")),
+		PasswordCheck::Wrong
+	);
+	let users = users.into_inner().unwrap();
+
+	let mut bytes = users.to_bytes();
+	bytes[USERS_FILE_MAGIC.len()] = USERS_FILE_VERSION + 1;
+	assert!(Users::from_bytes(&bytes).is_err());
+}
+
+#[test]
+fn y6ptf1rwn0ecqh() {
+	let salt = String::from("LEGACY_SALT");
+	// same layout than `legacy::LegacyUsers` in bincode
+	let legacy_bytes = bincode::serialize(&(
+		salt.clone(),
+		vec![
+			(
+				String::from("user"),
+				vec![UserRight::ManageUsers],
+				password::legacy_hash(&salt, "password"),
+			),
+			(
+				String::from("other"),
+				vec![],
+				password::legacy_hash(&salt, "other_password"),
+			),
+		],
+	))
+	.unwrap();
+
+	let mut users = Users::from_bytes(&legacy_bytes).unwrap();
+	users.set_password_hashing(crate::http_server::SettingsPasswordHashing {
+		memory_kib: 64,
+		iterations: 1,
+		parallelism: 1,
+	});
+	assert_eq!(users.get_usernames(), vec!["user", "other"]);
+
+	assert_eq!(
+		users.check("user", &mut String::from("WRONG")),
+		PasswordCheck::Wrong
+	);
+	assert_eq!(
+		users.check("user", &mut String::from("password")),
+		PasswordCheck::Rehashed
+	);
+	assert!(matches!(users.list[0].password, UserPassword::Argon2(_)));
+	assert!(matches!(users.list[1].password, UserPassword::Legacy(_)));
+	assert_eq!(users.legacy_salt, salt);
+
+	let mut users = Users::from_bytes(&users.to_bytes()).unwrap();
+	assert_eq!(
+		users.check("user", &mut String::from("password")),
+		PasswordCheck::Rehashed
+	);
+	assert_eq!(
+		users.check("other", &mut String::from("other_password")),
+		PasswordCheck::Rehashed
+	);
+	assert_eq!(users.legacy_salt, "");
+	assert_eq!(
+		users.check("other", &mut String::from("other_password")),
+		PasswordCheck::Correct
+	);
+}
+
+#[test]
+fn m5tq0zgk2wyr8sd() {
+	let scope = |scope: &str| crate::scope::Scope::try_from(scope).unwrap();
+
+	let mut users = Users::new();
+	users.set_password_hashing(crate::http_server::SettingsPasswordHashing {
+		memory_kib: 64,
+		iterations: 1,
+		parallelism: 1,
+	});
+	users.insert("user", &mut String::from("password")).unwrap();
+
+	assert!(!users.is_granted("user", "https://app.example.com", &[scope("documents:r")]));
+	assert_eq!(
+		users.add_grant("RANDOM", "https://app.example.com", &[scope("documents:r")]),
+		Err(UsersError::UserNotFound)
+	);
+
+	users
+		.add_grant(
+			"user",
+			"https://app.example.com",
+			&[scope("documents:r"), scope("contacts:rw")],
+		)
+		.unwrap();
+	assert!(users.is_granted("user", "https://app.example.com", &[scope("documents:r")]));
+	assert!(users.is_granted(
+		"user",
+		"https://app.example.com",
+		&[scope("contacts:r"), scope("contacts:rw")]
+	));
+	assert!(!users.is_granted("user", "https://app.example.com", &[scope("documents:rw")]));
+	assert!(!users.is_granted("user", "https://app.example.com", &[scope("notes:r")]));
+	assert!(!users.is_granted("user", "https://other.example.com", &[scope("documents:r")]));
+
+	users
+		.add_grant("user", "https://app.example.com", &[scope("documents:rw")])
+		.unwrap();
+	assert_eq!(
+		users.get_grants("user").unwrap()[0].scopes,
+		vec![String::from("contacts:rw"), String::from("documents:rw")]
+	);
+
+	let mut users = Users::from_bytes(&users.to_bytes()).unwrap();
+	assert!(users.is_granted("user", "https://app.example.com", &[scope("documents:rw")]));
+
+	assert_eq!(
+		users.remove_grant("user", "https://other.example.com"),
+		Err(UsersError::GrantNotFound)
+	);
+	assert_eq!(
+		users.remove_grant("user", "https://app.example.com"),
+		Ok(())
+	);
+	assert!(!users.is_granted("user", "https://app.example.com", &[scope("documents:r")]));
+
+	// version 1 of the users file, without grants
+	let mut bytes = USERS_FILE_MAGIC.to_vec();
+	bytes.push(1);
+	bytes.append(
+		&mut bincode::serialize(&(
+			String::new(),
+			vec![(
+				String::from("user"),
+				vec![UserRight::ManageUsers],
+				users.list[0].password.clone(),
+			)],
+		))
+		.unwrap(),
+	);
+	let mut users = Users::from_bytes(&bytes).unwrap();
+	assert_eq!(users.get_grants("user"), Some(&vec![]));
+	assert_eq!(
+		users.get_rights("user"),
+		Some(&vec![UserRight::ManageUsers])
+	);
+	assert!(users
+		.check("user", &mut String::from("password"))
+		.is_correct());
+}
+
+#[test]
+fn z4rgq8wn1tkhc3y() {
+	let mut users = Users::new();
+	users.set_password_hashing(crate::http_server::SettingsPasswordHashing {
+		memory_kib: 64,
+		iterations: 1,
+		parallelism: 1,
+	});
+	users.insert("user", &mut String::from("password")).unwrap();
+	users
+		.add_grant(
+			"user",
+			"https://app.example.com",
+			&[crate::scope::Scope::try_from("documents:r").unwrap()],
+		)
+		.unwrap();
+
+	// version 2 of the users file, without two-factor authentication
+	let mut bytes = USERS_FILE_MAGIC.to_vec();
+	bytes.push(2);
+	bytes.append(
+		&mut bincode::serialize(&(
+			String::new(),
+			vec![(
+				String::from("user"),
+				Vec::<UserRight>::new(),
+				users.list[0].password.clone(),
+				users.list[0].grants.clone(),
+			)],
+		))
+		.unwrap(),
+	);
+	let mut users = Users::from_bytes(&bytes).unwrap();
+	assert_eq!(users.get_grants("user").unwrap().len(), 1);
+	assert!(!users.has_totp("user"));
+	assert_eq!(users.check_totp("user", ""), SecondFactorCheck::Disabled);
+
+	assert_eq!(users.start_totp("RANDOM"), Err(UsersError::UserNotFound));
+	assert_eq!(
+		users.confirm_totp("user", "000000"),
+		Err(UsersError::TotpNotStarted)
+	);
+	assert_eq!(users.start_totp("user"), Ok(()));
+	assert!(!users.has_totp("user"));
+	assert_eq!(users.check_totp("user", ""), SecondFactorCheck::Disabled);
+
+	let uri = users.get_totp_setup_uri("user", "pontus_onyx").unwrap();
+	assert!(uri.starts_with("otpauth://totp/pontus_onyx:user?secret="));
+	let secret = users.list[0].totp.as_ref().unwrap().secret.clone();
+	assert!(uri.contains(&totp::encode_secret(&secret)));
+
+	let step = totp::current_step();
+	let wrong_code = if totp::code(&secret, step) == "000000" {
+		"111111"
+	} else {
+		"000000"
+	};
+	assert!(totp::find_step(&secret, wrong_code, step).is_none());
+	assert_eq!(
+		users.confirm_totp("user", wrong_code),
+		Err(UsersError::WrongTotpCode)
+	);
+
+	// the code of the next step, which is accepted once the code of this step has been used
+	let recovery_codes = users
+		.confirm_totp("user", &totp::code(&secret, step + 1))
+		.unwrap();
+	assert!(users.has_totp("user"));
+	assert_eq!(users.get_totp_setup_uri("user", "pontus_onyx"), None);
+	assert_eq!(users.get_recovery_codes_count("user"), recovery_codes.len());
+	assert_eq!(
+		users.start_totp("user"),
+		Err(UsersError::TotpAlreadyEnabled)
+	);
+
+	let mut users = Users::from_bytes(&users.to_bytes()).unwrap();
+	assert!(users.has_totp("user"));
+
+	assert_eq!(
+		users.check_totp("user", &totp::code(&secret, step + 1)),
+		SecondFactorCheck::Wrong
+	);
+	assert_eq!(
+		users.check_totp("user", wrong_code),
+		SecondFactorCheck::Wrong
+	);
+	assert_eq!(users.check_totp("user", ""), SecondFactorCheck::Wrong);
+
+	assert_eq!(
+		users.check_totp("user", &recovery_codes[0]),
+		SecondFactorCheck::Correct
+	);
+	assert_eq!(
+		users.check_totp("user", &recovery_codes[0]),
+		SecondFactorCheck::Wrong
+	);
+	assert_eq!(
+		users.get_recovery_codes_count("user"),
+		recovery_codes.len() - 1
+	);
+
+	assert_eq!(users.reset_totp("user"), Ok(()));
+	assert!(!users.has_totp("user"));
+	assert_eq!(users.check_totp("user", ""), SecondFactorCheck::Disabled);
+}
+
+#[test]
+fn h8cw2pj5tx0rqym() {
+	let mut users = Users::new();
+	users.set_password_hashing(crate::http_server::SettingsPasswordHashing {
+		memory_kib: 64,
+		iterations: 1,
+		parallelism: 1,
+	});
+	users.insert("user", &mut String::from("password")).unwrap();
+	users.add_right("user", UserRight::ManageUsers).unwrap();
+	users
+		.add_grant(
+			"user",
+			"https://app.example.com",
+			&[crate::scope::Scope::try_from("documents:r").unwrap()],
+		)
+		.unwrap();
+	users.start_totp("user").unwrap();
+	let secret = users.list[0].totp.as_ref().unwrap().secret.clone();
+	users
+		.confirm_totp("user", &totp::code(&secret, totp::current_step()))
+		.unwrap();
+	users.list.push(User {
+		name: String::from("legacy"),
+		rights: vec![],
+		password: UserPassword::Legacy(vec![0x00, 0x0f, 0xf0, 0xff]),
+		grants: vec![],
+		totp: None,
+	});
+
+	let tests = vec![
+		(010, "users.toml", UsersFileFormat::Toml),
+		(020, "users.json", UsersFileFormat::Json),
+		(030, "users.bin", UsersFileFormat::Bincode),
+		(040, "users", UsersFileFormat::Bincode),
+	];
+
+	let tempdir = tempfile::tempdir().unwrap();
+	for test in tests {
+		print!("#{:03} : save and load users in `{}` ... ", test.0, test.1);
+
+		let path = tempdir.path().join(test.1);
+		assert_eq!(UsersFileFormat::from_path(&path), test.2);
+		users.save(&path).unwrap();
+
+		let mut loaded = Users::load(&path).unwrap();
+		assert_eq!(loaded.to_bytes(), users.to_bytes());
+		assert!(loaded
+			.check("user", &mut String::from("password"))
+			.is_correct());
+
+		println!("OK");
+	}
+
+	let content = String::from_utf8(users.serialize(UsersFileFormat::Toml).unwrap()).unwrap();
+	assert!(content.contains("[[list]]\nname = 'user'\nrights = ['ManageUsers']\n"));
+	assert!(content.contains(&format!("secret = '{}'", user::hex_encode(&secret))));
+	assert!(content.contains("password = 'legacy-sha512:000ff0ff'"));
+
+	let tests = vec![
+		(
+			110,
+			UsersFileFormat::Toml,
+			content.replacen("name = 'user'", "name = user", 1),
+			"line 4 column 8",
+		),
+		(
+			120,
+			UsersFileFormat::Toml,
+			content.replacen("000ff0ff", "000ff0f", 1),
+			"incorrect hexadecimal value `000ff0f`",
+		),
+		(
+			130,
+			UsersFileFormat::Json,
+			String::from("{\n\t\"legacy_salt\": \"\",\n\t\"list\": [\n\t\t{ \"name\": 1 }\n\t]\n}"),
+			"invalid type: integer `1`, expected a string at line 4 column 13",
+		),
+		(
+			140,
+			UsersFileFormat::Json,
+			String::from("{\n\t\"legacy_salt\": \"\"\n}"),
+			"missing field `list` at line 3 column 1",
+		),
+	];
+
+	for test in tests {
+		print!(
+			"#{:03} : parse error of {:?} users file ... ",
+			test.0, test.1
+		);
+
+		let error = Users::parse(test.2.as_bytes(), test.1).err().unwrap();
+		assert!(error.contains(test.3), "{error}");
+
+		println!("OK");
+	}
+
+	let path = tempdir.path().join("users.json");
+	std::fs::write(&path, "{").unwrap();
+	let error = Users::load(&path).err().unwrap();
+	assert_eq!(error.kind(), std::io::ErrorKind::InvalidData);
+	assert!(error
+		.to_string()
+		.starts_with(&format!("can not parse `{}` : ", path.to_string_lossy())));
+	assert_eq!(
+		Users::load(&tempdir.path().join("RANDOM.toml"))
+			.err()
+			.unwrap()
+			.kind(),
+		std::io::ErrorKind::NotFound
 	);
 }
@@ -0,0 +1,131 @@
+use argon2::{PasswordHasher, PasswordVerifier};
+use rand::Rng;
+
+/// Hashes `password` with Argon2id and a new random salt, in the PHC string format
+/// (like `$argon2id$v=19$m=19456,t=2,p=1$<salt>$<hash>`).
+pub fn hash(
+	password: &str,
+	settings: &crate::http_server::SettingsPasswordHashing,
+) -> Result<String, String> {
+	let mut salt = [0_u8; 16];
+	rand::thread_rng().fill(&mut salt);
+	let salt = argon2::password_hash::SaltString::encode_b64(&salt).map_err(|e| e.to_string())?;
+
+	return hasher(settings)?
+		.hash_password(password.as_bytes(), &salt)
+		.map(|hash| hash.to_string())
+		.map_err(|e| e.to_string());
+}
+
+pub fn verify(password: &str, password_hash: &str) -> bool {
+	match argon2::PasswordHash::new(password_hash) {
+		Ok(password_hash) => argon2::Argon2::default()
+			.verify_password(password.as_bytes(), &password_hash)
+			.is_ok(),
+		Err(_) => false,
+	}
+}
+
+/// Hash of a fixed password with these cost parameters, computed once, which is verified for
+/// unknown users in order to answer in the same time than for known ones.
+pub fn dummy_hash(settings: &crate::http_server::SettingsPasswordHashing) -> String {
+	static DUMMY_HASHES: std::sync::Mutex<
+		Vec<(crate::http_server::SettingsPasswordHashing, String)>,
+	> = std::sync::Mutex::new(Vec::new());
+
+	let mut dummy_hashes = DUMMY_HASHES.lock().unwrap();
+	if let Some((_, dummy_hash)) = dummy_hashes
+		.iter()
+		.find(|(dummy_settings, _)| dummy_settings == settings)
+	{
+		return dummy_hash.clone();
+	}
+
+	let dummy_hash = hash("pontus_onyx_dummy_password", settings).unwrap_or_default();
+	dummy_hashes.push((settings.clone(), dummy_hash.clone()));
+
+	return dummy_hash;
+}
+
+/// Returns `true` if `password_hash` was not computed with the current cost parameters.
+pub fn needs_rehash(
+	password_hash: &str,
+	settings: &crate::http_server::SettingsPasswordHashing,
+) -> bool {
+	let password_hash = match argon2::PasswordHash::new(password_hash) {
+		Ok(password_hash) => password_hash,
+		Err(_) => return true,
+	};
+
+	if password_hash.algorithm != argon2::Algorithm::Argon2id.ident() {
+		return true;
+	}
+
+	match argon2::Params::try_from(&password_hash) {
+		Ok(params) => {
+			params.m_cost() != settings.memory_kib
+				|| params.t_cost() != settings.iterations
+				|| params.p_cost() != settings.parallelism
+		}
+		Err(_) => true,
+	}
+}
+
+/// Hash of the previous versions : one round of SHA-512, with the salt shared by all users.
+pub fn legacy_hash(salt: &str, password: &str) -> Vec<u8> {
+	let mut hasher = hmac_sha512::Hash::new();
+	hasher.update(salt.as_bytes());
+	hasher.update(password.as_bytes());
+	hasher.update(salt.as_bytes());
+
+	return hasher.finalize().to_vec();
+}
+
+fn hasher(
+	settings: &crate::http_server::SettingsPasswordHashing,
+) -> Result<argon2::Argon2<'static>, String> {
+	let params = argon2::Params::new(
+		settings.memory_kib,
+		settings.iterations,
+		settings.parallelism,
+		None,
+	)
+	.map_err(|e| e.to_string())?;
+
+	return Ok(argon2::Argon2::new(
+		argon2::Algorithm::Argon2id,
+		argon2::Version::V0x13,
+		params,
+	));
+}
+
+#[test]
+fn r8vnq3c0jfx6pzu() {
+	let settings = crate::http_server::SettingsPasswordHashing {
+		memory_kib: 64,
+		iterations: 1,
+		parallelism: 1,
+	};
+
+	let first = hash("password", &settings).unwrap();
+	let second = hash("password", &settings).unwrap();
+
+	assert!(first.starts_with("$argon2id$v=19$m=64,t=1,p=1$"));
+	assert_ne!(first, second);
+
+	assert!(verify("password", &first));
+	assert!(verify("password", &second));
+	assert!(!verify("PASSWORD", &first));
+	assert!(!verify("password", "not a PHC string"));
+
+	assert!(!needs_rehash(&first, &settings));
+	assert!(needs_rehash(
+		&first,
+		&crate::http_server::SettingsPasswordHashing {
+			memory_kib: 128,
+			iterations: 1,
+			parallelism: 1,
+		}
+	));
+	assert!(needs_rehash("not a PHC string", &settings));
+}
@@ -0,0 +1,141 @@
+use rand::seq::IteratorRandom;
+use rand::Rng;
+
+/// Lifetime of a code, in seconds.
+const PERIOD: u64 = 30;
+const DIGITS: u32 = 6;
+/// Codes of the previous and next periods are also accepted, because clocks are never exactly in
+/// sync.
+const ALLOWED_DRIFT: u64 = 1;
+
+const RECOVERY_CODES_COUNT: usize = 10;
+/// Without characters which can be confused when written down, like `l` and `1`.
+const RECOVERY_CODE_ALPHABET: &str = "abcdefghjkmnpqrstuvwxyz23456789";
+
+/// Random secret shared with the authenticator application of the user.
+pub fn new_secret() -> Vec<u8> {
+	let mut secret = vec![0_u8; 20];
+	rand::thread_rng().fill(&mut secret[..]);
+
+	return secret;
+}
+
+/// Base32 form of `secret`, which can be typed in authenticator applications.
+pub fn encode_secret(secret: &[u8]) -> String {
+	base32::encode(base32::Alphabet::RFC4648 { padding: false }, secret)
+}
+
+/// Address to scan (as QR code) or to open with authenticator applications.
+pub fn uri(secret: &[u8], issuer: &str, account: &str) -> String {
+	let issuer = pct_str::PctString::encode(issuer.chars(), pct_str::URIReserved);
+
+	format!(
+		"otpauth://totp/{}:{}?secret={}&issuer={}&algorithm=SHA1&digits={}&period={}",
+		issuer,
+		pct_str::PctString::encode(account.chars(), pct_str::URIReserved),
+		encode_secret(secret),
+		issuer,
+		DIGITS,
+		PERIOD
+	)
+}
+
+/// Number of periods since the UNIX epoch.
+pub fn current_step() -> u64 {
+	std::time::SystemTime::now()
+		.duration_since(std::time::UNIX_EPOCH)
+		.map(|duration| duration.as_secs() / PERIOD)
+		.unwrap_or_default()
+}
+
+/// Code of `step`, as described in RFC 6238 (and RFC 4226), with HMAC-SHA1.
+pub fn code(secret: &[u8], step: u64) -> String {
+	let mac = hmac_sha1_compact::HMAC::mac(&step.to_be_bytes(), secret);
+
+	let offset = (mac[mac.len() - 1] & 0x0f) as usize;
+	let binary = u32::from_be_bytes([
+		mac[offset] & 0x7f,
+		mac[offset + 1],
+		mac[offset + 2],
+		mac[offset + 3],
+	]);
+
+	return format!(
+		"{:0width$}",
+		binary % 10_u32.pow(DIGITS),
+		width = DIGITS as usize
+	);
+}
+
+/// Returns the step of `code` if it is valid around `step`.
+pub fn find_step(secret: &[u8], code: &str, step: u64) -> Option<u64> {
+	let code: String = code.chars().filter(|c| !c.is_whitespace()).collect();
+	if code.len() != DIGITS as usize {
+		return None;
+	}
+
+	(step.saturating_sub(ALLOWED_DRIFT)..=step + ALLOWED_DRIFT)
+		.find(|candidate| self::code(secret, *candidate) == code)
+}
+
+/// Single-use codes which replace the authenticator application if it is lost, like
+/// `abcde-fghjk`.
+pub fn new_recovery_codes() -> Vec<String> {
+	let mut rng = rand::thread_rng();
+
+	(0..RECOVERY_CODES_COUNT)
+		.map(|_| {
+			let code: String = (0..10)
+				.map(|_| RECOVERY_CODE_ALPHABET.chars().choose(&mut rng).unwrap())
+				.collect();
+
+			format!("{}-{}", &code[..5], &code[5..])
+		})
+		.collect()
+}
+
+/// Recovery codes are only saved as hashes, like passwords.
+pub fn hash_recovery_code(code: &str) -> Vec<u8> {
+	use sha2::Digest;
+
+	let code: String = code
+		.chars()
+		.filter(|c| c.is_alphanumeric())
+		.flat_map(|c| c.to_lowercase())
+		.collect();
+
+	return sha2::Sha256::digest(code.as_bytes()).to_vec();
+}
+
+#[test]
+fn b7dk2wqx9fnc0le() {
+	// test vectors of RFC 6238, with 6 digits
+	let secret = b"12345678901234567890";
+
+	assert_eq!(code(secret, 59 / PERIOD), "287082");
+	assert_eq!(code(secret, 1111111109 / PERIOD), "081804");
+	assert_eq!(code(secret, 1234567890 / PERIOD), "005924");
+	assert_eq!(code(secret, 20000000000 / PERIOD), "353130");
+
+	assert_eq!(encode_secret(secret), "GEZDGNBVGY3TQOJQGEZDGNBVGY3TQOJQ");
+
+	let step = 1111111109 / PERIOD;
+	assert_eq!(find_step(secret, "081804", step), Some(step));
+	assert_eq!(find_step(secret, "081 804", step), Some(step));
+	assert_eq!(find_step(secret, "081804", step + 1), Some(step));
+	assert_eq!(find_step(secret, "081804", step + 2), None);
+	assert_eq!(find_step(secret, "000000", step), None);
+	assert_eq!(find_step(secret, "", step), None);
+
+	let recovery_codes = new_recovery_codes();
+	assert_eq!(recovery_codes.len(), RECOVERY_CODES_COUNT);
+	assert_eq!(recovery_codes[0].len(), 11);
+	assert_eq!(
+		hash_recovery_code(&recovery_codes[0]),
+		hash_recovery_code(&recovery_codes[0].to_uppercase().replace('-', " "))
+	);
+	assert_ne!(
+		hash_recovery_code(&recovery_codes[0]),
+		hash_recovery_code(&recovery_codes[1])
+	);
+}
@@ -1,11 +1,94 @@
-#[derive(serde::Deserialize, serde::Serialize)]
+#[derive(serde::Deserialize, serde::Serialize, Clone)]
 pub struct User {
 	pub name: String,
 	pub rights: Vec<UserRight>,
-	pub hashed_password: Vec<u8>,
+	pub password: UserPassword,
+	pub grants: Vec<UserGrant>,
+	pub totp: Option<UserTotp>,
+}
+
+/// In human-readable formats (like TOML), it is written as a string : the PHC string of Argon2
+/// hashes, or the hexadecimal hash after [`LEGACY_PASSWORD_PREFIX`] for legacy ones.
+#[derive(PartialEq, Eq, Clone)]
+pub enum UserPassword {
+	/// One round of SHA-512 with the salt shared by all users, from previous versions.
+	///
+	/// It is replaced by an Argon2id hash on the next successful login of this user.
+	Legacy(Vec<u8>),
+	/// Argon2id hash in the PHC string format, which also contains the salt of this user.
+	Argon2(String),
+}
+
+const LEGACY_PASSWORD_PREFIX: &str = "legacy-sha512:";
+
+/// Form of [`UserPassword`] in bincode.
+#[derive(serde::Deserialize, serde::Serialize)]
+enum UserPasswordBincode {
+	Legacy(Vec<u8>),
+	Argon2(String),
+}
+
+impl serde::Serialize for UserPassword {
+	fn serialize<S: serde::Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+		if serializer.is_human_readable() {
+			match self {
+				Self::Legacy(hash) => serializer.serialize_str(&format!(
+					"{}{}",
+					LEGACY_PASSWORD_PREFIX,
+					hex_encode(hash)
+				)),
+				Self::Argon2(hash) => serializer.serialize_str(hash),
+			}
+		} else {
+			match self {
+				Self::Legacy(hash) => UserPasswordBincode::Legacy(hash.clone()),
+				Self::Argon2(hash) => UserPasswordBincode::Argon2(hash.clone()),
+			}
+			.serialize(serializer)
+		}
+	}
 }
+impl<'de> serde::Deserialize<'de> for UserPassword {
+	fn deserialize<D: serde::Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+		if deserializer.is_human_readable() {
+			let value = String::deserialize(deserializer)?;
 
-#[derive(serde::Deserialize, serde::Serialize, PartialEq, Eq, PartialOrd, Ord, Clone)]
+			if let Some(hash) = value.strip_prefix(LEGACY_PASSWORD_PREFIX) {
+				hex_decode(hash)
+					.map(Self::Legacy)
+					.map_err(serde::de::Error::custom)
+			} else if value.starts_with("$argon2") {
+				Ok(Self::Argon2(value))
+			} else {
+				Err(serde::de::Error::custom(
+					"unknown password hash, expected an Argon2 PHC string",
+				))
+			}
+		} else {
+			match UserPasswordBincode::deserialize(deserializer)? {
+				UserPasswordBincode::Legacy(hash) => Ok(Self::Legacy(hash)),
+				UserPasswordBincode::Argon2(hash) => Ok(Self::Argon2(hash)),
+			}
+		}
+	}
+}
+
+/// Second factor of authentication, with time-based one-time passwords (RFC 6238).
+#[derive(serde::Deserialize, serde::Serialize, Clone)]
+pub struct UserTotp {
+	#[serde(with = "hex")]
+	pub secret: Vec<u8>,
+	/// `false` until the user types its first code, to make sure that its authenticator
+	/// application is set up.
+	pub confirmed: bool,
+	/// Step of the last accepted code, which can not be used again.
+	pub last_step: u64,
+	/// Hashes of the unused recovery codes.
+	#[serde(with = "hex_list")]
+	pub recovery_codes: Vec<Vec<u8>>,
+}
+
+#[derive(serde::Deserialize, serde::Serialize, PartialEq, Eq, PartialOrd, Ord, Clone, Debug)]
 pub enum UserRight {
 	ManageServerSettings,
 	ManageUsers,
@@ -20,3 +103,95 @@ impl std::fmt::Display for UserRight {
 		}
 	}
 }
+
+/// Access allowed by an user to an application, which is not asked again to this user.
+#[derive(serde::Deserialize, serde::Serialize, PartialEq, Eq, Clone, Debug)]
+pub struct UserGrant {
+	pub client_id: String,
+	/// Like `documents:rw`.
+	pub scopes: Vec<String>,
+}
+impl UserGrant {
+	pub fn get_scopes(&self) -> Vec<crate::scope::Scope> {
+		self.scopes
+			.iter()
+			.filter_map(|scope| crate::scope::Scope::try_from(scope.as_str()).ok())
+			.collect()
+	}
+
+	/// Returns `true` if all of these `scopes` are included in this grant.
+	pub fn covers(&self, scopes: &[crate::scope::Scope]) -> bool {
+		let granted_scopes = self.get_scopes();
+
+		scopes.iter().all(|scope| {
+			granted_scopes
+				.iter()
+				.any(|granted_scope| granted_scope.includes(scope))
+		})
+	}
+}
+
+/// Bytes are written as hexadecimal strings in human-readable formats, and as is in bincode.
+mod hex {
+	pub fn serialize<S: serde::Serializer>(bytes: &[u8], serializer: S) -> Result<S::Ok, S::Error> {
+		if serializer.is_human_readable() {
+			serializer.serialize_str(&super::hex_encode(bytes))
+		} else {
+			serde::Serialize::serialize(bytes, serializer)
+		}
+	}
+
+	pub fn deserialize<'de, D: serde::Deserializer<'de>>(
+		deserializer: D,
+	) -> Result<Vec<u8>, D::Error> {
+		if deserializer.is_human_readable() {
+			let value: String = serde::Deserialize::deserialize(deserializer)?;
+			super::hex_decode(&value).map_err(serde::de::Error::custom)
+		} else {
+			serde::Deserialize::deserialize(deserializer)
+		}
+	}
+}
+
+mod hex_list {
+	pub fn serialize<S: serde::Serializer>(
+		list: &[Vec<u8>],
+		serializer: S,
+	) -> Result<S::Ok, S::Error> {
+		if serializer.is_human_readable() {
+			serializer.collect_seq(list.iter().map(|bytes| super::hex_encode(bytes)))
+		} else {
+			serde::Serialize::serialize(list, serializer)
+		}
+	}
+
+	pub fn deserialize<'de, D: serde::Deserializer<'de>>(
+		deserializer: D,
+	) -> Result<Vec<Vec<u8>>, D::Error> {
+		if deserializer.is_human_readable() {
+			let list: Vec<String> = serde::Deserialize::deserialize(deserializer)?;
+			list.iter()
+				.map(|value| super::hex_decode(value).map_err(serde::de::Error::custom))
+				.collect()
+		} else {
+			serde::Deserialize::deserialize(deserializer)
+		}
+	}
+}
+
+pub fn hex_encode(bytes: &[u8]) -> String {
+	bytes.iter().map(|byte| format!("{byte:02x}")).collect()
+}
+
+pub fn hex_decode(value: &str) -> Result<Vec<u8>, String> {
+	value
+		.as_bytes()
+		.chunks(2)
+		.map(|pair| match pair {
+			[high, low] if high.is_ascii_hexdigit() && low.is_ascii_hexdigit() => {
+				Ok(u8::from_str_radix(&format!("{}{}", *high as char, *low as char), 16).unwrap())
+			}
+			_ => Err(format!("incorrect hexadecimal value `{value}`")),
+		})
+		.collect()
+}
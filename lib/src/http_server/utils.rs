@@ -74,10 +74,124 @@ pub fn build_server_address(
 	format!("{}://{}{}/{}", protocol, domain, port, domain_suffix)
 }
 
+/// Returns the ASCII serialization of the origin of `uri` (like `https://example.com:8080`),
+/// or `None` if it is not an URL or if its origin is opaque (like `file:` or custom schemes).
+pub fn origin_of(uri: &str) -> Option<String> {
+	match url::Url::parse(uri.trim()) {
+		Ok(url) => {
+			let origin = url.origin();
+			if origin.is_tuple() {
+				Some(origin.ascii_serialization())
+			} else {
+				None
+			}
+		}
+		Err(_) => None,
+	}
+}
+
+/// Origins from which the forms of this server (like the OAuth one) can be submitted.
+pub fn server_origins(settings: &crate::http_server::Settings, https_mode: bool) -> Vec<String> {
+	let domain = settings
+		.domain
+		.clone()
+		.unwrap_or_else(|| String::from("localhost"));
+
+	let mut result = vec![format!("http://{domain}:{}", settings.port)];
+	if https_mode {
+		if let Some(https) = &settings.https {
+			result.push(format!("https://{domain}:{}", https.port));
+		}
+	}
+
+	return result
+		.iter()
+		.filter_map(|origin| origin_of(origin))
+		.collect();
+}
+
+/// Escapes `text` before printing it in an HTML page.
+pub fn escape_html(text: &str) -> String {
+	let mut result = String::with_capacity(text.len());
+	for c in text.chars() {
+		match c {
+			'&' => result.push_str("&amp;"),
+			'<' => result.push_str("&lt;"),
+			'>' => result.push_str("&gt;"),
+			'"' => result.push_str("&quot;"),
+			'\'' => result.push_str("&#39;"),
+			_ => result.push(c),
+		}
+	}
+
+	return result;
+}
+
+/// Total size of the files in `path` and its sub-folders, or `0` if it does not exist.
+pub fn folder_size(path: &std::path::Path) -> u64 {
+	match std::fs::read_dir(path) {
+		Ok(entries) => entries
+			.filter_map(|entry| entry.ok())
+			.map(|entry| match entry.metadata() {
+				Ok(metadata) if metadata.is_dir() => folder_size(&entry.path()),
+				Ok(metadata) => metadata.len(),
+				Err(_) => 0,
+			})
+			.sum(),
+		Err(_) => 0,
+	}
+}
+
+/// Writes `content` in a temporary file before moving it to `path`, in order to never leave an
+/// incomplete file.
+pub fn write_atomically(path: &std::path::Path, content: &[u8]) -> Result<(), String> {
+	return write_with_temp_file(path, content, false);
+}
+
+/// Same as [`write_atomically`], but only the owner of the file can read it (on Unix), for
+/// private keys.
+pub fn write_private_atomically(path: &std::path::Path, content: &[u8]) -> Result<(), String> {
+	return write_with_temp_file(path, content, true);
+}
+
+fn write_with_temp_file(
+	path: &std::path::Path,
+	content: &[u8],
+	is_private: bool,
+) -> Result<(), String> {
+	let temp_path = path.with_file_name(format!(
+		".{}.tmp",
+		path.file_name()
+			.map(|name| name.to_string_lossy())
+			.unwrap_or_default()
+	));
+
+	let mut temp_file = std::fs::File::create(&temp_path).map_err(|e| e.to_string())?;
+	// before writing, since the temporary file may already exist with other permissions
+	if is_private {
+		#[cfg(unix)]
+		temp_file
+			.set_permissions(std::os::unix::fs::PermissionsExt::from_mode(0o600))
+			.map_err(|e| e.to_string())?;
+	}
+	std::io::Write::write_all(&mut temp_file, content).map_err(|e| e.to_string())?;
+	drop(temp_file);
+
+	if let Err(e) = std::fs::rename(&temp_path, path) {
+		std::fs::remove_file(&temp_path).ok();
+		return Err(e.to_string());
+	}
+
+	return Ok(());
+}
+
 #[test]
 fn pbw1cgzctiqe163() {
 	let settings = super::Settings::new(tempfile::tempdir().unwrap().into_path());
-	let state = super::ProgramState { https_mode: false };
+	let state = super::ProgramState {
+		https_mode: false,
+		..Default::default()
+	};
 
 	assert_eq!(
 		build_server_address(&settings, &state),
@@ -95,7 +209,10 @@ fn pbw1cgzctiqe163() {
 fn ykf0gcnr7z2ko4wtx8uub() {
 	let mut settings = super::Settings::new(tempfile::tempdir().unwrap().into_path());
 	settings.domain_suffix = Some(String::from("test"));
-	let state = super::ProgramState { https_mode: false };
+	let state = super::ProgramState {
+		https_mode: false,
+		..Default::default()
+	};
 
 	assert_eq!(
 		build_server_address(&settings, &state),
@@ -113,7 +230,10 @@ fn ykf0gcnr7z2ko4wtx8uub() {
 fn wxpy6tncuwbbavvxi() {
 	let mut settings = super::Settings::new(tempfile::tempdir().unwrap().into_path());
 	settings.domain_suffix = Some(String::from("test/"));
-	let state = super::ProgramState { https_mode: false };
+	let state = super::ProgramState {
+		https_mode: false,
+		..Default::default()
+	};
 
 	assert_eq!(
 		build_server_address(&settings, &state),
@@ -131,7 +251,10 @@ fn wxpy6tncuwbbavvxi() {
 fn fpfxwrixa1jz7t() {
 	let settings = super::Settings::new(tempfile::tempdir().unwrap().into_path());
 
-	let state = super::ProgramState { https_mode: true };
+	let state = super::ProgramState {
+		https_mode: true,
+		..Default::default()
+	};
 
 	assert_eq!(
 		build_server_address(&settings, &state),
@@ -150,7 +273,10 @@ fn xtgfpc3x1zcmb() {
 	let domain = String::from("example.com");
 	let mut settings = super::Settings::new(tempfile::tempdir().unwrap().into_path());
 	settings.domain = Some(domain.clone());
-	let state = super::ProgramState { https_mode: false };
+	let state = super::ProgramState {
+		https_mode: false,
+		..Default::default()
+	};
 
 	assert_eq!(
 		build_server_address(&settings, &state),
@@ -168,7 +294,10 @@ fn ekkvpuijzifxc() {
 	let domain = String::from("example.com");
 	let mut settings = super::Settings::new(tempfile::tempdir().unwrap().into_path());
 	settings.domain = Some(domain.clone());
-	let state = super::ProgramState { https_mode: true };
+	let state = super::ProgramState {
+		https_mode: true,
+		..Default::default()
+	};
 
 	assert_eq!(
 		build_server_address(&settings, &state),
@@ -190,7 +319,10 @@ fn bj8n5zhu2oaaed55561ygk() {
 	if let Some(https) = &mut settings.https {
 		https.port = 443;
 	}
-	let state = super::ProgramState { https_mode: false };
+	let state = super::ProgramState {
+		https_mode: false,
+		..Default::default()
+	};
 
 	assert_eq!(
 		build_server_address(&settings, &state),
@@ -207,10 +339,43 @@ fn d434yaaxfqcnd4j() {
 	if let Some(https) = &mut settings.https {
 		https.port = 443;
 	}
-	let state = super::ProgramState { https_mode: true };
+	let state = super::ProgramState {
+		https_mode: true,
+		..Default::default()
+	};
 
 	assert_eq!(
 		build_server_address(&settings, &state),
 		format!("{}://{}/{}", "https", domain, "")
 	);
 }
+
+#[test]
+fn m3xq9ysbq2u5ljr() {
+	assert_eq!(
+		origin_of("https://app.example.com/some/path?query#fragment"),
+		Some(String::from("https://app.example.com"))
+	);
+	assert_eq!(
+		origin_of("https://app.example.com:443/"),
+		Some(String::from("https://app.example.com"))
+	);
+	assert_eq!(
+		origin_of("http://localhost:8080"),
+		Some(String::from("http://localhost:8080"))
+	);
+	assert_eq!(origin_of("not an URL"), None);
+	assert_eq!(origin_of("file:///home/user/index.html"), None);
+}
+
+#[test]
+fn c3vx9mw0qzlu5ap() {
+	assert_eq!(
+		escape_html(r#"<a href="x">Tom & Jerry's</a>"#),
+		"&lt;a href=&quot;x&quot;&gt;Tom &amp; Jerry&#39;s&lt;/a&gt;"
+	);
+	assert_eq!(
+		escape_html("https://app.example.com"),
+		"https://app.example.com"
+	);
+}
@@ -0,0 +1,176 @@
+const REMOTESTORAGE_REL: &str = "http://tools.ietf.org/id/draft-dejong-remotestorage";
+
+#[actix_web::route("/.well-known/webfinger", method = "GET", method = "HEAD")]
+pub async fn webfinger_handle(
+	request: actix_web::HttpRequest,
+	settings: actix_web::web::Data<std::sync::Arc<std::sync::Mutex<super::Settings>>>,
+	program_state: actix_web::web::Data<std::sync::Arc<std::sync::Mutex<super::ProgramState>>>,
+	users: actix_web::web::Data<std::sync::Arc<std::sync::Mutex<super::Users>>>,
+) -> impl actix_web::Responder {
+	let settings = settings.lock().unwrap().clone();
+	let server_addr = super::build_server_address(&settings, &program_state.lock().unwrap());
+
+	let query = WebfingerQuery::parse(request.query_string());
+
+	// the authorization dialog depends on the account, so it is only available with `acct:` resources
+	let default_body = format!(
+		r#"{{
+	"href":"/",
+	"rel":"http://tools.ietf.org/id/draft-dejong-remotestorage",
+	"properties":{{
+		"http://remotestorage.io/spec/version":"{}",
+		"http://tools.ietf.org/html/rfc6749#section-4.2":{},
+		"http://tools.ietf.org/html/rfc6749#section-3.2":"{}oauth/token"
+	}}
+}}"#,
+		"draft-dejong-remotestorage-19", "null", server_addr
+	);
+
+	return match &query.resource {
+		Some(resource) if resource.starts_with("acct:") => {
+			match resource.strip_prefix("acct:").unwrap().rsplit_once('@') {
+				Some((user, domain)) if !user.is_empty() => {
+					if !is_served_domain(&settings, domain) {
+						build_error(actix_web::HttpResponse::NotFound(), "unknown domain")
+					} else if !user_exists(&settings, &users.lock().unwrap(), user) {
+						build_error(actix_web::HttpResponse::NotFound(), "user not found")
+					} else {
+						let links = vec![serde_json::json!({
+							"href": format!("{server_addr}storage/{user}"),
+							"rel": REMOTESTORAGE_REL,
+							"properties": {
+								"http://remotestorage.io/spec/version": "draft-dejong-remotestorage-19",
+								"http://tools.ietf.org/html/rfc6749#section-4.2": format!("{server_addr}oauth/{user}"),
+								"http://tools.ietf.org/html/rfc6749#section-3.2": format!("{server_addr}oauth/token"),
+								"http://tools.ietf.org/html/rfc6750#section-2.3": "true",
+								"http://tools.ietf.org/html/rfc7233": null,
+								"http://remotestorage.io/spec/web-authoring": null,
+							},
+						})];
+
+						let mut response = actix_web::HttpResponse::Ok();
+						response.content_type("application/ld+json");
+						response.body(
+							serde_json::json!({
+								"subject": resource,
+								"links": query.filter_links(links),
+							})
+							.to_string(),
+						)
+					}
+				}
+				_ => build_error(
+					actix_web::HttpResponse::BadRequest(),
+					"incorrect `acct:` resource, expected `acct:<user>@<domain>`",
+				),
+			}
+		}
+		Some(_) => actix_web::HttpResponse::Ok()
+			.content_type("application/ld+json")
+			.body(default_body),
+		None => actix_web::HttpResponse::Ok()
+			.content_type("application/ld+json")
+			.body(default_body),
+	};
+
+	/*
+	TODO :
+		If <auth-dialog> is a URL, the user can supply their credentials
+		for accessing the account (how, is out of scope), and allow or
+		reject a request by the connecting application to obtain a bearer
+		token for a certain list of access scopes.
+	*/
+}
+
+#[actix_web::options("/.well-known/webfinger")]
+pub async fn options_webfinger() -> impl actix_web::Responder {
+	let mut response = actix_web::HttpResponse::Ok();
+	response.insert_header((actix_web::http::header::ALLOW, "OPTIONS, GET, HEAD"));
+
+	return response.finish();
+}
+
+fn build_error(
+	mut response: actix_web::HttpResponseBuilder,
+	message: &str,
+) -> actix_web::HttpResponse {
+	response
+		.content_type("application/ld+json")
+		.body(serde_json::json!({ "error": message }).to_string())
+}
+
+/// Returns `true` if `domain` (of an `acct:` resource) is the one of this server or one of its
+/// aliases, regardless of case and port.
+fn is_served_domain(settings: &super::Settings, domain: &str) -> bool {
+	let server_domain = settings
+		.domain
+		.clone()
+		.filter(|domain| !domain.trim().is_empty())
+		.unwrap_or_else(|| String::from("localhost"));
+
+	let domain = without_port(domain);
+
+	std::iter::once(&server_domain)
+		.chain(settings.domain_aliases.iter().flatten())
+		.any(|served_domain| without_port(served_domain.trim()).eq_ignore_ascii_case(domain))
+}
+
+fn without_port(domain: &str) -> &str {
+	match domain.rsplit_once(':') {
+		Some((host, port)) if !port.is_empty() && port.chars().all(|c| c.is_ascii_digit()) => host,
+		_ => domain,
+	}
+}
+
+/// Other authentication backends can not list their users, so any name which they could accept
+/// is considered as existing.
+fn user_exists(settings: &super::Settings, users: &super::Users, user: &str) -> bool {
+	match settings.auth_backend.clone().unwrap_or_default() {
+		super::SettingsAuthBackend::UsersFile => users
+			.get_usernames()
+			.iter()
+			.any(|username| *username == user),
+		_ => super::auth_backends::is_valid_username(user),
+	}
+}
+
+pub struct WebfingerQuery {
+	resource: Option<String>,
+	/// Only links with one of these relation types are returned, if any (RFC 7033, section 4.3).
+	rels: Vec<String>,
+}
+impl WebfingerQuery {
+	/// `rel` can be repeated, which is not supported by [`actix_web::web::Query`].
+	fn parse(query_string: &str) -> Self {
+		let mut result = Self {
+			resource: None,
+			rels: vec![],
+		};
+
+		for (key, value) in url::form_urlencoded::parse(query_string.as_bytes()) {
+			match key.as_ref() {
+				"resource" => result.resource = Some(value.into_owned()),
+				"rel" => result.rels.push(value.into_owned()),
+				_ => {}
+			}
+		}
+
+		return result;
+	}
+
+	fn filter_links(&self, links: Vec<serde_json::Value>) -> Vec<serde_json::Value> {
+		links
+			.into_iter()
+			.filter(|link| {
+				self.rels.is_empty()
+					|| self
+						.rels
+						.iter()
+						.any(|rel| link.get("rel").and_then(|value| value.as_str()) == Some(rel))
+			})
+			.collect()
+	}
+}
+
+#[cfg(test)]
+mod tests;
@@ -0,0 +1,133 @@
+#[actix_rt::test]
+async fn v2kd8rx0mqn5tjw() {
+	use std::sync::{Arc, Mutex};
+
+	let mut settings = crate::http_server::Settings::new(std::path::PathBuf::from("."));
+	settings.domain = Some(String::from("storage.example.com"));
+	settings.domain_aliases = Some(vec![String::from("example.com")]);
+	settings.force_https = Some(false);
+	settings.port = 80;
+	let settings = Arc::new(Mutex::new(settings));
+
+	let mut users = crate::http_server::Users::new();
+	users.set_password_hashing(crate::http_server::SettingsPasswordHashing {
+		memory_kib: 64,
+		iterations: 1,
+		parallelism: 1,
+	});
+	users.insert("user", &mut String::from("password")).unwrap();
+	let users = Arc::new(Mutex::new(users));
+
+	let program_state = Arc::new(Mutex::new(crate::http_server::ProgramState::default()));
+
+	let app = actix_web::test::init_service(
+		actix_web::App::new()
+			.app_data(actix_web::web::Data::new(settings.clone()))
+			.app_data(actix_web::web::Data::new(program_state))
+			.app_data(actix_web::web::Data::new(users))
+			.wrap(crate::http_server::middlewares::Cors)
+			.service(super::webfinger_handle)
+			.service(super::options_webfinger),
+	)
+	.await;
+
+	let tests = vec![
+		(010, "acct:user@storage.example.com", "", 200, 1),
+		(020, "acct:user@Storage.Example.com:8080", "", 200, 1),
+		(030, "acct:user@example.com", "", 200, 1),
+		(040, "acct:user@other.example.com", "", 404, 0),
+		(050, "acct:RANDOM@storage.example.com", "", 404, 0),
+		(060, "acct:storage.example.com", "", 400, 0),
+		(070, "acct:@storage.example.com", "", 400, 0),
+		(
+			080,
+			"acct:user@example.com",
+			"&rel=http%3A%2F%2Ftools.ietf.org%2Fid%2Fdraft-dejong-remotestorage",
+			200,
+			1,
+		),
+		(
+			090,
+			"acct:user@example.com",
+			"&rel=http%3A%2F%2Fwebfinger.net%2Frel%2Favatar",
+			200,
+			0,
+		),
+	];
+
+	for test in tests {
+		print!(
+			"#{:03} : GET webfinger of `{}` with `{}` ... ",
+			test.0, test.1, test.2
+		);
+
+		let request = actix_web::test::TestRequest::get()
+			.uri(&format!(
+				"/.well-known/webfinger?resource={}{}",
+				pct_str::PctString::encode(test.1.chars(), pct_str::URIReserved),
+				test.2
+			))
+			.insert_header((actix_web::http::header::ORIGIN, "https://app.example.com"))
+			.to_request();
+		let response = actix_web::test::call_service(&app, request).await;
+
+		assert_eq!(response.status().as_u16(), test.3);
+		assert_eq!(
+			response
+				.headers()
+				.get(actix_web::http::header::ACCESS_CONTROL_ALLOW_ORIGIN)
+				.unwrap(),
+			"https://app.example.com"
+		);
+
+		let body: serde_json::Value =
+			serde_json::from_slice(&actix_web::test::read_body(response).await).unwrap();
+		if test.3 == 200 {
+			assert_eq!(body["subject"], test.1);
+			assert_eq!(body["links"].as_array().unwrap().len(), test.4);
+			if test.4 > 0 {
+				assert_eq!(
+					body["links"][0]["href"],
+					"http://storage.example.com/storage/user"
+				);
+				assert_eq!(
+					body["links"][0]["properties"]
+						["http://tools.ietf.org/html/rfc6750#section-2.3"],
+					"true"
+				);
+			}
+		} else {
+			assert!(body["error"].is_string());
+		}
+
+		println!("OK");
+	}
+
+	let request = actix_web::test::TestRequest::default()
+		.method(actix_web::http::Method::HEAD)
+		.uri("/.well-known/webfinger?resource=acct:user@example.com")
+		.to_request();
+	let response = actix_web::test::call_service(&app, request).await;
+	assert_eq!(response.status(), actix_web::http::StatusCode::OK);
+	assert_eq!(
+		response
+			.headers()
+			.get(actix_web::http::header::ACCESS_CONTROL_ALLOW_ORIGIN)
+			.unwrap(),
+		"*"
+	);
+
+	let request = actix_web::test::TestRequest::default()
+		.method(actix_web::http::Method::OPTIONS)
+		.uri("/.well-known/webfinger")
+		.to_request();
+	let response = actix_web::test::call_service(&app, request).await;
+	assert_eq!(response.status(), actix_web::http::StatusCode::OK);
+	assert_eq!(
+		response
+			.headers()
+			.get(actix_web::http::header::ALLOW)
+			.unwrap(),
+		"OPTIONS, GET, HEAD"
+	);
+}
@@ -42,6 +42,14 @@ pub struct Scope {
 	pub right_type: ScopeRightType,
 	pub module: String,
 }
+impl Scope {
+	/// Returns `true` if all accesses allowed by `other` are also allowed by this scope.
+	pub fn includes(&self, other: &Scope) -> bool {
+		(self.module == "*" || self.module == other.module)
+			&& (self.right_type == ScopeRightType::ReadWrite
+				|| other.right_type == ScopeRightType::Read)
+	}
+}
 #[cfg(feature = "server")]
 impl Scope {
 	pub fn allowed_methods(&self) -> Vec<actix_web::http::Method> {